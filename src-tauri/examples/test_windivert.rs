@@ -1,8 +1,18 @@
+#[cfg(windows)]
 use std::time::Duration;
+#[cfg(windows)]
 use windivert::WinDivert;
+#[cfg(windows)]
 use windivert::layer::NetworkLayer;
+#[cfg(windows)]
 use windivert_sys::WinDivertFlags;
 
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("WinDivert is only available on Windows");
+}
+
+#[cfg(windows)]
 fn main() {
 
     // Test various filter combinations
@@ -0,0 +1,927 @@
+//! Headless command-line front end for Myra.
+//!
+//! Runs the standard or classic pipeline without the GUI so that scripts and
+//! test harnesses can drive it. Module parameters use the same flags as the
+//! option structs (`--lag-ms`, `--drop-probability`, ...) and giving any flag
+//! of a module enables that module. Live statistics are printed until Ctrl-C
+//! or until `--duration` runs out. Packets are captured with `WinDivert` on
+//! Windows and from a TUN device (`--backend tun`) on Linux.
+#![warn(clippy::all)]
+
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, mpsc};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use log::{LevelFilter, error, info};
+
+use myra::commands::classic::start_classic_packet_processing;
+use myra::commands::config::read_config;
+use myra::commands::types::ProcessingStatisticsDto;
+use myra::commands::validate_filter;
+use myra::control::ScenarioRunner;
+use myra::logger::init_logger;
+use myra::network::backend::{BackendHandle, CaptureBackend};
+use myra::network::core::{flush_wfp_cache, restore_timer_resolution, set_high_precision_timer};
+use myra::network::modules::stats::PacketProcessingStatistics;
+use myra::network::modules::stats::tap_stats::TapStats;
+use myra::network::pcap::SharedRecorder;
+use myra::network::processing::tap::TapPhase;
+use myra::network::processing::{receive_packets, start_packet_processing};
+use myra::network::types::clock::SystemClock;
+use myra::settings::bandwidth::BandwidthOptions;
+use myra::settings::burst::BurstOptions;
+use myra::settings::corruption::CorruptionOptions;
+use myra::settings::drop::DropOptions;
+use myra::settings::duplicate::DuplicateOptions;
+use myra::settings::lag::LagOptions;
+use myra::settings::reorder::ReorderOptions;
+use myra::settings::throttle::ThrottleOptions;
+use myra::settings::{
+    ClassicSettings, NetworkProfile, Scenario, Settings, TapOptions, builtin_profiles, find_profile,
+};
+use myra::utils::is_admin;
+
+/// Set by the console control handler when the user presses Ctrl-C
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Time held packets get to drain after the modules are switched off
+const DRAIN_TIME: Duration = Duration::from_millis(300);
+
+/// How long to wait for the receiver thread, which may be blocked in `recv`
+const RECEIVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Pipeline to run
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Module chain used by the main window
+    Standard,
+    /// Clumsy-style pipeline; needs classic settings from `--config`
+    Classic,
+}
+
+/// Capture backend to run on
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Backend {
+    /// `WinDivert` driver; Windows only
+    #[value(name = "windivert")]
+    WinDivert,
+    /// TUN device that traffic is routed through; Linux only
+    Tun,
+}
+
+/// Traffic direction that modules enabled on the command line apply to
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Both,
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    fn inbound(self) -> bool {
+        self != Self::Outbound
+    }
+
+    fn outbound(self) -> bool {
+        self != Self::Inbound
+    }
+}
+
+/// Simulate poor network conditions without the GUI.
+///
+/// Passing any flag of a module enables it. Modules given on the command line
+/// replace the same module in the loaded configuration; the others are kept.
+#[derive(Parser, Debug)]
+#[command(name = "myra-cli", version)]
+struct Cli {
+    /// `WinDivert` filter selecting the packets to impair
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Name of a configuration saved from the app to start from
+    #[arg(long)]
+    config: Option<String>,
+
+    /// Built-in network profile applied on top of the configuration
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// List the built-in network profiles and exit
+    #[arg(long)]
+    list_profiles: bool,
+
+    /// Pipeline to run [default: the mode saved in the configuration, else standard]
+    #[arg(long, value_enum)]
+    mode: Option<Mode>,
+
+    /// Capture backend [default: windivert on Windows, else tun]
+    #[arg(long, value_enum)]
+    backend: Option<Backend>,
+
+    /// TUN interface to attach to with `--backend tun`
+    #[arg(long, default_value = "myra0")]
+    tun_device: String,
+
+    /// Local address on the TUN device, whose packets are outbound; may be repeated
+    #[arg(long = "tun-local-address", value_name = "ADDRESS")]
+    tun_local_addresses: Vec<IpAddr>,
+
+    /// Direction that modules enabled on the command line apply to
+    #[arg(long, value_enum, default_value_t = Direction::Both)]
+    direction: Direction,
+
+    /// Session seed, making runs over the same traffic reproducible
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Scenario file (TOML or JSON) to play on top of the module settings
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+
+    /// Stop after this many seconds instead of waiting for Ctrl-C
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// Milliseconds between two statistics lines
+    #[arg(long, default_value_t = 1000)]
+    stats_interval: u64,
+
+    /// Do not print statistics
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Print log messages; repeat for more detail
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    #[command(flatten, next_help_heading = "Drop")]
+    drop: DropOptions,
+
+    #[command(flatten, next_help_heading = "Lag")]
+    lag: LagOptions,
+
+    #[command(flatten, next_help_heading = "Throttle")]
+    throttle: ThrottleOptions,
+
+    #[command(flatten, next_help_heading = "Reorder")]
+    reorder: ReorderOptions,
+
+    #[command(flatten, next_help_heading = "Corruption")]
+    corruption: CorruptionOptions,
+
+    #[command(flatten, next_help_heading = "Duplicate")]
+    duplicate: DuplicateOptions,
+
+    #[command(flatten, next_help_heading = "Bandwidth")]
+    bandwidth: BandwidthOptions,
+
+    #[command(flatten, next_help_heading = "Burst")]
+    burst: BurstOptions,
+
+    #[command(flatten, next_help_heading = "Tap")]
+    tap: TapOptions,
+}
+
+/// Threads of a running pipeline.
+struct Pipeline {
+    running: Arc<AtomicBool>,
+    receiver: JoinHandle<myra::Result<()>>,
+    processor: JoinHandle<myra::Result<()>>,
+}
+
+impl Pipeline {
+    /// Whether either thread has exited on its own.
+    fn has_stopped(&self) -> bool {
+        self.receiver.is_finished() || self.processor.is_finished()
+    }
+
+    /// Stops both threads and returns the first error either reported.
+    fn stop(self) -> Result<(), String> {
+
+        self.running.store(false, Ordering::SeqCst);
+
+        let processed = join(self.processor, "Packet processing");
+
+        // The receiver only notices the flag once `recv` returns, which needs
+        // one more matching packet. Leave it behind if none arrives in time.
+        let deadline = Instant::now() + RECEIVER_SHUTDOWN_TIMEOUT;
+
+        while !self.receiver.is_finished() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let received = if self.receiver.is_finished() {
+            join(self.receiver, "Packet receiving")
+        } else {
+            Ok(())
+        };
+
+        flush_wfp_cache();
+        restore_timer_resolution();
+
+        processed.and(received)
+
+    }
+}
+
+fn main() -> ExitCode {
+
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    let level = match cli.verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    };
+
+    if let Err(e) = init_logger(level) {
+        eprintln!("Failed to initialize logger: {}", e);
+        return ExitCode::FAILURE;
+    }
+
+    match run(cli, &matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("myra-cli: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+
+}
+
+/// Starts the selected pipeline and reports on it until it should stop.
+fn run(cli: Cli, matches: &ArgMatches) -> Result<(), String> {
+
+    if cli.list_profiles {
+        print_profiles();
+        return Ok(());
+    }
+
+    let backend = capture_backend(&cli);
+
+    if backend == CaptureBackend::WinDivert && !is_admin() {
+        return Err("administrator privileges are required to capture packets".to_string());
+    }
+
+    let config = cli.config.as_deref().map(read_config).transpose()?;
+
+    let filter = cli
+        .filter
+        .clone()
+        .or_else(|| config.as_ref().and_then(|c| c.filter.clone()))
+        .ok_or_else(|| "No filter given; pass --filter or a --config that has one".to_string())?;
+
+    validate_filter(filter.clone())?;
+
+    let scenario = cli.scenario.as_deref().map(Scenario::load).transpose()?;
+    let profile = cli.profile.as_deref().map(profile_by_id).transpose()?;
+
+    let mode = match cli.mode {
+        Some(mode) => mode,
+        None => config
+            .as_ref()
+            .and_then(|c| c.mode.as_deref())
+            .map(|mode| Mode::from_str(mode, true))
+            .transpose()?
+            .unwrap_or(Mode::Standard),
+    };
+
+    let backend = backend.open()?;
+
+    install_interrupt_handler()?;
+
+    let started = Instant::now();
+
+    match mode {
+        Mode::Standard => {
+            let mut base = config.map(|c| c.settings).unwrap_or_default();
+
+            if let Some(profile) = &profile {
+                profile.apply(&mut base);
+            }
+
+            let settings = Arc::new(Mutex::new(build_settings(&cli, matches, base)));
+            let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+            let mut scenario = scenario
+                .map(|scenario| {
+                    ScenarioRunner::start(scenario, settings.clone(), Arc::new(SystemClock))
+                })
+                .transpose()?;
+            let pipeline = start_standard(backend, settings.clone(), statistics.clone(), filter);
+
+            info!("Started standard pipeline");
+
+            wait(&cli, started, &pipeline, |elapsed| {
+                let settings = settings.lock().map_err(|e| e.to_string())?;
+                let statistics = statistics.read().map_err(|e| e.to_string())?;
+
+                Ok(format_statistics(&statistics, &settings, elapsed))
+            });
+
+            if let Some(runner) = scenario.as_mut() {
+                runner.stop();
+            }
+
+            // Switch every module off first so held packets are released
+            *settings
+                .lock()
+                .map_err(|e| format!("Failed to lock settings mutex: {}", e))? =
+                Settings::default();
+
+            thread::sleep(DRAIN_TIME);
+            pipeline.stop()
+        }
+        Mode::Classic => {
+            if modules_on_command_line(matches) || scenario.is_some() || profile.is_some() {
+                return Err(
+                    "Module flags, profiles and scenarios apply to the standard pipeline only"
+                        .to_string(),
+                );
+            }
+
+            let mut classic = config
+                .and_then(|c| c.classic)
+                .ok_or_else(|| "Classic mode needs a --config with classic settings".to_string())?;
+
+            if given::<TapOptions>(matches) {
+                classic.tap = Some(TapOptions {
+                    enabled: true,
+                    ..cli.tap.clone()
+                });
+            }
+
+            let tap_stats = Arc::new(RwLock::new(TapStats::new()));
+            let pipeline = start_classic(backend, classic, filter, tap_stats.clone());
+
+            info!("Started classic pipeline");
+
+            wait(&cli, started, &pipeline, |elapsed| {
+                let tapping = tap_stats
+                    .read()
+                    .map_err(|e| format!("Failed to lock tap stats: {}", e))?
+                    .phase()
+                    == TapPhase::Tapping;
+
+                Ok(format!(
+                    "[{:>7.1}s] classic pipeline running{}",
+                    elapsed.as_secs_f64(),
+                    if tapping { " (tapping)" } else { "" }
+                ))
+            });
+
+            pipeline.stop()
+        }
+    }
+
+}
+
+/// Builds the capture backend selected on the command line.
+fn capture_backend(cli: &Cli) -> CaptureBackend {
+
+    let backend = cli.backend.unwrap_or(if cfg!(windows) {
+        Backend::WinDivert
+    } else {
+        Backend::Tun
+    });
+
+    match backend {
+        Backend::WinDivert => CaptureBackend::WinDivert,
+        Backend::Tun => CaptureBackend::Tun {
+            device: cli.tun_device.clone(),
+            local_addresses: cli.tun_local_addresses.clone(),
+        },
+    }
+
+}
+
+/// Spawns the receiver and processor threads of the standard pipeline.
+fn start_standard(
+    backend: BackendHandle,
+    settings: Arc<Mutex<Settings>>,
+    statistics: Arc<RwLock<PacketProcessingStatistics>>,
+    filter: String,
+) -> Pipeline {
+
+    let running = Arc::new(AtomicBool::new(true));
+    let filter = Arc::new(Mutex::new(Some(filter)));
+    let recorder = SharedRecorder::default();
+    let (packet_sender, packet_receiver) = mpsc::channel();
+
+    set_high_precision_timer();
+
+    let receiver = {
+        let backend = backend.clone();
+        let running = running.clone();
+        let settings = settings.clone();
+        let recorder = recorder.clone();
+
+        thread::spawn(move || {
+            receive_packets(backend, packet_sender, running, settings, filter, recorder)
+        })
+    };
+
+    let processor = {
+        let running = running.clone();
+
+        thread::spawn(move || {
+            start_packet_processing(
+                backend,
+                settings,
+                packet_receiver,
+                running,
+                statistics,
+                recorder,
+            )
+        })
+    };
+
+    Pipeline {
+        running,
+        receiver,
+        processor,
+    }
+
+}
+
+/// Spawns the receiver and processor threads of the classic pipeline.
+fn start_classic(
+    backend: BackendHandle,
+    classic: ClassicSettings,
+    filter: String,
+    tap_stats: Arc<RwLock<TapStats>>,
+) -> Pipeline {
+
+    let running = Arc::new(AtomicBool::new(true));
+    let filter = Arc::new(Mutex::new(Some(filter)));
+    let settings = Arc::new(Mutex::new(classic));
+    let (packet_sender, packet_receiver) = mpsc::channel();
+
+    set_high_precision_timer();
+
+    let receiver = {
+        let backend = backend.clone();
+        let running = running.clone();
+
+        thread::spawn(move || {
+            receive_packets(
+                backend,
+                packet_sender,
+                running,
+                Arc::new(Mutex::new(Settings::default())),
+                filter,
+                SharedRecorder::default(),
+            )
+        })
+    };
+
+    let processor = {
+        let running = running.clone();
+
+        thread::spawn(move || {
+            start_classic_packet_processing(backend, settings, packet_receiver, running, tap_stats)
+        })
+    };
+
+    Pipeline {
+        running,
+        receiver,
+        processor,
+    }
+
+}
+
+/// Prints a statistics line every interval until the run should end.
+///
+/// Returns on Ctrl-C, when `--duration` has passed or when a pipeline thread
+/// exits on its own.
+fn wait<F>(cli: &Cli, started: Instant, pipeline: &Pipeline, mut report: F)
+where
+    F: FnMut(Duration) -> Result<String, String>,
+{
+
+    let deadline = cli.duration.map(|secs| started + Duration::from_secs(secs));
+    let interval = Duration::from_millis(cli.stats_interval.max(1));
+    let mut next_report = started + interval;
+
+    while !INTERRUPTED.load(Ordering::SeqCst) && !pipeline.has_stopped() {
+        let now = Instant::now();
+
+        if deadline.is_some_and(|deadline| now >= deadline) {
+            break;
+        }
+
+        if !cli.quiet && now >= next_report {
+            match report(now - started) {
+                Ok(line) => println!("{}", line),
+                Err(e) => error!("Failed to read statistics: {}", e),
+            }
+            next_report += interval;
+        }
+
+        thread::sleep(Duration::from_millis(20));
+    }
+
+}
+
+/// Looks up a built-in profile, listing the valid ids if there is none.
+fn profile_by_id(id: &str) -> Result<NetworkProfile, String> {
+    find_profile(id).ok_or_else(|| {
+        let ids: Vec<_> = builtin_profiles().iter().map(|profile| profile.id).collect();
+        format!("Unknown profile {}; available profiles: {}", id, ids.join(", "))
+    })
+}
+
+/// Prints the id, name, description and source of every built-in profile.
+fn print_profiles() {
+    for profile in builtin_profiles() {
+        println!("{:<18}{}", profile.id, profile.name);
+        println!("{:<18}{}", "", profile.description);
+        println!("{:<18}Source: {}", "", profile.source);
+    }
+}
+
+/// Applies the command line on top of the settings loaded from a configuration.
+fn build_settings(cli: &Cli, matches: &ArgMatches, mut settings: Settings) -> Settings {
+
+    let inbound = cli.direction.inbound();
+    let outbound = cli.direction.outbound();
+
+    if given::<DropOptions>(matches) {
+        settings.drop = Some(DropOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.drop.clone()
+        });
+    }
+
+    if given::<LagOptions>(matches) {
+        settings.lag = Some(LagOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.lag.clone()
+        });
+    }
+
+    if given::<ThrottleOptions>(matches) {
+        settings.throttle = Some(ThrottleOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.throttle.clone()
+        });
+    }
+
+    if given::<ReorderOptions>(matches) {
+        settings.reorder = Some(ReorderOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.reorder.clone()
+        });
+    }
+
+    if given::<CorruptionOptions>(matches) {
+        settings.corruption = Some(CorruptionOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.corruption.clone()
+        });
+    }
+
+    if given::<DuplicateOptions>(matches) {
+        settings.duplicate = Some(DuplicateOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.duplicate.clone()
+        });
+    }
+
+    if given::<BandwidthOptions>(matches) {
+        settings.bandwidth = Some(BandwidthOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.bandwidth.clone()
+        });
+    }
+
+    if given::<BurstOptions>(matches) {
+        settings.burst = Some(BurstOptions {
+            enabled: true,
+            inbound,
+            outbound,
+            ..cli.burst.clone()
+        });
+    }
+
+    if given::<TapOptions>(matches) {
+        settings.tap = Some(TapOptions {
+            enabled: true,
+            ..cli.tap.clone()
+        });
+    }
+
+    if cli.seed.is_some() {
+        settings.seed = cli.seed;
+    }
+
+    settings
+
+}
+
+/// Whether any flag of the module with options `T` was given on the command line.
+fn given<T: CommandFactory>(matches: &ArgMatches) -> bool {
+    T::command().get_arguments().any(|arg| {
+        matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+    })
+}
+
+/// Whether a flag of any module was given on the command line.
+fn modules_on_command_line(matches: &ArgMatches) -> bool {
+    given::<DropOptions>(matches)
+        || given::<LagOptions>(matches)
+        || given::<ThrottleOptions>(matches)
+        || given::<ReorderOptions>(matches)
+        || given::<CorruptionOptions>(matches)
+        || given::<DuplicateOptions>(matches)
+        || given::<BandwidthOptions>(matches)
+        || given::<BurstOptions>(matches)
+}
+
+/// Formats one statistics line covering the enabled modules.
+fn format_statistics(
+    stats: &PacketProcessingStatistics,
+    settings: &Settings,
+    elapsed: Duration,
+) -> String {
+
+    let dto = ProcessingStatisticsDto::from(stats);
+    let mut parts = vec![format!("[{:>7.1}s]", elapsed.as_secs_f64())];
+
+    if settings.drop.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!(
+            "drop {}/{} ({:.1}%)",
+            stats.drop_stats.total_dropped,
+            stats.drop_stats.total_packets,
+            stats.drop_stats.total_drop_rate() * 100.0
+        ));
+    }
+
+    if settings.lag.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!("lag {} held", dto.lag_current_lagged));
+    }
+
+    if settings.throttle.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!(
+            "throttle {} buffered, {} dropped{}",
+            dto.throttle_buffered_count,
+            dto.throttle_dropped_count,
+            if dto.throttle_is_throttling { " (active)" } else { "" }
+        ));
+    }
+
+    if settings.reorder.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!("reorder {} delayed", dto.reorder_delayed_packets));
+    }
+
+    if settings.bandwidth.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!(
+            "bandwidth {:.1} KiB sent, {} queued, {} dropped, {:.0} ms queueing",
+            stats.bandwidth_stats.total_bytes() as f64 / 1024.0,
+            dto.bandwidth_buffered_count,
+            dto.bandwidth_dropped_count,
+            dto.bandwidth_queue_delay_ms
+        ));
+    }
+
+    if settings.burst.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!("burst {} buffered", dto.burst_buffered_count));
+    }
+
+    if settings.tap.as_ref().is_some_and(|o| o.enabled) {
+        parts.push(format!(
+            "tap {} started{}",
+            dto.tap_count,
+            if dto.tap_phase == TapPhase::Tapping { " (tapping)" } else { "" }
+        ));
+    }
+
+    parts.join(" | ")
+
+}
+
+/// Joins a pipeline thread, turning a panic or error into a message.
+fn join(handle: JoinHandle<myra::Result<()>>, name: &str) -> Result<(), String> {
+    match handle.join() {
+        Ok(result) => result.map_err(|e| format!("{} error: {}", name, e)),
+        Err(_) => Err(format!("{} thread panicked", name)),
+    }
+}
+
+/// Routes Ctrl-C and Ctrl-Break to [`INTERRUPTED`] instead of killing the process.
+#[cfg(windows)]
+fn install_interrupt_handler() -> Result<(), String> {
+
+    use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
+    use winapi::um::consoleapi::SetConsoleCtrlHandler;
+    use winapi::um::wincon::{CTRL_BREAK_EVENT, CTRL_C_EVENT};
+
+    unsafe extern "system" fn handler(ctrl_type: DWORD) -> BOOL {
+        match ctrl_type {
+            CTRL_C_EVENT | CTRL_BREAK_EVENT => {
+                INTERRUPTED.store(true, Ordering::SeqCst);
+                TRUE
+            }
+            _ => FALSE,
+        }
+    }
+
+    if unsafe { SetConsoleCtrlHandler(Some(handler), TRUE) } == 0 {
+        return Err("Failed to install the Ctrl-C handler".to_string());
+    }
+
+    Ok(())
+
+}
+
+/// Routes Ctrl-C and termination requests to [`INTERRUPTED`] instead of killing the process.
+#[cfg(target_os = "linux")]
+fn install_interrupt_handler() -> Result<(), String> {
+
+    extern "C" fn handler(_signal: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        let handler = handler as extern "C" fn(libc::c_int);
+
+        if unsafe { libc::signal(signal, handler as libc::sighandler_t) } == libc::SIG_ERR {
+            return Err("Failed to install the Ctrl-C handler".to_string());
+        }
+    }
+
+    Ok(())
+
+}
+
+/// Packet capture is not available here; keep the default Ctrl-C behaviour.
+#[cfg(not(any(windows, target_os = "linux")))]
+fn install_interrupt_handler() -> Result<(), String> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use myra::settings::ModuleKind;
+
+    fn parse(args: &[&str]) -> (Cli, ArgMatches) {
+
+        let matches = Cli::command()
+            .try_get_matches_from(std::iter::once("myra-cli").chain(args.iter().copied()))
+            .unwrap();
+        let cli = Cli::from_arg_matches(&matches).unwrap();
+
+        (cli, matches)
+
+    }
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_module_flags_enable_module() {
+
+        let (cli, matches) = parse(&["--lag-ms", "120", "--drop-probability", "0.1"]);
+        let settings = build_settings(&cli, &matches, Settings::default());
+
+        let lag = settings.lag.unwrap();
+        assert!(lag.enabled && lag.inbound && lag.outbound);
+        assert_eq!(lag.delay_ms, 120);
+
+        let drop = settings.drop.unwrap();
+        assert!(drop.enabled);
+        assert!((drop.probability.value() - 0.1).abs() < f64::EPSILON);
+
+        assert!(settings.throttle.is_none());
+        assert!(settings.burst.is_none());
+
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+
+        let base = Settings {
+            throttle: Some(ThrottleOptions {
+                enabled: true,
+                ..Default::default()
+            }),
+            seed: Some(1),
+            ..Default::default()
+        };
+
+        let (cli, matches) = parse(&["--lag-ms", "50", "--direction", "outbound", "--seed", "7"]);
+        let settings = build_settings(&cli, &matches, base);
+
+        let lag = settings.lag.unwrap();
+        assert!(!lag.inbound && lag.outbound);
+        assert!(settings.throttle.is_some_and(|t| t.enabled));
+        assert_eq!(settings.seed, Some(7));
+
+    }
+
+    #[test]
+    fn test_tap_flags_enable_tapping() {
+
+        let (cli, matches) = parse(&["--tap-interval-ms", "500", "--tap-modules", "lag,burst"]);
+        let tap = build_settings(&cli, &matches, Settings::default()).tap.unwrap();
+
+        assert!(tap.enabled && !tap.auto);
+        assert_eq!(tap.interval_ms, 500);
+        assert_eq!(tap.duration_ms, 600);
+        assert_eq!(tap.modules, vec![ModuleKind::Lag, ModuleKind::Burst]);
+
+        // Tapping also applies to the classic pipeline
+        assert!(!modules_on_command_line(&matches));
+
+    }
+
+    #[test]
+    fn test_module_flags_override_profile() {
+
+        let mut base = Settings::default();
+        profile_by_id("3g").unwrap().apply(&mut base);
+
+        let (cli, matches) = parse(&["--profile", "3g", "--lag-ms", "20"]);
+        let settings = build_settings(&cli, &matches, base);
+
+        assert_eq!(settings.lag.map(|lag| lag.delay_ms), Some(20));
+        assert!(settings.bandwidth.is_some_and(|bandwidth| bandwidth.enabled));
+        assert!(profile_by_id("dial-up").unwrap_err().contains("lte"));
+
+    }
+
+    #[test]
+    fn test_tun_backend_flags() {
+
+        let (cli, _) = parse(&[
+            "--backend",
+            "tun",
+            "--tun-device",
+            "myra1",
+            "--tun-local-address",
+            "10.0.0.2",
+        ]);
+
+        assert_eq!(
+            capture_backend(&cli),
+            CaptureBackend::Tun {
+                device: "myra1".to_string(),
+                local_addresses: vec!["10.0.0.2".parse().unwrap()],
+            }
+        );
+
+        let (cli, _) = parse(&["--backend", "windivert"]);
+
+        assert_eq!(capture_backend(&cli), CaptureBackend::WinDivert);
+
+    }
+
+    #[test]
+    fn test_defaults_do_not_enable_modules() {
+
+        let (cli, matches) = parse(&["--filter", "outbound"]);
+
+        assert!(!modules_on_command_line(&matches));
+        assert!(build_settings(&cli, &matches, Settings::default()).lag.is_none());
+
+    }
+
+    #[test]
+    fn test_statistics_line_lists_enabled_modules() {
+
+        let mut stats = PacketProcessingStatistics::default();
+        stats.drop_stats.record(true);
+        stats.drop_stats.record(false);
+
+        let (cli, matches) = parse(&["--drop-probability", "0.5"]);
+        let settings = build_settings(&cli, &matches, Settings::default());
+        let line = format_statistics(&stats, &settings, Duration::from_millis(1500));
+
+        assert_eq!(line, "[    1.5s] | drop 1/2 (50.0%)");
+
+    }
+}
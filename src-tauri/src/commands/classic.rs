@@ -10,19 +10,27 @@ use tauri::State;
 
 use crate::commands::classic_state::ClassicProcessingState;
 use crate::commands::state::PacketProcessingState;
+use crate::network::backend::{BackendHandle, CaptureBackend, PacketSink};
 use crate::network::classic::{
     ClassicProcessingState as ClassicModuleState, process_classic_packets,
 };
 use crate::network::core::set_high_precision_timer;
+use crate::network::modules::stats::tap_stats::TapStats;
+use crate::network::pcap::SharedRecorder;
+use crate::network::processing::tap::TapPhase;
 use crate::settings::classic::ClassicSettings;
 
 /// Starts Classic mode packet processing with the given settings and filter.
+///
+/// Captures through `backend`, or `WinDivert` when it is omitted. The tap
+/// options set through `set_tap` are kept unless `settings` has its own.
 #[tauri::command]
 pub async fn start_classic_processing(
     state: State<'_, PacketProcessingState>,
     classic_state: State<'_, ClassicProcessingState>,
     settings: ClassicSettings,
     filter: Option<String>,
+    backend: Option<CaptureBackend>,
 ) -> Result<(), String> {
 
     // Check if standard mode is running
@@ -35,11 +43,15 @@ pub async fn start_classic_processing(
         return Err("Classic mode processing already running".to_string());
     }
 
+    let backend = backend.unwrap_or_default().open()?;
+
     // Store settings
+    store_classic_settings(&classic_state, settings)?;
+
     *classic_state
-        .settings
-        .lock()
-        .map_err(|e| format!("Failed to lock classic settings mutex: {}", e))? = settings;
+        .tap_stats
+        .write()
+        .map_err(|e| format!("Failed to lock classic tap stats: {}", e))? = TapStats::new();
 
     // Store filter in the standard state (shared)
     *state
@@ -58,15 +70,18 @@ pub async fn start_classic_processing(
     let running_recv = classic_state.running.clone();
     let settings_recv = state.settings.clone(); // Needed for receiver
     let filter_recv = state.filter.clone();
+    let backend_recv = backend.clone();
 
     // Spawn packet receiver thread (reuses standard receiver)
     thread::spawn(move || {
 
         if let Err(e) = crate::network::processing::receive_packets(
+            backend_recv,
             packet_sender,
             running_recv,
             settings_recv,
             filter_recv,
+            SharedRecorder::default(), // Recording covers the standard pipeline only
         ) {
             error!("Classic mode packet receiving error: {}", e);
         }
@@ -75,13 +90,18 @@ pub async fn start_classic_processing(
 
     let running_proc = classic_state.running.clone();
     let classic_settings = classic_state.settings.clone();
+    let tap_stats = classic_state.tap_stats.clone();
 
     // Spawn Classic mode processor thread
     thread::spawn(move || {
 
-        if let Err(e) =
-            start_classic_packet_processing(classic_settings, packet_receiver, running_proc)
-        {
+        if let Err(e) = start_classic_packet_processing(
+            backend,
+            classic_settings,
+            packet_receiver,
+            running_proc,
+            tap_stats,
+        ) {
             error!("Classic mode packet processing error: {}", e);
         }
 
@@ -124,10 +144,28 @@ pub async fn update_classic_settings(
         settings.throttle.as_ref().map(|o| o.enabled)
     );
 
-    *classic_state
+    store_classic_settings(&classic_state, settings)
+
+}
+
+/// Replaces the Classic settings of `classic_state` with `settings`.
+///
+/// The current tap options are kept when `settings` has none, since they are
+/// set through `set_tap` rather than with the modules.
+fn store_classic_settings(
+    classic_state: &ClassicProcessingState,
+    mut settings: ClassicSettings,
+) -> Result<(), String> {
+
+    let mut current = classic_state
         .settings
         .lock()
-        .map_err(|e| format!("Failed to lock classic settings mutex: {}", e))? = settings;
+        .map_err(|e| format!("Failed to lock classic settings mutex: {}", e))?;
+
+    if settings.tap.is_none() {
+        settings.tap = current.tap.take();
+    }
+    *current = settings;
 
     Ok(())
 
@@ -157,28 +195,58 @@ pub struct ClassicStatusResponse {
 }
 
 /// Classic mode packet processing loop.
-fn start_classic_packet_processing(
+///
+/// Creates the sink of the session's capture backend and runs
+/// [`run_classic_packet_processing`] on it until `running` is cleared.
+///
+/// # Arguments
+///
+/// * `backend` - Backend to create the packet sink from
+/// * `settings` - Shared Classic mode settings
+/// * `packet_receiver` - Channel receiver for incoming packet data
+/// * `running` - Atomic flag that controls when processing should stop
+/// * `tap_stats` - Tap phase of the loop, for status reporting
+pub fn start_classic_packet_processing(
+    backend: BackendHandle,
     settings: std::sync::Arc<std::sync::Mutex<ClassicSettings>>,
     packet_receiver: std::sync::mpsc::Receiver<crate::network::core::PacketData>,
     running: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    tap_stats: std::sync::Arc<std::sync::RwLock<TapStats>>,
 ) -> crate::error::Result<()> {
 
-    use std::time::{Duration, Instant};
-    use windivert::layer::NetworkLayer;
-    use windivert::{CloseAction, WinDivert};
-    use windivert_sys::WinDivertFlags;
-
-    // Initialize WinDivert for sending packets only
-    let mut wd = WinDivert::<NetworkLayer>::network(
-        "false",
-        0,
-        WinDivertFlags::set_send_only(WinDivertFlags::new()),
-    )
-    .map_err(|e| {
-        error!("Failed to initialize WinDivert for Classic mode: {}", e);
-        crate::error::MyraError::WinDivert(e)
+    let mut sink = backend.sink().map_err(|e| {
+        error!("Failed to open the packet sink for Classic mode: {}", e);
+        e
     })?;
 
+    run_classic_packet_processing(sink.as_mut(), settings, packet_receiver, running, tap_stats)?;
+
+    // Close handle
+    let _ = sink.shutdown();
+
+    Ok(())
+
+}
+
+/// Runs the Classic mode processing loop, sending packets through `sink`.
+///
+/// # Arguments
+///
+/// * `sink` - Backend that processed packets are sent through
+/// * `settings` - Shared Classic mode settings
+/// * `packet_receiver` - Channel receiver for incoming packet data
+/// * `running` - Atomic flag that controls when processing should stop
+/// * `tap_stats` - Tap phase of the loop, for status reporting
+pub fn run_classic_packet_processing<S: PacketSink + ?Sized>(
+    sink: &mut S,
+    settings: std::sync::Arc<std::sync::Mutex<ClassicSettings>>,
+    packet_receiver: std::sync::mpsc::Receiver<crate::network::core::PacketData>,
+    running: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    tap_stats: std::sync::Arc<std::sync::RwLock<TapStats>>,
+) -> crate::error::Result<()> {
+
+    use std::time::{Duration, Instant};
+
     let mut state = ClassicModuleState::new();
 
     // Use 40ms processing cycles (same as original)
@@ -220,7 +288,9 @@ fn start_classic_packet_processing(
         // Apply Classic mode processing
         match settings.lock() {
             Ok(settings) => {
-                process_classic_packets(&mut packets, &settings, &mut state);
+                let phase = process_classic_packets(&mut packets, &settings, &mut state);
+
+                record_tap_phase(&tap_stats, phase);
             }
             Err(e) => {
                 error!("Failed to acquire lock on Classic settings: {}", e);
@@ -229,7 +299,7 @@ fn start_classic_packet_processing(
 
         // Send processed packets
         for packet_data in packets {
-            if let Err(e) = wd.send(&packet_data.packet) {
+            if let Err(e) = sink.send_packet(&packet_data) {
                 error!("Failed to send packet: {}", e);
             }
         }
@@ -246,16 +316,22 @@ fn start_classic_packet_processing(
     let remaining = state.flush_all_buffers();
 
     for packet_data in remaining {
-        if let Err(e) = wd.send(&packet_data.packet) {
+        if let Err(e) = sink.send_packet(&packet_data) {
             error!("Failed to send buffered packet on shutdown: {}", e);
         }
     }
 
-    // Close handle
-    let _ = wd.close(CloseAction::Nothing);
-
+    record_tap_phase(&tap_stats, TapPhase::Off);
     info!("Classic mode processing stopped");
 
     Ok(())
 
 }
+
+/// Records the tap phase of a Classic processing cycle.
+fn record_tap_phase(tap_stats: &std::sync::RwLock<TapStats>, phase: TapPhase) {
+    match tap_stats.write() {
+        Ok(mut stats) => stats.record(phase),
+        Err(e) => error!("Failed to record Classic tap phase: {}", e),
+    }
+}
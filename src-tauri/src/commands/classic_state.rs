@@ -1,7 +1,8 @@
 //! Classic mode state management.
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 
+use crate::network::modules::stats::tap_stats::TapStats;
 use crate::settings::classic::ClassicSettings;
 
 /// Global state for Classic mode packet processing.
@@ -10,6 +11,8 @@ pub struct ClassicProcessingState {
     pub running: Arc<AtomicBool>,
     /// Current Classic mode settings
     pub settings: Arc<Mutex<ClassicSettings>>,
+    /// Tap phase of the Classic processing loop
+    pub tap_stats: Arc<RwLock<TapStats>>,
 }
 
 impl Default for ClassicProcessingState {
@@ -18,6 +21,7 @@ impl Default for ClassicProcessingState {
         Self {
             running: Arc::new(AtomicBool::new(false)),
             settings: Arc::new(Mutex::new(ClassicSettings::default())),
+            tap_stats: Arc::new(RwLock::new(TapStats::default())),
         }
 
     }
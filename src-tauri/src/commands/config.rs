@@ -6,7 +6,11 @@ use std::path::PathBuf;
 use tauri::State;
 
 use crate::commands::PacketProcessingState;
-use crate::settings::Settings;
+use crate::commands::system::process_targets;
+use crate::network::core::{ProcessSelector, snapshot_processes};
+use crate::network::filter::{Expr, Filter, FilterBuilder, Protocol};
+use crate::settings::rules::PortRange;
+use crate::settings::{Settings, TapOptions};
 
 /// Filter target mode for targeting specific processes or devices
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -39,6 +43,122 @@ pub struct FilterTarget {
     /// Include outbound traffic (default: true)
     #[serde(default = "default_true")]
     pub include_outbound: bool,
+    /// Further processes, targeted along with `process_id`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub process_ids: Vec<u32>,
+    /// Further devices, targeted along with `device_ip`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub device_ips: Vec<String>,
+    /// Protocols to capture; all if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protocols: Vec<Protocol>,
+    /// Remote ports to capture; all if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remote_ports: Vec<PortRange>,
+    /// Executable names of further processes, such as `game.exe`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub process_names: Vec<String>,
+    /// Also target the processes the targeted processes spawn
+    #[serde(default)]
+    pub include_children: bool,
+}
+
+impl FilterTarget {
+    /// Compiles the target into a capture filter.
+    ///
+    /// Processes are matched by the connections they have open when this is
+    /// called; a process without any matches no traffic. Names and
+    /// process trees are resolved against the processes running at the time.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Filter)` - The filter capturing the target
+    /// * `Err(String)` - If the target is incomplete or has an invalid address or filter
+    pub fn compile(&self) -> Result<Filter, String> {
+
+        let builder = FilterBuilder::new().directions(self.include_inbound, self.include_outbound);
+        let builder = self
+            .protocols
+            .iter()
+            .copied()
+            .fold(builder, FilterBuilder::protocol);
+        let mut builder = self
+            .remote_ports
+            .iter()
+            .copied()
+            .fold(builder, FilterBuilder::remote_ports);
+
+        match self.mode {
+            FilterTargetMode::All => {}
+            FilterTargetMode::Process => {
+                let selector = self.process_selector();
+
+                if selector.is_empty() {
+                    return Err("No process selected".to_string());
+                }
+
+                // Names and trees only mean something against running processes
+                let resolve = !selector.names.is_empty() || selector.include_children;
+                let mut pids: Vec<u32> = if resolve {
+                    selector
+                        .resolve(&snapshot_processes())
+                        .into_iter()
+                        .collect()
+                } else {
+                    selector.pids
+                };
+
+                pids.sort_unstable();
+                builder = pids.into_iter().fold(builder, process_targets);
+
+                // The direction terms alone would capture every other process too
+                if !builder.has_targets() {
+                    return Ok(Filter::new(Expr::Bool(false)));
+                }
+            }
+            FilterTargetMode::Device => {
+                for ip in self.device_ip.iter().chain(&self.device_ips) {
+                    let addr = ip
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid device address '{}'", ip))?;
+
+                    builder = builder.host(addr);
+                }
+
+                if !builder.has_targets() {
+                    return Err("No device selected".to_string());
+                }
+            }
+            FilterTargetMode::Custom => {
+                let custom = self
+                    .custom_filter
+                    .as_deref()
+                    .filter(|filter| !filter.trim().is_empty())
+                    .ok_or_else(|| "No custom filter given".to_string())?;
+                let filter = Filter::parse(custom).map_err(|e| format!("Invalid filter: {}", e))?;
+
+                builder = builder.expr(filter.into_expr());
+            }
+        }
+
+        Ok(Filter::new(builder.build()))
+
+    }
+
+    /// Returns the processes the target names, for the flow tracker.
+    pub fn process_selector(&self) -> ProcessSelector {
+        ProcessSelector {
+            pids: self
+                .process_id
+                .iter()
+                .chain(&self.process_ids)
+                .copied()
+                .collect(),
+            names: self.process_names.clone(),
+            include_children: self.include_children,
+        }
+    }
 }
 
 fn default_true() -> bool {
@@ -53,28 +173,6 @@ pub struct HotkeyBinding {
     pub enabled: bool,
 }
 
-/// Tap feature settings (frontend-only, stored in config for persistence)
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct TapSettings {
-    /// Whether tap is enabled (should always default to false)
-    #[serde(default)]
-    pub enabled: bool,
-    /// How often to tap in milliseconds
-    #[serde(default = "default_tap_interval")]
-    pub interval_ms: u64,
-    /// How long to keep modules off in milliseconds
-    #[serde(default = "default_tap_duration")]
-    pub duration_ms: u64,
-}
-
-fn default_tap_interval() -> u64 {
-    3000
-}
-
-fn default_tap_duration() -> u64 {
-    600
-}
-
 /// Configuration file structure for storing application settings
 ///
 /// Contains both the packet manipulation settings and the active filter string.
@@ -91,9 +189,9 @@ struct ConfigFile {
     /// Hotkey bindings
     #[serde(default)]
     hotkeys: Option<Vec<HotkeyBinding>>,
-    /// Tap feature settings
-    #[serde(default)]
-    tap: Option<TapSettings>,
+    /// Tap options of configs saved before they became part of `settings`
+    #[serde(default, skip_serializing)]
+    tap: Option<TapOptions>,
     /// Classic mode settings
     #[serde(default)]
     classic: Option<crate::settings::classic::ClassicSettings>,
@@ -121,12 +219,12 @@ pub async fn save_config(
     name: String,
     filter_target: Option<FilterTarget>,
     hotkeys: Option<Vec<HotkeyBinding>>,
-    tap: Option<TapSettings>,
+    tap: Option<TapOptions>,
     classic: Option<crate::settings::classic::ClassicSettings>,
     mode: Option<String>,
 ) -> Result<(), String> {
 
-    let settings = state
+    let mut settings = state
         .settings
         .lock()
         .map_err(|e| format!("Failed to lock settings mutex: {}", e))?
@@ -152,6 +250,10 @@ pub async fn save_config(
         }
     };
 
+    if tap.is_some() {
+        settings.tap = tap;
+    }
+
     let config_path = get_config_path(&name)?;
 
     let config = ConfigFile {
@@ -159,7 +261,7 @@ pub async fn save_config(
         filter,
         filter_target,
         hotkeys,
-        tap,
+        tap: None,
         classic: classic_settings,
         mode,
     };
@@ -179,6 +281,21 @@ pub async fn save_config(
 
 }
 
+/// Compiles a filter target into a filter string
+///
+/// # Arguments
+///
+/// * `target` - The processes, devices or custom filter to capture, with restrictions
+///
+/// # Returns
+///
+/// * `Ok(String)` - The filter capturing the target
+/// * `Err(String)` - If the target cannot be compiled
+#[tauri::command]
+pub fn compile_filter_target(target: FilterTarget) -> Result<String, String> {
+    target.compile().map(|filter| filter.to_string())
+}
+
 /// Response structure for `load_config` that includes filter target
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoadConfigResponse {
@@ -186,7 +303,7 @@ pub struct LoadConfigResponse {
     pub filter: Option<String>,
     pub filter_target: Option<FilterTarget>,
     pub hotkeys: Option<Vec<HotkeyBinding>>,
-    pub tap: Option<TapSettings>,
+    pub tap: Option<TapOptions>,
     pub classic: Option<crate::settings::classic::ClassicSettings>,
     pub mode: Option<String>,
 }
@@ -209,13 +326,7 @@ pub async fn load_config(
     name: String,
 ) -> Result<LoadConfigResponse, String> {
 
-    let config_path = get_config_path(&name)?;
-
-    let content = fs::read_to_string(&config_path)
-        .map_err(|e| format!("Failed to read config file: {}", e))?;
-
-    let config: ConfigFile =
-        toml::from_str(&content).map_err(|e| format!("Failed to deserialize config: {}", e))?;
+    let config = read_config(&name)?;
 
     *state
         .settings
@@ -237,18 +348,57 @@ pub async fn load_config(
 
     info!("Loaded configuration from {}", name);
 
-    Ok(LoadConfigResponse {
-        settings: config.settings,
-        filter: config.filter,
-        filter_target: config.filter_target,
-        hotkeys: config.hotkeys,
-        tap: config.tap,
-        classic: config.classic,
-        mode: config.mode,
-    })
+    Ok(config)
 
 }
 
+/// Reads a named configuration file without touching application state
+///
+/// # Arguments
+///
+/// * `name` - The name of the configuration file to read
+///
+/// # Returns
+///
+/// * `Ok(LoadConfigResponse)` - The stored settings, filter and mode
+/// * `Err(String)` - If the file is missing or cannot be parsed
+pub fn read_config(name: &str) -> Result<LoadConfigResponse, String> {
+
+    let config_path = get_config_path(name)?;
+
+    let content = fs::read_to_string(&config_path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+
+    let config: ConfigFile =
+        toml::from_str(&content).map_err(|e| format!("Failed to deserialize config: {}", e))?;
+
+    Ok(config.into())
+
+}
+
+impl From<ConfigFile> for LoadConfigResponse {
+    /// Moves tap options stored next to the settings by older versions into them.
+    fn from(config: ConfigFile) -> Self {
+
+        let mut settings = config.settings;
+
+        if settings.tap.is_none() {
+            settings.tap = config.tap;
+        }
+
+        Self {
+            tap: settings.tap.clone(),
+            settings,
+            filter: config.filter,
+            filter_target: config.filter_target,
+            hotkeys: config.hotkeys,
+            classic: config.classic,
+            mode: config.mode,
+        }
+
+    }
+}
+
 /// Lists all available configuration files
 ///
 /// # Returns
@@ -390,6 +540,7 @@ mod tests {
             custom_filter: None,
             include_inbound: true,
             include_outbound: false,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&target).unwrap();
@@ -440,6 +591,65 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_filter_target_compiles_devices() {
+
+        let target: FilterTarget = serde_json::from_str(
+            r#"{
+                "mode": "device",
+                "device_ip": "192.168.1.20",
+                "device_ips": ["fe80::1"],
+                "include_inbound": false,
+                "protocols": ["udp"],
+                "remote_ports": [{"start": 3074, "end": 3075}]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            target.compile().unwrap().to_string(),
+            "outbound and udp and remotePort >= 3074 and remotePort <= 3075 \
+             and (ip.DstAddr == 192.168.1.20 or ipv6.DstAddr == fe80::1)"
+        );
+
+    }
+
+    #[test]
+    fn test_filter_target_compile_errors() {
+
+        let mut target = FilterTarget {
+            mode: FilterTargetMode::Device,
+            device_ip: Some("not-an-ip".to_string()),
+            include_inbound: true,
+            include_outbound: true,
+            ..Default::default()
+        };
+
+        assert!(target.compile().is_err());
+
+        target.device_ip = None;
+        assert_eq!(target.compile().unwrap_err(), "No device selected");
+
+        target.mode = FilterTargetMode::Custom;
+        target.custom_filter = Some("tcp.DstPort == 443".to_string());
+        assert_eq!(target.compile().unwrap().to_string(), "tcp.DstPort == 443");
+
+        target.include_inbound = false;
+        assert_eq!(
+            target.compile().unwrap().to_string(),
+            "outbound and tcp.DstPort == 443"
+        );
+
+        target.custom_filter = Some("tcp.DstPort ==".to_string());
+        assert!(target.compile().unwrap_err().starts_with("Invalid filter"));
+
+        // A process without connections must not widen to the whole direction
+        target.mode = FilterTargetMode::Process;
+        target.process_id = Some(u32::MAX);
+        assert_eq!(target.compile().unwrap().to_string(), "false");
+
+    }
+
     #[test]
     fn test_hotkey_binding_default() {
 
@@ -474,7 +684,7 @@ mod tests {
 
         // When deserializing with missing fields, serde uses the default functions
         let json = r#"{}"#;
-        let tap: TapSettings = serde_json::from_str(json).unwrap();
+        let tap: TapOptions = serde_json::from_str(json).unwrap();
 
         assert!(!tap.enabled);
         assert_eq!(tap.interval_ms, 3000);
@@ -485,14 +695,15 @@ mod tests {
     #[test]
     fn test_tap_settings_serialization() {
 
-        let tap = TapSettings {
+        let tap = TapOptions {
             enabled: true,
             interval_ms: 5000,
             duration_ms: 1000,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&tap).unwrap();
-        let parsed: TapSettings = serde_json::from_str(&json).unwrap();
+        let parsed: TapOptions = serde_json::from_str(&json).unwrap();
 
         assert!(parsed.enabled);
         assert_eq!(parsed.interval_ms, 5000);
@@ -500,6 +711,31 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_legacy_tap_moves_into_settings() {
+
+        let toml = r#"
+            filter = "outbound"
+
+            [settings]
+            burst_release_delay_us = 500
+
+            [tap]
+            enabled = true
+            interval_ms = 2000
+        "#;
+
+        let config: ConfigFile = toml::from_str(toml).unwrap();
+        let response = LoadConfigResponse::from(config);
+        let tap = response.settings.tap.unwrap();
+
+        assert!(tap.enabled);
+        assert_eq!(tap.interval_ms, 2000);
+        assert_eq!(tap.duration_ms, 600);
+        assert!(response.tap.is_some());
+
+    }
+
     #[test]
     fn test_load_config_response_serialization() {
 
@@ -512,7 +748,9 @@ mod tests {
                 shortcut: Some("F9".to_string()),
                 enabled: true,
             }]),
-            tap: Some(TapSettings::default()),
+            tap: Some(TapOptions::default()),
+            classic: None,
+            mode: None,
         };
 
         let json = serde_json::to_string(&response).unwrap();
@@ -0,0 +1,108 @@
+//! Control server commands.
+//!
+//! Starts and stops the localhost control API that scripts use to drive
+//! the same session as the GUI.
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::commands::state::PacketProcessingState;
+use crate::control::{ControlServer, DEFAULT_PORT};
+
+/// Global state for the control server
+#[derive(Default)]
+pub struct ControlServerState {
+    pub server: Mutex<Option<ControlServer>>,
+}
+
+/// Status response for the control server
+#[derive(Debug, Serialize)]
+pub struct ControlServerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub token: Option<String>,
+}
+
+impl ControlServerStatus {
+    fn of(server: Option<&ControlServer>) -> Self {
+        Self {
+            running: server.is_some_and(ControlServer::is_running),
+            port: server.map(ControlServer::port),
+            token: server.map(|server| server.token().to_string()),
+        }
+    }
+}
+
+/// Starts the control server, replacing one that is already running.
+///
+/// # Arguments
+///
+/// * `state` - The session the server controls
+/// * `control` - The control server state
+/// * `port` - Port on 127.0.0.1 to listen on, `DEFAULT_PORT` if omitted
+/// * `token` - Token clients must send, generated if omitted
+///
+/// # Returns
+///
+/// * `Ok(ControlServerStatus)` - The port and token of the running server
+/// * `Err(String)` - If the port cannot be bound
+#[tauri::command]
+pub fn start_control_server(
+    state: State<'_, PacketProcessingState>,
+    control: State<'_, ControlServerState>,
+    port: Option<u16>,
+    token: Option<String>,
+) -> Result<ControlServerStatus, String> {
+
+    let mut server = control
+        .server
+        .lock()
+        .map_err(|e| format!("Failed to lock control server mutex: {}", e))?;
+
+    if let Some(mut existing) = server.take() {
+        existing.stop();
+    }
+
+    let started = ControlServer::start((*state).clone(), port.unwrap_or(DEFAULT_PORT), token)
+        .map_err(|e| format!("Failed to start control server: {}", e))?;
+
+    *server = Some(started);
+
+    Ok(ControlServerStatus::of(server.as_ref()))
+
+}
+
+/// Stops the control server.
+#[tauri::command]
+pub fn stop_control_server(control: State<'_, ControlServerState>) -> Result<(), String> {
+
+    let server = control
+        .server
+        .lock()
+        .map_err(|e| format!("Failed to lock control server mutex: {}", e))?
+        .take();
+
+    let Some(mut server) = server else {
+        return Err("Control server not running".to_string());
+    };
+
+    server.stop();
+    Ok(())
+
+}
+
+/// Gets the port and token of the control server, if it is running.
+#[tauri::command]
+pub fn get_control_server_status(
+    control: State<'_, ControlServerState>,
+) -> Result<ControlServerStatus, String> {
+
+    let server = control
+        .server
+        .lock()
+        .map_err(|e| format!("Failed to lock control server mutex: {}", e))?;
+
+    Ok(ControlServerStatus::of(server.as_ref()))
+
+}
@@ -1,13 +1,342 @@
-use serde::{Deserialize, Serialize};
+//! Filter history and library.
+//!
+//! Every filter applied through `update_filter` is recorded with how often and
+//! when it was last used. Entries given a name, description, tags or a pin are
+//! saved in the library and kept; the others form the history of recent
+//! filters, capped at `MAX_HISTORY`. Saved entries can be exported as JSON and
+//! imported elsewhere, so a team can share its filters.
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::commands::system::validate_filter;
 
 const MAX_HISTORY: usize = 20;
 
+/// Version written to exported libraries.
+const EXPORT_VERSION: u32 = 1;
+
+/// A filter in the history or library.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FilterEntry {
+    /// Filter expression
+    pub filter: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Listed before other saved filters
+    #[serde(default)]
+    pub pinned: bool,
+    /// How many times the filter was applied
+    #[serde(default)]
+    pub use_count: u64,
+    /// When the filter was last applied, in milliseconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<u64>,
+}
+
+/// Details of a saved filter.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FilterDetails {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// A filter as shared with others, without personal usage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SharedFilter {
+    filter: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// Exported library.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FilterExport {
+    version: u32,
+    filters: Vec<SharedFilter>,
+}
+
+/// Entries, most recently used first.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct FilterHistory {
-    entries: Vec<String>,
+    #[serde(default, deserialize_with = "deserialize_entries")]
+    entries: Vec<FilterEntry>,
+}
+
+impl FilterEntry {
+    fn new(filter: &str) -> Self {
+        Self {
+            filter: filter.to_string(),
+            name: None,
+            description: None,
+            tags: Vec::new(),
+            pinned: false,
+            use_count: 0,
+            last_used: None,
+        }
+    }
+
+    /// Returns whether the entry is in the library rather than only the history.
+    pub fn is_saved(&self) -> bool {
+        self.pinned || self.name.is_some() || self.description.is_some() || !self.tags.is_empty()
+    }
+
+    fn set_details(&mut self, details: FilterDetails) {
+
+        self.name = non_empty(details.name);
+        self.description = non_empty(details.description);
+        self.tags = normalize_tags(details.tags);
+        self.pinned = details.pinned;
+
+    }
+}
+
+impl FilterHistory {
+    fn position(&self, filter: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.filter == filter)
+    }
+
+    /// Records that `filter` was applied at `now` and moves it to the front.
+    fn record_use(&mut self, filter: &str, now: u64) {
+
+        let mut entry = self.position(filter).map_or_else(
+            || FilterEntry::new(filter),
+            |index| self.entries.remove(index),
+        );
+
+        entry.use_count += 1;
+        entry.last_used = Some(now);
+        self.entries.insert(0, entry);
+        self.prune();
+
+    }
+
+    /// Drops the least recently used history entries beyond `MAX_HISTORY`.
+    fn prune(&mut self) {
+
+        let mut kept = 0;
+
+        self.entries.retain(|entry| {
+            if entry.is_saved() {
+                return true;
+            }
+            kept += 1;
+            kept <= MAX_HISTORY
+        });
+
+    }
+
+    /// Returns the filters applied since the history was last cleared.
+    fn recent(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.use_count > 0)
+            .take(MAX_HISTORY)
+            .map(|entry| entry.filter.clone())
+            .collect()
+    }
+
+    /// Returns the saved entries, pinned first and then most recently used.
+    fn library(&self) -> Vec<FilterEntry> {
+
+        let mut saved: Vec<FilterEntry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.is_saved())
+            .cloned()
+            .collect();
+
+        saved.sort_by_key(|entry| !entry.pinned);
+        saved
+
+    }
+
+    /// Saves `filter` with `details`, replacing the details it had.
+    fn save(&mut self, filter: &str, details: FilterDetails) -> FilterEntry {
+
+        let index = self.position(filter).unwrap_or_else(|| {
+            self.entries.push(FilterEntry::new(filter));
+            self.entries.len() - 1
+        });
+        let entry = &mut self.entries[index];
+
+        entry.set_details(details);
+
+        let entry = entry.clone();
+
+        self.prune();
+        entry
+
+    }
+
+    /// Removes `filter` from the history and library.
+    fn remove(&mut self, filter: &str) -> bool {
+
+        let len = self.entries.len();
+
+        self.entries.retain(|entry| entry.filter != filter);
+        self.entries.len() != len
+
+    }
+
+    /// Forgets history entries and the usage of saved ones.
+    fn clear_history(&mut self) {
+
+        self.entries.retain(FilterEntry::is_saved);
+
+        for entry in &mut self.entries {
+            entry.use_count = 0;
+            entry.last_used = None;
+        }
+
+    }
+
+    /// Returns the saved entries as shared, keeping only those tagged `tag` if given.
+    fn export(&self, tag: Option<&str>) -> FilterExport {
+
+        let filters = self
+            .library()
+            .into_iter()
+            .filter(|entry| tag.map_or(true, |tag| entry.tags.iter().any(|t| t == tag)))
+            .map(|entry| SharedFilter {
+                filter: entry.filter,
+                name: entry.name,
+                description: entry.description,
+                tags: entry.tags,
+            })
+            .collect();
+
+        FilterExport {
+            version: EXPORT_VERSION,
+            filters,
+        }
+
+    }
+
+    /// Adds shared filters to the library.
+    ///
+    /// A filter already in the library keeps its name and description unless
+    /// it had none, and gains the shared tags. A new filter without any details
+    /// is pinned, so that it stays in the library.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - How many filters were added or changed
+    fn import(&mut self, shared: Vec<SharedFilter>) -> usize {
+
+        let mut changed = 0;
+
+        for shared in shared {
+            let filter = shared.filter.trim();
+            let Some(index) = self.position(filter) else {
+                let pinned =
+                    shared.name.is_none() && shared.description.is_none() && shared.tags.is_empty();
+
+                self.save(
+                    filter,
+                    FilterDetails {
+                        name: shared.name,
+                        description: shared.description,
+                        tags: shared.tags,
+                        pinned,
+                    },
+                );
+                changed += 1;
+                continue;
+            };
+            let entry = &self.entries[index];
+            let mut tags = entry.tags.clone();
+
+            tags.extend(shared.tags);
+
+            let details = FilterDetails {
+                name: entry.name.clone().or(shared.name),
+                description: entry.description.clone().or(shared.description),
+                tags,
+                pinned: entry.pinned,
+            };
+            let before = entry.clone();
+
+            if self.save(filter, details) != before {
+                changed += 1;
+            }
+        }
+        changed
+
+    }
+}
+
+/// Reads entries, accepting the plain filter strings older versions stored.
+fn deserialize_entries<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<FilterEntry>, D::Error> {
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Filter(String),
+        Entry(FilterEntry),
+    }
+
+    let stored = Vec::<Stored>::deserialize(deserializer)?;
+
+    Ok(stored
+        .into_iter()
+        .map(|stored| match stored {
+            Stored::Filter(filter) => FilterEntry {
+                // Used at least once, when it was recorded
+                use_count: 1,
+                ..FilterEntry::new(&filter)
+            },
+            Stored::Entry(entry) => entry,
+        })
+        .collect())
+
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Trims tags and drops empty and repeated ones.
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+
+    let mut normalized: Vec<String> = Vec::new();
+
+    for tag in tags {
+        let tag = tag.trim();
+
+        if !tag.is_empty() && !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+        })
 }
 
 fn get_history_dir() -> Result<PathBuf, String> {
@@ -74,36 +403,246 @@ fn save_history(history: &FilterHistory) -> Result<(), String> {
 
 pub fn add_to_history(filter: &str) -> Result<(), String> {
 
-    if filter.trim().is_empty() {
+    let filter = filter.trim();
+
+    if filter.is_empty() {
         return Ok(());
     }
 
     let mut history = load_history()?;
 
-    if history
-        .entries
-        .first()
-        .map(|f| f == filter)
-        .unwrap_or(false)
-    {
-        return Ok(());
-    }
-    history.entries.retain(|f| f != filter);
-    history.entries.insert(0, filter.to_string());
-
-    if history.entries.len() > MAX_HISTORY {
-        history.entries.truncate(MAX_HISTORY);
-    }
+    history.record_use(filter, now_millis());
     save_history(&history)
 
 }
 
 #[tauri::command]
 pub async fn get_filter_history() -> Result<Vec<String>, String> {
-    Ok(load_history()?.entries)
+    Ok(load_history()?.recent())
 }
 
+/// Clears the recent filters; saved filters are kept, without their usage.
 #[tauri::command]
 pub async fn clear_filter_history() -> Result<(), String> {
-    save_history(&FilterHistory::default())
+
+    let mut history = load_history()?;
+
+    history.clear_history();
+    save_history(&history)
+
+}
+
+/// Gets the saved filters, pinned first and then most recently used.
+#[tauri::command]
+pub async fn get_filter_library() -> Result<Vec<FilterEntry>, String> {
+    Ok(load_history()?.library())
+}
+
+/// Saves a filter in the library, or replaces the details of a saved one.
+///
+/// A filter saved without any details returns to the history.
+///
+/// # Arguments
+///
+/// * `filter` - The filter expression
+/// * `details` - Its name, description, tags and pin
+///
+/// # Returns
+///
+/// * `Ok(FilterEntry)` - The saved entry
+/// * `Err(String)` - If the filter is invalid or the library could not be written
+#[tauri::command]
+pub async fn save_filter(filter: String, details: FilterDetails) -> Result<FilterEntry, String> {
+
+    validate_filter(filter.clone())?;
+
+    let mut history = load_history()?;
+    let entry = history.save(filter.trim(), details);
+
+    save_history(&history)?;
+    Ok(entry)
+
+}
+
+/// Removes a filter from the library and history.
+#[tauri::command]
+pub async fn remove_filter(filter: String) -> Result<(), String> {
+
+    let mut history = load_history()?;
+
+    if !history.remove(filter.trim()) {
+        return Err(format!("Filter '{}' is not saved", filter));
+    }
+    save_history(&history)
+
+}
+
+/// Exports the saved filters as JSON to share with others.
+///
+/// # Arguments
+///
+/// * `tag` - Only export filters with this tag, such as the name of a game
+#[tauri::command]
+pub async fn export_filter_library(tag: Option<String>) -> Result<String, String> {
+
+    let history = load_history()?;
+
+    serde_json::to_string_pretty(&history.export(tag.as_deref()))
+        .map_err(|e| format!("Failed serializing filter library: {}", e))
+
+}
+
+/// Imports filters exported with `export_filter_library`.
+///
+/// Nothing is imported if any filter is invalid.
+///
+/// # Returns
+///
+/// * `Ok(usize)` - How many filters were added or changed
+/// * `Err(String)` - If the JSON or a filter in it is invalid
+#[tauri::command]
+pub async fn import_filter_library(data: String) -> Result<usize, String> {
+
+    let export: FilterExport =
+        serde_json::from_str(&data).map_err(|e| format!("Invalid filter library: {}", e))?;
+
+    if export.version > EXPORT_VERSION {
+        return Err(format!(
+            "Filter library version {} is newer than this app supports",
+            export.version
+        ));
+    }
+
+    for shared in &export.filters {
+        validate_filter(shared.filter.clone()).map_err(|e| {
+            format!(
+                "{}: {}",
+                shared.name.as_deref().unwrap_or(&shared.filter),
+                e
+            )
+        })?;
+    }
+
+    let mut history = load_history()?;
+    let changed = history.import(export.filters);
+
+    save_history(&history)?;
+    Ok(changed)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(name: &str, tags: &[&str]) -> FilterDetails {
+        FilterDetails {
+            name: Some(name.to_string()),
+            tags: tags.iter().copied().map(String::from).collect(),
+            ..FilterDetails::default()
+        }
+    }
+
+    #[test]
+    fn test_history_keeps_saved_filters() {
+
+        let mut history = FilterHistory::default();
+
+        history.record_use("udp", 1);
+        history.save("udp", details("Game", &["cs2"]));
+
+        for i in 0..30 {
+            history.record_use(&format!("tcp.DstPort == {}", i), 2 + i);
+        }
+        history.record_use("udp", 100);
+
+        assert_eq!(history.entries.len(), MAX_HISTORY + 1);
+        assert_eq!(history.recent().len(), MAX_HISTORY);
+        assert_eq!(history.recent()[0], "udp");
+
+        let library = history.library();
+
+        assert_eq!(library.len(), 1);
+        assert_eq!(library[0].use_count, 2);
+        assert_eq!(library[0].last_used, Some(100));
+
+        history.clear_history();
+
+        assert!(history.recent().is_empty());
+        assert_eq!(history.library()[0].use_count, 0);
+
+    }
+
+    #[test]
+    fn test_library_order_and_details() {
+
+        let mut history = FilterHistory::default();
+
+        history.save("tcp", details(" Web ", &["a", " a", "", "b"]));
+        history.save(
+            "udp",
+            FilterDetails {
+                pinned: true,
+                ..FilterDetails::default()
+            },
+        );
+
+        let library = history.library();
+
+        assert_eq!(library[0].filter, "udp");
+        assert_eq!(library[1].name.as_deref(), Some("Web"));
+        assert_eq!(library[1].tags, ["a", "b"]);
+
+        // No details left, so it is only history and pruned as such
+        history.save("udp", FilterDetails::default());
+        assert_eq!(history.library().len(), 1);
+        assert!(history.remove("tcp"));
+        assert!(!history.remove("tcp"));
+
+    }
+
+    #[test]
+    fn test_export_and_import() {
+
+        let mut source = FilterHistory::default();
+
+        source.record_use("udp", 1);
+        source.save("udp", details("Game", &["cs2"]));
+        source.save("tcp", details("Web", &["browser"]));
+
+        let export = source.export(Some("cs2"));
+        let json = serde_json::to_string(&export).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"version":1,"filters":[{"filter":"udp","name":"Game","tags":["cs2"]}]}"#
+        );
+
+        let mut target = FilterHistory::default();
+
+        target.save("udp", details("Mine", &["team"]));
+
+        let export: FilterExport = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(target.import(export.filters.clone()), 1);
+        assert_eq!(target.import(export.filters), 0);
+
+        let entry = &target.library()[0];
+
+        assert_eq!(entry.name.as_deref(), Some("Mine"));
+        assert_eq!(entry.tags, ["team", "cs2"]);
+        assert_eq!(entry.use_count, 0);
+
+    }
+
+    #[test]
+    fn test_reads_plain_history() {
+
+        let history: FilterHistory =
+            serde_json::from_str(r#"{"entries":["udp",{"filter":"tcp","name":"Web"}]}"#).unwrap();
+
+        assert_eq!(history.recent(), ["udp"]);
+        assert_eq!(history.library()[0].name.as_deref(), Some("Web"));
+
+    }
 }
@@ -0,0 +1,45 @@
+//! Per-flow statistics commands.
+//!
+//! The processing loop accounts every flow it sees; these commands read the
+//! busiest ones for a top-talkers view.
+use std::time::Instant;
+
+use tauri::State;
+
+use crate::commands::state::PacketProcessingState;
+use crate::commands::types::FlowDto;
+
+/// Number of flows returned when no limit is given
+const DEFAULT_FLOW_LIMIT: usize = 10;
+
+/// Gets the flows with the most traffic this session.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `limit` - Most flows to return, 10 if not given
+///
+/// # Returns
+///
+/// * `Ok(Vec<FlowDto>)` - The flows by bytes captured in both directions, most first
+/// * `Err(String)` - If the statistics cannot be read
+#[tauri::command]
+pub fn get_top_flows(
+    state: State<'_, PacketProcessingState>,
+    limit: Option<usize>,
+) -> Result<Vec<FlowDto>, String> {
+
+    let stats = state
+        .statistics
+        .read()
+        .map_err(|e| format!("Failed to read statistics: {}", e))?;
+    let now = Instant::now();
+
+    Ok(stats
+        .flow_stats
+        .top(limit.unwrap_or(DEFAULT_FLOW_LIMIT))
+        .into_iter()
+        .map(|flow| FlowDto::new(flow, now))
+        .collect())
+
+}
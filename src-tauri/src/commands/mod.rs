@@ -5,17 +5,28 @@
 pub mod classic;
 pub mod classic_state;
 pub mod config;
+pub mod control;
 pub mod filter_history;
+pub mod flows;
+pub mod profiles;
+pub mod recording;
+pub mod replay;
+pub mod rules;
+pub mod scenario;
 pub mod start;
 pub mod state;
+pub mod stats_stream;
 pub mod status;
 pub mod stop;
 pub mod system;
+pub mod tap;
 pub mod tc_bandwidth;
 pub mod types;
 pub mod update;
 
 pub use classic_state::ClassicProcessingState;
+pub use control::ControlServerState;
+pub use scenario::ScenarioState;
 pub use state::PacketProcessingState;
 pub use tc_bandwidth::TcLimiterState;
 
@@ -24,9 +35,27 @@ pub use classic::{
     __cmd__update_classic_settings, get_classic_status, start_classic_processing,
     stop_classic_processing, update_classic_settings,
 };
+pub use control::{
+    __cmd__get_control_server_status, __cmd__start_control_server, __cmd__stop_control_server,
+    get_control_server_status, start_control_server, stop_control_server,
+};
 pub use filter_history::{
-    __cmd__clear_filter_history, __cmd__get_filter_history, clear_filter_history,
-    get_filter_history,
+    __cmd__clear_filter_history, __cmd__export_filter_library, __cmd__get_filter_history,
+    __cmd__get_filter_library, __cmd__import_filter_library, __cmd__remove_filter,
+    __cmd__save_filter, clear_filter_history, export_filter_library, get_filter_history,
+    get_filter_library, import_filter_library, remove_filter, save_filter,
+};
+pub use flows::{__cmd__get_top_flows, get_top_flows};
+pub use profiles::{__cmd__apply_profile, __cmd__list_profiles, apply_profile, list_profiles};
+pub use recording::{
+    __cmd__is_recording, __cmd__start_recording, __cmd__stop_recording, is_recording,
+    start_recording, stop_recording,
+};
+pub use replay::{__cmd__replay_capture_file, replay_capture_file};
+pub use rules::{__cmd__get_rules, __cmd__set_rules, get_rules, set_rules};
+pub use scenario::{
+    __cmd__get_scenario_status, __cmd__start_scenario, __cmd__stop_scenario, get_scenario_status,
+    start_scenario, stop_scenario,
 };
 pub use start::{__cmd__start_processing, start_processing};
 pub use status::{
@@ -36,11 +65,13 @@ pub use status::{
 pub use stop::{__cmd__stop_processing, stop_processing};
 pub use system::{
     __cmd__build_device_filter, __cmd__build_process_filter, __cmd__get_flow_filter,
-    __cmd__is_flow_tracking, __cmd__list_processes, __cmd__scan_network_devices,
-    __cmd__start_flow_tracking, __cmd__stop_flow_tracking, __cmd__validate_filter,
-    build_device_filter, build_process_filter, get_flow_filter, is_flow_tracking, list_processes,
-    scan_network_devices, start_flow_tracking, stop_flow_tracking, validate_filter,
+    __cmd__get_tracked_processes, __cmd__is_flow_tracking, __cmd__list_processes,
+    __cmd__scan_network_devices, __cmd__start_flow_tracking, __cmd__stop_flow_tracking,
+    __cmd__validate_filter, build_device_filter, build_process_filter, get_flow_filter,
+    get_tracked_processes, is_flow_tracking, list_processes, scan_network_devices,
+    start_flow_tracking, stop_flow_tracking, validate_filter,
 };
+pub use tap::{__cmd__get_tap, __cmd__set_tap, get_tap, set_tap};
 pub use tc_bandwidth::{
     __cmd__get_tc_bandwidth_status, __cmd__start_tc_bandwidth, __cmd__stop_tc_bandwidth,
     get_tc_bandwidth_status, start_tc_bandwidth, stop_tc_bandwidth,
@@ -50,5 +81,9 @@ pub use update::{__cmd__update_settings, update_settings};
 use tauri::App;
 
 pub fn register_commands(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
-    state::register_state(app)
+
+    state::register_state(app)?;
+    stats_stream::forward_to_frontend(app);
+    Ok(())
+
 }
@@ -0,0 +1,70 @@
+//! Network profile commands.
+//!
+//! Lists the built-in link profiles and applies them to the session, either
+//! on top of the current settings or on top of a saved configuration.
+use log::info;
+use tauri::State;
+
+use crate::commands::config::read_config;
+use crate::commands::state::PacketProcessingState;
+use crate::settings::{NetworkProfile, Settings, builtin_profiles, find_profile};
+
+/// Lists the built-in network profiles.
+#[tauri::command]
+pub fn list_profiles() -> Vec<NetworkProfile> {
+    builtin_profiles()
+}
+
+/// Applies a network profile to the session settings.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `id` - Identifier of the built-in profile
+/// * `config` - Saved configuration to merge the profile into; the current
+///   settings are used if omitted
+///
+/// # Returns
+///
+/// * `Ok(Settings)` - The settings now in effect
+/// * `Err(String)` - If the profile or configuration does not exist
+#[tauri::command]
+pub fn apply_profile(
+    state: State<'_, PacketProcessingState>,
+    id: String,
+    config: Option<String>,
+) -> Result<Settings, String> {
+    apply_profile_to(&state, &id, config.as_deref())
+}
+
+/// Merges the profile `id` into the settings of `state` or of a saved config.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `id` - Identifier of the built-in profile
+/// * `config` - Saved configuration whose settings the profile is merged into
+pub fn apply_profile_to(
+    state: &PacketProcessingState,
+    id: &str,
+    config: Option<&str>,
+) -> Result<Settings, String> {
+
+    let profile = find_profile(id).ok_or_else(|| format!("Unknown network profile: {}", id))?;
+    let base = config.map(read_config).transpose()?.map(|config| config.settings);
+
+    let mut settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
+
+    if let Some(base) = base {
+        *settings = base;
+    }
+
+    profile.apply(&mut settings);
+    info!("Applied network profile {}", profile.name);
+
+    Ok(settings.clone())
+
+}
@@ -0,0 +1,74 @@
+//! Session recording commands.
+//!
+//! Starts and stops the pcapng recording of original and impaired traffic.
+use std::path::PathBuf;
+
+use tauri::State;
+
+use crate::commands::state::PacketProcessingState;
+use crate::network::pcap::{PacketRecorder, RecordingSummary};
+
+/// Starts recording packets to two pcapng files.
+///
+/// Packets are recorded once as captured and once as sent, with comments
+/// naming the modules that acted on each packet.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `original_path` - File for packets as captured
+/// * `impaired_path` - File for packets as sent
+///
+/// # Returns
+///
+/// * `Ok(())` - If the recording was started
+/// * `Err(String)` - If a recording is already active or the files cannot be created
+#[tauri::command]
+pub fn start_recording(
+    state: State<'_, PacketProcessingState>,
+    original_path: String,
+    impaired_path: String,
+) -> Result<(), String> {
+
+    // Checked first so the files of the active recording are not truncated
+    if state.recorder.is_active() {
+        return Err("Recording already running".to_string());
+    }
+
+    let new_recorder =
+        PacketRecorder::create(&PathBuf::from(original_path), &PathBuf::from(impaired_path))
+            .map_err(|e| format!("Failed to start recording: {}", e))?;
+
+    state.recorder.start(new_recorder)
+
+}
+
+/// Stops the active recording and flushes both files.
+///
+/// # Returns
+///
+/// * `Ok(RecordingSummary)` - Packet counts and file paths of the recording
+/// * `Err(String)` - If no recording is active or the files cannot be flushed
+#[tauri::command]
+pub fn stop_recording(
+    state: State<'_, PacketProcessingState>,
+) -> Result<RecordingSummary, String> {
+
+    let recorder = state
+        .recorder
+        .take()?
+        .ok_or_else(|| "Recording not running".to_string())?;
+
+    recorder
+        .finish()
+        .map_err(|e| format!("Failed to finish recording: {}", e))
+
+}
+
+/// Check if a recording is active
+#[tauri::command]
+pub fn is_recording(state: State<'_, PacketProcessingState>) -> Result<bool, String> {
+
+    Ok(state.recorder.is_active())
+
+}
@@ -0,0 +1,59 @@
+//! Capture replay command.
+//!
+//! Runs a recorded pcap/pcapng file through the module chain offline and
+//! writes the impaired result to a pcapng file.
+use std::path::PathBuf;
+
+use log::info;
+use tauri::State;
+
+use crate::commands::state::PacketProcessingState;
+use crate::network::processing::{ReplayConfig, ReplaySummary, replay_capture};
+use crate::settings::Settings;
+
+/// Replays a capture file through the packet manipulation modules.
+///
+/// The replay runs in the background at the capture's original pace and does
+/// not touch live traffic or the live statistics.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `input_path` - Path to the `.pcap` or `.pcapng` file to replay
+/// * `output_path` - Path of the pcapng file to write
+/// * `settings` - Settings to apply; defaults to the current settings
+///
+/// # Returns
+///
+/// * `Ok(ReplaySummary)` - Packet counts for the replay
+/// * `Err(String)` - If the replay failed
+#[tauri::command]
+pub async fn replay_capture_file(
+    state: State<'_, PacketProcessingState>,
+    input_path: String,
+    output_path: String,
+    settings: Option<Settings>,
+) -> Result<ReplaySummary, String> {
+
+    let settings = match settings {
+        Some(settings) => settings,
+        None => state
+            .settings
+            .lock()
+            .map_err(|e| format!("Failed to lock settings mutex: {}", e))?
+            .clone(),
+    };
+
+    info!("Starting capture replay of {}", input_path);
+
+    let input = PathBuf::from(input_path);
+    let output = PathBuf::from(output_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        replay_capture(&input, &output, &settings, &ReplayConfig::new())
+    })
+    .await
+    .map_err(|e| format!("Replay task failed: {}", e))?
+    .map_err(|e| format!("Replay failed: {}", e))
+
+}
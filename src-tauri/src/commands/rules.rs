@@ -0,0 +1,63 @@
+//! Impairment rule commands.
+//!
+//! Reads and replaces the per-flow rule table of the running settings.
+use log::info;
+use tauri::State;
+
+use crate::commands::state::PacketProcessingState;
+use crate::settings::ImpairmentRule;
+
+/// Gets the impairment rules in evaluation order.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+///
+/// # Returns
+///
+/// * `Ok(Vec<ImpairmentRule>)` - The current rules
+/// * `Err(String)` - If the settings cannot be locked
+#[tauri::command]
+pub fn get_rules(state: State<'_, PacketProcessingState>) -> Result<Vec<ImpairmentRule>, String> {
+
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
+
+    Ok(settings.rules.clone())
+
+}
+
+/// Replaces the impairment rules.
+///
+/// Takes effect on the next processing cycle. Rules are matched to the
+/// previous ones by name and match, so they keep the packets their modules
+/// hold. Packets held by the modules of a removed rule are sent on unchanged.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `rules` - New rules in evaluation order; the first enabled match wins
+///
+/// # Returns
+///
+/// * `Ok(())` - If the rules were replaced
+/// * `Err(String)` - If the settings cannot be locked
+#[tauri::command]
+pub fn set_rules(
+    state: State<'_, PacketProcessingState>,
+    rules: Vec<ImpairmentRule>,
+) -> Result<(), String> {
+
+    let mut settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
+
+    info!("Updated impairment rules: {} rule(s)", rules.len());
+    settings.rules = rules;
+
+    Ok(())
+
+}
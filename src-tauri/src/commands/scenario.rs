@@ -0,0 +1,92 @@
+//! Scenario commands.
+//!
+//! Loads scenario files and plays them against the session settings.
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use tauri::State;
+
+use crate::commands::state::PacketProcessingState;
+use crate::control::{ScenarioProgress, ScenarioRunner};
+use crate::network::types::clock::SystemClock;
+use crate::settings::Scenario;
+
+/// Global state for the scenario scheduler
+#[derive(Default)]
+pub struct ScenarioState {
+    pub runner: Mutex<Option<ScenarioRunner>>,
+}
+
+/// Starts playing a scenario file, replacing one that is already playing.
+///
+/// # Arguments
+///
+/// * `state` - The session whose settings the scenario changes
+/// * `scenario_state` - The scenario scheduler state
+/// * `path` - Scenario file in TOML or JSON
+///
+/// # Returns
+///
+/// * `Ok(ScenarioProgress)` - The progress of the new scenario
+/// * `Err(String)` - If the file cannot be loaded
+#[tauri::command]
+pub fn start_scenario(
+    state: State<'_, PacketProcessingState>,
+    scenario_state: State<'_, ScenarioState>,
+    path: String,
+) -> Result<ScenarioProgress, String> {
+
+    let scenario = Scenario::load(&PathBuf::from(path))?;
+
+    let mut runner = scenario_state
+        .runner
+        .lock()
+        .map_err(|e| format!("Failed to lock scenario mutex: {}", e))?;
+
+    // Restore the modules of the previous scenario before taking the new base
+    if let Some(mut previous) = runner.take() {
+        previous.stop();
+    }
+
+    let started = ScenarioRunner::start(scenario, state.settings.clone(), Arc::new(SystemClock))?;
+    let progress = started.progress();
+
+    *runner = Some(started);
+
+    Ok(progress)
+
+}
+
+/// Stops the playing scenario and restores the modules it used.
+#[tauri::command]
+pub fn stop_scenario(scenario_state: State<'_, ScenarioState>) -> Result<(), String> {
+
+    let runner = scenario_state
+        .runner
+        .lock()
+        .map_err(|e| format!("Failed to lock scenario mutex: {}", e))?
+        .take();
+
+    let Some(mut runner) = runner else {
+        return Err("No scenario running".to_string());
+    };
+
+    runner.stop();
+    Ok(())
+
+}
+
+/// Gets the progress of the current scenario, if one was started.
+#[tauri::command]
+pub fn get_scenario_status(
+    scenario_state: State<'_, ScenarioState>,
+) -> Result<Option<ScenarioProgress>, String> {
+
+    let runner = scenario_state
+        .runner
+        .lock()
+        .map_err(|e| format!("Failed to lock scenario mutex: {}", e))?;
+
+    Ok(runner.as_ref().map(ScenarioRunner::progress))
+
+}
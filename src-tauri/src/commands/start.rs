@@ -9,6 +9,8 @@ use log::{error, info};
 use tauri::State;
 
 use crate::commands::state::PacketProcessingState;
+use crate::commands::stats_stream::run_stats_stream;
+use crate::network::backend::{BackendHandle, CaptureBackend};
 use crate::network::core::set_high_precision_timer;
 use crate::network::processing::{receive_packets, start_packet_processing};
 use crate::settings::Settings;
@@ -16,13 +18,15 @@ use crate::settings::Settings;
 /// Starts packet processing with the given settings and filter.
 ///
 /// Creates and launches the packet receiving and processing threads
-/// that will intercept and modify network packets according to the settings.
+/// that will intercept and modify network packets according to the settings,
+/// and the thread that streams their statistics.
 ///
 /// # Arguments
 ///
 /// * `state` - The application state containing shared resources
 /// * `settings` - The packet manipulation settings to apply
 /// * `filter` - Optional `WinDivert` filter expression to select packets
+/// * `backend` - Capture backend to run on; `WinDivert` if omitted
 ///
 /// # Returns
 ///
@@ -33,36 +37,65 @@ pub async fn start_processing(
     state: State<'_, PacketProcessingState>,
     settings: Settings,
     filter: Option<String>,
+    backend: Option<CaptureBackend>,
 ) -> Result<(), String> {
+    start_session(&state, settings, filter, &backend.unwrap_or_default())
+}
 
-    let running = state.running.load(Ordering::SeqCst);
+/// Starts packet processing on `state`.
+///
+/// Shared by the Tauri command and the control server so both drive the
+/// same session.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `settings` - The packet manipulation settings to apply
+/// * `filter` - Optional `WinDivert` filter expression to select packets
+/// * `backend` - Capture backend to run on
+pub fn start_session(
+    state: &PacketProcessingState,
+    settings: Settings,
+    filter: Option<String>,
+    backend: &CaptureBackend,
+) -> Result<(), String> {
 
-    if running {
+    // Claim the session atomically so two concurrent starts cannot both run
+    if state
+        .running
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
         return Err("Packet processing already running".to_string());
     }
 
-    *state
-        .settings
-        .lock()
-        .map_err(|e| format!("Failed to lock settings mutex: {}", e))? = settings;
-
-    *state
-        .filter
-        .lock()
-        .map_err(|e| format!("Failed to lock filter mutex: {}", e))? = filter;
+    let backend = match prepare_session(state, settings, filter, backend) {
+        Ok(backend) => backend,
+        Err(e) => {
+            state.running.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
 
     let (packet_sender, packet_receiver) = mpsc::channel();
 
-    state.running.store(true, Ordering::SeqCst);
-
     set_high_precision_timer();
 
     let running_recv = state.running.clone();
     let settings_recv = state.settings.clone();
     let filter_recv = state.filter.clone();
+    let recorder_recv = state.recorder.clone();
+    let backend_recv = backend.clone();
 
     thread::spawn(move || {
-        if let Err(e) = receive_packets(packet_sender, running_recv, settings_recv, filter_recv) {
+        if let Err(e) = receive_packets(
+            backend_recv,
+            packet_sender,
+            running_recv,
+            settings_recv,
+            filter_recv,
+            recorder_recv,
+        ) {
             error!("Packet receiving error: {}", e);
         }
     });
@@ -70,19 +103,92 @@ pub async fn start_processing(
     let running_proc = state.running.clone();
     let settings_proc = state.settings.clone();
     let statistics = state.statistics.clone();
+    let recorder_proc = state.recorder.clone();
 
     thread::spawn(move || {
 
-        if let Err(e) =
-            start_packet_processing(settings_proc, packet_receiver, running_proc, statistics)
-        {
+        if let Err(e) = start_packet_processing(
+            backend,
+            settings_proc,
+            packet_receiver,
+            running_proc,
+            statistics,
+            recorder_proc,
+        ) {
             error!("Packet processing error: {}", e);
         }
 
     });
 
+    let running_stats = state.running.clone();
+    let statistics = state.statistics.clone();
+    let stats_hub = state.stats_hub.clone();
+
+    thread::spawn(move || run_stats_stream(&statistics, &running_stats, &stats_hub));
+
     info!("Started packet processing");
 
     Ok(())
 
 }
+
+/// Opens the capture backend and stores the session's settings and filter.
+///
+/// # Returns
+///
+/// * `Ok(BackendHandle)` - The opened backend
+/// * `Err(String)` - If the backend cannot be opened or a mutex is poisoned
+fn prepare_session(
+    state: &PacketProcessingState,
+    settings: Settings,
+    filter: Option<String>,
+    backend: &CaptureBackend,
+) -> Result<BackendHandle, String> {
+
+    let backend = backend.open()?;
+
+    *state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))? = settings;
+
+    *state
+        .filter
+        .lock()
+        .map_err(|e| format!("Failed to lock filter mutex: {}", e))? = filter;
+
+    Ok(backend)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_failed_start_releases_running() {
+
+        let state = PacketProcessingState::default();
+
+        // WinDivert cannot be opened here, so the start fails after claiming the session
+        let result = start_session(&state, Settings::default(), None, &CaptureBackend::WinDivert);
+
+        assert!(result.is_err());
+        assert!(!state.running.load(Ordering::SeqCst));
+
+    }
+
+    #[test]
+    fn test_start_rejected_while_running() {
+
+        let state = PacketProcessingState::default();
+        state.running.store(true, Ordering::SeqCst);
+
+        let result = start_session(&state, Settings::default(), None, &CaptureBackend::default());
+
+        assert_eq!(result, Err("Packet processing already running".to_string()));
+        assert!(state.running.load(Ordering::SeqCst));
+
+    }
+}
@@ -7,14 +7,18 @@ use std::sync::{Arc, Mutex, RwLock};
 
 use tauri::{App, Manager};
 
+use crate::commands::stats_stream::StatsHub;
 use crate::network::core::FlowTracker;
 use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::pcap::SharedRecorder;
 use crate::settings::Settings;
 
 /// Global state for the packet processing system.
 ///
 /// This struct holds all shared state needed for packet interception
 /// and manipulation, including settings, statistics, and control flags.
+/// Every field is reference-counted, so a clone shares the same session.
+#[derive(Clone)]
 pub struct PacketProcessingState {
     /// Flag indicating whether packet processing is currently active
     pub running: Arc<AtomicBool>,
@@ -26,6 +30,10 @@ pub struct PacketProcessingState {
     pub filter: Arc<Mutex<Option<String>>>,
     /// Flow tracker for process-based filtering
     pub flow_tracker: Arc<Mutex<FlowTracker>>,
+    /// Active pcapng recording of the session, if any
+    pub recorder: SharedRecorder,
+    /// Subscribers to the statistics snapshots of the session
+    pub stats_hub: StatsHub,
 }
 
 impl Default for PacketProcessingState {
@@ -37,6 +45,8 @@ impl Default for PacketProcessingState {
             statistics: Arc::new(RwLock::new(PacketProcessingStatistics::default())),
             filter: Arc::new(Mutex::new(None)),
             flow_tracker: Arc::new(Mutex::new(FlowTracker::new())),
+            recorder: SharedRecorder::default(),
+            stats_hub: StatsHub::default(),
         }
 
     }
@@ -56,10 +66,14 @@ impl PacketProcessingState {
 pub fn register_state(app: &mut App) -> Result<(), Box<dyn std::error::Error>> {
 
     use crate::commands::classic_state::ClassicProcessingState;
+    use crate::commands::control::ControlServerState;
+    use crate::commands::scenario::ScenarioState;
     use crate::commands::tc_bandwidth::TcLimiterState;
 
     app.manage(PacketProcessingState::default());
     app.manage(ClassicProcessingState::default());
+    app.manage(ControlServerState::default());
+    app.manage(ScenarioState::default());
     app.manage(TcLimiterState::default());
     Ok(())
 
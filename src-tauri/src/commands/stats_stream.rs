@@ -0,0 +1,330 @@
+//! Statistics event stream.
+//!
+//! Polling `get_status` only sees the session at the moment it asks, so a
+//! burst between two polls goes unnoticed. While a session runs, a sampler
+//! thread takes a [`StatisticsSnapshot`] every [`SNAPSHOT_INTERVAL`] and
+//! publishes it to each subscriber of the session's [`StatsHub`]: the GUI
+//! receives it as the [`STATISTICS_EVENT`] Tauri event, and control clients
+//! on `GET /stats/stream`.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use log::{debug, error};
+use tauri::{App, Emitter, Manager};
+
+use crate::commands::state::PacketProcessingState;
+use crate::commands::types::{
+    BandwidthCountersDto, CorruptionCountersDto, DirectionTrafficDto, DropCountersDto,
+    DuplicateCountersDto, ProcessingStatisticsDto, QueueDepthsDto, StatisticsSnapshot,
+};
+use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::modules::stats::traffic_stats::{DirectionalCount, TrafficCount};
+
+/// Name of the Tauri event that carries each snapshot
+pub const STATISTICS_EVENT: &str = "statistics";
+
+/// Time between two snapshots of a running session
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Fans statistics snapshots out to any number of subscribers.
+///
+/// Clones share the same subscribers. A subscriber is forgotten on the first
+/// publish after its receiver was dropped.
+#[derive(Clone, Default)]
+pub struct StatsHub {
+    subscribers: Arc<Mutex<Vec<Sender<Arc<StatisticsSnapshot>>>>>,
+}
+
+impl StatsHub {
+    /// Returns a receiver for every snapshot published from now on.
+    pub fn subscribe(&self) -> Receiver<Arc<StatisticsSnapshot>> {
+
+        let (sender, receiver) = mpsc::channel();
+
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => subscribers.push(sender),
+            Err(e) => error!("Failed to lock statistics subscribers mutex: {}", e),
+        }
+
+        receiver
+
+    }
+
+    /// Sends `snapshot` to every subscriber still listening.
+    pub fn publish(&self, snapshot: StatisticsSnapshot) {
+
+        let snapshot = Arc::new(snapshot);
+
+        match self.subscribers.lock() {
+            Ok(mut subscribers) => {
+                subscribers.retain(|subscriber| subscriber.send(snapshot.clone()).is_ok());
+            }
+            Err(e) => error!("Failed to lock statistics subscribers mutex: {}", e),
+        }
+
+    }
+
+    /// Returns the number of subscribers, including any that stopped listening
+    /// since the last publish.
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().map_or(0, |subscribers| subscribers.len())
+    }
+}
+
+/// Takes snapshots of the session statistics, with rates since the previous one.
+pub struct StatsSampler {
+    last_sample: Instant,
+    captured: DirectionalCount,
+    delivered: DirectionalCount,
+}
+
+impl StatsSampler {
+    /// Creates a sampler whose first rates cover the time from `now`.
+    pub fn new(stats: &PacketProcessingStatistics, now: Instant) -> Self {
+        Self {
+            last_sample: now,
+            captured: stats.traffic_stats.captured(),
+            delivered: stats.traffic_stats.delivered(),
+        }
+    }
+
+    /// Takes a snapshot of `stats` and starts a new interval.
+    ///
+    /// # Arguments
+    ///
+    /// * `stats` - Statistics of the session; the held packet peak is reset
+    /// * `running` - Whether the session is still running
+    /// * `now` - Time of the snapshot
+    ///
+    /// # Returns
+    ///
+    /// * `StatisticsSnapshot` - The snapshot
+    pub fn sample(
+        &mut self,
+        stats: &mut PacketProcessingStatistics,
+        running: bool,
+        now: Instant,
+    ) -> StatisticsSnapshot {
+
+        let interval = now.saturating_duration_since(self.last_sample);
+        let captured = stats.traffic_stats.captured();
+        let delivered = stats.traffic_stats.delivered();
+
+        let snapshot = StatisticsSnapshot {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as u64),
+            interval_ms: interval.as_secs_f64() * 1000.0,
+            running,
+            inbound: direction_traffic(
+                captured.inbound,
+                delivered.inbound,
+                (self.captured.inbound, self.delivered.inbound),
+                interval,
+            ),
+            outbound: direction_traffic(
+                captured.outbound,
+                delivered.outbound,
+                (self.captured.outbound, self.delivered.outbound),
+                interval,
+            ),
+            queues: QueueDepthsDto {
+                held_packets: stats.traffic_stats.held_packets(),
+                peak_held_packets: stats.traffic_stats.take_peak_held_packets(),
+                lag: stats.lag_stats.current_lagged(),
+                throttle: stats.throttle_stats.buffered_count(),
+                reorder: stats.reorder_stats.delayed_packets,
+                bandwidth: stats.bandwidth_stats.buffered_packets(),
+                burst: stats.burst_stats.buffered_count,
+            },
+            drop: DropCountersDto {
+                total_packets: stats.drop_stats.total_packets,
+                dropped_packets: stats.drop_stats.total_dropped,
+                drop_rate: stats.drop_stats.total_drop_rate(),
+                recent_drop_rate: stats.drop_stats.recent_drop_rate(),
+            },
+            duplicate: DuplicateCountersDto {
+                incoming_packets: stats.duplicate_stats.incoming_packets(),
+                outgoing_packets: stats.duplicate_stats.outgoing_packets(),
+                recent_multiplier: stats.duplicate_stats.recent_multiplier(),
+            },
+            bandwidth: BandwidthCountersDto {
+                total_bytes: stats.bandwidth_stats.total_bytes(),
+                dropped_packets: stats.bandwidth_stats.dropped_packets(),
+                queue_delay_ms: stats.bandwidth_stats.queue_delay().as_secs_f64() * 1000.0,
+                recent_kbps: stats.bandwidth_stats.recent_throughput(),
+            },
+            corruption: CorruptionCountersDto {
+                corrupted_bytes: stats.corruption_stats.corruptioned_byte_count(),
+                checksum_valid: stats.corruption_stats.checksum_valid(),
+            },
+            statistics: ProcessingStatisticsDto::from(&*stats),
+        };
+
+        self.last_sample = now;
+        self.captured = captured;
+        self.delivered = delivered;
+
+        snapshot
+
+    }
+}
+
+/// Publishes a snapshot of `statistics` to `hub` every [`SNAPSHOT_INTERVAL`].
+///
+/// Runs until `running` is cleared, then publishes a last snapshot with
+/// `running` set to false.
+///
+/// # Arguments
+///
+/// * `statistics` - Statistics of the session
+/// * `running` - Flag that is cleared when the session stops
+/// * `hub` - Subscribers the snapshots are sent to
+pub fn run_stats_stream(
+    statistics: &RwLock<PacketProcessingStatistics>,
+    running: &AtomicBool,
+    hub: &StatsHub,
+) {
+
+    let mut sampler = match statistics.read() {
+        Ok(stats) => StatsSampler::new(&stats, Instant::now()),
+        Err(e) => {
+            error!("Failed to read statistics for the stream: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        thread::sleep(SNAPSHOT_INTERVAL);
+
+        let running = running.load(Ordering::SeqCst);
+
+        // Publish outside the lock so the processor is not kept waiting
+        match statistics.write() {
+            Ok(mut stats) => {
+                let snapshot = sampler.sample(&mut stats, running, Instant::now());
+
+                drop(stats);
+                hub.publish(snapshot);
+            }
+            Err(e) => error!("Failed to sample statistics: {}", e),
+        }
+
+        if !running {
+            break;
+        }
+    }
+
+}
+
+/// Emits every snapshot of the session to the frontend as [`STATISTICS_EVENT`].
+///
+/// # Arguments
+///
+/// * `app` - The application, with the [`PacketProcessingState`] already managed
+pub fn forward_to_frontend(app: &App) {
+
+    let snapshots = app.state::<PacketProcessingState>().stats_hub.subscribe();
+    let handle = app.handle().clone();
+
+    thread::spawn(move || {
+        for snapshot in snapshots {
+            if let Err(e) = handle.emit(STATISTICS_EVENT, &*snapshot) {
+                debug!("Failed to emit statistics event: {}", e);
+            }
+        }
+    });
+
+}
+
+/// Returns the traffic of one direction, with rates over `interval`.
+fn direction_traffic(
+    captured: TrafficCount,
+    delivered: TrafficCount,
+    (previous_captured, previous_delivered): (TrafficCount, TrafficCount),
+    interval: Duration,
+) -> DirectionTrafficDto {
+
+    let kbps = |bytes: usize, previous: usize| {
+        if interval.is_zero() {
+            0.0
+        } else {
+            bytes.saturating_sub(previous) as f64 / 1024.0 / interval.as_secs_f64()
+        }
+    };
+
+    DirectionTrafficDto {
+        captured_packets: captured.packets,
+        captured_bytes: captured.bytes,
+        delivered_packets: delivered.packets,
+        delivered_bytes: delivered.bytes,
+        captured_kbps: kbps(captured.bytes, previous_captured.bytes),
+        delivered_kbps: kbps(delivered.bytes, previous_delivered.bytes),
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::PacketData;
+
+    #[test]
+    fn test_snapshot_rates_and_peaks_cover_the_interval() {
+
+        let start = Instant::now();
+        let mut stats = PacketProcessingStatistics::default();
+        let mut sampler = StatsSampler::new(&stats, start);
+        let mut cycle = DirectionalCount::default();
+
+        for _ in 0..4 {
+            cycle.add(&PacketData::new(vec![0; 256], true));
+        }
+
+        stats
+            .traffic_stats
+            .record_cycle(&cycle, &DirectionalCount::default(), 40);
+        stats.traffic_stats.record_cycle(&cycle, &cycle, 2);
+
+        let snapshot = sampler.sample(&mut stats, true, start + Duration::from_millis(500));
+
+        assert_eq!(snapshot.outbound.captured_packets, 8);
+        assert!((snapshot.outbound.captured_kbps - 4.0).abs() < 1e-9);
+        assert!((snapshot.outbound.delivered_kbps - 2.0).abs() < 1e-9);
+        assert_eq!(snapshot.inbound, DirectionTrafficDto::default());
+        assert_eq!(snapshot.queues.held_packets, 2);
+        assert_eq!(snapshot.queues.peak_held_packets, 40);
+
+        // The next snapshot only covers what happened since the last one
+        let snapshot = sampler.sample(&mut stats, false, start + Duration::from_secs(1));
+
+        assert_eq!(snapshot.outbound.captured_packets, 8);
+        assert!(snapshot.outbound.captured_kbps.abs() < 1e-9);
+        assert_eq!(snapshot.queues.peak_held_packets, 2);
+        assert!(!snapshot.running);
+
+    }
+
+    #[test]
+    fn test_hub_forgets_dropped_subscribers() {
+
+        let hub = StatsHub::default();
+        let kept = hub.subscribe();
+        let gone = hub.subscribe();
+
+        drop(gone);
+
+        let statistics = RwLock::new(PacketProcessingStatistics::default());
+
+        // A stopped session publishes exactly one last snapshot
+        run_stats_stream(&statistics, &AtomicBool::new(false), &hub);
+
+        assert_eq!(hub.subscriber_count(), 1);
+        assert!(!kept.try_recv().unwrap().running);
+        assert!(kept.try_recv().is_err());
+
+    }
+}
@@ -7,22 +7,33 @@ use std::sync::atomic::Ordering;
 use log::debug;
 use tauri::State;
 
+use crate::commands::classic_state::ClassicProcessingState;
 use crate::commands::filter_history::add_to_history;
 use crate::commands::state::PacketProcessingState;
 use crate::commands::system::validate_filter;
 use crate::commands::types::{
     ModuleConfig, ModuleInfo, ModuleParams, ProcessingStatisticsDto, ProcessingStatus,
 };
+use crate::network::processing::tap::TapPhase;
 use crate::settings::Settings;
+use crate::settings::bandwidth::BandwidthOptions;
+use crate::settings::burst::BurstOptions;
+use crate::settings::corruption::CorruptionOptions;
+use crate::settings::drop::DropOptions;
+use crate::settings::duplicate::DuplicateOptions;
+use crate::settings::reorder::ReorderOptions;
+use crate::settings::throttle::ThrottleOptions;
 
 /// Gets the current status of the processing engine.
 ///
 /// Returns the current state of the packet processing engine, including
 /// whether it's running, statistics, and configurations of all modules.
+/// While Classic mode runs, the tap phase is that of the Classic loop.
 ///
 /// # Arguments
 ///
 /// * `state` - The application state containing shared resources
+/// * `classic_state` - The Classic mode state
 ///
 /// # Returns
 ///
@@ -30,8 +41,30 @@ use crate::settings::Settings;
 #[tauri::command]
 pub async fn get_status(
     state: State<'_, PacketProcessingState>,
+    classic_state: State<'_, ClassicProcessingState>,
 ) -> Result<ProcessingStatus, String> {
 
+    let mut status = session_status(&state)?;
+
+    if classic_state.running.load(Ordering::SeqCst) {
+        status.tap_phase = classic_state
+            .tap_stats
+            .read()
+            .map_err(|e| e.to_string())?
+            .phase();
+    }
+
+    Ok(status)
+
+}
+
+/// Builds the processing status of `state`.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+pub fn session_status(state: &PacketProcessingState) -> Result<ProcessingStatus, String> {
+
     let running = state.running.load(Ordering::SeqCst);
     let settings = state.settings.lock().map_err(|e| e.to_string())?;
     let modules = build_module_info_list(&settings);
@@ -39,24 +72,19 @@ pub async fn get_status(
     let statistics = if running {
         let stats = state.statistics.read().map_err(|e| e.to_string())?;
 
-        Some(ProcessingStatisticsDto {
-            burst_buffered: stats.burst_stats.buffered,
-            burst_released: stats.burst_stats.released,
-            burst_buffered_count: stats.burst_stats.buffered_count,
-            throttle_buffered_count: stats.throttle_stats.buffered_count(),
-            throttle_dropped_count: stats.throttle_stats.dropped_count(),
-            throttle_is_throttling: stats.throttle_stats.is_throttling(),
-            lag_current_lagged: stats.lag_stats.current_lagged(),
-            reorder_delayed_packets: stats.reorder_stats.delayed_packets,
-        })
+        Some(ProcessingStatisticsDto::from(&*stats))
     } else {
         None
     };
+    let tap_phase = statistics
+        .as_ref()
+        .map_or(TapPhase::Off, |stats| stats.tap_phase);
 
     Ok(ProcessingStatus {
         running,
         statistics,
         modules,
+        tap_phase,
     })
 
 }
@@ -121,6 +149,16 @@ pub async fn update_filter(
     state: State<'_, PacketProcessingState>,
     filter: Option<String>,
 ) -> Result<(), String> {
+    set_filter(&state, filter)
+}
+
+/// Replaces the filter of `state` and records valid filters in the history.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `filter` - The new filter expression
+pub fn set_filter(state: &PacketProcessingState, filter: Option<String>) -> Result<(), String> {
 
     *state
         .filter
@@ -158,6 +196,19 @@ fn build_module_info_list(settings: &Settings) -> Vec<ModuleInfo> {
         delay_ms: 1000,
         ..Default::default()
     });
+    let lag_config = |lag: &LagOptions| ModuleConfig {
+        inbound: lag.inbound,
+        outbound: lag.outbound,
+        chance: lag.probability.value() * 100.0,
+        enabled: lag.enabled,
+        duration_ms: Some(lag.delay_ms),
+        throttle_ms: Some(lag.delay_ms),
+        jitter_ms: Some(lag.jitter_ms),
+        jitter_distribution: Some(lag.distribution),
+        jitter_correlation: Some(lag.correlation.value() * 100.0),
+        preserve_order: Some(lag.preserve_order),
+        ..Default::default()
+    };
 
     let lag_info = ModuleInfo {
         params: Some(ModuleParams {
@@ -168,137 +219,166 @@ fn build_module_info_list(settings: &Settings) -> Vec<ModuleInfo> {
             "Lag",
             lag.enabled,
             ModuleConfig {
-                inbound: lag.inbound,
-                outbound: lag.outbound,
-                chance: lag.probability.value() * 100.0,
-                enabled: lag.enabled,
-                duration_ms: Some(lag.delay_ms),
-                throttle_ms: Some(lag.delay_ms),
-                ..Default::default()
+                per_direction: lag.per_direction.map(lag_config),
+                ..lag_config(&lag)
             },
         )
     };
 
     let drop = settings.drop.clone().unwrap_or_default();
+    let drop_config = |drop: &DropOptions| ModuleConfig {
+        inbound: drop.inbound,
+        outbound: drop.outbound,
+        chance: drop.probability.value() * 100.0,
+        enabled: drop.enabled,
+        duration_ms: Some(drop.duration_ms),
+        drop_model: Some(drop.model),
+        good_to_bad: Some(drop.gilbert_elliott.good_to_bad.value() * 100.0),
+        bad_to_good: Some(drop.gilbert_elliott.bad_to_good.value() * 100.0),
+        good_loss: Some(drop.gilbert_elliott.good_loss.value() * 100.0),
+        bad_loss: Some(drop.gilbert_elliott.bad_loss.value() * 100.0),
+        ..Default::default()
+    };
     let drop_info = module(
         "drop",
         "Drop",
         drop.enabled,
         ModuleConfig {
-            inbound: drop.inbound,
-            outbound: drop.outbound,
-            chance: drop.probability.value() * 100.0,
-            enabled: drop.enabled,
-            duration_ms: Some(drop.duration_ms),
-            ..Default::default()
+            per_direction: drop.per_direction.map(drop_config),
+            ..drop_config(&drop)
         },
     );
 
     let throttle = settings.throttle.clone().unwrap_or_default();
+    let throttle_config = |throttle: &ThrottleOptions| ModuleConfig {
+        inbound: throttle.inbound,
+        outbound: throttle.outbound,
+        chance: throttle.probability.value() * 100.0,
+        enabled: throttle.enabled,
+        duration_ms: Some(throttle.duration_ms),
+        throttle_ms: Some(throttle.throttle_ms),
+        drop: Some(throttle.drop),
+        max_buffer: Some(throttle.max_buffer),
+        freeze_mode: Some(throttle.freeze_mode),
+        ..Default::default()
+    };
     let throttle_info = module(
         "throttle",
         "Throttle",
         throttle.enabled,
         ModuleConfig {
-            inbound: throttle.inbound,
-            outbound: throttle.outbound,
-            chance: throttle.probability.value() * 100.0,
-            enabled: throttle.enabled,
-            duration_ms: Some(throttle.duration_ms),
-            throttle_ms: Some(throttle.throttle_ms),
-            drop: Some(throttle.drop),
-            max_buffer: Some(throttle.max_buffer),
-            freeze_mode: Some(throttle.freeze_mode),
-            ..Default::default()
+            per_direction: throttle.per_direction.map(throttle_config),
+            ..throttle_config(&throttle)
         },
     );
 
     let duplicate = settings.duplicate.clone().unwrap_or_default();
+    let duplicate_config = |duplicate: &DuplicateOptions| ModuleConfig {
+        inbound: duplicate.inbound,
+        outbound: duplicate.outbound,
+        chance: duplicate.probability.value() * 100.0,
+        enabled: duplicate.enabled,
+        duration_ms: Some(duplicate.duration_ms),
+        count: Some(duplicate.count),
+        ..Default::default()
+    };
     let duplicate_info = module(
         "duplicate",
         "Duplicate",
         duplicate.enabled,
         ModuleConfig {
-            inbound: duplicate.inbound,
-            outbound: duplicate.outbound,
-            chance: duplicate.probability.value() * 100.0,
-            enabled: duplicate.enabled,
-            duration_ms: Some(duplicate.duration_ms),
-            count: Some(duplicate.count),
-            ..Default::default()
+            per_direction: duplicate.per_direction.map(duplicate_config),
+            ..duplicate_config(&duplicate)
         },
     );
 
     let bandwidth = settings.bandwidth.clone().unwrap_or_default();
+    let bandwidth_config = |bandwidth: &BandwidthOptions| ModuleConfig {
+        inbound: bandwidth.inbound,
+        outbound: bandwidth.outbound,
+        chance: bandwidth.probability.value() * 100.0,
+        enabled: bandwidth.enabled,
+        duration_ms: Some(bandwidth.duration_ms),
+        limit_kbps: Some(if bandwidth.limit == 0 {
+            50
+        } else {
+            bandwidth.limit as u64
+        }),
+        passthrough_threshold: Some(bandwidth.passthrough_threshold),
+        use_wfp: Some(bandwidth.use_wfp),
+        shaper: Some(bandwidth.shaper),
+        ..Default::default()
+    };
     let bandwidth_info = module(
         "bandwidth",
         "Bandwidth",
         bandwidth.enabled,
         ModuleConfig {
-            inbound: bandwidth.inbound,
-            outbound: bandwidth.outbound,
-            chance: bandwidth.probability.value() * 100.0,
-            enabled: bandwidth.enabled,
-            duration_ms: Some(bandwidth.duration_ms),
-            limit_kbps: Some(if bandwidth.limit == 0 {
-                50
-            } else {
-                bandwidth.limit as u64
-            }),
-            passthrough_threshold: Some(bandwidth.passthrough_threshold),
-            use_wfp: Some(bandwidth.use_wfp),
-            ..Default::default()
+            per_direction: bandwidth.per_direction.map(bandwidth_config),
+            ..bandwidth_config(&bandwidth)
         },
     );
 
     let corruption = settings.corruption.clone().unwrap_or_default();
+    let corruption_config = |corruption: &CorruptionOptions| ModuleConfig {
+        inbound: corruption.inbound,
+        outbound: corruption.outbound,
+        chance: corruption.probability.value() * 100.0,
+        enabled: corruption.enabled,
+        duration_ms: Some(corruption.duration_ms),
+        ..Default::default()
+    };
     let corruption_info = module(
         "corruption",
         "Corruption",
         corruption.enabled,
         ModuleConfig {
-            inbound: corruption.inbound,
-            outbound: corruption.outbound,
-            chance: corruption.probability.value() * 100.0,
-            enabled: corruption.enabled,
-            duration_ms: Some(corruption.duration_ms),
-            ..Default::default()
+            per_direction: corruption.per_direction.map(corruption_config),
+            ..corruption_config(&corruption)
         },
     );
 
     let reorder = settings.reorder.clone().unwrap_or_default();
+    let reorder_config = |reorder: &ReorderOptions| ModuleConfig {
+        inbound: reorder.inbound,
+        outbound: reorder.outbound,
+        chance: reorder.probability.value() * 100.0,
+        enabled: reorder.enabled,
+        duration_ms: Some(reorder.duration_ms),
+        throttle_ms: Some(reorder.max_delay),
+        ..Default::default()
+    };
     let reorder_info = module(
         "reorder",
         "Reorder",
         reorder.enabled,
         ModuleConfig {
-            inbound: reorder.inbound,
-            outbound: reorder.outbound,
-            chance: reorder.probability.value() * 100.0,
-            enabled: reorder.enabled,
-            duration_ms: Some(reorder.duration_ms),
-            throttle_ms: Some(reorder.max_delay),
-            ..Default::default()
+            per_direction: reorder.per_direction.map(reorder_config),
+            ..reorder_config(&reorder)
         },
     );
 
     let burst = settings.burst.clone().unwrap_or_default();
+    let burst_config = |burst: &BurstOptions| ModuleConfig {
+        inbound: burst.inbound,
+        outbound: burst.outbound,
+        chance: burst.probability.value() * 100.0,
+        enabled: burst.enabled,
+        duration_ms: Some(burst.duration_ms),
+        buffer_ms: Some(burst.buffer_ms),
+        keepalive_ms: Some(burst.keepalive_ms),
+        release_delay_us: Some(burst.release_delay_us),
+        lag_bypass: Some(settings.lag_bypass),
+        reverse: Some(burst.reverse),
+        ..Default::default()
+    };
     let burst_info = module(
         "burst",
         "Burst",
         burst.enabled,
         ModuleConfig {
-            inbound: burst.inbound,
-            outbound: burst.outbound,
-            chance: burst.probability.value() * 100.0,
-            enabled: burst.enabled,
-            duration_ms: Some(burst.duration_ms),
-            buffer_ms: Some(burst.buffer_ms),
-            keepalive_ms: Some(burst.keepalive_ms),
-            release_delay_us: Some(burst.release_delay_us),
-            lag_bypass: Some(settings.lag_bypass),
-            reverse: Some(burst.reverse),
-            ..Default::default()
+            per_direction: burst.per_direction.map(burst_config),
+            ..burst_config(&burst)
         },
     );
 
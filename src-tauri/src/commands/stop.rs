@@ -5,7 +5,7 @@ use std::sync::atomic::Ordering;
 use std::thread;
 use std::time::Duration;
 
-use log::info;
+use log::{error, info};
 use tauri::State;
 
 use crate::commands::state::PacketProcessingState;
@@ -27,6 +27,15 @@ use crate::settings::Settings;
 /// * `Err(String)` - If there was an error stopping processing
 #[tauri::command]
 pub async fn stop_processing(state: State<'_, PacketProcessingState>) -> Result<(), String> {
+    stop_session(&state)
+}
+
+/// Stops packet processing on `state`, releasing held packets first.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+pub fn stop_session(state: &PacketProcessingState) -> Result<(), String> {
 
     if !state.running.load(Ordering::SeqCst) {
         return Err("Packet processing not running".to_string());
@@ -58,6 +67,15 @@ pub async fn stop_processing(state: State<'_, PacketProcessingState>) -> Result<
 
     thread::sleep(Duration::from_millis(500));
 
+    // The session is over, so close any recording to flush its files
+    let recorder = state.recorder.take()?;
+
+    if let Some(recorder) = recorder {
+        if let Err(e) = recorder.finish() {
+            error!("Failed to finish packet recording: {}", e);
+        }
+    }
+
     flush_wfp_cache();
 
     restore_timer_resolution();
@@ -241,62 +241,99 @@ pub async fn scan_network_devices() -> Result<Vec<NetworkDevice>, String> {
 }
 
 use crate::commands::state::PacketProcessingState;
+use crate::network::core::{FilterFeed, ProcessSelector};
+use crate::network::filter::{Filter, FilterBuilder};
+use std::sync::Arc;
 use tauri::State;
-use windivert::{CloseAction, WinDivert, layer::NetworkLayer};
-use windivert_sys::WinDivertFlags;
 
 #[tauri::command]
-pub fn build_process_filter(pid: u32, _include_inbound: bool, _include_outbound: bool) -> String {
+pub fn build_process_filter(pid: u32, include_inbound: bool, include_outbound: bool) -> String {
 
-    let (local_ports, remote_ips) = get_process_connections(pid);
+    let builder = FilterBuilder::new().directions(include_inbound, include_outbound);
+    let builder = process_targets(builder, pid);
 
-    if local_ports.is_empty() && remote_ips.is_empty() {
+    // Without connections the filter would match every packet in the directions
+    if !builder.has_targets() {
         log::warn!(
-            "No active connections found for PID {}. Using broad outbound filter.",
+            "No active connections found for PID {}. Capturing nothing until it opens one.",
             pid
         );
-
-        return "outbound".to_string();
+        return "false".to_string();
     }
 
-    let mut filters: Vec<String> = Vec::new();
+    builder.build().to_string()
 
-    // Add local port filters
-    for port in &local_ports {
-        filters.push(format!("localPort == {}", port));
-    }
+}
 
-    // Add remote IP filters (for established connections)
-    for ip in &remote_ips {
-        filters.push(format!("ip.DstAddr == {}", ip));
-    }
+/// Adds the local ports and remote hosts of the connections a process has open.
+///
+/// # Arguments
+///
+/// * `builder` - The filter to add the process to
+/// * `pid` - The process ID
+pub(crate) fn process_targets(builder: FilterBuilder, pid: u32) -> FilterBuilder {
 
-    if filters.len() == 1 {
-        return format!("outbound and {}", filters[0]);
-    }
+    let (local_ports, remote_ips) = get_process_connections(pid);
+    let builder = local_ports
+        .into_iter()
+        .fold(builder, FilterBuilder::local_port);
 
-    format!("outbound and ({})", filters.join(" or "))
+    remote_ips.into_iter().fold(builder, FilterBuilder::host)
 
 }
 
-/// Start flow tracking for a process - this enables dynamic filter updates
+/// Start flow tracking for processes - this enables dynamic filter updates
+///
+/// Processes are given by `pid`, by `selector`, or both. While tracking, the
+/// session filter follows the connections of the tracked processes, which
+/// captures outbound traffic only unless the directions are given.
 #[tauri::command]
 pub fn start_flow_tracking(
     state: State<'_, PacketProcessingState>,
-    pid: u32,
+    pid: Option<u32>,
+    selector: Option<ProcessSelector>,
+    include_inbound: Option<bool>,
+    include_outbound: Option<bool>,
 ) -> Result<(), String> {
 
+    let mut selector = selector.unwrap_or_default();
+
+    if let Some(pid) = pid.filter(|pid| !selector.pids.contains(pid)) {
+        selector.pids.push(pid);
+    }
+
     let mut tracker = state
         .flow_tracker
         .lock()
         .map_err(|e| format!("Failed to lock flow tracker: {}", e))?;
 
-    tracker.start(pid)?;
-    log::info!("Started flow tracking for PID {}", pid);
+    tracker.start(selector)?;
+    tracker.attach_filter(Some(FilterFeed {
+        filter: Arc::clone(&state.filter),
+        include_inbound: include_inbound.unwrap_or(false),
+        include_outbound: include_outbound.unwrap_or(true),
+    }))?;
+    log::info!(
+        "Started flow tracking for PIDs {:?}",
+        tracker.tracked_pids()
+    );
     Ok(())
 
 }
 
+/// Get the IDs of the processes flow tracking currently follows
+#[tauri::command]
+pub fn get_tracked_processes(state: State<'_, PacketProcessingState>) -> Result<Vec<u32>, String> {
+
+    let tracker = state
+        .flow_tracker
+        .lock()
+        .map_err(|e| format!("Failed to lock flow tracker: {}", e))?;
+
+    Ok(tracker.tracked_pids())
+
+}
+
 /// Stop flow tracking
 #[tauri::command]
 pub fn stop_flow_tracking(state: State<'_, PacketProcessingState>) -> Result<(), String> {
@@ -313,15 +350,24 @@ pub fn stop_flow_tracking(state: State<'_, PacketProcessingState>) -> Result<(),
 }
 
 /// Get the current dynamic filter based on tracked flows
+///
+/// Captures outbound traffic only unless the directions are given.
 #[tauri::command]
-pub fn get_flow_filter(state: State<'_, PacketProcessingState>) -> Result<Option<String>, String> {
+pub fn get_flow_filter(
+    state: State<'_, PacketProcessingState>,
+    include_inbound: Option<bool>,
+    include_outbound: Option<bool>,
+) -> Result<Option<String>, String> {
 
     let tracker = state
         .flow_tracker
         .lock()
         .map_err(|e| format!("Failed to lock flow tracker: {}", e))?;
 
-    Ok(tracker.build_filter())
+    Ok(tracker.build_filter(
+        include_inbound.unwrap_or(false),
+        include_outbound.unwrap_or(true),
+    ))
 
 }
 
@@ -339,24 +385,22 @@ pub fn is_flow_tracking(state: State<'_, PacketProcessingState>) -> Result<bool,
 }
 
 #[tauri::command]
-pub fn build_device_filter(ip: String, include_inbound: bool, include_outbound: bool) -> String {
-
-    if !include_inbound && !include_outbound {
-        return "false".to_string();
-    }
-
-    if include_outbound && include_inbound {
-        return format!(
-            "(outbound and ip.DstAddr == {}) or (inbound and ip.SrcAddr == {})",
-            ip, ip
-        );
-    }
-
-    if include_outbound {
-        return format!("(outbound and ip.DstAddr == {})", ip);
-    }
-
-    format!("(inbound and ip.SrcAddr == {})", ip)
+pub fn build_device_filter(
+    ip: String,
+    include_inbound: bool,
+    include_outbound: bool,
+) -> Result<String, String> {
+
+    let addr: IpAddr = ip
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid device address '{}'", ip))?;
+
+    Ok(FilterBuilder::new()
+        .directions(include_inbound, include_outbound)
+        .host(addr)
+        .build()
+        .to_string())
 
 }
 
@@ -369,25 +413,17 @@ pub fn validate_filter(filter: String) -> Result<bool, String> {
         return Err("Filter cannot be empty".to_string());
     }
 
-    let open_parens = filter.chars().filter(|&c| c == '(').count();
-    let close_parens = filter.chars().filter(|&c| c == ')').count();
+    let filter = Filter::parse(filter).map_err(|e| format!("Invalid filter: {}", e))?;
 
-    if open_parens != close_parens {
-        return Err("Unbalanced parentheses".to_string());
+    if let Some(reason) = filter.contradiction() {
+        return Err(format!("Filter never matches: {}", reason));
     }
 
-    // Try opening a WinDivert handle to validate the expression
-    match WinDivert::<NetworkLayer>::network(filter, 0, WinDivertFlags::new()) {
-        Ok(mut handle) => {
-            let _ = handle.close(CloseAction::Nothing);
-
-            Ok(true)
-        }
-        Err(e) => Err(format!("Invalid filter: {}", e)),
-    }
+    Ok(true)
 
 }
 
+#[cfg(windows)]
 fn extract_icon(exe_path: &str) -> Option<String> {
 
     use std::ffi::OsStr;
@@ -478,6 +514,7 @@ fn extract_icon(exe_path: &str) -> Option<String> {
 
 }
 
+#[cfg(windows)]
 unsafe fn cleanup_icon_resources(
     icon_info: &winapi::um::winuser::ICONINFO,
     icon: winapi::shared::windef::HICON,
@@ -498,6 +535,12 @@ unsafe fn cleanup_icon_resources(
 
 }
 
+/// Icons are read from Windows executables only.
+#[cfg(not(windows))]
+fn extract_icon(_exe_path: &str) -> Option<String> {
+    None
+}
+
 fn discover_mdns_names(ips_to_resolve: &[String]) -> HashMap<String, String> {
 
     use mdns_sd::{ServiceDaemon, ServiceEvent};
@@ -1109,7 +1152,7 @@ fn is_broadcast_or_multicast(ip: &IpAddr) -> bool {
 }
 /// Gets both local ports and remote IPs for a process.
 /// Returns (`local_ports`, `remote_ips`) for building comprehensive filters.
-fn get_process_connections(pid: u32) -> (Vec<u16>, Vec<String>) {
+fn get_process_connections(pid: u32) -> (Vec<u16>, Vec<IpAddr>) {
 
     let Ok(output) = Command::new("netstat").args(["-ano"]).output() else {
         return (Vec::new(), Vec::new());
@@ -1141,26 +1184,10 @@ fn get_process_connections(pid: u32) -> (Vec<u16>, Vec<String>) {
                 ports.push(port);
             }
         }
-        // Extract remote IP (parts[2] is foreign address)
-        let remote = parts[2];
-        if remote != "*:*" && remote != "0.0.0.0:0" && remote != "[::]:0" {
-            // Extract just the IP part (before the port)
-            if let Some(ip_part) = remote.rsplit(':').nth(1) {
-                // Handle IPv4 - the rsplit gives us the IP when splitting "ip:port"
-                let ip = if remote.starts_with('[') {
-                    ip_part.trim_start_matches('[').to_string()
-                } else {
-                    let parts: Vec<&str> = remote.rsplitn(2, ':').collect();
-
-                    if parts.len() != 2 {
-                        continue;
-                    }
-                    parts[1].to_string()
-                };
-                // Skip localhost and link-local addresses
-                if !ip.starts_with("127.") && !ip.starts_with("0.") && ip != "::1" {
-                    remote_ips.push(ip);
-                }
+        // Extract remote IP (parts[2] is foreign address), skipping wildcards and loopback
+        if let Some(ip) = parse_netstat_address(parts[2]) {
+            if !ip.is_unspecified() && !ip.is_loopback() {
+                remote_ips.push(ip);
             }
         }
     }
@@ -1181,6 +1208,18 @@ fn get_process_connections(pid: u32) -> (Vec<u16>, Vec<String>) {
 
 }
 
+/// Parses the address of a netstat endpoint such as `10.0.0.1:443` or
+/// `[fe80::1%12]:443`, dropping the port and any IPv6 zone.
+fn parse_netstat_address(endpoint: &str) -> Option<IpAddr> {
+
+    let (host, _port) = endpoint.rsplit_once(':')?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = host.split('%').next().unwrap_or(host);
+
+    host.parse().ok()
+
+}
+
 async fn lookup_and_update_devices(
     devices: &mut Vec<NetworkDevice>,
     mac_cache: &mut HashMap<String, String>,
@@ -0,0 +1,74 @@
+//! Tap commands.
+//!
+//! Reads and replaces the tap options of the running settings. The tap
+//! itself is timed by the processing loop of either mode.
+use log::info;
+use tauri::State;
+
+use crate::commands::classic_state::ClassicProcessingState;
+use crate::commands::state::PacketProcessingState;
+use crate::settings::TapOptions;
+
+/// Gets the tap options.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+///
+/// # Returns
+///
+/// * `Ok(Option<TapOptions>)` - The current tap options, if any were set
+/// * `Err(String)` - If the settings cannot be locked
+#[tauri::command]
+pub fn get_tap(state: State<'_, PacketProcessingState>) -> Result<Option<TapOptions>, String> {
+
+    let settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
+
+    Ok(settings.tap.clone())
+
+}
+
+/// Replaces the tap options of Standard and Classic mode.
+///
+/// Takes effect on the next processing cycle. Changing the interval starts a
+/// new schedule whose first tap comes one interval later; disabling tapping
+/// during a tap switches the tapped modules back on immediately.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `classic_state` - The Classic mode state
+/// * `tap` - New tap options; `None` removes them
+///
+/// # Returns
+///
+/// * `Ok(())` - If the options were replaced
+/// * `Err(String)` - If the settings cannot be locked
+#[tauri::command]
+pub fn set_tap(
+    state: State<'_, PacketProcessingState>,
+    classic_state: State<'_, ClassicProcessingState>,
+    tap: Option<TapOptions>,
+) -> Result<(), String> {
+
+    classic_state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock classic settings mutex: {}", e))?
+        .tap
+        .clone_from(&tap);
+
+    let mut settings = state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
+
+    info!("Updated tap options: {:?}", tap);
+    settings.tap = tap;
+
+    Ok(())
+
+}
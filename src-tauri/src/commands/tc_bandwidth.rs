@@ -1,4 +1,5 @@
 use crate::network::wfp_throttle::WfpThrottle;
+use crate::settings::shaper::ShaperOptions;
 use log::{error, info};
 use std::sync::Mutex;
 use tauri::State;
@@ -19,12 +20,15 @@ impl Default for TcLimiterState {
 /// Start the bandwidth limiter
 ///
 /// This provides bandwidth limiting using `WinDivert` with precise timing.
-/// Supports both inbound and outbound direction control.
+/// Supports both inbound and outbound direction control. `shaper` sets the
+/// token bucket depth, queue limits and queue discipline; defaults are used
+/// when it is omitted.
 #[tauri::command]
 pub fn start_tc_bandwidth(
     state: State<'_, TcLimiterState>,
     limit_kbps: f64,
     direction: String,
+    shaper: Option<ShaperOptions>,
 ) -> Result<String, String> {
 
     let mut limiter_guard = state.throttle.lock().map_err(|e| e.to_string())?;
@@ -45,7 +49,9 @@ pub fn start_tc_bandwidth(
         limit_kbps, direction, inbound, outbound
     );
 
-    match WfpThrottle::new(limit_kbps, "all", inbound, outbound) {
+    let shaper = shaper.unwrap_or_default();
+
+    match WfpThrottle::new(limit_kbps, &shaper, "all", inbound, outbound) {
         Ok(throttle) => {
             *limiter_guard = Some(throttle);
 
@@ -2,8 +2,19 @@
 //!
 //! This module contains the data structures used for communication
 //! between the Tauri frontend and backend.
+use std::collections::BTreeMap;
+use std::time::Instant;
+
 use serde::{Deserialize, Serialize};
 
+use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::modules::stats::flow_stats::{Flow, FlowDirection};
+use crate::network::processing::tap::TapPhase;
+use crate::settings::PerDirection;
+use crate::settings::drop::DropModel;
+use crate::settings::lag::JitterDistribution;
+use crate::settings::shaper::ShaperOptions;
+
 /// Information about a network condition simulation module.
 ///
 /// Contains the configuration, state, and parameters for a specific
@@ -75,9 +86,42 @@ pub struct ModuleConfig {
     /// Use WFP (`WinDivert`) token bucket algorithm for precise rate limiting (for bandwidth)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub use_wfp: Option<bool>,
+    /// Token bucket depth, queue limits and queue discipline (for bandwidth)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shaper: Option<ShaperOptions>,
     /// Reverse mode - release packets in reverse order (for reorder/burst)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub reverse: Option<bool>,
+    /// Lag time jitter in milliseconds (for lag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter_ms: Option<u64>,
+    /// Distribution of the lag jitter (for lag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter_distribution: Option<JitterDistribution>,
+    /// Correlation between consecutive jitter values, 0.0-100.0% (for lag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jitter_correlation: Option<f64>,
+    /// Keep packets of a flow in order despite jitter (for lag)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preserve_order: Option<bool>,
+    /// Loss model (for drop)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drop_model: Option<DropModel>,
+    /// Chance of entering the bad state per packet, 0.0-100.0% (for Gilbert-Elliott drop)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub good_to_bad: Option<f64>,
+    /// Chance of leaving the bad state per packet, 0.0-100.0% (for Gilbert-Elliott drop)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bad_to_good: Option<f64>,
+    /// Loss rate in the good state, 0.0-100.0% (for Gilbert-Elliott drop)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub good_loss: Option<f64>,
+    /// Loss rate in the bad state, 0.0-100.0% (for Gilbert-Elliott drop)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bad_loss: Option<f64>,
+    /// Configs used instead of this one for inbound or outbound packets
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
 }
 
 /// Additional parameters for a network condition simulation module.
@@ -104,10 +148,47 @@ pub struct ProcessingStatisticsDto {
     pub throttle_buffered_count: usize,
     pub throttle_dropped_count: usize,
     pub throttle_is_throttling: bool,
+    // Bandwidth stats: queued and dropped packets, and how long the last packet waited
+    pub bandwidth_buffered_count: usize,
+    pub bandwidth_dropped_count: usize,
+    pub bandwidth_queue_delay_ms: f64,
     // Lag stats
     pub lag_current_lagged: usize,
+    // Realized lag delays: packet count per bucket, keyed by bucket start in ms
+    pub lag_delay_histogram: BTreeMap<u64, usize>,
     // Reorder stats (optional, useful to know queued delayed packets)
     pub reorder_delayed_packets: usize,
+    // Drop stats: number of loss bursts of each length, and their mean length
+    pub drop_burst_lengths: BTreeMap<usize, usize>,
+    pub drop_mean_burst_length: f64,
+    // Tap stats: phase of the tap cycle and number of taps so far
+    pub tap_phase: TapPhase,
+    pub tap_count: usize,
+}
+
+impl From<&PacketProcessingStatistics> for ProcessingStatisticsDto {
+    fn from(stats: &PacketProcessingStatistics) -> Self {
+
+        Self {
+            burst_buffered: stats.burst_stats.buffered,
+            burst_released: stats.burst_stats.released,
+            burst_buffered_count: stats.burst_stats.buffered_count,
+            throttle_buffered_count: stats.throttle_stats.buffered_count(),
+            throttle_dropped_count: stats.throttle_stats.dropped_count(),
+            throttle_is_throttling: stats.throttle_stats.is_throttling(),
+            bandwidth_buffered_count: stats.bandwidth_stats.buffered_packets(),
+            bandwidth_dropped_count: stats.bandwidth_stats.dropped_packets(),
+            bandwidth_queue_delay_ms: stats.bandwidth_stats.queue_delay().as_secs_f64() * 1000.0,
+            lag_current_lagged: stats.lag_stats.current_lagged(),
+            reorder_delayed_packets: stats.reorder_stats.delayed_packets,
+            drop_burst_lengths: stats.drop_stats.burst_length_distribution().clone(),
+            drop_mean_burst_length: stats.drop_stats.mean_burst_length(),
+            lag_delay_histogram: stats.lag_stats.delay_histogram().clone(),
+            tap_phase: stats.tap_stats.phase(),
+            tap_count: stats.tap_stats.taps(),
+        }
+
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,4 +199,175 @@ pub struct ProcessingStatus {
     pub statistics: Option<ProcessingStatisticsDto>,
     /// Configuration of all available modules
     pub modules: Vec<ModuleInfo>,
+    /// Tap phase of the running processing loop, Standard or Classic
+    #[serde(default)]
+    pub tap_phase: TapPhase,
+}
+
+/// Traffic in one direction over the interval of a statistics snapshot.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DirectionTrafficDto {
+    /// Packets captured this session
+    pub captured_packets: usize,
+    /// Bytes captured this session
+    pub captured_bytes: usize,
+    /// Packets delivered after processing this session
+    pub delivered_packets: usize,
+    /// Bytes delivered after processing this session
+    pub delivered_bytes: usize,
+    /// Capture rate over the interval in KB/s
+    pub captured_kbps: f64,
+    /// Delivery rate over the interval in KB/s
+    pub delivered_kbps: f64,
+}
+
+/// Packets held by each module when a statistics snapshot is taken.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct QueueDepthsDto {
+    /// Packets held by all modules together
+    pub held_packets: usize,
+    /// Most packets held together at any point of the interval
+    pub peak_held_packets: usize,
+    pub lag: usize,
+    pub throttle: usize,
+    pub reorder: usize,
+    pub bandwidth: usize,
+    pub burst: usize,
+}
+
+/// Counters of the drop module.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DropCountersDto {
+    pub total_packets: usize,
+    pub dropped_packets: usize,
+    pub drop_rate: f64,
+    pub recent_drop_rate: f64,
+}
+
+/// Counters of the duplicate module.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DuplicateCountersDto {
+    pub incoming_packets: usize,
+    pub outgoing_packets: usize,
+    /// Recent average of packets sent per incoming packet
+    pub recent_multiplier: f64,
+}
+
+/// Counters of the bandwidth module.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct BandwidthCountersDto {
+    pub total_bytes: usize,
+    pub dropped_packets: usize,
+    pub queue_delay_ms: f64,
+    /// Recent throughput of the limiter in KB/s
+    pub recent_kbps: f64,
+}
+
+/// Counters of the corruption module.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct CorruptionCountersDto {
+    /// Bytes changed in the last sampled packet
+    pub corrupted_bytes: usize,
+    /// Whether the last sampled packet still had a valid checksum
+    pub checksum_valid: bool,
+}
+
+/// Statistics of a session at one point in time.
+///
+/// Published at a fixed interval while a session runs, as the `statistics`
+/// Tauri event and on the control server's `/stats/stream`. Carries the same
+/// fields as [`ProcessingStatisticsDto`] plus per-module counters, traffic
+/// rates and queue depths.
+#[derive(Debug, Serialize, Clone)]
+pub struct StatisticsSnapshot {
+    /// Milliseconds since the Unix epoch when the snapshot was taken
+    pub timestamp_ms: u64,
+    /// Milliseconds since the previous snapshot, which the rates cover
+    pub interval_ms: f64,
+    /// Whether the session was still running; the last snapshot has false
+    pub running: bool,
+    pub inbound: DirectionTrafficDto,
+    pub outbound: DirectionTrafficDto,
+    pub queues: QueueDepthsDto,
+    pub drop: DropCountersDto,
+    pub duplicate: DuplicateCountersDto,
+    pub bandwidth: BandwidthCountersDto,
+    pub corruption: CorruptionCountersDto,
+    #[serde(flatten)]
+    pub statistics: ProcessingStatisticsDto,
+}
+
+/// One direction of a flow.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FlowDirectionDto {
+    pub captured_packets: usize,
+    pub captured_bytes: usize,
+    pub delivered_packets: usize,
+    pub delivered_bytes: usize,
+    /// Captured packets that were never sent
+    pub dropped_packets: usize,
+    /// Mean time from capture to being sent
+    pub mean_delay_ms: f64,
+    /// Longest time from capture to being sent
+    pub max_delay_ms: f64,
+}
+
+impl From<&FlowDirection> for FlowDirectionDto {
+    fn from(direction: &FlowDirection) -> Self {
+        Self {
+            captured_packets: direction.captured.packets,
+            captured_bytes: direction.captured.bytes,
+            delivered_packets: direction.delivered.packets,
+            delivered_bytes: direction.delivered.bytes,
+            dropped_packets: direction.dropped_packets,
+            mean_delay_ms: direction.mean_delay().as_secs_f64() * 1000.0,
+            max_delay_ms: direction.max_delay.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Statistics of one flow, seen from the local endpoint.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FlowDto {
+    /// "tcp", "udp", "icmp", "icmpv6" or the protocol number
+    pub protocol: String,
+    pub local_addr: String,
+    pub local_port: u16,
+    pub remote_addr: String,
+    pub remote_port: u16,
+    /// Packets from the remote endpoint
+    pub inbound: FlowDirectionDto,
+    /// Packets to the remote endpoint
+    pub outbound: FlowDirectionDto,
+    /// Smoothed round-trip time of a TCP flow, once a segment was acknowledged
+    pub rtt_ms: Option<f64>,
+    /// Milliseconds since the last packet of the flow
+    pub idle_ms: f64,
+}
+
+impl FlowDto {
+    /// Converts `flow` as of `now`.
+    pub fn new(flow: &Flow, now: Instant) -> Self {
+
+        let protocol = match flow.key.protocol {
+            1 => "icmp".to_string(),
+            6 => "tcp".to_string(),
+            17 => "udp".to_string(),
+            58 => "icmpv6".to_string(),
+            other => other.to_string(),
+        };
+
+        Self {
+            protocol,
+            local_addr: flow.key.src_addr.to_string(),
+            local_port: flow.key.src_port,
+            remote_addr: flow.key.dst_addr.to_string(),
+            remote_port: flow.key.dst_port,
+            inbound: FlowDirectionDto::from(&flow.inbound),
+            outbound: FlowDirectionDto::from(&flow.outbound),
+            rtt_ms: flow.rtt().map(|rtt| rtt.as_secs_f64() * 1000.0),
+            idle_ms: now.saturating_duration_since(flow.last_seen).as_secs_f64() * 1000.0,
+        }
+
+    }
 }
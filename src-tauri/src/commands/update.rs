@@ -5,13 +5,13 @@ use log::info;
 use tauri::State;
 
 use crate::commands::state::PacketProcessingState;
-use crate::commands::types::ModuleInfo;
+use crate::commands::types::{ModuleConfig, ModuleInfo};
 use crate::network::types::probability::Probability;
-use crate::settings::Settings;
+use crate::settings::{PerDirection, Settings};
 use crate::settings::bandwidth::BandwidthOptions;
 use crate::settings::burst::BurstOptions;
 use crate::settings::corruption::CorruptionOptions;
-use crate::settings::drop::DropOptions;
+use crate::settings::drop::{DropOptions, GilbertElliottOptions};
 use crate::settings::duplicate::DuplicateOptions;
 use crate::settings::lag::LagOptions;
 use crate::settings::reorder::ReorderOptions;
@@ -26,6 +26,7 @@ use crate::settings::throttle::ThrottleOptions;
 ///
 /// * `state` - The application state containing shared resources
 /// * `modules` - List of module configurations to update
+/// * `seed` - Seed for the module random number generators; `None` uses system entropy
 ///
 /// # Returns
 ///
@@ -35,15 +36,38 @@ use crate::settings::throttle::ThrottleOptions;
 pub async fn update_settings(
     state: State<'_, PacketProcessingState>,
     modules: Vec<ModuleInfo>,
+    seed: Option<u64>,
+) -> Result<(), String> {
+    apply_modules(&state, modules, Some(seed))
+}
+
+/// Replaces the module settings of `state` with `modules`.
+///
+/// The rule table and tap options are kept, and so is the session seed
+/// unless `seed` replaces it.
+///
+/// # Arguments
+///
+/// * `state` - The application state containing shared resources
+/// * `modules` - List of module configurations to apply
+/// * `seed` - New session seed, or `None` to keep the current one
+pub fn apply_modules(
+    state: &PacketProcessingState,
+    modules: Vec<ModuleInfo>,
+    seed: Option<Option<u64>>,
 ) -> Result<(), String> {
 
-    let settings = build_settings_from_modules(modules)?;
+    let mut settings = build_settings_from_modules(modules)?;
 
     let mut state_settings = state
         .settings
         .lock()
         .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
 
+    // The rule table and tapping are set per session, not per module
+    settings.seed = seed.unwrap_or(state_settings.seed);
+    settings.rules = std::mem::take(&mut state_settings.rules);
+    settings.tap = state_settings.tap.take();
     *state_settings = settings;
 
     info!("Settings updated successfully");
@@ -101,17 +125,56 @@ fn build_settings_from_modules(modules: Vec<ModuleInfo>) -> Result<Settings, Str
 
 }
 
+/// Builds the options of each direction that has a config of its own.
+///
+/// # Arguments
+///
+/// * `module` - Module whose per-direction configs are built
+/// * `build` - Builds the options of the module from one config
+fn build_per_direction<T>(
+    module: &ModuleInfo,
+    build: fn(&ModuleInfo) -> Result<T, String>,
+) -> Result<PerDirection<T>, String> {
+    module.config.per_direction.try_map(|config| {
+        build(&ModuleInfo {
+            config: ModuleConfig {
+                per_direction: PerDirection::default(),
+                ..config.clone()
+            },
+            ..module.clone()
+        })
+    })
+}
+
 fn build_drop_options(module: &ModuleInfo) -> Result<DropOptions, String> {
 
     let probability = Probability::new(module.config.chance / 100.0)
         .map_err(|e| format!("Invalid drop probability: {}", e))?;
 
+    let defaults = GilbertElliottOptions::default();
+    let percent = |value: Option<f64>, default: Probability, name: &str| {
+        value.map_or(Ok(default), |chance| {
+            Probability::new(chance / 100.0)
+                .map_err(|e| format!("Invalid drop {} probability: {}", name, e))
+        })
+    };
+
+    let gilbert_elliott = GilbertElliottOptions {
+        good_to_bad: percent(module.config.good_to_bad, defaults.good_to_bad, "good-to-bad")?,
+        bad_to_good: percent(module.config.bad_to_good, defaults.bad_to_good, "bad-to-good")?,
+        good_loss: percent(module.config.good_loss, defaults.good_loss, "good-state loss")?,
+        bad_loss: percent(module.config.bad_loss, defaults.bad_loss, "bad-state loss")?,
+    };
+
     Ok(DropOptions {
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_drop_options)?,
         probability,
         duration_ms: module.config.duration_ms.unwrap_or(0),
+        model: module.config.drop_model.unwrap_or_default(),
+        gilbert_elliott,
     })
 
 }
@@ -121,6 +184,9 @@ fn build_lag_options(module: &ModuleInfo) -> Result<LagOptions, String> {
     let probability = Probability::new(module.config.chance / 100.0)
         .map_err(|e| format!("Invalid lag probability: {}", e))?;
 
+    let correlation = Probability::new(module.config.jitter_correlation.unwrap_or(0.0) / 100.0)
+        .map_err(|e| format!("Invalid lag jitter correlation: {}", e))?;
+
     let lag_time = module.config.duration_ms.unwrap_or(1000);
     let lag_time = match lag_time {
         0 => 1000,
@@ -131,9 +197,14 @@ fn build_lag_options(module: &ModuleInfo) -> Result<LagOptions, String> {
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_lag_options)?,
         delay_ms: lag_time,
         probability,
         duration_ms: 0,
+        jitter_ms: module.config.jitter_ms.unwrap_or(0),
+        distribution: module.config.jitter_distribution.unwrap_or_default(),
+        correlation,
+        preserve_order: module.config.preserve_order.unwrap_or(false),
     })
 
 }
@@ -147,6 +218,7 @@ fn build_throttle_options(module: &ModuleInfo) -> Result<ThrottleOptions, String
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_throttle_options)?,
         probability,
         throttle_ms: module.config.throttle_ms.unwrap_or(300),
         duration_ms: module.config.duration_ms.unwrap_or(0),
@@ -166,6 +238,7 @@ fn build_duplicate_options(module: &ModuleInfo) -> Result<DuplicateOptions, Stri
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_duplicate_options)?,
         probability,
         count: module.config.count.unwrap_or(1),
         duration_ms: module.config.duration_ms.unwrap_or(0),
@@ -184,11 +257,13 @@ fn build_bandwidth_options(module: &ModuleInfo) -> Result<BandwidthOptions, Stri
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_bandwidth_options)?,
         limit,
         probability,
         duration_ms: module.config.duration_ms.unwrap_or(0),
         passthrough_threshold: module.config.passthrough_threshold.unwrap_or(200),
         use_wfp: module.config.use_wfp.unwrap_or(false),
+        shaper: module.config.shaper.unwrap_or_default(),
     })
 
 }
@@ -204,6 +279,7 @@ fn build_corruption_options(module: &ModuleInfo) -> Result<CorruptionOptions, St
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_corruption_options)?,
         probability,
         amount,
         duration_ms: module.config.duration_ms.unwrap_or(0),
@@ -221,6 +297,7 @@ fn build_reorder_options(module: &ModuleInfo) -> Result<ReorderOptions, String>
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_reorder_options)?,
         probability,
         max_delay: module.config.throttle_ms.unwrap_or(100),
         duration_ms: module.config.duration_ms.unwrap_or(0),
@@ -237,6 +314,7 @@ fn build_burst_options(module: &ModuleInfo) -> Result<BurstOptions, String> {
         enabled: module.enabled,
         inbound: module.config.inbound,
         outbound: module.config.outbound,
+        per_direction: build_per_direction(module, build_burst_options)?,
         probability,
         buffer_ms: module.config.buffer_ms.unwrap_or(0),
         duration_ms: module.config.duration_ms.unwrap_or(0),
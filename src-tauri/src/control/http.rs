@@ -0,0 +1,274 @@
+//! Minimal HTTP/1.1 framing for the control server.
+//!
+//! Only what a scripting client needs: one request per connection, a body
+//! sized by `Content-Length`, and a response followed by closing the
+//! connection. A response can instead be a stream of Server-Sent Events that
+//! lasts until the connection closes.
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// Largest request head (request line plus headers) that is accepted
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Largest request body that is accepted
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// A parsed HTTP request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// Request method, e.g. `GET`
+    pub method: String,
+    /// Request path without the query string
+    pub path: String,
+    /// Bearer token from the `Authorization` header, if present
+    pub token: Option<String>,
+    /// Request body
+    pub body: Vec<u8>,
+}
+
+/// Content type of JSON responses
+const JSON: &str = "application/json";
+
+/// A response, with a JSON body unless created with [`Response::with_content_type`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// HTTP status code
+    pub status: u16,
+    /// Content type of the body
+    pub content_type: &'static str,
+    /// Response body
+    pub body: String,
+}
+
+impl Response {
+    /// Creates a `200 OK` response.
+    pub fn ok(body: String) -> Self {
+        Self::with_content_type(JSON, body)
+    }
+
+    /// Creates a `200 OK` response with a body of `content_type`.
+    pub fn with_content_type(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: 200,
+            content_type,
+            body,
+        }
+    }
+
+    /// Creates an error response with a `{"error": ...}` body.
+    pub fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: JSON,
+            body: serde_json::json!({ "error": message }).to_string(),
+        }
+    }
+}
+
+/// Reads one request from `stream`.
+///
+/// # Returns
+///
+/// * `Ok(Request)` - The parsed request
+/// * `Err(io::Error)` - If the connection fails or the request is malformed or too large
+pub fn read_request<R: Read>(stream: R) -> io::Result<Request> {
+
+    let mut reader = BufReader::new(stream);
+    let mut head_bytes = 0;
+
+    let request_line = read_line(&mut reader, &mut head_bytes)?;
+    let mut parts = request_line.split_whitespace();
+
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid("Malformed request line"));
+    };
+
+    if !version.starts_with("HTTP/1.") {
+        return Err(invalid("Unsupported HTTP version"));
+    }
+
+    let mut content_length = 0;
+    let mut token = None;
+
+    loop {
+        let line = read_line(&mut reader, &mut head_bytes)?;
+
+        if line.is_empty() {
+            break;
+        }
+
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(invalid("Malformed header"));
+        };
+        let value = value.trim();
+
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .parse::<usize>()
+                .map_err(|_| invalid("Invalid Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            token = value
+                .strip_prefix("Bearer ")
+                .map(|token| token.trim().to_string());
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return Err(invalid("Request body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+
+    reader.read_exact(&mut body)?;
+
+    Ok(Request {
+        method: method.to_ascii_uppercase(),
+        path: target.split('?').next().unwrap_or(target).to_string(),
+        token,
+        body,
+    })
+
+}
+
+/// Writes `response` to `stream`, announcing that the connection will close.
+pub fn write_response<W: Write>(mut stream: W, response: &Response) -> io::Result<()> {
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        reason_phrase(response.status),
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+
+    stream.flush()
+
+}
+
+/// Writes the head of a Server-Sent Events response to `stream`.
+///
+/// The events follow with [`write_event`] until the connection closes.
+pub fn write_event_stream_head<W: Write>(mut stream: W) -> io::Result<()> {
+
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n"
+    )?;
+
+    stream.flush()
+
+}
+
+/// Writes one Server-Sent Event named `event` with single-line `data`.
+pub fn write_event<W: Write>(mut stream: W, event: &str, data: &str) -> io::Result<()> {
+
+    write!(stream, "event: {}\ndata: {}\n\n", event, data)?;
+
+    stream.flush()
+
+}
+
+/// Writes a Server-Sent Events comment, which clients ignore.
+///
+/// Sent while there are no events, to notice clients that went away.
+pub fn write_comment<W: Write>(mut stream: W, comment: &str) -> io::Result<()> {
+
+    write!(stream, ": {}\n\n", comment)?;
+
+    stream.flush()
+
+}
+
+/// Reads one CRLF-terminated line of the request head.
+fn read_line<R: BufRead>(reader: &mut R, head_bytes: &mut usize) -> io::Result<String> {
+
+    let mut line = String::new();
+    let remaining = MAX_HEAD_BYTES.saturating_sub(*head_bytes) as u64;
+    let read = reader.by_ref().take(remaining).read_line(&mut line)?;
+
+    *head_bytes += read;
+
+    if read == 0 || !line.ends_with('\n') {
+        return Err(invalid("Incomplete or oversized request head"));
+    }
+
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request_with_body_and_token() {
+
+        let raw = b"PUT /filter?x=1 HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer abc\r\nContent-Length: 4\r\n\r\nnull";
+        let request = read_request(&raw[..]).unwrap();
+
+        assert_eq!(request.method, "PUT");
+        assert_eq!(request.path, "/filter");
+        assert_eq!(request.token.as_deref(), Some("abc"));
+        assert_eq!(request.body, b"null");
+
+    }
+
+    #[test]
+    fn test_read_request_rejects_malformed_input() {
+
+        assert!(read_request(&b"GET\r\n\r\n"[..]).is_err());
+        assert!(read_request(&b"GET / SPDY/3\r\n\r\n"[..]).is_err());
+        assert!(read_request(&b"GET / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort"[..]).is_err());
+
+        let oversized = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_HEAD_BYTES));
+        assert!(read_request(oversized.as_bytes()).is_err());
+
+    }
+
+    #[test]
+    fn test_write_response() {
+
+        let mut out = Vec::new();
+        write_response(&mut out, &Response::error(401, "nope")).unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(text.contains("Content-Type: application/json\r\n"));
+        assert!(text.ends_with("\r\n\r\n{\"error\":\"nope\"}"));
+
+    }
+
+    #[test]
+    fn test_write_event_stream() {
+
+        let mut out = Vec::new();
+        write_event_stream_head(&mut out).unwrap();
+        write_event(&mut out, "statistics", "{}").unwrap();
+        write_comment(&mut out, "keepalive").unwrap();
+
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("Content-Type: text/event-stream\r\n"));
+        assert!(text.ends_with("\r\n\r\nevent: statistics\ndata: {}\n\n: keepalive\n\n"));
+
+    }
+}
@@ -0,0 +1,443 @@
+//! Prometheus metrics of the session.
+//!
+//! Renders [`PacketProcessingStatistics`] in the Prometheus text exposition
+//! format for the control server's `/metrics` route. Module counters carry a
+//! `module` label and traffic counters a `direction` label, so one scrape
+//! config covers every module.
+use std::fmt::Write;
+
+use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::modules::stats::traffic_stats::TrafficCount;
+use crate::network::processing::tap::TapPhase;
+
+/// Content type of the text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Type of a metric family, as written on its `# TYPE` line.
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+/// Builds the text of one scrape.
+#[derive(Debug, Default)]
+struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    /// Starts a metric family with its help text.
+    fn family(&mut self, name: &str, kind: Kind, help: &str) -> &mut Self {
+
+        let kind = match kind {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+        };
+
+        let _ = writeln!(self.text, "# HELP {} {}", name, help);
+        let _ = writeln!(self.text, "# TYPE {} {}", name, kind);
+        self
+
+    }
+
+    /// Adds a sample of the current family.
+    fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+
+        self.text.push_str(name);
+
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, value))
+                .collect();
+
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+
+        let _ = writeln!(self.text, " {}", format_value(value));
+        self
+
+    }
+
+    /// Adds one sample per direction of a traffic count.
+    fn directions(
+        &mut self,
+        name: &str,
+        (inbound, outbound): (TrafficCount, TrafficCount),
+        value: fn(TrafficCount) -> usize,
+    ) -> &mut Self {
+
+        self.sample(name, &[("direction", "inbound")], value(inbound) as f64)
+            .sample(name, &[("direction", "outbound")], value(outbound) as f64)
+
+    }
+}
+
+/// Renders `stats` in the Prometheus text exposition format.
+///
+/// # Arguments
+///
+/// * `stats` - Statistics of the session
+/// * `running` - Whether packet processing is running
+///
+/// # Returns
+///
+/// * `String` - The body of a scrape, served as [`CONTENT_TYPE`]
+pub fn render(stats: &PacketProcessingStatistics, running: bool) -> String {
+
+    let mut metrics = Exposition::default();
+    let traffic = &stats.traffic_stats;
+    let captured = traffic.captured();
+    let delivered = traffic.delivered();
+    let captured = (captured.inbound, captured.outbound);
+    let delivered = (delivered.inbound, delivered.outbound);
+
+    metrics
+        .family(
+            "myra_running",
+            Kind::Gauge,
+            "Whether packet processing is running.",
+        )
+        .sample("myra_running", &[], f64::from(u8::from(running)));
+
+    metrics
+        .family(
+            "myra_received_packets_total",
+            Kind::Counter,
+            "Packets captured for processing.",
+        )
+        .directions("myra_received_packets_total", captured, |count| {
+            count.packets
+        })
+        .family(
+            "myra_received_bytes_total",
+            Kind::Counter,
+            "Bytes captured for processing.",
+        )
+        .directions("myra_received_bytes_total", captured, |count| count.bytes)
+        .family(
+            "myra_sent_packets_total",
+            Kind::Counter,
+            "Packets sent after processing.",
+        )
+        .directions("myra_sent_packets_total", delivered, |count| count.packets)
+        .family(
+            "myra_sent_bytes_total",
+            Kind::Counter,
+            "Bytes sent after processing.",
+        )
+        .directions("myra_sent_bytes_total", delivered, |count| count.bytes);
+
+    metrics
+        .family(
+            "myra_module_packets_total",
+            Kind::Counter,
+            "Packets that reached a module.",
+        )
+        .sample(
+            "myra_module_packets_total",
+            &[("module", "drop")],
+            stats.drop_stats.total_packets as f64,
+        )
+        .sample(
+            "myra_module_packets_total",
+            &[("module", "reorder")],
+            stats.reorder_stats.total_packets as f64,
+        )
+        .sample(
+            "myra_module_packets_total",
+            &[("module", "duplicate")],
+            stats.duplicate_stats.incoming_packets() as f64,
+        );
+
+    metrics
+        .family(
+            "myra_module_dropped_packets_total",
+            Kind::Counter,
+            "Packets a module dropped.",
+        )
+        .sample(
+            "myra_module_dropped_packets_total",
+            &[("module", "drop")],
+            stats.drop_stats.total_dropped as f64,
+        )
+        .sample(
+            "myra_module_dropped_packets_total",
+            &[("module", "throttle")],
+            stats.throttle_stats.dropped_count() as f64,
+        )
+        .sample(
+            "myra_module_dropped_packets_total",
+            &[("module", "bandwidth")],
+            stats.bandwidth_stats.dropped_packets() as f64,
+        );
+
+    metrics
+        .family(
+            "myra_module_held_packets",
+            Kind::Gauge,
+            "Packets a module is holding back.",
+        )
+        .sample(
+            "myra_module_held_packets",
+            &[("module", "lag")],
+            stats.lag_stats.current_lagged() as f64,
+        )
+        .sample(
+            "myra_module_held_packets",
+            &[("module", "throttle")],
+            stats.throttle_stats.buffered_count() as f64,
+        )
+        .sample(
+            "myra_module_held_packets",
+            &[("module", "reorder")],
+            stats.reorder_stats.delayed_packets as f64,
+        )
+        .sample(
+            "myra_module_held_packets",
+            &[("module", "bandwidth")],
+            stats.bandwidth_stats.buffered_packets() as f64,
+        )
+        .sample(
+            "myra_module_held_packets",
+            &[("module", "burst")],
+            stats.burst_stats.buffered_count as f64,
+        );
+
+    metrics
+        .family(
+            "myra_drop_rate",
+            Kind::Gauge,
+            "Share of packets dropped, over the session or as a moving average.",
+        )
+        .sample(
+            "myra_drop_rate",
+            &[("window", "session")],
+            stats.drop_stats.total_drop_rate(),
+        )
+        .sample(
+            "myra_drop_rate",
+            &[("window", "recent")],
+            stats.drop_stats.recent_drop_rate(),
+        )
+        .family(
+            "myra_drop_mean_burst_length",
+            Kind::Gauge,
+            "Mean number of packets lost in a row.",
+        )
+        .sample(
+            "myra_drop_mean_burst_length",
+            &[],
+            stats.drop_stats.mean_burst_length(),
+        );
+
+    metrics
+        .family(
+            "myra_lag_max_held_packets",
+            Kind::Gauge,
+            "Most packets held by the lag module at once.",
+        )
+        .sample(
+            "myra_lag_max_held_packets",
+            &[],
+            stats.lag_stats.max_lagged() as f64,
+        )
+        .family(
+            "myra_throttle_active",
+            Kind::Gauge,
+            "Whether the throttle module is holding packets back.",
+        )
+        .sample(
+            "myra_throttle_active",
+            &[],
+            f64::from(u8::from(stats.throttle_stats.is_throttling())),
+        )
+        .family(
+            "myra_reorder_reordered_packets_total",
+            Kind::Counter,
+            "Packets sent out of order.",
+        )
+        .sample(
+            "myra_reorder_reordered_packets_total",
+            &[],
+            stats.reorder_stats.reordered_packets as f64,
+        );
+
+    metrics
+        .family(
+            "myra_bandwidth_bytes_total",
+            Kind::Counter,
+            "Bytes released by the bandwidth limiter.",
+        )
+        .sample(
+            "myra_bandwidth_bytes_total",
+            &[],
+            stats.bandwidth_stats.total_bytes() as f64,
+        )
+        .family(
+            "myra_bandwidth_throughput_kbps",
+            Kind::Gauge,
+            "Moving average of the bandwidth limiter throughput in KB/s.",
+        )
+        .sample(
+            "myra_bandwidth_throughput_kbps",
+            &[],
+            stats.bandwidth_stats.recent_throughput(),
+        )
+        .family(
+            "myra_bandwidth_queue_delay_seconds",
+            Kind::Gauge,
+            "How long the last packet released by the bandwidth limiter was queued.",
+        )
+        .sample(
+            "myra_bandwidth_queue_delay_seconds",
+            &[],
+            stats.bandwidth_stats.queue_delay().as_secs_f64(),
+        );
+
+    metrics
+        .family(
+            "myra_burst_buffered_packets",
+            Kind::Gauge,
+            "Packets buffered by the burst module since its last release.",
+        )
+        .sample(
+            "myra_burst_buffered_packets",
+            &[],
+            stats.burst_stats.buffered as f64,
+        )
+        .family(
+            "myra_burst_released_packets",
+            Kind::Gauge,
+            "Packets sent in the last burst.",
+        )
+        .sample(
+            "myra_burst_released_packets",
+            &[],
+            stats.burst_stats.released as f64,
+        );
+
+    metrics
+        .family(
+            "myra_duplicate_sent_packets_total",
+            Kind::Counter,
+            "Packets sent by the duplicate module, copies included.",
+        )
+        .sample(
+            "myra_duplicate_sent_packets_total",
+            &[],
+            stats.duplicate_stats.outgoing_packets() as f64,
+        )
+        .family(
+            "myra_duplicate_multiplier",
+            Kind::Gauge,
+            "Moving average of the packets sent per packet duplicated.",
+        )
+        .sample(
+            "myra_duplicate_multiplier",
+            &[],
+            stats.duplicate_stats.recent_multiplier(),
+        );
+
+    metrics
+        .family(
+            "myra_corruption_corrupted_bytes",
+            Kind::Gauge,
+            "Bytes changed in the last packet sampled by the corruption module.",
+        )
+        .sample(
+            "myra_corruption_corrupted_bytes",
+            &[],
+            stats.corruption_stats.corruptioned_byte_count() as f64,
+        );
+
+    metrics
+        .family(
+            "myra_taps_total",
+            Kind::Counter,
+            "Taps started this session.",
+        )
+        .sample("myra_taps_total", &[], stats.tap_stats.taps() as f64)
+        .family(
+            "myra_tapping",
+            Kind::Gauge,
+            "Whether modules are switched off by a tap.",
+        )
+        .sample(
+            "myra_tapping",
+            &[],
+            f64::from(u8::from(stats.tap_stats.phase() == TapPhase::Tapping)),
+        );
+
+    metrics.text
+
+}
+
+/// Formats a sample value the way Prometheus parses it.
+fn format_value(value: f64) -> String {
+
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::PacketData;
+    use crate::network::modules::stats::traffic_stats::DirectionalCount;
+
+    #[test]
+    fn test_render_labels_modules_and_directions() {
+
+        let mut stats = PacketProcessingStatistics::default();
+        let mut captured = DirectionalCount::default();
+
+        captured.add(&PacketData::new(vec![0; 100], false));
+        captured.add(&PacketData::new(vec![0; 50], true));
+
+        stats
+            .traffic_stats
+            .record_cycle(&captured, &DirectionalCount::default(), 0);
+        stats.drop_stats.record(true);
+        stats.drop_stats.record(false);
+
+        let text = render(&stats, true);
+
+        assert!(text.contains("# TYPE myra_received_bytes_total counter\n"));
+        assert!(text.contains("myra_received_bytes_total{direction=\"inbound\"} 100\n"));
+        assert!(text.contains("myra_received_bytes_total{direction=\"outbound\"} 50\n"));
+        assert!(text.contains("myra_sent_packets_total{direction=\"inbound\"} 0\n"));
+        assert!(text.contains("myra_module_dropped_packets_total{module=\"drop\"} 1\n"));
+        assert!(text.contains("myra_drop_rate{window=\"session\"} 0.5\n"));
+        assert!(text.contains("myra_running 1\n"));
+
+        // Every sample line belongs to the family announced before it
+        let mut family = "";
+
+        for line in text.lines() {
+            if let Some(name) = line.strip_prefix("# TYPE ") {
+                family = name.split(' ').next().unwrap();
+            } else if !line.starts_with('#') {
+                assert!(line.starts_with(family), "{line}");
+            }
+        }
+
+    }
+
+    #[test]
+    fn test_format_value() {
+
+        assert_eq!(format_value(3.0), "3");
+        assert_eq!(format_value(0.25), "0.25");
+        assert_eq!(format_value(f64::NAN), "NaN");
+        assert_eq!(format_value(f64::NEG_INFINITY), "-Inf");
+
+    }
+}
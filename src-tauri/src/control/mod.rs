@@ -0,0 +1,11 @@
+//! Local control API.
+//!
+//! Lets scripts drive the running session next to the Tauri commands used by
+//! the GUI: over HTTP on localhost, or by playing a scenario timeline.
+pub mod http;
+pub mod metrics;
+pub mod scenario;
+pub mod server;
+
+pub use scenario::{ScenarioProgress, ScenarioRunner};
+pub use server::{ControlServer, DEFAULT_PORT};
@@ -0,0 +1,358 @@
+//! Scenario scheduler.
+//!
+//! Plays a [`Scenario`] against the shared settings on a background thread,
+//! writing the modules the scenario uses whenever the set of active steps
+//! changes. Every other setting is left to the app and the control API.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use log::{debug, error, info};
+use serde::Serialize;
+
+use crate::network::types::clock::SharedClock;
+use crate::settings::{ModuleKind, Scenario, Settings};
+
+/// How often the scheduler checks the timeline
+const TICK_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Where a running scenario is on its timeline.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScenarioProgress {
+    /// Name of the scenario, if it has one
+    pub name: Option<String>,
+    /// Time since the scenario started
+    pub elapsed_ms: u64,
+    /// Completed passes over the timeline
+    pub cycle: u64,
+    /// Indices of the steps currently in effect
+    pub active_steps: Vec<usize>,
+    /// Whether a non-repeating scenario has reached its end
+    pub finished: bool,
+}
+
+/// Applies a scenario to settings as time passes.
+///
+/// Kept separate from the thread in [`ScenarioRunner`] so the timing can be
+/// driven by hand.
+#[derive(Debug)]
+pub struct ScenarioPlayer {
+    scenario: Scenario,
+    modules: Vec<ModuleKind>,
+    base: Settings,
+    started: Instant,
+    applied: Option<Vec<usize>>,
+}
+
+impl ScenarioPlayer {
+    /// Creates a player whose timeline starts at `started`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario` - Timeline to play
+    /// * `base` - Settings in place before the scenario, restored by expiring steps
+    /// * `started` - Moment the timeline starts
+    pub fn new(scenario: Scenario, base: Settings, started: Instant) -> Self {
+        Self {
+            modules: scenario.modules(),
+            scenario,
+            base,
+            started,
+            applied: None,
+        }
+    }
+
+    /// Writes the modules due at `now` into `settings` if the active steps changed.
+    ///
+    /// Modules no step uses keep whatever they were changed to meanwhile.
+    ///
+    /// # Returns
+    ///
+    /// The progress of the scenario at `now`
+    pub fn tick(
+        &mut self,
+        now: Instant,
+        settings: &Mutex<Settings>,
+    ) -> Result<ScenarioProgress, String> {
+
+        let elapsed = now.saturating_duration_since(self.started);
+        let active = self.scenario.active_steps(elapsed);
+
+        if self.applied.as_ref() != Some(&active) {
+            let due = self.scenario.settings_at(&self.base, elapsed);
+            let mut settings = settings
+                .lock()
+                .map_err(|e| format!("Failed to lock settings mutex: {}", e))?;
+
+            for module in &self.modules {
+                module.copy(&due, &mut settings);
+            }
+
+            debug!("Scenario steps now active: {:?}", active);
+            self.applied = Some(active.clone());
+        }
+
+        Ok(ScenarioProgress {
+            name: self.scenario.name.clone(),
+            elapsed_ms: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            cycle: self.scenario.cycle(elapsed),
+            active_steps: active,
+            finished: self.scenario.is_finished(elapsed),
+        })
+
+    }
+}
+
+/// Scenario playing on a background thread.
+///
+/// Stopping the runner puts back the modules the scenario uses as they were
+/// before it; a scenario that reaches its end on its own leaves its final
+/// settings in place.
+pub struct ScenarioRunner {
+    running: Arc<AtomicBool>,
+    progress: Arc<Mutex<ScenarioProgress>>,
+    settings: Arc<Mutex<Settings>>,
+    modules: Vec<ModuleKind>,
+    base: Settings,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ScenarioRunner {
+    /// Starts playing `scenario` on top of the current contents of `settings`.
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario` - Timeline to play
+    /// * `settings` - Shared settings the processing loop reads
+    /// * `clock` - Time source for the timeline
+    pub fn start(
+        scenario: Scenario,
+        settings: Arc<Mutex<Settings>>,
+        clock: SharedClock,
+    ) -> Result<Self, String> {
+
+        let base = settings
+            .lock()
+            .map_err(|e| format!("Failed to lock settings mutex: {}", e))?
+            .clone();
+        let modules = scenario.modules();
+        let running = Arc::new(AtomicBool::new(true));
+        let progress = Arc::new(Mutex::new(ScenarioProgress {
+            name: scenario.name.clone(),
+            ..Default::default()
+        }));
+
+        info!(
+            "Starting scenario {} with {} steps",
+            scenario.name.as_deref().unwrap_or("(unnamed)"),
+            scenario.steps.len()
+        );
+
+        let handle = {
+            let mut player = ScenarioPlayer::new(scenario, base.clone(), clock.now());
+            let running = running.clone();
+            let progress = progress.clone();
+            let settings = settings.clone();
+
+            thread::spawn(move || {
+
+                while running.load(Ordering::SeqCst) {
+                    let current = match player.tick(clock.now(), &settings) {
+                        Ok(current) => current,
+                        Err(e) => {
+                            error!("Scenario stopped: {}", e);
+                            break;
+                        }
+                    };
+                    let finished = current.finished;
+
+                    if let Ok(mut progress) = progress.lock() {
+                        *progress = current;
+                    }
+
+                    if finished {
+                        info!("Scenario finished");
+                        break;
+                    }
+
+                    thread::sleep(TICK_INTERVAL);
+                }
+
+                running.store(false, Ordering::SeqCst);
+
+            })
+        };
+
+        Ok(Self {
+            running,
+            progress,
+            settings,
+            modules,
+            base,
+            handle: Some(handle),
+        })
+
+    }
+
+    /// Whether the scenario is still playing.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Latest progress reported by the scheduler thread.
+    pub fn progress(&self) -> ScenarioProgress {
+        self.progress
+            .lock()
+            .map(|progress| progress.clone())
+            .unwrap_or_default()
+    }
+
+    /// Stops the scenario and restores the modules it uses to their state before it.
+    ///
+    /// A scenario that already finished keeps its final settings.
+    pub fn stop(&mut self) {
+
+        let was_running = self.running.swap(false, Ordering::SeqCst);
+
+        let Some(handle) = self.handle.take() else {
+            return;
+        };
+
+        let _ = handle.join();
+
+        if !was_running {
+            return;
+        }
+
+        match self.settings.lock() {
+            Ok(mut settings) => {
+                for module in &self.modules {
+                    module.copy(&self.base, &mut settings);
+                }
+            }
+            Err(e) => error!("Failed to restore settings after scenario: {}", e),
+        }
+
+        info!("Scenario stopped");
+
+    }
+}
+
+impl Drop for ScenarioRunner {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::types::clock::{Clock, ManualClock};
+    use crate::settings::ScenarioStep;
+    use crate::settings::drop::DropOptions;
+    use crate::settings::lag::LagOptions;
+
+    fn lag_for(at_ms: u64, duration_ms: u64, delay_ms: u64) -> ScenarioStep {
+        ScenarioStep {
+            at_ms,
+            duration_ms: Some(duration_ms),
+            lag: Some(LagOptions {
+                delay_ms,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_player_applies_and_reverts_steps() {
+
+        let clock = ManualClock::new();
+        let settings = Mutex::new(Settings::default());
+        let scenario = Scenario {
+            steps: vec![lag_for(100, 50, 80)],
+            ..Default::default()
+        };
+        let mut player = ScenarioPlayer::new(scenario, Settings::default(), clock.now());
+
+        player.tick(clock.now(), &settings).unwrap();
+        assert!(settings.lock().unwrap().lag.is_none());
+
+        clock.advance(Duration::from_millis(120));
+        let progress = player.tick(clock.now(), &settings).unwrap();
+        assert_eq!(progress.active_steps, vec![0]);
+        assert_eq!(settings.lock().unwrap().lag.as_ref().map(|lag| lag.delay_ms), Some(80));
+
+        clock.advance(Duration::from_millis(40));
+        let progress = player.tick(clock.now(), &settings).unwrap();
+        assert!(progress.active_steps.is_empty());
+        assert!(progress.finished);
+        assert!(settings.lock().unwrap().lag.is_none());
+
+    }
+
+    #[test]
+    fn test_player_leaves_unchanged_settings_alone() {
+
+        let clock = ManualClock::new();
+        let settings = Mutex::new(Settings::default());
+        let scenario = Scenario {
+            steps: vec![lag_for(0, 1000, 80)],
+            ..Default::default()
+        };
+        let mut player = ScenarioPlayer::new(scenario, Settings::default(), clock.now());
+
+        player.tick(clock.now(), &settings).unwrap();
+
+        // An edit made while the same steps stay active is not overwritten
+        settings.lock().unwrap().seed = Some(5);
+        clock.advance(Duration::from_millis(10));
+        player.tick(clock.now(), &settings).unwrap();
+
+        assert_eq!(settings.lock().unwrap().seed, Some(5));
+
+        // Nor are modules no step uses once the steps change
+        settings.lock().unwrap().drop = Some(DropOptions::default());
+        clock.advance(Duration::from_secs(1));
+        player.tick(clock.now(), &settings).unwrap();
+
+        let settings = settings.lock().unwrap();
+
+        assert!(settings.lag.is_none());
+        assert!(settings.drop.is_some());
+        assert_eq!(settings.seed, Some(5));
+
+    }
+
+    #[test]
+    fn test_runner_restores_settings_on_stop() {
+
+        let clock = Arc::new(ManualClock::new());
+        let settings = Arc::new(Mutex::new(Settings::default()));
+        let scenario = Scenario {
+            repeat: true,
+            steps: vec![lag_for(0, 1000, 80)],
+            ..Default::default()
+        };
+
+        let mut runner = ScenarioRunner::start(scenario, settings.clone(), clock).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+
+        while settings.lock().unwrap().lag.is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert!(settings.lock().unwrap().lag.is_some());
+        assert!(runner.is_running());
+
+        // Changes the app makes meanwhile outlive the scenario
+        settings.lock().unwrap().drop = Some(DropOptions::default());
+        runner.stop();
+
+        assert!(!runner.is_running());
+        assert!(settings.lock().unwrap().lag.is_none());
+        assert!(settings.lock().unwrap().drop.is_some());
+
+    }
+}
@@ -0,0 +1,609 @@
+//! Localhost control server.
+//!
+//! Serves the session operations behind the Tauri commands as a JSON API on
+//! `127.0.0.1`, sharing the [`PacketProcessingState`] of the GUI. Every request
+//! must carry the server token as `Authorization: Bearer <token>`.
+//!
+//! | Method | Path            | Body                                   | Response                 |
+//! |--------|-----------------|----------------------------------------|--------------------------|
+//! | GET    | `/status`       |                                        | `ProcessingStatus`       |
+//! | POST   | `/start`        | `{"settings"?, "filter"?, "backend"?}` | `{"ok": true}`           |
+//! | POST   | `/stop`         |                                        | `{"ok": true}`           |
+//! | GET    | `/settings`     |                                        | `Settings`               |
+//! | PUT    | `/settings`     | `Settings`                             | `{"ok": true}`           |
+//! | PUT    | `/modules`      | `[ModuleInfo]`                         | `{"ok": true}`           |
+//! | GET    | `/filter`       |                                        | `string \| null`         |
+//! | PUT    | `/filter`       | `{"filter": string \| null}`           | `{"ok": true}`           |
+//! | GET    | `/profiles`     |                                        | `[NetworkProfile]`       |
+//! | POST   | `/profile`      | `{"id", "config"?}`                    | `Settings`               |
+//! | GET    | `/stats/stream` |                                        | `StatisticsSnapshot` SSE |
+//! | GET    | `/metrics`      |                                        | Prometheus text format   |
+//!
+//! `/stats/stream` answers with Server-Sent Events: a `statistics` event for
+//! every snapshot of the running session, until the client disconnects.
+//! `/metrics` is meant for a Prometheus scrape job, with the token set as its
+//! bearer credentials.
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::{debug, info, warn};
+use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::profiles::apply_profile_to;
+use crate::commands::start::start_session;
+use crate::commands::state::PacketProcessingState;
+use crate::commands::stats_stream::STATISTICS_EVENT;
+use crate::commands::status::{session_status, set_filter};
+use crate::commands::stop::stop_session;
+use crate::commands::types::ModuleInfo;
+use crate::commands::update::apply_modules;
+use crate::control::http::{
+    Request, Response, read_request, write_comment, write_event, write_event_stream_head,
+    write_response,
+};
+use crate::control::metrics;
+use crate::error::Result;
+use crate::network::backend::CaptureBackend;
+use crate::settings::{Settings, builtin_profiles};
+
+/// Port the control server listens on unless told otherwise
+pub const DEFAULT_PORT: u16 = 6972;
+
+/// How often the accept loop checks whether it should stop
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Path of the statistics event stream
+const STATS_STREAM_PATH: &str = "/stats/stream";
+
+/// Longest a statistics stream stays silent; idle streams send a comment
+/// instead, which also notices clients that went away
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Body of `POST /start`; missing fields keep the current session values
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct StartRequest {
+    settings: Option<Settings>,
+    filter: Option<String>,
+    /// Capture backend; `WinDivert` if omitted
+    backend: Option<CaptureBackend>,
+}
+
+/// Body of `PUT /filter`
+#[derive(Debug, Deserialize)]
+struct FilterRequest {
+    filter: Option<String>,
+}
+
+/// Body of `POST /profile`
+#[derive(Debug, Deserialize)]
+struct ProfileRequest {
+    id: String,
+    config: Option<String>,
+}
+
+/// Control server running on a background thread.
+///
+/// The server stops when [`stop`](Self::stop) is called or when it is dropped.
+pub struct ControlServer {
+    port: u16,
+    token: String,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl ControlServer {
+    /// Starts serving `state` on `127.0.0.1:port`.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - Session shared with the GUI
+    /// * `port` - Port to listen on; 0 picks a free one
+    /// * `token` - Token clients must send; `None` generates a random one
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ControlServer)` - The running server
+    /// * `Err(MyraError)` - If the port cannot be bound
+    pub fn start(state: PacketProcessingState, port: u16, token: Option<String>) -> Result<Self> {
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+
+        listener.set_nonblocking(true)?;
+
+        let port = listener.local_addr()?.port();
+        let token = token
+            .filter(|token| !token.is_empty())
+            .unwrap_or_else(generate_token);
+        let running = Arc::new(AtomicBool::new(true));
+
+        let handle = {
+            let token = token.clone();
+            let running = running.clone();
+
+            thread::spawn(move || serve(&listener, &state, &token, &running))
+        };
+
+        info!("Control server listening on 127.0.0.1:{}", port);
+
+        Ok(Self {
+            port,
+            token,
+            running,
+            handle: Some(handle),
+        })
+
+    }
+
+    /// Port the server is bound to.
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Token clients must send.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Whether the accept loop is still running.
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Stops accepting connections and waits for the accept loop to exit.
+    ///
+    /// Requests already being handled run to completion on their own threads;
+    /// statistics streams end within a second.
+    pub fn stop(&mut self) {
+
+        self.running.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+            info!("Control server on port {} stopped", self.port);
+        }
+
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Accepts connections until `running` is cleared, one thread per connection.
+fn serve(
+    listener: &TcpListener,
+    state: &PacketProcessingState,
+    token: &str,
+    running: &Arc<AtomicBool>,
+) {
+
+    while running.load(Ordering::SeqCst) {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                debug!("Control connection from {}", peer);
+
+                let state = state.clone();
+                let token = token.to_string();
+                let running = running.clone();
+
+                thread::spawn(move || handle_connection(&stream, &state, &token, &running));
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+            Err(e) => {
+                warn!("Failed to accept control connection: {}", e);
+                thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+        }
+    }
+
+}
+
+/// Reads one request from `stream`, answers it and closes the connection.
+fn handle_connection(
+    stream: &TcpStream,
+    state: &PacketProcessingState,
+    token: &str,
+    running: &AtomicBool,
+) {
+
+    // Accepted sockets inherit non-blocking mode from the listener on Windows
+    if let Err(e) = stream
+        .set_nonblocking(false)
+        .and_then(|()| stream.set_read_timeout(Some(READ_TIMEOUT)))
+    {
+        warn!("Failed to configure control connection: {}", e);
+        return;
+    }
+
+    let response = match read_request(stream) {
+        Ok(request) if is_stats_stream(&request, token) => {
+            stream_statistics(stream, state, running);
+            return;
+        }
+        Ok(request) => handle_request(state, token, &request),
+        Err(e) => Response::error(400, &e.to_string()),
+    };
+
+    if let Err(e) = write_response(stream, &response) {
+        debug!("Failed to write control response: {}", e);
+    }
+
+}
+
+/// Checks the token and dispatches `request` to the matching operation.
+fn handle_request(state: &PacketProcessingState, token: &str, request: &Request) -> Response {
+
+    if !request.token.as_deref().is_some_and(|given| tokens_match(given, token)) {
+        return Response::error(401, "Missing or invalid token");
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => respond(session_status(state)),
+        ("POST", "/start") => with_body(request, true, |body| {
+            acknowledge(start(state, body), 409)
+        }),
+        ("POST", "/stop") => acknowledge(stop_session(state), 409),
+        ("GET", "/settings") => respond(
+            state
+                .settings
+                .lock()
+                .map(|settings| settings.clone())
+                .map_err(|e| format!("Failed to lock settings mutex: {}", e)),
+        ),
+        ("PUT", "/settings") => with_body(request, false, |settings: Settings| {
+            acknowledge(replace_settings(state, settings), 500)
+        }),
+        ("PUT", "/modules") => with_body(request, false, |modules: Vec<ModuleInfo>| {
+            acknowledge(apply_modules(state, modules, None), 500)
+        }),
+        ("GET", "/filter") => respond(
+            state
+                .filter
+                .lock()
+                .map(|filter| filter.clone())
+                .map_err(|e| format!("Failed to lock filter mutex: {}", e)),
+        ),
+        ("PUT", "/filter") => with_body(request, false, |body: FilterRequest| {
+            acknowledge(set_filter(state, body.filter), 500)
+        }),
+        ("GET", "/profiles") => respond(Ok(builtin_profiles())),
+        ("GET", "/metrics") => match state.statistics.read() {
+            Ok(stats) => Response::with_content_type(
+                metrics::CONTENT_TYPE,
+                metrics::render(&stats, state.running.load(Ordering::SeqCst)),
+            ),
+            Err(e) => Response::error(500, &format!("Failed to read statistics: {}", e)),
+        },
+        ("POST", "/profile") => with_body(request, false, |body: ProfileRequest| {
+            respond(apply_profile_to(state, &body.id, body.config.as_deref()))
+        }),
+        (
+            _,
+            "/status" | "/start" | "/stop" | "/settings" | "/modules" | "/filter" | "/profiles"
+            | "/profile" | "/metrics" | STATS_STREAM_PATH,
+        ) => Response::error(405, "Method not allowed for this path"),
+        _ => Response::error(404, "Unknown path"),
+    }
+
+}
+
+/// Whether `request` is an authorized request for the statistics stream.
+fn is_stats_stream(request: &Request, token: &str) -> bool {
+    request.method == "GET"
+        && request.path == STATS_STREAM_PATH
+        && request.token.as_deref().is_some_and(|given| tokens_match(given, token))
+}
+
+/// Sends every statistics snapshot of the session as a Server-Sent Event.
+///
+/// Streams until the client disconnects or the server stops.
+fn stream_statistics(stream: &TcpStream, state: &PacketProcessingState, running: &AtomicBool) {
+
+    let snapshots = state.stats_hub.subscribe();
+
+    if let Err(e) = write_event_stream_head(stream) {
+        debug!("Failed to start statistics stream: {}", e);
+        return;
+    }
+
+    while running.load(Ordering::SeqCst) {
+        let written = match snapshots.recv_timeout(KEEPALIVE_INTERVAL) {
+            Ok(snapshot) => serde_json::to_string(&*snapshot)
+                .map_err(std::io::Error::from)
+                .and_then(|data| write_event(stream, STATISTICS_EVENT, &data)),
+            Err(RecvTimeoutError::Timeout) => write_comment(stream, "keepalive"),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        if let Err(e) = written {
+            debug!("Statistics stream closed: {}", e);
+            break;
+        }
+    }
+
+}
+
+/// Starts the session, keeping the current settings or filter when omitted.
+fn start(state: &PacketProcessingState, body: StartRequest) -> std::result::Result<(), String> {
+
+    let settings = match body.settings {
+        Some(settings) => settings,
+        None => state
+            .settings
+            .lock()
+            .map_err(|e| format!("Failed to lock settings mutex: {}", e))?
+            .clone(),
+    };
+
+    let filter = match body.filter {
+        Some(filter) => Some(filter),
+        None => state
+            .filter
+            .lock()
+            .map_err(|e| format!("Failed to lock filter mutex: {}", e))?
+            .clone(),
+    };
+
+    start_session(state, settings, filter, &body.backend.unwrap_or_default())
+
+}
+
+/// Replaces all settings of the session, including its seed and rules.
+fn replace_settings(
+    state: &PacketProcessingState,
+    settings: Settings,
+) -> std::result::Result<(), String> {
+
+    *state
+        .settings
+        .lock()
+        .map_err(|e| format!("Failed to lock settings mutex: {}", e))? = settings;
+
+    Ok(())
+
+}
+
+/// Parses the JSON body of `request` and passes it to `handler`.
+///
+/// An empty body counts as `{}` when `allow_empty` is set.
+fn with_body<T, F>(request: &Request, allow_empty: bool, handler: F) -> Response
+where
+    T: DeserializeOwned,
+    F: FnOnce(T) -> Response,
+{
+
+    let body = if allow_empty && request.body.iter().all(u8::is_ascii_whitespace) {
+        b"{}".as_slice()
+    } else {
+        &request.body
+    };
+
+    match serde_json::from_slice(body) {
+        Ok(body) => handler(body),
+        Err(e) => Response::error(400, &format!("Invalid body: {}", e)),
+    }
+
+}
+
+/// Serializes the result of a query, or reports its error.
+fn respond<T: Serialize>(result: std::result::Result<T, String>) -> Response {
+    match result.and_then(|value| serde_json::to_string(&value).map_err(|e| e.to_string())) {
+        Ok(body) => Response::ok(body),
+        Err(e) => Response::error(500, &e),
+    }
+}
+
+/// Acknowledges an operation, or reports its error with `error_status`.
+fn acknowledge(result: std::result::Result<(), String>, error_status: u16) -> Response {
+    match result {
+        Ok(()) => Response::ok(r#"{"ok":true}"#.to_string()),
+        Err(e) => Response::error(error_status, &e),
+    }
+}
+
+/// Compares tokens without stopping at the first differing byte.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Generates a random 128-bit token as hex.
+fn generate_token() -> String {
+    format!("{:032x}", rand::rng().random::<u128>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::stats_stream::run_stats_stream;
+    use std::io::{BufRead, Read, Write};
+
+    fn send(port: u16, raw: &str) -> String {
+
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        stream.write_all(raw.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+
+    }
+
+    fn request(port: u16, method: &str, path: &str, token: &str, body: &str) -> String {
+        send(
+            port,
+            &format!(
+                "{} {} HTTP/1.1\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\n\r\n{}",
+                method,
+                path,
+                token,
+                body.len(),
+                body
+            ),
+        )
+    }
+
+    #[test]
+    fn test_requests_need_the_token() {
+
+        let mut server =
+            ControlServer::start(PacketProcessingState::default(), 0, Some("secret".into()))
+                .unwrap();
+        let port = server.port();
+
+        let response = send(port, "GET /status HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 401"));
+
+        let response = request(port, "GET", "/status", "wrong!", "");
+        assert!(response.starts_with("HTTP/1.1 401"));
+
+        let response = request(port, "GET", "/status", "secret", "");
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains(r#""running":false"#));
+
+        server.stop();
+        assert!(!server.is_running());
+
+    }
+
+    #[test]
+    fn test_filter_is_shared_with_state() {
+
+        let state = PacketProcessingState::default();
+        let server = ControlServer::start(state.clone(), 0, None).unwrap();
+        let (port, token) = (server.port(), server.token().to_string());
+
+        assert_eq!(token.len(), 32);
+
+        let response = request(port, "PUT", "/filter", &token, r#"{"filter":"outbound"}"#);
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert_eq!(state.filter.lock().unwrap().as_deref(), Some("outbound"));
+
+        let response = request(port, "GET", "/filter", &token, "");
+        assert!(response.ends_with(r#""outbound""#));
+
+    }
+
+    #[test]
+    fn test_profile_is_applied_to_state() {
+
+        let state = PacketProcessingState::default();
+        let server = ControlServer::start(state.clone(), 0, None).unwrap();
+        let (port, token) = (server.port(), server.token().to_string());
+
+        let response = request(port, "GET", "/profiles", &token, "");
+        assert!(response.contains(r#""id":"lte""#));
+
+        let response = request(port, "POST", "/profile", &token, r#"{"id":"lte"}"#);
+        assert!(response.starts_with("HTTP/1.1 200"));
+
+        let settings = state.settings.lock().unwrap();
+        assert_eq!(settings.lag.as_ref().map(|lag| lag.delay_ms), Some(10));
+        drop(settings);
+
+        let response = request(port, "POST", "/profile", &token, r#"{"id":"dial-up"}"#);
+        assert!(response.starts_with("HTTP/1.1 500"));
+
+    }
+
+    #[test]
+    fn test_unknown_routes_and_bad_bodies() {
+
+        let server = ControlServer::start(PacketProcessingState::default(), 0, None).unwrap();
+        let (port, token) = (server.port(), server.token().to_string());
+
+        assert!(request(port, "GET", "/nope", &token, "").starts_with("HTTP/1.1 404"));
+        assert!(request(port, "DELETE", "/filter", &token, "").starts_with("HTTP/1.1 405"));
+        assert!(request(port, "PUT", "/settings", &token, "{").starts_with("HTTP/1.1 400"));
+        assert!(request(port, "POST", "/stop", &token, "").starts_with("HTTP/1.1 409"));
+
+    }
+
+    #[test]
+    fn test_stats_stream_sends_snapshots_as_events() {
+
+        let state = PacketProcessingState::default();
+        let server = ControlServer::start(state.clone(), 0, None).unwrap();
+        let (port, token) = (server.port(), server.token().to_string());
+
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        write!(
+            stream,
+            "GET /stats/stream HTTP/1.1\r\nAuthorization: Bearer {}\r\n\r\n",
+            token
+        )
+        .unwrap();
+
+        let mut reader = std::io::BufReader::new(stream);
+        let mut line = String::new();
+
+        reader.read_line(&mut line).unwrap();
+        assert!(line.starts_with("HTTP/1.1 200"));
+
+        // The head is written once the connection has subscribed
+        assert!(state.stats_hub.subscriber_count() > 0);
+
+        run_stats_stream(&state.statistics, &AtomicBool::new(false), &state.stats_hub);
+
+        let events: Vec<String> = reader
+            .lines()
+            .map(std::result::Result::unwrap)
+            .skip_while(|line| !line.starts_with("event:"))
+            .take(2)
+            .collect();
+
+        assert_eq!(events[0], "event: statistics");
+        assert!(events[1].starts_with("data: {"));
+        assert!(events[1].contains(r#""running":false"#));
+        assert!(events[1].contains(r#""peak_held_packets":0"#));
+
+    }
+
+    #[test]
+    fn test_metrics_are_served_as_text() {
+
+        let state = PacketProcessingState::default();
+        let server = ControlServer::start(state.clone(), 0, None).unwrap();
+        let (port, token) = (server.port(), server.token().to_string());
+
+        state.statistics.write().unwrap().drop_stats.record(true);
+
+        let response = request(port, "GET", "/metrics", &token, "");
+
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+        assert!(response.contains("myra_running 0\n"));
+        assert!(response.contains("myra_module_dropped_packets_total{module=\"drop\"} 1\n"));
+
+        assert!(request(port, "GET", "/metrics", "wrong", "").starts_with("HTTP/1.1 401"));
+        assert!(request(port, "POST", "/metrics", &token, "").starts_with("HTTP/1.1 405"));
+
+    }
+
+    #[test]
+    fn test_tokens_match() {
+
+        assert!(tokens_match("abc", "abc"));
+        assert!(!tokens_match("abd", "abc"));
+        assert!(!tokens_match("ab", "abc"));
+
+    }
+}
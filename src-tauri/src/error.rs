@@ -3,6 +3,7 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum MyraError {
     /// Error from `WinDivert` operations
+    #[cfg(windows)]
     #[error("WinDivert error: {0}")]
     WinDivert(#[from] windivert::error::WinDivertError),
     /// Error when a mutex/rwlock is poisoned
@@ -14,6 +15,18 @@ pub enum MyraError {
     /// I/O errors from file operations
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
+    /// Error from a packet I/O backend
+    #[error("Backend error: {0}")]
+    Backend(String),
+    /// Malformed or unsupported pcap/pcapng capture file
+    #[error("Capture file error: {0}")]
+    Capture(String),
+    /// Scenario file that cannot be parsed or never takes effect
+    #[error("Scenario error: {0}")]
+    Scenario(String),
+    /// Filter expression that cannot be parsed
+    #[error("Invalid filter: {0}")]
+    Filter(#[from] crate::network::filter::FilterError),
 }
 
 /// A convenient Result type alias using `MyraError`.
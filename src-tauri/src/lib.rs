@@ -64,8 +64,12 @@
 
 /// Commands exposed to the Tauri frontend.
 pub mod commands;
+/// Localhost control API for scripted sessions.
+pub mod control;
 /// Centralized error handling.
 pub mod error;
+/// Console logging for the binaries.
+pub mod logger;
 /// Network packet manipulation functionality.
 pub mod network;
 /// Prelude for convenient imports.
@@ -0,0 +1,52 @@
+//! Console logger shared by the GUI and CLI binaries.
+use log::{LevelFilter, SetLoggerError, error};
+use std::io::{self, Write};
+
+struct SimpleLogger;
+
+impl log::Log for SimpleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &log::Record) {
+
+        if self.enabled(record.metadata()) {
+            let mut stdout = io::stdout();
+            let timestamp = chrono::Local::now().format("%H:%M:%S%.3f");
+
+            writeln!(
+                stdout,
+                "[{}] {} - {}: {}",
+                timestamp,
+                record.level(),
+                record.target(),
+                record.args()
+            )
+            .unwrap_or_else(|e| error!("Failed to write log: {}", e));
+            stdout
+                .flush()
+                .unwrap_or_else(|e| error!("Failed to flush stdout: {}", e));
+        }
+
+    }
+
+    fn flush(&self) {
+        io::stdout()
+            .flush()
+            .unwrap_or_else(|e| error!("Failed to flush stdout: {}", e));
+    }
+}
+
+static LOGGER: SimpleLogger = SimpleLogger;
+
+/// Initialize the application logger
+///
+/// Sets up the `SimpleLogger`, writing records up to `level` to stdout
+///
+/// # Arguments
+///
+/// * `level` - Most verbose level that is printed
+pub fn init_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER).map(|()| log::set_max_level(level))
+}
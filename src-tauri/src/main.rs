@@ -1,60 +1,16 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![warn(clippy::all)]
 
-use log::{LevelFilter, SetLoggerError, error, info};
-use std::io::{self, Write};
+use log::{LevelFilter, error, info};
 
 use myra::commands;
-
-struct SimpleLogger;
-
-impl log::Log for SimpleLogger {
-    fn enabled(&self, metadata: &log::Metadata) -> bool {
-        metadata.level() <= log::max_level()
-    }
-
-    fn log(&self, record: &log::Record) {
-
-        if self.enabled(record.metadata()) {
-            let mut stdout = io::stdout();
-            let timestamp = chrono::Local::now().format("%H:%M:%S%.3f");
-
-            writeln!(
-                stdout,
-                "[{}] {} - {}: {}",
-                timestamp,
-                record.level(),
-                record.target(),
-                record.args()
-            )
-            .unwrap_or_else(|e| error!("Failed to write log: {}", e));
-            stdout
-                .flush()
-                .unwrap_or_else(|e| error!("Failed to flush stdout: {}", e));
-        }
-
-    }
-
-    fn flush(&self) {
-        io::stdout()
-            .flush()
-            .unwrap_or_else(|e| error!("Failed to flush stdout: {}", e));
-    }
-}
-
-static LOGGER: SimpleLogger = SimpleLogger;
-
-/// Initialize the application logger
-///
-/// Sets up the SimpleLogger with Info level filtering
-fn init_logger() -> Result<(), SetLoggerError> {
-    log::set_logger(&LOGGER).map(|()| log::set_max_level(LevelFilter::Info))
-}
+use myra::logger::init_logger;
+use myra::utils::is_admin;
 
 /// Main entry point for the Myra application
 fn main() {
 
-    if let Err(e) = init_logger() {
+    if let Err(e) = init_logger(LevelFilter::Info) {
         eprintln!("Failed to initialize logger: {}", e);
         return;
     }
@@ -80,16 +36,23 @@ fn main() {
             commands::start_processing,
             commands::stop_processing,
             commands::get_status,
+            commands::get_top_flows,
             commands::update_settings,
             commands::get_settings,
             commands::update_filter,
             commands::get_filter,
             commands::get_filter_history,
             commands::clear_filter_history,
+            commands::get_filter_library,
+            commands::save_filter,
+            commands::remove_filter,
+            commands::export_filter_library,
+            commands::import_filter_library,
             commands::config::save_config,
             commands::config::load_config,
             commands::config::list_configs,
             commands::config::delete_config,
+            commands::config::compile_filter_target,
             commands::list_processes,
             commands::scan_network_devices,
             commands::build_process_filter,
@@ -99,9 +62,26 @@ fn main() {
             commands::stop_flow_tracking,
             commands::get_flow_filter,
             commands::is_flow_tracking,
+            commands::get_tracked_processes,
             commands::start_tc_bandwidth,
             commands::stop_tc_bandwidth,
             commands::get_tc_bandwidth_status,
+            commands::replay_capture_file,
+            commands::start_recording,
+            commands::stop_recording,
+            commands::is_recording,
+            commands::get_rules,
+            commands::set_rules,
+            commands::start_control_server,
+            commands::stop_control_server,
+            commands::get_control_server_status,
+            commands::start_scenario,
+            commands::stop_scenario,
+            commands::get_scenario_status,
+            commands::get_tap,
+            commands::set_tap,
+            commands::list_profiles,
+            commands::apply_profile,
             // Classic mode commands
             commands::start_classic_processing,
             commands::stop_classic_processing,
@@ -112,49 +92,3 @@ fn main() {
         .expect("error while running tauri application");
 
 }
-
-/// Check if the current process is running with administrator privileges.
-///
-/// Uses Windows API to determine if the current process has admin rights,
-/// which are required for packet manipulation.
-///
-/// # Returns
-///
-/// `bool` - true if the process has administrator privileges
-fn is_admin() -> bool {
-
-    use winapi::um::securitybaseapi::{AllocateAndInitializeSid, CheckTokenMembership, FreeSid};
-    use winapi::um::winnt::{
-        DOMAIN_ALIAS_RID_ADMINS, SECURITY_BUILTIN_DOMAIN_RID, SECURITY_NT_AUTHORITY,
-    };
-
-    unsafe {
-        let mut sid = std::ptr::null_mut();
-        let sub_authorities = [SECURITY_BUILTIN_DOMAIN_RID, DOMAIN_ALIAS_RID_ADMINS];
-
-        if AllocateAndInitializeSid(
-            &SECURITY_NT_AUTHORITY as *const _ as *mut _,
-            2,
-            sub_authorities[0],
-            sub_authorities[1],
-            0,
-            0,
-            0,
-            0,
-            0,
-            0,
-            &mut sid,
-        ) == 0
-        {
-            return false;
-        }
-
-        let mut is_member = 0;
-        let is_admin =
-            CheckTokenMembership(std::ptr::null_mut(), sid, &mut is_member) != 0 && is_member != 0;
-
-        FreeSid(sid);
-        is_admin
-    }
-
-}
@@ -0,0 +1,191 @@
+//! In-memory channel packet backend.
+//!
+//! Feeds the pipeline from an `mpsc` channel and collects its output into
+//! another one. Used to drive the full module chain without a capture driver.
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use crate::error::{MyraError, Result};
+use crate::network::backend::{PacketSink, PacketSource};
+use crate::network::core::PacketData;
+use crate::network::filter::Filter;
+
+/// How long `recv_packet` waits for a packet before returning `None`.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Packet source that yields packets pushed into an `mpsc` channel.
+///
+/// Packets sent into the channel are delivered while a filter is applied
+/// and matches them; the rest are discarded.
+pub struct ChannelSource {
+    receiver: Receiver<PacketData>,
+    filter: Option<String>,
+    compiled: Option<Filter>,
+    poll_interval: Duration,
+}
+
+impl ChannelSource {
+    /// Creates a source reading from the given channel.
+    pub fn new(receiver: Receiver<PacketData>) -> Self {
+
+        Self {
+            receiver,
+            filter: None,
+            compiled: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+
+    }
+
+    /// Sets how long a receive call blocks waiting for a packet.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Returns the currently applied filter.
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+}
+
+impl PacketSource for ChannelSource {
+    fn apply_filter(&mut self, filter: Option<&str>) -> Result<()> {
+
+        self.compiled = filter.map(Filter::parse).transpose()?;
+        self.filter = filter.map(str::to_string);
+        Ok(())
+
+    }
+
+    fn is_capturing(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    fn recv_packet(&mut self) -> Result<Option<PacketData>> {
+
+        let Some(filter) = &self.compiled else {
+            return Ok(None);
+        };
+
+        match self.receiver.recv_timeout(self.poll_interval) {
+            Ok(packet) => Ok(filter.matches(&packet).then_some(packet)),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => {
+                // Nothing more will arrive; behave like an idle capture
+                std::thread::sleep(self.poll_interval);
+                Ok(None)
+            }
+        }
+
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+
+        self.filter = None;
+        self.compiled = None;
+        Ok(())
+
+    }
+}
+
+/// Packet sink that forwards sent packets into an `mpsc` channel.
+pub struct ChannelSink {
+    sender: Sender<PacketData>,
+}
+
+impl ChannelSink {
+    /// Creates a sink writing into the given channel.
+    pub fn new(sender: Sender<PacketData>) -> Self {
+        Self { sender }
+    }
+}
+
+impl PacketSink for ChannelSink {
+    fn send_packet(&mut self, packet: &PacketData) -> Result<()> {
+
+        self.sender
+            .send(packet.clone())
+            .map_err(|_| MyraError::Backend("Packet sink channel disconnected".to_string()))
+
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+    use std::sync::mpsc;
+
+    fn create_packet(data: Vec<u8>) -> PacketData {
+        PacketData::new(data, true)
+    }
+
+    #[test]
+    fn test_channel_source_requires_filter() {
+
+        let (tx, rx) = mpsc::channel();
+        let mut source = ChannelSource::new(rx).poll_interval(Duration::from_millis(1));
+
+        tx.send(create_packet(vec![1, 2, 3])).unwrap();
+
+        assert!(!source.is_capturing());
+        assert!(source.recv_packet().unwrap().is_none());
+
+        source.apply_filter(Some("true")).unwrap();
+
+        assert!(source.is_capturing());
+        assert_eq!(source.filter(), Some("true"));
+
+        let packet = source.recv_packet().unwrap().expect("packet should be delivered");
+
+        assert_eq!(packet.data[..], [1, 2, 3]);
+        assert!(source.recv_packet().unwrap().is_none());
+
+    }
+
+    #[test]
+    fn test_channel_source_evaluates_filter() {
+
+        let (tx, rx) = mpsc::channel();
+        let mut source = ChannelSource::new(rx).poll_interval(Duration::from_millis(1));
+
+        assert!(source.apply_filter(Some("udp.DstPort ==")).is_err());
+
+        source.apply_filter(Some("udp.DstPort == 27015")).unwrap();
+
+        let dns = udp_ipv4_packet(5000, 53, b"");
+        let game = udp_ipv4_packet(5000, 27015, b"");
+
+        tx.send(create_packet(dns)).unwrap();
+        tx.send(create_packet(game.clone())).unwrap();
+
+        assert!(source.recv_packet().unwrap().is_none());
+        assert_eq!(source.recv_packet().unwrap().map(|packet| packet.data), Some(game));
+
+    }
+
+    #[test]
+    fn test_channel_sink_forwards_packets() {
+
+        let (tx, rx) = mpsc::channel();
+        let mut sink = ChannelSink::new(tx);
+        let packet = create_packet(vec![4, 5, 6]);
+
+        sink.send_packet(&packet).unwrap();
+
+        let sent = rx.try_recv().unwrap();
+
+        assert_eq!(sent.data[..], [4, 5, 6]);
+        assert!(sent.is_outbound);
+
+        drop(rx);
+
+        assert!(sink.send_packet(&packet).is_err());
+
+    }
+}
@@ -0,0 +1,249 @@
+//! Capture handle hand-over on filter changes.
+//!
+//! A capture driver queues packets per handle, so closing a handle to apply
+//! a new filter would lose whatever it had queued. [`HandleHandover`] opens
+//! the replacement first, stops the previous handle from capturing and keeps
+//! receiving from it until its queue is empty, so no packet is lost or
+//! reordered across the change.
+use log::debug;
+
+use crate::error::Result;
+use crate::network::core::PacketData;
+
+/// A capture handle that can stop capturing while its queue is drained.
+pub trait DrainableHandle {
+    /// Receives the next queued packet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(PacketData))` - The next packet
+    /// * `Ok(None)` - If the handle stopped capturing and its queue is empty
+    /// * `Err(MyraError)` - If receiving failed
+    fn recv(&mut self) -> Result<Option<PacketData>>;
+
+    /// Stops the handle capturing new packets; queued ones can still be received.
+    fn stop_capturing(&mut self) -> Result<()>;
+
+    /// Closes the handle, discarding anything still queued.
+    fn close(&mut self) -> Result<()>;
+}
+
+/// The current capture handle and the one it replaced, until that is drained.
+pub struct HandleHandover<H: DrainableHandle> {
+    current: Option<H>,
+    draining: Option<H>,
+}
+
+impl<H: DrainableHandle> HandleHandover<H> {
+    /// Creates a hand-over without any handle.
+    pub const fn new() -> Self {
+
+        Self {
+            current: None,
+            draining: None,
+        }
+
+    }
+
+    /// Returns whether a handle is capturing.
+    pub const fn is_capturing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Makes `handle` the current handle and starts draining the previous one.
+    ///
+    /// A handle still draining from an earlier change is closed first.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - Newly opened handle, already capturing
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the handles were swapped
+    /// * `Err(MyraError)` - If the previous handle could not be stopped or closed
+    pub fn replace(&mut self, handle: H) -> Result<()> {
+
+        self.close_draining()?;
+
+        if let Some(mut previous) = self.current.replace(handle) {
+            if let Err(e) = previous.stop_capturing() {
+                previous.close()?;
+                return Err(e);
+            }
+            self.draining = Some(previous);
+        }
+        Ok(())
+
+    }
+
+    /// Closes both handles.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every open handle was closed
+    /// * `Err(MyraError)` - If closing a handle failed
+    pub fn close(&mut self) -> Result<()> {
+
+        let draining = self.close_draining();
+
+        if let Some(mut handle) = self.current.take() {
+            handle.close()?;
+        }
+        draining
+
+    }
+
+    /// Receives the next packet, from the draining handle while it has any.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(PacketData))` - The next packet
+    /// * `Ok(None)` - If no handle is capturing
+    /// * `Err(MyraError)` - If receiving failed
+    pub fn recv(&mut self) -> Result<Option<PacketData>> {
+
+        if let Some(handle) = self.draining.as_mut() {
+            match handle.recv() {
+                Ok(Some(packet)) => return Ok(Some(packet)),
+                Ok(None) => {
+                    debug!("Drained the queue of the previous capture handle");
+                    self.close_draining()?;
+                }
+                Err(e) => {
+                    self.close_draining()?;
+                    return Err(e);
+                }
+            }
+        }
+
+        self.current.as_mut().map_or(Ok(None), DrainableHandle::recv)
+
+    }
+
+    /// Closes the handle being drained, if any.
+    fn close_draining(&mut self) -> Result<()> {
+
+        if let Some(mut handle) = self.draining.take() {
+            debug!("Closing drained capture handle");
+            handle.close()?;
+        }
+        Ok(())
+
+    }
+}
+
+impl<H: DrainableHandle> Default for HandleHandover<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+
+    /// Events recorded by the fake handles, in order.
+    type Log = Rc<RefCell<Vec<String>>>;
+
+    /// In-memory handle whose queue is filled by the test.
+    struct FakeHandle {
+        name: &'static str,
+        queue: VecDeque<u8>,
+        capturing: bool,
+        log: Log,
+    }
+
+    impl FakeHandle {
+        fn new(name: &'static str, queued: &[u8], log: &Log) -> Self {
+            Self {
+                name,
+                queue: queued.iter().copied().collect(),
+                capturing: true,
+                log: Rc::clone(log),
+            }
+        }
+    }
+
+    impl DrainableHandle for FakeHandle {
+        fn recv(&mut self) -> Result<Option<PacketData>> {
+            match self.queue.pop_front() {
+                Some(byte) => Ok(Some(PacketData::new(vec![byte], true))),
+                // A capturing handle would block; the tests never get here
+                None if self.capturing => panic!("{} would block", self.name),
+                None => Ok(None),
+            }
+        }
+
+        fn stop_capturing(&mut self) -> Result<()> {
+            self.capturing = false;
+            self.log.borrow_mut().push(format!("stop {}", self.name));
+            Ok(())
+        }
+
+        fn close(&mut self) -> Result<()> {
+            self.log.borrow_mut().push(format!("close {}", self.name));
+            Ok(())
+        }
+    }
+
+    fn next_byte(handover: &mut HandleHandover<FakeHandle>) -> u8 {
+        handover.recv().unwrap().unwrap().data[0]
+    }
+
+    #[test]
+    fn test_replace_drains_previous_handle_first() {
+
+        let log = Log::default();
+        let mut handover = HandleHandover::new();
+
+        handover.replace(FakeHandle::new("old", &[1, 2], &log)).unwrap();
+        assert_eq!(next_byte(&mut handover), 1);
+
+        // The new handle already queued packets while the old one still held some
+        handover.replace(FakeHandle::new("new", &[3, 4], &log)).unwrap();
+        assert_eq!(*log.borrow(), ["stop old"]);
+
+        assert_eq!(next_byte(&mut handover), 2);
+        assert_eq!(next_byte(&mut handover), 3);
+        assert_eq!(*log.borrow(), ["stop old", "close old"]);
+        assert_eq!(next_byte(&mut handover), 4);
+        assert!(handover.is_capturing());
+
+    }
+
+    #[test]
+    fn test_replace_while_draining_closes_oldest_handle() {
+
+        let log = Log::default();
+        let mut handover = HandleHandover::new();
+
+        handover.replace(FakeHandle::new("a", &[1], &log)).unwrap();
+        handover.replace(FakeHandle::new("b", &[2], &log)).unwrap();
+        handover.replace(FakeHandle::new("c", &[3], &log)).unwrap();
+
+        assert_eq!(*log.borrow(), ["stop a", "close a", "stop b"]);
+        assert_eq!(next_byte(&mut handover), 2);
+        assert_eq!(next_byte(&mut handover), 3);
+
+    }
+
+    #[test]
+    fn test_close_closes_both_handles() {
+
+        let log = Log::default();
+        let mut handover = HandleHandover::new();
+
+        handover.replace(FakeHandle::new("old", &[1], &log)).unwrap();
+        handover.replace(FakeHandle::new("new", &[], &log)).unwrap();
+        handover.close().unwrap();
+
+        assert_eq!(*log.borrow(), ["stop old", "close old", "close new"]);
+        assert!(!handover.is_capturing());
+        assert!(handover.recv().unwrap().is_none());
+
+    }
+}
@@ -0,0 +1,170 @@
+//! Packet I/O backends.
+//!
+//! The processing pipeline never talks to a capture driver directly. Packets
+//! are read from a [`PacketSource`] and written back through a [`PacketSink`],
+//! so the same module chain can run on top of `WinDivert`, a Linux TUN device,
+//! or an in-memory channel (for tests and offline tooling).
+pub mod channel;
+pub mod handover;
+#[cfg(target_os = "linux")]
+pub mod tun;
+#[cfg(windows)]
+pub mod windivert;
+
+pub use channel::{ChannelSink, ChannelSource};
+pub use handover::{DrainableHandle, HandleHandover};
+#[cfg(target_os = "linux")]
+pub use tun::{TunConfig, TunDevice, TunSink, TunSource};
+#[cfg(windows)]
+pub use windivert::{WinDivertSink, WinDivertSource};
+
+use std::net::IpAddr;
+#[cfg(target_os = "linux")]
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MyraError, Result};
+use crate::network::core::PacketData;
+
+/// Capture backend a session runs on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum CaptureBackend {
+    /// The `WinDivert` driver, on Windows
+    #[default]
+    WinDivert,
+    /// A Linux TUN device that traffic is routed through
+    Tun {
+        /// Interface name (e.g. `myra0`)
+        device: String,
+        /// Addresses considered local; packets sourced from them are outbound
+        #[serde(default)]
+        local_addresses: Vec<IpAddr>,
+    },
+}
+
+impl CaptureBackend {
+    /// Prepares the backend for a session, opening the TUN device if there is one.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(BackendHandle)` - The backend to create the session's source and sink from
+    /// * `Err(MyraError)` - If the backend is unavailable on this platform or cannot be opened
+    pub fn open(&self) -> Result<BackendHandle> {
+
+        match self {
+            #[cfg(windows)]
+            Self::WinDivert => Ok(BackendHandle::WinDivert),
+            #[cfg(not(windows))]
+            Self::WinDivert => Err(MyraError::Backend(
+                "WinDivert capture is only available on Windows".to_string(),
+            )),
+            #[cfg(target_os = "linux")]
+            Self::Tun {
+                device,
+                local_addresses,
+            } => {
+                let config = local_addresses
+                    .iter()
+                    .fold(TunConfig::with_name(device), |config, address| {
+                        config.local_address(*address)
+                    });
+
+                Ok(BackendHandle::Tun(TunDevice::open(config)?))
+            }
+            #[cfg(not(target_os = "linux"))]
+            Self::Tun { .. } => Err(MyraError::Backend(
+                "TUN capture is only available on Linux".to_string(),
+            )),
+        }
+
+    }
+}
+
+/// An opened capture backend.
+///
+/// `WinDivert` handles cannot move between threads, so the receiver and
+/// processor threads each create their end of the backend from this.
+#[derive(Clone)]
+pub enum BackendHandle {
+    #[cfg(windows)]
+    WinDivert,
+    #[cfg(target_os = "linux")]
+    Tun(Arc<TunDevice>),
+}
+
+impl BackendHandle {
+    /// Creates the source that captures the session's packets.
+    pub fn source(&self) -> Box<dyn PacketSource> {
+        match *self {
+            #[cfg(windows)]
+            Self::WinDivert => Box::new(WinDivertSource::new()),
+            #[cfg(target_os = "linux")]
+            Self::Tun(ref device) => Box::new(TunSource::new(Arc::clone(device))),
+        }
+    }
+
+    /// Creates the sink that processed packets are sent through.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Box<dyn PacketSink>)` - The sink
+    /// * `Err(MyraError)` - If the backend could not open a send handle
+    pub fn sink(&self) -> Result<Box<dyn PacketSink>> {
+        match *self {
+            #[cfg(windows)]
+            Self::WinDivert => Ok(Box::new(WinDivertSink::open()?)),
+            #[cfg(target_os = "linux")]
+            Self::Tun(ref device) => Ok(Box::new(TunSink::new(Arc::clone(device)))),
+        }
+    }
+}
+
+/// A backend that captures packets from the network.
+pub trait PacketSource {
+    /// Applies a capture filter, replacing any previously applied filter.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Filter expression to capture, or `None` to stop capturing
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the filter was applied
+    /// * `Err(MyraError)` - If the backend could not be reconfigured
+    fn apply_filter(&mut self, filter: Option<&str>) -> Result<()>;
+
+    /// Returns whether the source currently has an active capture.
+    fn is_capturing(&self) -> bool;
+
+    /// Receives the next captured packet.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(PacketData))` - A packet was captured
+    /// * `Ok(None)` - No packet was available before the backend returned
+    /// * `Err(MyraError)` - If receiving failed
+    fn recv_packet(&mut self) -> Result<Option<PacketData>>;
+
+    /// Stops capturing and releases any backend resources.
+    fn shutdown(&mut self) -> Result<()>;
+}
+
+/// A backend that injects processed packets back into the network.
+pub trait PacketSink {
+    /// Sends a single packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - The packet to inject
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the packet was sent
+    /// * `Err(MyraError)` - If sending failed
+    fn send_packet(&mut self, packet: &PacketData) -> Result<()>;
+
+    /// Releases any backend resources.
+    fn shutdown(&mut self) -> Result<()>;
+}
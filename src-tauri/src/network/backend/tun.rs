@@ -0,0 +1,327 @@
+//! Linux TUN device packet backend.
+//!
+//! Traffic that the kernel routes into a TUN interface is read as raw IP
+//! packets, run through the module chain, and written back to the same
+//! interface, where the kernel forwards it on as usual. The interface and
+//! routing (e.g. `ip route` / `ip rule` towards the device) are set up by the
+//! operator; this backend only attaches to it.
+use std::fs::File;
+use std::io::{Read, Write};
+use std::net::IpAddr;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::sync::Arc;
+
+use log::{debug, info};
+
+use crate::error::{MyraError, Result};
+use crate::network::backend::{PacketSink, PacketSource};
+use crate::network::core::PacketData;
+use crate::network::filter::Filter;
+
+/// `TUNSETIFF` ioctl request number.
+const TUNSETIFF: libc::c_ulong = 0x4004_54ca;
+
+/// Maximum size of a single packet read from the device.
+const MAX_PACKET_SIZE: usize = 65535;
+
+/// How long a receive call waits for the device to become readable, in milliseconds.
+const POLL_TIMEOUT_MS: libc::c_int = 100;
+
+/// Interface request passed to `TUNSETIFF`.
+#[repr(C)]
+struct IfReq {
+    name: [libc::c_char; libc::IFNAMSIZ],
+    flags: libc::c_short,
+    _padding: [u8; 22],
+}
+
+/// Configuration for attaching to a TUN device.
+#[derive(Debug, Clone)]
+pub struct TunConfig {
+    /// Interface name (e.g. `myra0`)
+    pub name: String,
+    /// Addresses considered local; packets sourced from them are outbound
+    pub local_addresses: Vec<IpAddr>,
+}
+
+impl TunConfig {
+    /// Creates a new `TunConfig` for the given interface name.
+    pub fn with_name(name: impl Into<String>) -> Self {
+
+        Self {
+            name: name.into(),
+            local_addresses: Vec::new(),
+        }
+
+    }
+
+    /// Adds an address whose traffic is treated as outbound.
+    pub fn local_address(mut self, address: IpAddr) -> Self {
+        self.local_addresses.push(address);
+        self
+    }
+
+    /// Determines packet direction from its IP source address.
+    ///
+    /// With no local addresses configured every packet is treated as outbound.
+    fn is_outbound(&self, data: &[u8]) -> bool {
+
+        if self.local_addresses.is_empty() {
+            return true;
+        }
+
+        source_address(data).is_some_and(|src| self.local_addresses.contains(&src))
+
+    }
+}
+
+/// An open TUN device shared by a [`TunSource`] and a [`TunSink`].
+pub struct TunDevice {
+    file: File,
+    config: TunConfig,
+}
+
+impl TunDevice {
+    /// Attaches to (or creates) the TUN interface described by `config`.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Interface name and direction settings
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Arc<TunDevice>)` - If the device was opened
+    /// * `Err(MyraError)` - If `/dev/net/tun` could not be opened or configured
+    pub fn open(config: TunConfig) -> Result<Arc<Self>> {
+
+        if config.name.is_empty() || config.name.len() >= libc::IFNAMSIZ {
+            return Err(MyraError::Backend(format!(
+                "Invalid TUN interface name: '{}'",
+                config.name
+            )));
+        }
+
+        let fd = unsafe {
+            libc::open(
+                b"/dev/net/tun\0".as_ptr().cast(),
+                libc::O_RDWR | libc::O_CLOEXEC,
+            )
+        };
+
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        // Take ownership right away so the descriptor is closed on every error path
+        let file = unsafe { File::from_raw_fd(fd) };
+
+        let mut request = IfReq {
+            name: [0; libc::IFNAMSIZ],
+            flags: (libc::IFF_TUN | libc::IFF_NO_PI) as libc::c_short,
+            _padding: [0; 22],
+        };
+
+        for (dst, src) in request.name.iter_mut().zip(config.name.bytes()) {
+            *dst = libc::c_char::from_ne_bytes([src]);
+        }
+
+        if unsafe { libc::ioctl(file.as_raw_fd(), TUNSETIFF, &mut request) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+
+        info!("Attached to TUN device {}", config.name);
+
+        Ok(Arc::new(Self { file, config }))
+
+    }
+
+    /// Returns the interface name.
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Waits until the device is readable or the poll timeout expires.
+    fn wait_readable(&self) -> Result<bool> {
+
+        let mut poll_fd = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        let ready = unsafe { libc::poll(std::ptr::addr_of_mut!(poll_fd), 1, POLL_TIMEOUT_MS) };
+
+        if ready < 0 {
+            let error = std::io::Error::last_os_error();
+
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            return Err(error.into());
+        }
+
+        Ok(ready > 0 && poll_fd.revents & libc::POLLIN != 0)
+
+    }
+}
+
+/// Packet source reading from a [`TunDevice`].
+///
+/// Routing decides what reaches the device; of that, packets the filter
+/// does not match are written straight back to it untouched.
+pub struct TunSource {
+    device: Arc<TunDevice>,
+    filter: Option<Filter>,
+    buffer: Vec<u8>,
+}
+
+impl TunSource {
+    /// Creates a source on the given device.
+    pub fn new(device: Arc<TunDevice>) -> Self {
+
+        Self {
+            device,
+            filter: None,
+            buffer: vec![0u8; MAX_PACKET_SIZE],
+        }
+
+    }
+}
+
+impl PacketSource for TunSource {
+    fn apply_filter(&mut self, filter: Option<&str>) -> Result<()> {
+
+        self.filter = filter.map(Filter::parse).transpose()?;
+
+        if let Some(filter) = &self.filter {
+            debug!("TUN backend on {} capturing {}", self.device.name(), filter);
+        }
+        Ok(())
+
+    }
+
+    fn is_capturing(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    fn recv_packet(&mut self) -> Result<Option<PacketData>> {
+
+        let Some(filter) = &self.filter else {
+            return Ok(None);
+        };
+
+        if !self.device.wait_readable()? {
+            return Ok(None);
+        }
+
+        let len = (&self.device.file).read(&mut self.buffer)?;
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let data = self.buffer[..len].to_vec();
+        let is_outbound = self.device.config.is_outbound(&data);
+        let packet = PacketData::new(data, is_outbound);
+
+        if !filter.matches(&packet) {
+            (&self.device.file).write_all(&packet.data)?;
+            return Ok(None);
+        }
+
+        Ok(Some(packet))
+
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        self.filter = None;
+        Ok(())
+    }
+}
+
+/// Packet sink writing back into a [`TunDevice`].
+pub struct TunSink {
+    device: Arc<TunDevice>,
+}
+
+impl TunSink {
+    /// Creates a sink on the given device.
+    pub fn new(device: Arc<TunDevice>) -> Self {
+        Self { device }
+    }
+}
+
+impl PacketSink for TunSink {
+    fn send_packet(&mut self, packet: &PacketData) -> Result<()> {
+        (&self.device.file).write_all(&packet.data)?;
+        Ok(())
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Extracts the IPv4 or IPv6 source address from a raw IP packet.
+fn source_address(data: &[u8]) -> Option<IpAddr> {
+
+    match data.first()? >> 4 {
+        4 if data.len() >= 20 => {
+            let octets: [u8; 4] = data[12..16].try_into().ok()?;
+
+            Some(IpAddr::from(octets))
+        }
+        6 if data.len() >= 40 => {
+            let octets: [u8; 16] = data[8..24].try_into().ok()?;
+
+            Some(IpAddr::from(octets))
+        }
+        _ => None,
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    fn ipv4_packet(src: [u8; 4], dst: [u8; 4]) -> Vec<u8> {
+
+        let mut data = vec![0u8; 20];
+
+        data[0] = 0x45;
+        data[12..16].copy_from_slice(&src);
+        data[16..20].copy_from_slice(&dst);
+        data
+
+    }
+
+    #[test]
+    fn test_source_address_ipv4() {
+
+        let data = ipv4_packet([10, 0, 0, 2], [1, 1, 1, 1]);
+
+        assert_eq!(
+            source_address(&data),
+            Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)))
+        );
+        assert_eq!(source_address(&[0x45, 0, 0]), None);
+
+    }
+
+    #[test]
+    fn test_direction_from_local_addresses() {
+
+        let config = TunConfig::with_name("myra0")
+            .local_address(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+
+        assert!(config.is_outbound(&ipv4_packet([10, 0, 0, 2], [1, 1, 1, 1])));
+        assert!(!config.is_outbound(&ipv4_packet([1, 1, 1, 1], [10, 0, 0, 2])));
+
+        let unconfigured = TunConfig::with_name("myra0");
+
+        assert!(unconfigured.is_outbound(&ipv4_packet([1, 1, 1, 1], [10, 0, 0, 2])));
+
+    }
+}
@@ -0,0 +1,265 @@
+//! `WinDivert` packet backend.
+//!
+//! Captures packets through `WinDivert` network layer handles and injects them
+//! through a dedicated send-only handle. A new filter is applied through a
+//! [`HandleHandover`]: its handle is opened before the previous one stops
+//! capturing, and packets the previous handle still queues are received
+//! before any from the new one.
+use std::borrow::Cow;
+
+use log::{debug, error};
+use windivert::address::WinDivertAddress;
+use windivert::error::{WinDivertError, WinDivertRecvError};
+use windivert::layer::NetworkLayer;
+use windivert::packet::WinDivertPacket;
+use windivert::{CloseAction, WinDivert};
+use windivert_sys::{WinDivertFlags, WinDivertShutdownMode};
+
+use crate::error::{MyraError, Result};
+use crate::network::backend::{DrainableHandle, HandleHandover, PacketSink, PacketSource};
+use crate::network::core::handle::open_handle;
+use crate::network::core::{
+    HandleConfig, PacketData, PacketFlags, construct_filter_with_exclusions, flush_wfp_cache,
+};
+
+/// Maximum size of a single captured packet.
+const MAX_PACKET_SIZE: usize = 65535;
+
+/// Priorities capture handles alternate between across filter changes.
+///
+/// While a replaced handle drains, both handles match traffic. At equal
+/// priorities `WinDivert` leaves the order between them undefined, so the
+/// replacement takes the other priority. Both stay at or above the send
+/// handle's priority 0, so packets it injects are not diverted again.
+const CAPTURE_PRIORITIES: [i16; 2] = [0, 1];
+
+/// A capturing `WinDivert` handle with its receive buffer.
+struct WinDivertCapture {
+    handle: WinDivert<NetworkLayer>,
+    priority: i16,
+    buffer: Vec<u8>,
+}
+
+impl DrainableHandle for WinDivertCapture {
+    fn recv(&mut self) -> Result<Option<PacketData>> {
+
+        match self.handle.recv(Some(&mut self.buffer)) {
+            Ok(packet) => Ok(Some(packet_from_windivert(&packet))),
+            Err(WinDivertError::Recv(WinDivertRecvError::NoData)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+
+    }
+
+    fn stop_capturing(&mut self) -> Result<()> {
+
+        self.handle
+            .shutdown(WinDivertShutdownMode::Recv)
+            .map_err(WinDivertError::from)?;
+        Ok(())
+
+    }
+
+    fn close(&mut self) -> Result<()> {
+
+        debug!("Closing WinDivert capture handle at priority {}", self.priority);
+        self.handle
+            .close(CloseAction::Nothing)
+            .map_err(WinDivertError::from)?;
+        flush_wfp_cache();
+        Ok(())
+
+    }
+}
+
+/// Packet source backed by a `WinDivert` network layer handle.
+pub struct WinDivertSource {
+    handover: HandleHandover<WinDivertCapture>,
+    /// Priority of the most recently opened capture handle
+    priority: i16,
+}
+
+impl WinDivertSource {
+    /// Creates a new source without an active capture.
+    pub const fn new() -> Self {
+
+        Self {
+            handover: HandleHandover::new(),
+            priority: CAPTURE_PRIORITIES[0],
+        }
+
+    }
+}
+
+impl Default for WinDivertSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PacketSource for WinDivertSource {
+    fn apply_filter(&mut self, filter: Option<&str>) -> Result<()> {
+
+        let filter = filter.map(str::to_string);
+        let Some(filter_str) = construct_filter_with_exclusions(&filter) else {
+            return self.handover.close();
+        };
+
+        let priority = if !self.handover.is_capturing() {
+            CAPTURE_PRIORITIES[0]
+        } else if self.priority == CAPTURE_PRIORITIES[0] {
+            CAPTURE_PRIORITIES[1]
+        } else {
+            CAPTURE_PRIORITIES[0]
+        };
+
+        // Exclusions are already part of the filter
+        let config = HandleConfig::with_filter(filter_str)
+            .priority(priority)
+            .recv_only(false)
+            .exclude_tauri_port(false);
+
+        let handle = open_handle(&config)?;
+
+        self.priority = priority;
+        self.handover.replace(WinDivertCapture {
+            handle,
+            priority,
+            buffer: vec![0u8; MAX_PACKET_SIZE],
+        })
+
+    }
+
+    fn is_capturing(&self) -> bool {
+        self.handover.is_capturing()
+    }
+
+    fn recv_packet(&mut self) -> Result<Option<PacketData>> {
+        self.handover.recv()
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+
+        debug!("Closing packet receiving WinDivert handles on shutdown");
+        self.handover.close()
+
+    }
+}
+
+impl Drop for WinDivertSource {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            error!("Error closing WinDivert capture handles on drop: {}", e);
+        }
+    }
+}
+
+/// Packet sink backed by a send-only `WinDivert` handle.
+pub struct WinDivertSink {
+    handle: Option<WinDivert<NetworkLayer>>,
+}
+
+impl WinDivertSink {
+    /// Opens a send-only `WinDivert` handle.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(WinDivertSink)` - If the handle was opened
+    /// * `Err(MyraError)` - If `WinDivert` could not be initialized
+    pub fn open() -> Result<Self> {
+
+        let handle = WinDivert::<NetworkLayer>::network(
+            "false",
+            0,
+            WinDivertFlags::set_send_only(WinDivertFlags::new()),
+        )
+        .map_err(|e| {
+            error!("Failed to initialize WinDivert: {}", e);
+            error!("WinDivert error detailed: {:?}", e);
+            MyraError::WinDivert(e)
+        })?;
+
+        Ok(Self {
+            handle: Some(handle),
+        })
+
+    }
+}
+
+impl PacketSink for WinDivertSink {
+    fn send_packet(&mut self, packet: &PacketData) -> Result<()> {
+
+        let Some(handle) = self.handle.as_ref() else {
+            return Err(MyraError::Backend("WinDivert send handle is closed".to_string()));
+        };
+
+        handle.send(&packet_to_windivert(packet))?;
+        Ok(())
+
+    }
+
+    fn shutdown(&mut self) -> Result<()> {
+
+        if let Some(mut handle) = self.handle.take() {
+            debug!("Closing packet processing WinDivert handle");
+            handle
+                .close(CloseAction::Nothing)
+                .map_err(WinDivertError::from)?;
+            debug!("Successfully closed packet processing WinDivert handle");
+        }
+        Ok(())
+
+    }
+}
+
+impl Drop for WinDivertSink {
+    fn drop(&mut self) {
+        if let Err(e) = self.shutdown() {
+            error!("Error closing WinDivert send handle on drop: {}", e);
+        }
+    }
+}
+
+/// Converts a captured `WinDivert` packet into an owned [`PacketData`].
+fn packet_from_windivert(packet: &WinDivertPacket<NetworkLayer>) -> PacketData {
+
+    let address = &packet.address;
+    let mut packet_data = PacketData::new(packet.data.to_vec(), address.outbound());
+
+    packet_data.interface_index = address.interface_index();
+    packet_data.subinterface_index = address.subinterface_index();
+    packet_data.flags = PacketFlags {
+        loopback: address.loopback(),
+        impostor: address.impostor(),
+        ip_checksum: address.ip_checksum(),
+        tcp_checksum: address.tcp_checksum(),
+        udp_checksum: address.udp_checksum(),
+    };
+    packet_data
+
+}
+
+/// Builds a `WinDivert` packet borrowing the bytes of `packet` for injection.
+fn packet_to_windivert(packet: &PacketData) -> WinDivertPacket<'_, NetworkLayer> {
+
+    // SAFETY: every address field WinDivert reads on send is filled in below
+    let mut address = unsafe { WinDivertAddress::<NetworkLayer>::new() };
+
+    address.set_outbound(packet.is_outbound);
+    address.set_interface_index(packet.interface_index);
+    address.set_subinterface_index(packet.subinterface_index);
+    address.set_impostor(packet.flags.impostor);
+    address.set_ip_checksum(packet.flags.ip_checksum);
+    address.set_tcp_checksum(packet.flags.tcp_checksum);
+    address.set_udp_checksum(packet.flags.udp_checksum);
+    address.as_mut().set_loopback(packet.flags.loopback);
+    address
+        .as_mut()
+        .set_ipv6(packet.data.first().is_some_and(|b| b >> 4 == 6));
+
+    WinDivertPacket {
+        address,
+        data: Cow::Borrowed(&packet.data),
+    }
+
+}
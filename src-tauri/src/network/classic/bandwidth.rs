@@ -7,8 +7,8 @@ use crate::settings::classic::ClassicBandwidthOptions;
 use std::time::Instant;
 
 /// Process packets through the Classic Bandwidth module.
-pub fn process_bandwidth<'a>(
-    packets: &mut Vec<PacketData<'a>>,
+pub fn process_bandwidth(
+    packets: &mut Vec<PacketData>,
     options: &ClassicBandwidthOptions,
     state: &mut ClassicBandwidthState,
 ) {
@@ -33,16 +33,14 @@ pub fn process_bandwidth<'a>(
         state.byte_budget = max_budget;
     }
 
-    // SAFETY: Storage outlives processing calls
-    let buffer: &mut std::collections::VecDeque<PacketData<'a>> =
-        unsafe { std::mem::transmute(&mut state.buffer) };
+    let buffer = &mut state.buffer;
 
     let mut output = Vec::new();
     let mut bytes_used: f64 = 0.0;
 
     // First, release buffered packets within budget
     while let Some(packet) = buffer.front() {
-        let packet_len = packet.packet.data.len() as f64;
+        let packet_len = packet.data.len() as f64;
 
         if bytes_used + packet_len > state.byte_budget {
             break; // Over budget
@@ -64,7 +62,7 @@ pub fn process_bandwidth<'a>(
             continue;
         }
 
-        let packet_len = packet.packet.data.len() as f64;
+        let packet_len = packet.data.len() as f64;
 
         // Check if within budget
         if bytes_used + packet_len <= state.byte_budget {
@@ -6,9 +6,12 @@ use crate::settings::classic::ClassicDropOptions;
 use rand::Rng;
 
 /// Process packets through the Classic Drop module.
-pub fn process_drop<'a>(packets: &mut Vec<PacketData<'a>>, options: &ClassicDropOptions) {
+pub fn process_drop(
+    packets: &mut Vec<PacketData>,
+    options: &ClassicDropOptions,
+    rng: &mut impl Rng,
+) {
 
-    let mut rng = rand::rng();
     let chance = options.chance / 100.0;
 
     packets.retain(|packet| {
@@ -11,20 +11,18 @@ use std::time::{Duration, Instant};
 const MAX_BUFFER: usize = 15000;
 
 /// Process packets through the Classic Latency module.
-pub fn process_latency<'a>(
-    packets: &mut Vec<PacketData<'a>>,
+pub fn process_latency(
+    packets: &mut Vec<PacketData>,
     options: &ClassicLatencyOptions,
     state: &mut ClassicLatencyState,
+    rng: &mut impl Rng,
 ) {
 
-    let mut rng = rand::rng();
     let lag_duration = Duration::from_millis(options.delay_ms);
     let now = Instant::now();
     let chance = options.chance / 100.0;
 
-    // SAFETY: Storage outlives processing calls
-    let buffer: &mut std::collections::VecDeque<(PacketData<'a>, Instant)> =
-        unsafe { std::mem::transmute(&mut state.buffer) };
+    let buffer = &mut state.buffer;
 
     let mut passthrough = Vec::new();
 
@@ -1,9 +1,12 @@
 //! Classic mode packet processor.
 //!
 //! Orchestrates all Classic mode modules in the correct order.
+use std::time::Instant;
+
 use crate::network::classic::state::ClassicProcessingState;
 use crate::network::classic::{bandwidth, drop, latency, reorder, tamper, throttle};
 use crate::network::core::PacketData;
+use crate::network::processing::tap::TapPhase;
 use crate::settings::classic::ClassicSettings;
 
 /// Process packets through all enabled Classic mode modules.
@@ -15,44 +18,83 @@ use crate::settings::classic::ClassicSettings;
 /// 4. Reorder - Swap adjacent packets
 /// 5. Tamper - Corrupt packet data
 /// 6. Bandwidth - Rate limit output
-pub fn process_classic_packets<'a>(
-    packets: &mut Vec<PacketData<'a>>,
+///
+/// While a tap is in progress the tapped modules are processed as disabled.
+///
+/// # Returns
+///
+/// The tap phase of this cycle
+pub fn process_classic_packets(
+    packets: &mut Vec<PacketData>,
+    settings: &ClassicSettings,
+    state: &mut ClassicProcessingState,
+) -> TapPhase {
+
+    let tap = settings.tap.as_ref();
+    let under_pressure =
+        tap.is_some_and(|tap| tap.auto && state.largest_buffer() >= tap.min_buffered);
+    let phase = state.tap.update(tap, under_pressure, Instant::now());
+
+    match tap {
+        Some(tap) if phase == TapPhase::Tapping => {
+            let mut tapped = settings.clone();
+
+            tapped.apply_tap(tap);
+            process_modules(packets, &tapped, state);
+        }
+        _ => process_modules(packets, settings, state),
+    }
+
+    phase
+
+}
+
+/// Runs `packets` through every enabled module of `settings`.
+fn process_modules(
+    packets: &mut Vec<PacketData>,
     settings: &ClassicSettings,
     state: &mut ClassicProcessingState,
 ) {
 
+    state.rngs.reseed(settings.seed);
+
     // 1. Drop module
     if let Some(opts) = &settings.drop {
         if opts.enabled {
-            drop::process_drop(packets, opts);
+            drop::process_drop(packets, opts, &mut state.rngs.drop);
         }
     }
 
     // 2. Latency module
     if let Some(opts) = &settings.latency {
         if opts.enabled {
-            latency::process_latency(packets, opts, &mut state.latency);
+            latency::process_latency(packets, opts, &mut state.latency, &mut state.rngs.latency);
         }
     }
 
     // 3. Throttle module
     if let Some(opts) = &settings.throttle {
         if opts.enabled {
-            throttle::process_throttle(packets, opts, &mut state.throttle);
+            throttle::process_throttle(
+                packets,
+                opts,
+                &mut state.throttle,
+                &mut state.rngs.throttle,
+            );
         }
     }
 
     // 4. Reorder module
     if let Some(opts) = &settings.reorder {
         if opts.enabled {
-            reorder::process_reorder(packets, opts, &mut state.reorder);
+            reorder::process_reorder(packets, opts, &mut state.reorder, &mut state.rngs.reorder);
         }
     }
 
     // 5. Tamper module
     if let Some(opts) = &settings.tamper {
         if opts.enabled {
-            tamper::process_tamper(packets, opts, &mut state.tamper);
+            tamper::process_tamper(packets, opts, &mut state.tamper, &mut state.rngs.tamper);
         }
     }
 
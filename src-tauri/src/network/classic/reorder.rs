@@ -7,18 +7,16 @@ use crate::settings::classic::ClassicReorderOptions;
 use rand::Rng;
 
 /// Process packets through the Classic Reorder module.
-pub fn process_reorder<'a>(
-    packets: &mut Vec<PacketData<'a>>,
+pub fn process_reorder(
+    packets: &mut Vec<PacketData>,
     options: &ClassicReorderOptions,
     state: &mut ClassicReorderState,
+    rng: &mut impl Rng,
 ) {
 
-    let mut rng = rand::rng();
     let chance = options.chance / 100.0;
 
-    // SAFETY: Storage outlives processing calls
-    let held_packet: &mut Option<PacketData<'a>> =
-        unsafe { std::mem::transmute(&mut state.held_packet) };
+    let held_packet = &mut state.held_packet;
 
     // If we're holding a packet, check if we should release it
     if held_packet.is_some() {
@@ -1,5 +1,7 @@
 //! Classic mode processing state.
 use crate::network::core::PacketData;
+use crate::network::processing::tap::TapState;
+use crate::network::types::rng::{ModuleRng, module_rng};
 use std::collections::VecDeque;
 use std::time::Instant;
 
@@ -7,14 +9,14 @@ use std::time::Instant;
 #[derive(Debug, Default)]
 pub struct ClassicLatencyState {
     /// Queue of packets being held (with their capture time)
-    pub buffer: VecDeque<(PacketData<'static>, Instant)>,
+    pub buffer: VecDeque<(PacketData, Instant)>,
 }
 
 /// State for Classic Throttle module.
 #[derive(Debug, Default)]
 pub struct ClassicThrottleState {
     /// Queue of buffered packets
-    pub buffer: VecDeque<PacketData<'static>>,
+    pub buffer: VecDeque<PacketData>,
     /// When the current throttle window started (None = not throttling)
     pub window_start: Option<Instant>,
 }
@@ -23,7 +25,7 @@ pub struct ClassicThrottleState {
 #[derive(Debug, Default)]
 pub struct ClassicReorderState {
     /// Single packet being held for reordering
-    pub held_packet: Option<PacketData<'static>>,
+    pub held_packet: Option<PacketData>,
     /// How many cycles we've been holding this packet
     pub hold_cycles: u32,
 }
@@ -53,7 +55,7 @@ impl Default for ClassicTamperState {
 #[derive(Debug)]
 pub struct ClassicBandwidthState {
     /// Queue of buffered packets (exceeding bandwidth budget)
-    pub buffer: VecDeque<PacketData<'static>>,
+    pub buffer: VecDeque<PacketData>,
     /// Last tick time for calculating byte budget
     pub last_tick: Instant,
     /// Accumulated byte budget available to release
@@ -72,6 +74,45 @@ impl Default for ClassicBandwidthState {
     }
 }
 
+/// Random number generators for Classic mode modules.
+#[derive(Debug)]
+pub struct ClassicRngs {
+    /// Seed the generators were derived from
+    seed: Option<u64>,
+    pub drop: ModuleRng,
+    pub latency: ModuleRng,
+    pub throttle: ModuleRng,
+    pub reorder: ModuleRng,
+    pub tamper: ModuleRng,
+}
+
+impl ClassicRngs {
+    /// Creates generators for all modules from the given seed.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            drop: module_rng(seed, "classic_drop"),
+            latency: module_rng(seed, "classic_latency"),
+            throttle: module_rng(seed, "classic_throttle"),
+            reorder: module_rng(seed, "classic_reorder"),
+            tamper: module_rng(seed, "classic_tamper"),
+        }
+    }
+
+    /// Recreates the generators if the seed differs from the current one.
+    pub fn reseed(&mut self, seed: Option<u64>) {
+        if self.seed != seed {
+            *self = Self::new(seed);
+        }
+    }
+}
+
+impl Default for ClassicRngs {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
 /// Combined state for all Classic mode modules.
 #[derive(Debug, Default)]
 pub struct ClassicProcessingState {
@@ -80,6 +121,8 @@ pub struct ClassicProcessingState {
     pub reorder: ClassicReorderState,
     pub tamper: ClassicTamperState,
     pub bandwidth: ClassicBandwidthState,
+    pub rngs: ClassicRngs,
+    pub tap: TapState,
 }
 
 impl ClassicProcessingState {
@@ -87,8 +130,17 @@ impl ClassicProcessingState {
         Self::default()
     }
 
+    /// Returns the number of packets in the fullest module buffer.
+    pub fn largest_buffer(&self) -> usize {
+        self.latency
+            .buffer
+            .len()
+            .max(self.throttle.buffer.len())
+            .max(self.bandwidth.buffer.len())
+    }
+
     /// Flush all buffered packets (for shutdown).
-    pub fn flush_all_buffers(&mut self) -> Vec<PacketData<'static>> {
+    pub fn flush_all_buffers(&mut self) -> Vec<PacketData> {
 
         let mut packets = Vec::new();
 
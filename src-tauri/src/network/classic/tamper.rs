@@ -7,13 +7,13 @@ use crate::settings::classic::ClassicTamperOptions;
 use rand::Rng;
 
 /// Process packets through the Classic Tamper module.
-pub fn process_tamper<'a>(
-    packets: &mut Vec<PacketData<'a>>,
+pub fn process_tamper(
+    packets: &mut Vec<PacketData>,
     options: &ClassicTamperOptions,
     state: &mut ClassicTamperState,
+    rng: &mut impl Rng,
 ) {
 
-    let mut rng = rand::rng();
     let chance = options.chance / 100.0;
 
     for packet in packets.iter_mut() {
@@ -29,7 +29,7 @@ pub fn process_tamper<'a>(
         }
 
         // Get mutable access to packet data
-        let data = packet.packet.data.to_mut();
+        let data = &mut packet.data;
 
         // Find the payload start (skip IP + TCP/UDP headers)
         // Minimum IPv4 header is 20 bytes
@@ -8,20 +8,18 @@ use rand::Rng;
 use std::time::{Duration, Instant};
 
 /// Process packets through the Classic Throttle module.
-pub fn process_throttle<'a>(
-    packets: &mut Vec<PacketData<'a>>,
+pub fn process_throttle(
+    packets: &mut Vec<PacketData>,
     options: &ClassicThrottleOptions,
     state: &mut ClassicThrottleState,
+    rng: &mut impl Rng,
 ) {
 
-    let mut rng = rand::rng();
     let window_duration = Duration::from_millis(options.window_ms);
     let now = Instant::now();
     let chance = options.chance / 100.0;
 
-    // SAFETY: Storage outlives processing calls
-    let buffer: &mut std::collections::VecDeque<PacketData<'a>> =
-        unsafe { std::mem::transmute(&mut state.buffer) };
+    let buffer = &mut state.buffer;
 
     // Check if we should start a new throttle window
     if state.window_start.is_none() {
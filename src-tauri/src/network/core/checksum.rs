@@ -0,0 +1,174 @@
+//! Internet checksum calculation.
+//!
+//! Recomputes IPv4 header and TCP/UDP/ICMP checksums for packets whose
+//! bytes were modified, e.g. by the corruption module.
+use crate::network::core::headers::{
+    PROTOCOL_ICMP, PROTOCOL_ICMPV6, PROTOCOL_TCP, PROTOCOL_UDP, PacketHeaders,
+};
+use std::net::IpAddr;
+
+/// Recalculates all checksums of a packet in place.
+///
+/// # Arguments
+///
+/// * `data` - Packet bytes starting at the IP header
+/// * `headers` - Parsed headers of `data`
+pub fn recalculate(data: &mut [u8], headers: &PacketHeaders) {
+
+    let ip_len = ip_packet_len(data, headers);
+
+    if headers.ip_version == 4 {
+        data[10] = 0;
+        data[11] = 0;
+
+        let sum = fold(sum_words(&data[..headers.ip_header_len], 0));
+
+        data[10..12].copy_from_slice(&(!sum).to_be_bytes());
+    }
+
+    let checksum_offset = match headers.protocol {
+        PROTOCOL_TCP => 16,
+        PROTOCOL_UDP => 6,
+        PROTOCOL_ICMP if headers.ip_version == 4 => 2,
+        PROTOCOL_ICMPV6 if headers.ip_version == 6 => 2,
+        _ => return,
+    };
+
+    let start = headers.ip_header_len;
+    let offset = start + checksum_offset;
+
+    if ip_len < offset + 2 {
+        return;
+    }
+
+    data[offset] = 0;
+    data[offset + 1] = 0;
+
+    let segment = &data[start..ip_len];
+    let initial = if headers.protocol == PROTOCOL_ICMP {
+        0
+    } else {
+        pseudo_header_sum(headers, segment.len())
+    };
+
+    let mut checksum = !fold(sum_words(segment, initial));
+
+    if headers.protocol == PROTOCOL_UDP && checksum == 0 {
+        checksum = 0xFFFF;
+    }
+
+    data[offset..offset + 2].copy_from_slice(&checksum.to_be_bytes());
+
+}
+
+/// Returns the length of the IP packet, bounded by the captured data.
+fn ip_packet_len(data: &[u8], headers: &PacketHeaders) -> usize {
+
+    let declared = if headers.ip_version == 4 {
+        usize::from(u16::from_be_bytes([data[2], data[3]]))
+    } else {
+        40 + usize::from(u16::from_be_bytes([data[4], data[5]]))
+    };
+
+    if declared < headers.ip_header_len {
+        return data.len();
+    }
+    declared.min(data.len())
+
+}
+
+/// Sums the TCP/UDP pseudo-header fields.
+fn pseudo_header_sum(headers: &PacketHeaders, segment_len: usize) -> u32 {
+
+    let mut sum = 0;
+
+    for addr in [headers.src_addr, headers.dst_addr] {
+        sum = match addr {
+            IpAddr::V4(v4) => sum_words(&v4.octets(), sum),
+            IpAddr::V6(v6) => sum_words(&v6.octets(), sum),
+        };
+    }
+
+    sum += u32::from(headers.protocol);
+    sum += (segment_len as u32) >> 16;
+    sum + (segment_len as u32 & 0xFFFF)
+
+}
+
+/// Adds `bytes` as big-endian 16-bit words to `sum`.
+fn sum_words(bytes: &[u8], mut sum: u32) -> u32 {
+
+    let mut chunks = bytes.chunks_exact(2);
+
+    for chunk in chunks.by_ref() {
+        sum = sum.wrapping_add(u32::from(u16::from_be_bytes([chunk[0], chunk[1]])));
+    }
+
+    if let [last] = chunks.remainder() {
+        sum = sum.wrapping_add(u32::from(*last) << 8);
+    }
+    sum
+
+}
+
+/// Folds a 32-bit one's complement sum into 16 bits.
+fn fold(mut sum: u32) -> u16 {
+
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+
+    /// Verifies a checksum by summing the covered bytes including the checksum itself.
+    fn verifies(bytes: &[u8], initial: u32) -> bool {
+        fold(sum_words(bytes, initial)) == 0xFFFF
+    }
+
+    #[test]
+    fn test_ipv4_header_checksum() {
+
+        let mut data = udp_ipv4_packet(1234, 80, b"hello");
+        let headers = PacketHeaders::parse(&data).unwrap();
+
+        recalculate(&mut data, &headers);
+
+        assert!(verifies(&data[..20], 0));
+
+    }
+
+    #[test]
+    fn test_udp_checksum() {
+
+        let mut data = udp_ipv4_packet(1234, 80, b"hello world");
+        let headers = PacketHeaders::parse(&data).unwrap();
+
+        recalculate(&mut data, &headers);
+
+        let segment = &data[20..];
+
+        assert_ne!(&data[26..28], &[0, 0]);
+        assert!(verifies(segment, pseudo_header_sum(&headers, segment.len())));
+
+    }
+
+    #[test]
+    fn test_checksum_changes_with_payload() {
+
+        let mut original = udp_ipv4_packet(1234, 80, b"abcd");
+        let mut modified = udp_ipv4_packet(1234, 80, b"abce");
+        let headers = PacketHeaders::parse(&original).unwrap();
+
+        recalculate(&mut original, &headers);
+        recalculate(&mut modified, &headers);
+
+        assert_ne!(original[26..28], modified[26..28]);
+
+    }
+}
@@ -1,17 +1,47 @@
 //! Flow tracking for process-based filtering.
 //!
 //! Uses `WinDivert`'s Flow layer to track network connections by process ID,
-//! enabling reliable process-based packet filtering.
-use log::{debug, error, info, warn};
-use std::collections::HashMap;
-use std::net::IpAddr;
+//! enabling reliable process-based packet filtering. The processes to follow
+//! are given by a [`ProcessSelector`] and resolved again every second, so
+//! processes that start later, or restart under a new PID, are attached to
+//! automatically. With a [`FilterFeed`] attached, the tracker also rewrites
+//! the session's filter when flows of those processes are established or
+//! deleted. Changes are debounced, so a burst of connections costs a single
+//! filter swap in the receiver.
+//!
+//! The flow layer only exists on Windows; elsewhere the tracker cannot be
+//! started.
+use log::{debug, error, info};
+#[cfg(windows)]
+use log::warn;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+#[cfg(windows)]
 use windivert::WinDivert;
+#[cfg(windows)]
 use windivert::layer::FlowLayer;
+#[cfg(windows)]
 use windivert::prelude::WinDivertFlags;
 
+use crate::network::core::process_tree::{ProcessSelector, snapshot_processes};
+use crate::network::filter::FilterBuilder;
+
+/// How often the targeted processes are resolved.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How often the watcher thread checks for changes and whether it should stop.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long flows must stay unchanged before the filter is rebuilt.
+const FILTER_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Longest a change waits for the flows to settle before the filter is rebuilt.
+const FILTER_MAX_DELAY: Duration = Duration::from_secs(2);
+
 /// Tracked flow information
 #[derive(Debug, Clone)]
 pub struct FlowInfo {
@@ -28,12 +58,75 @@ pub struct ProcessFlows {
     pub flows: Vec<FlowInfo>,
 }
 
+/// Filter the tracker keeps up to date with the flows of its processes.
+#[derive(Debug, Clone)]
+pub struct FilterFeed {
+    /// Filter read by the receiver
+    pub filter: Arc<Mutex<Option<String>>>,
+    /// Whether to capture packets the processes receive
+    pub include_inbound: bool,
+    /// Whether to capture packets the processes send
+    pub include_outbound: bool,
+}
+
+/// State shared between the tracker and its threads
+#[derive(Debug, Default)]
+struct Shared {
+    /// Flows of every process, by process ID
+    flows: RwLock<HashMap<u32, ProcessFlows>>,
+    /// Processes to follow
+    selector: RwLock<ProcessSelector>,
+    /// Processes the selector matched at the last refresh
+    tracked: RwLock<HashSet<u32>>,
+    /// Where the rebuilt filter is written, if anywhere
+    feed: Mutex<Option<FilterFeed>>,
+    /// Changes to the tracked flows not yet written to the feed
+    pending: Mutex<Debounce>,
+    /// Whether a filter built from tracked flows was written to the current feed
+    fed: AtomicBool,
+}
+
+/// Delays filter rebuilds until changes settle.
+#[derive(Debug, Default)]
+struct Debounce {
+    /// When the first change since the last rebuild happened
+    first: Option<Instant>,
+    /// When the latest change happened
+    last: Option<Instant>,
+}
+
+impl Debounce {
+    /// Records a change at `now`.
+    fn touch(&mut self, now: Instant) {
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+    }
+
+    /// Returns whether to rebuild at `now`, and forgets the changes if so.
+    ///
+    /// Rebuilds once no change came for [`FILTER_DEBOUNCE`], or once the
+    /// first change has waited [`FILTER_MAX_DELAY`].
+    fn ready(&mut self, now: Instant) -> bool {
+
+        let (Some(first), Some(last)) = (self.first, self.last) else {
+            return false;
+        };
+        let ready = now.duration_since(last) >= FILTER_DEBOUNCE
+            || now.duration_since(first) >= FILTER_MAX_DELAY;
+
+        if ready {
+            *self = Self::default();
+        }
+        ready
+
+    }
+}
+
 /// Flow tracker that monitors connections for target processes
 pub struct FlowTracker {
     running: Arc<AtomicBool>,
-    thread_handle: Option<JoinHandle<()>>,
-    flows: Arc<RwLock<HashMap<u32, ProcessFlows>>>,
-    target_pid: Arc<RwLock<Option<u32>>>,
+    thread_handles: Vec<JoinHandle<()>>,
+    shared: Arc<Shared>,
 }
 
 impl FlowTracker {
@@ -41,33 +134,66 @@ impl FlowTracker {
 
         Self {
             running: Arc::new(AtomicBool::new(false)),
-            thread_handle: None,
-            flows: Arc::new(RwLock::new(HashMap::new())),
-            target_pid: Arc::new(RwLock::new(None)),
+            thread_handles: Vec::new(),
+            shared: Arc::new(Shared::default()),
         }
 
     }
 
-    /// Start tracking flows for a specific process
-    pub fn start(&mut self, pid: u32) -> Result<(), String> {
+    /// Start tracking flows for the processes `selector` targets
+    pub fn start(&mut self, selector: ProcessSelector) -> Result<(), String> {
+
+        if cfg!(not(windows)) {
+            return Err("Flow tracking is only available on Windows".to_string());
+        }
+
+        if selector.is_empty() {
+            return Err("No process to track".to_string());
+        }
 
         if self.running.load(Ordering::SeqCst) {
             self.stop();
         }
 
-        *self.target_pid.write().map_err(|e| e.to_string())? = Some(pid);
+        info!("Starting flow tracker for {:?}", selector);
+        self.set_selector(selector)?;
         self.running.store(true, Ordering::SeqCst);
 
         let running = Arc::clone(&self.running);
-        let flows = Arc::clone(&self.flows);
-        let target_pid = Arc::clone(&self.target_pid);
+        let shared = Arc::clone(&self.shared);
+
+        self.thread_handles.push(thread::spawn(move || {
+            run_flow_tracker(&running, &shared);
+        }));
 
-        let handle = thread::spawn(move || {
-            run_flow_tracker(running, flows, target_pid);
-        });
+        let running = Arc::clone(&self.running);
+        let shared = Arc::clone(&self.shared);
+
+        self.thread_handles.push(thread::spawn(move || {
+            run_process_watcher(&running, &shared);
+        }));
+        Ok(())
 
-        self.thread_handle = Some(handle);
-        info!("Started flow tracker for PID {}", pid);
+    }
+
+    /// Changes which processes are tracked, without restarting
+    pub fn set_selector(&self, selector: ProcessSelector) -> Result<(), String> {
+
+        *self.shared.selector.write().map_err(|e| e.to_string())? = selector;
+        refresh_tracked(&self.shared);
+        Ok(())
+
+    }
+
+    /// Sets where the filter for the tracked flows is written, or stops writing it
+    pub fn attach_filter(&self, feed: Option<FilterFeed>) -> Result<(), String> {
+
+        *self.shared.feed.lock().map_err(|e| e.to_string())? = feed;
+        self.shared.fed.store(false, Ordering::SeqCst);
+
+        if self.is_running() {
+            feed_filter(&self.shared);
+        }
         Ok(())
 
     }
@@ -77,110 +203,241 @@ impl FlowTracker {
 
         self.running.store(false, Ordering::SeqCst);
 
-        if let Some(handle) = self.thread_handle.take() {
+        for handle in self.thread_handles.drain(..) {
             let _ = handle.join();
         }
 
-        if let Ok(mut flows) = self.flows.write() {
+        if let Ok(mut flows) = self.shared.flows.write() {
             flows.clear();
         }
 
-        if let Ok(mut pid) = self.target_pid.write() {
-            *pid = None;
+        if let Ok(mut tracked) = self.shared.tracked.write() {
+            tracked.clear();
+        }
+
+        if let Ok(mut feed) = self.shared.feed.lock() {
+            *feed = None;
         }
 
         info!("Stopped flow tracker");
 
     }
 
-    /// Get current flows for the target process
-    pub fn get_flows(&self) -> Vec<FlowInfo> {
-
-        let target = match self.target_pid.read() {
-            Ok(guard) => *guard,
-            Err(_) => return Vec::new(),
-        };
+    /// Get the IDs of the processes currently tracked, in ascending order
+    pub fn tracked_pids(&self) -> Vec<u32> {
 
-        let Some(pid) = target else {
+        let Ok(tracked) = self.shared.tracked.read() else {
             return Vec::new();
         };
+        let mut pids: Vec<u32> = tracked.iter().copied().collect();
 
-        let Ok(flows) = self.flows.read() else {
-            return Vec::new();
-        };
+        pids.sort_unstable();
+        pids
 
-        flows.get(&pid).map(|p| p.flows.clone()).unwrap_or_default()
+    }
 
+    /// Get current flows of the tracked processes
+    pub fn get_flows(&self) -> Vec<FlowInfo> {
+        tracked_flows(&self.shared)
     }
 
     /// Build a `WinDivert` filter string for the tracked flows
-    pub fn build_filter(&self) -> Option<String> {
+    ///
+    /// # Arguments
+    ///
+    /// * `include_inbound` - Whether to capture packets the process receives
+    /// * `include_outbound` - Whether to capture packets the process sends
+    pub fn build_filter(&self, include_inbound: bool, include_outbound: bool) -> Option<String> {
+        build_filter(
+            &tracked_flows(&self.shared),
+            include_inbound,
+            include_outbound,
+        )
+    }
 
-        let flows = self.get_flows();
+    /// Check if tracker is running
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+}
 
-        if flows.is_empty() {
-            return None;
-        }
+impl Default for FlowTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let mut conditions: Vec<String> = Vec::new();
+impl Drop for FlowTracker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
 
-        for flow in &flows {
-            let remote_ip = flow.remote_addr;
-            let local_port = flow.local_port;
-            let remote_port = flow.remote_port;
+fn tracked_flows(shared: &Shared) -> Vec<FlowInfo> {
 
-            // Match by remote IP and ports
-            conditions.push(format!(
-                "(ip.DstAddr == {} and localPort == {} and remotePort == {})",
-                remote_ip, local_port, remote_port
-            ));
-        }
+    let (Ok(tracked), Ok(flows)) = (shared.tracked.read(), shared.flows.read()) else {
+        return Vec::new();
+    };
 
-        // Also add standalone remote IPs for broader matching
-        let unique_ips: Vec<IpAddr> = flows.iter().map(|f| f.remote_addr).collect();
+    let mut pids: Vec<&u32> = tracked.iter().collect();
 
-        for ip in unique_ips.iter().take(10) {
-            // Limit to prevent filter explosion
-            if !ip.is_loopback() {
-                conditions.push(format!("ip.DstAddr == {}", ip));
-            }
+    // Keep the filter stable between refreshes
+    pids.sort_unstable();
+    pids.into_iter()
+        .filter_map(|pid| flows.get(pid))
+        .flat_map(|process| process.flows.iter().cloned())
+        .collect()
+
+}
+
+fn build_filter(
+    flows: &[FlowInfo],
+    include_inbound: bool,
+    include_outbound: bool,
+) -> Option<String> {
+
+    if flows.is_empty() {
+        return None;
+    }
+
+    // Match each connection by remote IP and ports
+    let builder = FilterBuilder::new().directions(include_inbound, include_outbound);
+    let builder = flows.iter().fold(builder, |builder, flow| {
+        builder.connection(
+            flow.local_port,
+            SocketAddr::new(flow.remote_addr, flow.remote_port),
+        )
+    });
+
+    // Also add standalone remote IPs for broader matching
+    let mut remote_ips: Vec<IpAddr> = Vec::new();
+
+    for flow in flows {
+        if !flow.remote_addr.is_loopback() && !remote_ips.contains(&flow.remote_addr) {
+            remote_ips.push(flow.remote_addr);
         }
+    }
+
+    // Limit to prevent filter explosion
+    let builder = remote_ips
+        .into_iter()
+        .take(10)
+        .fold(builder, FilterBuilder::host);
 
-        conditions.dedup();
+    Some(builder.build().to_string())
 
-        if conditions.is_empty() {
-            return None;
+}
+
+/// Resolves the selector against the running processes, and forgets the
+/// flows of processes that have exited.
+fn refresh_tracked(shared: &Shared) {
+
+    let Ok(selector) = shared.selector.read().map(|selector| selector.clone()) else {
+        return;
+    };
+    let processes = snapshot_processes();
+    let pids = selector.resolve(&processes);
+
+    if let Ok(mut tracked) = shared.tracked.write() {
+        if *tracked != pids {
+            for process in processes
+                .iter()
+                .filter(|process| !tracked.contains(&process.pid))
+            {
+                if pids.contains(&process.pid) {
+                    info!("Tracking flows of {} (PID {})", process.name, process.pid);
+                }
+            }
+            *tracked = pids;
+            mark_changed(shared);
         }
+    }
 
-        Some(format!("outbound and ({})", conditions.join(" or ")))
+    let alive: HashSet<u32> = processes.iter().map(|process| process.pid).collect();
 
+    if let Ok(mut flows) = shared.flows.write() {
+        flows.retain(|pid, _| alive.contains(pid));
     }
 
-    /// Check if tracker is running
-    pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::SeqCst)
+}
+
+/// Records that the tracked flows changed.
+fn mark_changed(shared: &Shared) {
+    if let Ok(mut pending) = shared.pending.lock() {
+        pending.touch(Instant::now());
     }
 }
 
-impl Default for FlowTracker {
-    fn default() -> Self {
-        Self::new()
+/// Writes the filter for the tracked flows into the attached feed.
+///
+/// Until the tracker has seen a flow of its processes, the filter the feed
+/// was attached with is left alone. Once the processes have no flows left,
+/// the filter captures nothing rather than their former remote hosts.
+fn feed_filter(shared: &Shared) {
+
+    let Ok(feed) = shared.feed.lock() else {
+        return;
+    };
+    let Some(feed) = feed.as_ref() else {
+        return;
+    };
+    let built = build_filter(
+        &tracked_flows(shared),
+        feed.include_inbound,
+        feed.include_outbound,
+    );
+    let filter = match built {
+        Some(filter) => {
+            shared.fed.store(true, Ordering::SeqCst);
+            filter
+        }
+        None if shared.fed.load(Ordering::SeqCst) => "false".to_string(),
+        None => return,
+    };
+    let Ok(mut current) = feed.filter.lock() else {
+        return;
+    };
+
+    if current.as_deref() != Some(filter.as_str()) {
+        debug!("Flow tracker updated the filter: {}", filter);
+        *current = Some(filter);
     }
+
 }
 
-impl Drop for FlowTracker {
-    fn drop(&mut self) {
-        self.stop();
+fn run_process_watcher(running: &AtomicBool, shared: &Shared) {
+
+    let mut next_refresh = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+
+        if now >= next_refresh {
+            refresh_tracked(shared);
+            next_refresh = now + REFRESH_INTERVAL;
+        }
+
+        let settled = shared
+            .pending
+            .lock()
+            .is_ok_and(|mut pending| pending.ready(now));
+
+        if settled {
+            feed_filter(shared);
+        }
+
+        thread::sleep(POLL_INTERVAL);
     }
+
+    debug!("Process watcher thread exiting");
+
 }
 
-fn run_flow_tracker(
-    running: Arc<AtomicBool>,
-    flows: Arc<RwLock<HashMap<u32, ProcessFlows>>>,
-    target_pid: Arc<RwLock<Option<u32>>>,
-) {
+#[cfg(windows)]
+fn run_flow_tracker(running: &AtomicBool, shared: &Shared) {
 
-    // Open flow layer handle - filter for all flows, we'll check PID ourselves
+    // Open flow layer handle - filter for all flows, as processes may be
+    // targeted after their flows were established
     let flow_handle = match WinDivert::<FlowLayer>::flow("true", 0, WinDivertFlags::new()) {
         Ok(h) => h,
         Err(e) => {
@@ -205,20 +462,6 @@ fn run_flow_tracker(
         let addr = packet.address;
         let pid = addr.process_id();
 
-        // Check if this is our target process
-        let target = match target_pid.read() {
-            Ok(guard) => *guard,
-            Err(_) => continue,
-        };
-
-        let Some(target_pid_value) = target else {
-            continue;
-        };
-
-        if pid != target_pid_value {
-            continue;
-        }
-
         let flow_info = FlowInfo {
             local_addr: addr.local_address(),
             remote_addr: addr.remote_address(),
@@ -233,19 +476,24 @@ fn run_flow_tracker(
         }
 
         let event = addr.event();
-        let Ok(mut flows_guard) = flows.write() else {
+        let tracked = shared
+            .tracked
+            .read()
+            .is_ok_and(|tracked| tracked.contains(&pid));
+        let Ok(mut flows_guard) = shared.flows.write() else {
             continue;
         };
 
         let process_flows = flows_guard.entry(pid).or_default();
 
-        match event {
+        let changed = match event {
             windivert::prelude::WinDivertEvent::FlowStablished => {
                 debug!(
                     "Flow established: PID {} -> {}:{} (proto: {})",
                     pid, flow_info.remote_addr, flow_info.remote_port, flow_info.protocol
                 );
                 process_flows.flows.push(flow_info);
+                true
             }
             windivert::prelude::WinDivertEvent::FlowDeleted => {
                 debug!(
@@ -257,11 +505,132 @@ fn run_flow_tracker(
                         || f.remote_port != flow_info.remote_port
                         || f.local_port != flow_info.local_port
                 });
+                true
             }
-            _ => {}
+            _ => false,
+        };
+
+        drop(flows_guard);
+
+        if changed && tracked {
+            mark_changed(shared);
         }
     }
 
     debug!("Flow tracker thread exiting");
 
 }
+
+/// Flows are reported by the `WinDivert` flow layer only.
+#[cfg(not(windows))]
+fn run_flow_tracker(_running: &AtomicBool, _shared: &Shared) {
+    error!("Flow tracking is only available on Windows");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow(remote: &str, local_port: u16) -> FlowInfo {
+
+        let remote: SocketAddr = remote.parse().unwrap();
+
+        FlowInfo {
+            local_addr: "192.168.1.10".parse().unwrap(),
+            remote_addr: remote.ip(),
+            local_port,
+            remote_port: remote.port(),
+            protocol: 17,
+        }
+
+    }
+
+    #[test]
+    fn test_filter_covers_tracked_processes_only() {
+
+        let tracker = FlowTracker::new();
+
+        let mut flows = tracker.shared.flows.write().unwrap();
+
+        for (pid, remote, local_port) in [
+            (10, "10.0.0.1:3074", 50000),
+            (11, "10.0.0.2:443", 50001),
+            (20, "10.0.0.3:80", 50002),
+        ] {
+            let process = flows.entry(pid).or_default();
+
+            process.flows.push(flow(remote, local_port));
+        }
+        drop(flows);
+
+        assert_eq!(tracker.build_filter(false, true), None);
+
+        tracker.shared.tracked.write().unwrap().extend([10, 11]);
+
+        let filter = tracker.build_filter(false, true).unwrap();
+
+        assert!(filter.contains("ip.DstAddr == 10.0.0.1 and localPort == 50000"));
+        assert!(filter.contains("ip.DstAddr == 10.0.0.2"));
+        assert!(!filter.contains("10.0.0.3"));
+        assert_eq!(tracker.get_flows().len(), 2);
+
+    }
+
+    #[test]
+    fn test_feed_captures_nothing_once_the_last_flow_is_deleted() {
+
+        let tracker = FlowTracker::new();
+        let filter = Arc::new(Mutex::new(Some("initial".to_string())));
+
+        tracker
+            .attach_filter(Some(FilterFeed {
+                filter: Arc::clone(&filter),
+                include_inbound: true,
+                include_outbound: true,
+            }))
+            .unwrap();
+        tracker.shared.tracked.write().unwrap().insert(10);
+
+        // Before any flow is seen the filter the session started with stays
+        feed_filter(&tracker.shared);
+        assert_eq!(filter.lock().unwrap().as_deref(), Some("initial"));
+
+        let mut flows = tracker.shared.flows.write().unwrap();
+        flows.entry(10).or_default().flows.push(flow("10.0.0.1:3074", 50000));
+        drop(flows);
+
+        feed_filter(&tracker.shared);
+        assert!(filter.lock().unwrap().as_deref().unwrap().contains("10.0.0.1"));
+
+        tracker.shared.flows.write().unwrap().get_mut(&10).unwrap().flows.clear();
+
+        feed_filter(&tracker.shared);
+        assert_eq!(filter.lock().unwrap().as_deref(), Some("false"));
+
+    }
+
+    #[test]
+    fn test_debounce_waits_for_flows_to_settle() {
+
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut pending = Debounce::default();
+
+        assert!(!pending.ready(at(0)));
+
+        // A burst keeps postponing the rebuild
+        for millis in (0..=200).step_by(100) {
+            pending.touch(at(millis));
+            assert!(!pending.ready(at(millis + 100)));
+        }
+        assert!(pending.ready(at(450)));
+        assert!(!pending.ready(at(1000)));
+
+        // Until the first change has waited too long
+        for millis in (0..=2000).step_by(200) {
+            pending.touch(at(millis));
+        }
+        assert!(pending.ready(at(2000)));
+
+    }
+}
@@ -2,12 +2,23 @@
 //!
 //! This module provides centralized management of `WinDivert` handles,
 //! including creation, configuration, and proper cleanup.
-use log::{debug, error, info, warn};
+//!
+//! Only the filter helpers are available on other platforms; the handle
+//! functions there do nothing.
+use log::warn;
+#[cfg(windows)]
+use log::{debug, error, info};
+#[cfg(windows)]
 use windivert::error::WinDivertError;
+#[cfg(windows)]
 use windivert::layer::NetworkLayer;
+#[cfg(windows)]
 use windivert::{CloseAction, WinDivert};
+#[cfg(windows)]
 use windivert_sys::WinDivertFlags;
 
+use crate::network::filter::{CompareOp, Expr, Filter, NamedField, Value};
+
 #[cfg(windows)]
 extern "system" {
     fn timeBeginPeriod(uPeriod: u32) -> u32;
@@ -15,6 +26,7 @@ extern "system" {
 }
 
 /// Timer resolution tracker for high-precision timing
+#[cfg(windows)]
 static TIMER_RESOLUTION_SET: std::sync::atomic::AtomicBool =
     std::sync::atomic::AtomicBool::new(false);
 
@@ -65,12 +77,14 @@ pub fn set_high_precision_timer() {}
 pub fn restore_timer_resolution() {}
 
 /// Default priority for packet interception.
+#[cfg(windows)]
 pub const DEFAULT_PRIORITY: i16 = 1;
 
 /// Port used by Tauri for local communication.
 const TAURI_PORT: u16 = 1420;
 
 /// Configuration for creating a `WinDivert` handle.
+#[cfg(windows)]
 #[derive(Debug, Clone)]
 pub struct HandleConfig {
     /// Filter expression for packet matching
@@ -83,6 +97,7 @@ pub struct HandleConfig {
     pub exclude_tauri_port: bool,
 }
 
+#[cfg(windows)]
 impl Default for HandleConfig {
     fn default() -> Self {
 
@@ -96,6 +111,7 @@ impl Default for HandleConfig {
     }
 }
 
+#[cfg(windows)]
 impl HandleConfig {
     /// Creates a new `HandleConfig` with the given filter.
     pub fn with_filter(filter: impl Into<String>) -> Self {
@@ -147,11 +163,13 @@ impl HandleConfig {
 ///
 /// This struct provides a safe wrapper around `WinDivert` handles,
 /// ensuring proper initialization and cleanup.
+#[cfg(windows)]
 pub struct HandleManager {
     handle: Option<WinDivert<NetworkLayer>>,
     current_config: Option<HandleConfig>,
 }
 
+#[cfg(windows)]
 impl HandleManager {
     /// Creates a new `HandleManager` without an active handle.
     pub fn new() -> Self {
@@ -188,42 +206,17 @@ impl HandleManager {
 
         flush_wfp_cache();
 
-        let filter = config.build_filter();
-
-        info!("Opening WinDivert handle with filter: {}", filter);
-
-        let flags = if config.recv_only {
-            WinDivertFlags::set_recv_only(WinDivertFlags::new())
-        } else {
-            WinDivertFlags::new()
-        };
-
-        match WinDivert::<NetworkLayer>::network(&filter, config.priority, flags) {
-            Ok(handle) => {
-                debug!("WinDivert handle opened successfully");
-
-                use windivert_sys::WinDivertParam;
-
-                match handle.set_param(WinDivertParam::QueueLength, 2048) {
-                    Err(e) => warn!("Failed to set WinDivert queue length: {}", e),
-                    Ok(()) => info!("Set WinDivert queue length to 2048 packets"),
-                }
-
-                match handle.set_param(WinDivertParam::QueueTime, 1024) {
-                    Err(e) => warn!("Failed to set WinDivert queue time: {}", e),
-                    Ok(()) => info!("Set WinDivert queue time to 1024ms"),
-                }
-
-                self.handle = Some(handle);
-                self.current_config = Some(config);
-                Ok(())
-            }
+        let handle = match open_handle(&config) {
+            Ok(handle) => handle,
             Err(e) => {
-                error!("Failed to open WinDivert handle: {}", e);
                 flush_wfp_cache();
-                Err(e)
+                return Err(e);
             }
-        }
+        };
+
+        self.handle = Some(handle);
+        self.current_config = Some(config);
+        Ok(())
 
     }
 
@@ -268,12 +261,14 @@ impl HandleManager {
     }
 }
 
+#[cfg(windows)]
 impl Default for HandleManager {
     fn default() -> Self {
         Self::new()
     }
 }
 
+#[cfg(windows)]
 impl Drop for HandleManager {
     fn drop(&mut self) {
         if let Err(e) = self.close() {
@@ -282,10 +277,50 @@ impl Drop for HandleManager {
     }
 }
 
+/// Opens a `WinDivert` network layer handle and sizes its packet queue.
+#[cfg(windows)]
+pub(crate) fn open_handle(
+    config: &HandleConfig,
+) -> Result<WinDivert<NetworkLayer>, WinDivertError> {
+
+    let filter = config.build_filter();
+
+    info!("Opening WinDivert handle with filter: {}", filter);
+
+    let flags = if config.recv_only {
+        WinDivertFlags::set_recv_only(WinDivertFlags::new())
+    } else {
+        WinDivertFlags::new()
+    };
+
+    let handle =
+        WinDivert::<NetworkLayer>::network(&filter, config.priority, flags).map_err(|e| {
+            error!("Failed to open WinDivert handle: {}", e);
+            e
+        })?;
+
+    debug!("WinDivert handle opened successfully");
+
+    use windivert_sys::WinDivertParam;
+
+    match handle.set_param(WinDivertParam::QueueLength, 2048) {
+        Err(e) => warn!("Failed to set WinDivert queue length: {}", e),
+        Ok(()) => info!("Set WinDivert queue length to 2048 packets"),
+    }
+
+    match handle.set_param(WinDivertParam::QueueTime, 1024) {
+        Err(e) => warn!("Failed to set WinDivert queue time: {}", e),
+        Ok(()) => info!("Set WinDivert queue time to 1024ms"),
+    }
+    Ok(handle)
+
+}
+
 /// Flushes the Windows Filtering Platform (WFP) cache.
 ///
 /// This is a workaround for `WinDivert` caching issues. It opens and
 /// immediately closes a handle with a no-match filter to clear stale state.
+#[cfg(windows)]
 pub fn flush_wfp_cache() {
 
     for priority in [0, 1000, -1000] {
@@ -300,10 +335,15 @@ pub fn flush_wfp_cache() {
 
 }
 
+#[cfg(not(windows))]
+pub fn flush_wfp_cache() {}
+
 /// Creates a `WinDivert` filter that excludes Tauri app ports.
 ///
 /// Takes a user-provided filter and adds conditions to exclude traffic
 /// on ports used by the Tauri app to prevent disrupting the app's functionality.
+/// A filter whose terms contradict each other, such as `inbound and outbound`,
+/// is replaced with `false`, capturing nothing as the filter says.
 ///
 /// # Arguments
 ///
@@ -314,28 +354,33 @@ pub fn flush_wfp_cache() {
 /// The complete filter string with Tauri port exclusions, or None if no filter provided
 pub fn construct_filter_with_exclusions(user_filter: &Option<String>) -> Option<String> {
 
-    user_filter.as_ref()?;
-
-    let tauri_exclusion = format!("localPort != {0} and remotePort != {0}", TAURI_PORT);
-
-    Some(match user_filter {
-        Some(filter) if !filter.is_empty() => {
-            let filter_lower = filter.to_lowercase();
-            let has_invalid_direction = filter_lower.contains("outbound and inbound")
-                || filter_lower.contains("inbound and outbound");
-            let corrected_filter = if has_invalid_direction {
-                log::warn!(
-                    "Filter '{}' is invalid: a packet cannot be both outbound AND inbound. Using 'true' to capture all traffic.",
-                    filter
-                );
-                "true".to_string()
-            } else {
-                filter.clone()
-            };
-            format!("({}) and {}", corrected_filter, tauri_exclusion)
+    let user_filter = user_filter.as_ref()?;
+
+    let port = Value::Number(TAURI_PORT.into());
+    let tauri_exclusion = Expr::and(
+        Expr::compare(NamedField::LocalPort, CompareOp::Ne, port),
+        Expr::compare(NamedField::RemotePort, CompareOp::Ne, port),
+    );
+
+    if user_filter.is_empty() {
+        return Some(tauri_exclusion.to_string());
+    }
+
+    let filter = match Filter::parse(user_filter) {
+        Ok(filter) => filter,
+        Err(e) => {
+            // Leave the filter to the driver, which reports it when opening
+            warn!("Filter '{}' could not be parsed: {}", user_filter, e);
+            return Some(format!("({}) and {}", user_filter, tauri_exclusion));
         }
-        _ => tauri_exclusion,
-    })
+    };
+
+    if let Some(reason) = filter.contradiction() {
+        warn!("Filter never matches, capturing no traffic: {}", reason);
+        return Some(Expr::Bool(false).to_string());
+    }
+
+    Some(Expr::and(filter.into_expr(), tauri_exclusion).to_string())
 
 }
 
@@ -343,6 +388,7 @@ pub fn construct_filter_with_exclusions(user_filter: &Option<String>) -> Option<
 mod tests {
     use super::*;
 
+    #[cfg(windows)]
     #[test]
     fn test_handle_config_default() {
 
@@ -355,6 +401,7 @@ mod tests {
 
     }
 
+    #[cfg(windows)]
     #[test]
     fn test_handle_config_builder() {
 
@@ -370,6 +417,7 @@ mod tests {
 
     }
 
+    #[cfg(windows)]
     #[test]
     fn test_build_filter_with_exclusions() {
 
@@ -381,6 +429,7 @@ mod tests {
 
     }
 
+    #[cfg(windows)]
     #[test]
     fn test_build_filter_without_exclusions() {
 
@@ -405,4 +454,13 @@ mod tests {
         assert!(filter.unwrap().contains("tcp"));
 
     }
+
+    #[test]
+    fn test_construct_filter_with_exclusions_contradiction() {
+
+        let filter = construct_filter_with_exclusions(&Some("inbound and outbound".to_string()));
+
+        assert_eq!(filter.as_deref(), Some("false"));
+
+    }
 }
@@ -0,0 +1,298 @@
+//! Parsed IP/transport header view.
+//!
+//! Provides a read-only, allocation-free view over the headers of a raw IP
+//! packet so modules can inspect addresses, ports and offsets without
+//! depending on a capture driver.
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// IP protocol number for ICMP.
+pub const PROTOCOL_ICMP: u8 = 1;
+/// IP protocol number for TCP.
+pub const PROTOCOL_TCP: u8 = 6;
+/// IP protocol number for UDP.
+pub const PROTOCOL_UDP: u8 = 17;
+/// IP protocol number for `ICMPv6`.
+pub const PROTOCOL_ICMPV6: u8 = 58;
+
+/// Parsed header information of a raw IP packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketHeaders {
+    /// IP version (4 or 6)
+    pub ip_version: u8,
+    /// Length of the IP header in bytes
+    pub ip_header_len: usize,
+    /// Source IP address
+    pub src_addr: IpAddr,
+    /// Destination IP address
+    pub dst_addr: IpAddr,
+    /// Transport protocol number (IPv4 protocol / IPv6 next header)
+    pub protocol: u8,
+    /// Differentiated services code point
+    pub dscp: u8,
+    /// Source port for TCP/UDP packets
+    pub src_port: Option<u16>,
+    /// Destination port for TCP/UDP packets
+    pub dst_port: Option<u16>,
+    /// Length of the transport header in bytes (0 if not TCP/UDP)
+    pub transport_header_len: usize,
+}
+
+impl PacketHeaders {
+    /// Parses the headers of a raw IP packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Packet bytes starting at the IP header
+    ///
+    /// # Returns
+    ///
+    /// * `Some(PacketHeaders)` - If the IP header (and TCP/UDP header, if any) is complete
+    /// * `None` - If the packet is truncated or not IPv4/IPv6
+    pub fn parse(data: &[u8]) -> Option<Self> {
+
+        let mut headers = match data.first()? >> 4 {
+            4 => parse_ipv4(data)?,
+            6 => parse_ipv6(data)?,
+            _ => return None,
+        };
+
+        let transport = &data[headers.ip_header_len..];
+
+        match headers.protocol {
+            PROTOCOL_TCP => {
+                if transport.len() < 20 {
+                    return None;
+                }
+
+                let data_offset = usize::from(transport[12] >> 4) * 4;
+
+                if data_offset < 20 || transport.len() < data_offset {
+                    return None;
+                }
+
+                headers.transport_header_len = data_offset;
+            }
+            PROTOCOL_UDP => {
+                if transport.len() < 8 {
+                    return None;
+                }
+
+                headers.transport_header_len = 8;
+            }
+            _ => return Some(headers),
+        }
+
+        headers.src_port = Some(u16::from_be_bytes([transport[0], transport[1]]));
+        headers.dst_port = Some(u16::from_be_bytes([transport[2], transport[3]]));
+
+        Some(headers)
+
+    }
+
+    /// Returns the offset of the transport payload within the packet.
+    pub fn payload_offset(&self) -> usize {
+        self.ip_header_len + self.transport_header_len
+    }
+
+    /// Returns whether the packet is a TCP segment.
+    pub fn is_tcp(&self) -> bool {
+        self.protocol == PROTOCOL_TCP
+    }
+
+    /// Returns whether the packet is a UDP datagram.
+    pub fn is_udp(&self) -> bool {
+        self.protocol == PROTOCOL_UDP
+    }
+
+    /// Returns the key of the flow this packet belongs to.
+    pub fn flow_key(&self) -> FlowKey {
+        FlowKey {
+            protocol: self.protocol,
+            src_addr: self.src_addr,
+            dst_addr: self.dst_addr,
+            src_port: self.src_port.unwrap_or(0),
+            dst_port: self.dst_port.unwrap_or(0),
+        }
+    }
+}
+
+/// Directional 5-tuple identifying a flow.
+///
+/// Packets travelling in opposite directions of the same connection have
+/// different keys. Ports are zero for protocols without them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FlowKey {
+    /// Transport protocol number
+    pub protocol: u8,
+    /// Source IP address
+    pub src_addr: IpAddr,
+    /// Destination IP address
+    pub dst_addr: IpAddr,
+    /// Source port
+    pub src_port: u16,
+    /// Destination port
+    pub dst_port: u16,
+}
+
+impl FlowKey {
+    /// Returns the key of the opposite direction of the flow.
+    pub fn reversed(self) -> Self {
+        Self {
+            protocol: self.protocol,
+            src_addr: self.dst_addr,
+            dst_addr: self.src_addr,
+            src_port: self.dst_port,
+            dst_port: self.src_port,
+        }
+    }
+}
+
+/// Parses a fixed IPv4 header.
+fn parse_ipv4(data: &[u8]) -> Option<PacketHeaders> {
+
+    if data.len() < 20 {
+        return None;
+    }
+
+    let ip_header_len = usize::from(data[0] & 0x0F) * 4;
+
+    if ip_header_len < 20 || data.len() < ip_header_len {
+        return None;
+    }
+
+    let src: [u8; 4] = data[12..16].try_into().ok()?;
+    let dst: [u8; 4] = data[16..20].try_into().ok()?;
+
+    Some(PacketHeaders {
+        ip_version: 4,
+        ip_header_len,
+        src_addr: IpAddr::V4(Ipv4Addr::from(src)),
+        dst_addr: IpAddr::V4(Ipv4Addr::from(dst)),
+        protocol: data[9],
+        dscp: data[1] >> 2,
+        src_port: None,
+        dst_port: None,
+        transport_header_len: 0,
+    })
+
+}
+
+/// Parses a fixed IPv6 header. Extension headers are not followed.
+fn parse_ipv6(data: &[u8]) -> Option<PacketHeaders> {
+
+    if data.len() < 40 {
+        return None;
+    }
+
+    let src: [u8; 16] = data[8..24].try_into().ok()?;
+    let dst: [u8; 16] = data[24..40].try_into().ok()?;
+    let traffic_class = ((data[0] & 0x0F) << 4) | (data[1] >> 4);
+
+    Some(PacketHeaders {
+        ip_version: 6,
+        ip_header_len: 40,
+        src_addr: IpAddr::V6(Ipv6Addr::from(src)),
+        dst_addr: IpAddr::V6(Ipv6Addr::from(dst)),
+        protocol: data[6],
+        dscp: traffic_class >> 2,
+        src_port: None,
+        dst_port: None,
+        transport_header_len: 0,
+    })
+
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a minimal IPv4/UDP packet with the given payload.
+    pub fn udp_ipv4_packet(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+
+        let total_len = 28 + payload.len();
+        let mut data = vec![0u8; total_len];
+
+        data[0] = 0x45;
+        data[1] = 46 << 2; // DSCP EF
+        data[2..4].copy_from_slice(&(total_len as u16).to_be_bytes());
+        data[8] = 64;
+        data[9] = PROTOCOL_UDP;
+        data[12..16].copy_from_slice(&[192, 168, 1, 10]);
+        data[16..20].copy_from_slice(&[10, 0, 0, 1]);
+        data[20..22].copy_from_slice(&src_port.to_be_bytes());
+        data[22..24].copy_from_slice(&dst_port.to_be_bytes());
+        data[24..26].copy_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        data[28..].copy_from_slice(payload);
+        data
+
+    }
+
+    #[test]
+    fn test_flow_key_is_directional() {
+
+        let outgoing = PacketHeaders::parse(&udp_ipv4_packet(5000, 27015, &[])).unwrap();
+        let other_port = PacketHeaders::parse(&udp_ipv4_packet(5001, 27015, &[])).unwrap();
+
+        assert_eq!(outgoing.flow_key().src_port, 5000);
+        assert_eq!(outgoing.flow_key(), outgoing.flow_key());
+        assert_ne!(outgoing.flow_key(), other_port.flow_key());
+        assert_eq!(outgoing.flow_key().reversed().dst_port, 5000);
+        assert_eq!(outgoing.flow_key().reversed().reversed(), outgoing.flow_key());
+
+    }
+
+    #[test]
+    fn test_parse_ipv4_udp() {
+
+        let data = udp_ipv4_packet(5000, 27015, &[1, 2, 3, 4]);
+        let headers = PacketHeaders::parse(&data).unwrap();
+
+        assert_eq!(headers.ip_version, 4);
+        assert_eq!(headers.ip_header_len, 20);
+        assert_eq!(headers.src_addr, IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10)));
+        assert_eq!(headers.dst_addr, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)));
+        assert!(headers.is_udp());
+        assert_eq!(headers.dscp, 46);
+        assert_eq!(headers.src_port, Some(5000));
+        assert_eq!(headers.dst_port, Some(27015));
+        assert_eq!(headers.payload_offset(), 28);
+
+    }
+
+    #[test]
+    fn test_parse_ipv6_tcp() {
+
+        let mut data = vec![0u8; 60];
+
+        data[0] = 0x60;
+        data[6] = PROTOCOL_TCP;
+        data[23] = 1;
+        data[39] = 2;
+        data[40..42].copy_from_slice(&443u16.to_be_bytes());
+        data[42..44].copy_from_slice(&51000u16.to_be_bytes());
+        data[52] = 5 << 4;
+
+        let headers = PacketHeaders::parse(&data).unwrap();
+
+        assert_eq!(headers.ip_version, 6);
+        assert_eq!(headers.src_addr, IpAddr::V6(Ipv6Addr::LOCALHOST));
+        assert!(headers.is_tcp());
+        assert_eq!(headers.src_port, Some(443));
+        assert_eq!(headers.dst_port, Some(51000));
+        assert_eq!(headers.payload_offset(), 60);
+
+    }
+
+    #[test]
+    fn test_parse_rejects_truncated_packets() {
+
+        assert!(PacketHeaders::parse(&[]).is_none());
+        assert!(PacketHeaders::parse(&[0x45, 0, 0, 0]).is_none());
+        assert!(PacketHeaders::parse(&[1, 2, 3, 4]).is_none());
+
+        let data = udp_ipv4_packet(1, 2, &[]);
+
+        assert!(PacketHeaders::parse(&data[..24]).is_none());
+
+    }
+}
@@ -2,13 +2,20 @@
 //!
 //! This module contains the core components for packet interception
 //! and manipulation, including handle management and packet data structures.
+pub mod checksum;
 pub mod flow_tracker;
 pub mod handle;
+pub mod headers;
 pub mod packet;
+pub mod process_tree;
 
-pub use flow_tracker::FlowTracker;
+pub use flow_tracker::{FilterFeed, FlowTracker};
+#[cfg(windows)]
+pub use handle::{HandleConfig, HandleManager};
 pub use handle::{
-    HandleConfig, HandleManager, construct_filter_with_exclusions, flush_wfp_cache,
-    restore_timer_resolution, set_high_precision_timer,
+    construct_filter_with_exclusions, flush_wfp_cache, restore_timer_resolution,
+    set_high_precision_timer,
 };
-pub use packet::PacketData;
+pub use headers::{FlowKey, PacketHeaders};
+pub use packet::{PacketData, PacketEffects, PacketFlags};
+pub use process_tree::{ProcessEntry, ProcessSelector, snapshot_processes};
@@ -1,61 +1,196 @@
-use std::time::Instant;
-use windivert::layer::NetworkLayer;
-use windivert::packet::WinDivertPacket;
+use crate::network::core::checksum;
+use crate::network::core::headers::PacketHeaders;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime};
+
+/// Source of unique packet ids.
+static NEXT_PACKET_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Checksum and routing flags carried alongside a packet.
+///
+/// Backends fill these in when a packet is captured and honor them when
+/// the packet is injected again.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketFlags {
+    /// Packet was sent over the loopback interface
+    pub loopback: bool,
+    /// Packet was injected by another driver
+    pub impostor: bool,
+    /// The IPv4 header checksum is valid
+    pub ip_checksum: bool,
+    /// The TCP checksum is valid
+    pub tcp_checksum: bool,
+    /// The UDP checksum is valid
+    pub udp_checksum: bool,
+}
+
+/// Set of modules that acted on a packet on its way through the pipeline.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PacketEffects(u16);
+
+impl PacketEffects {
+    /// Held back by the lag module
+    pub const LAG: Self = Self(1 << 0);
+    /// Buffered by the throttle module
+    pub const THROTTLE: Self = Self(1 << 1);
+    /// Delayed out of order by the reorder module
+    pub const REORDER: Self = Self(1 << 2);
+    /// Payload modified by the corruption module
+    pub const CORRUPTION: Self = Self(1 << 3);
+    /// Extra copy created by the duplicate module
+    pub const DUPLICATE: Self = Self(1 << 4);
+    /// Queued by the bandwidth module
+    pub const BANDWIDTH: Self = Self(1 << 5);
+    /// Buffered by the burst module
+    pub const BURST: Self = Self(1 << 6);
+    /// Sent with swapped addresses after a failed send
+    pub const BYPASS: Self = Self(1 << 7);
+    /// Dropped by a module instead of being sent
+    pub const DROP: Self = Self(1 << 8);
+
+    /// All effects paired with their display names, in pipeline order.
+    const NAMES: [(Self, &'static str); 9] = [
+        (Self::LAG, "lag"),
+        (Self::THROTTLE, "throttle"),
+        (Self::REORDER, "reorder"),
+        (Self::CORRUPTION, "corruption"),
+        (Self::DUPLICATE, "duplicate"),
+        (Self::BANDWIDTH, "bandwidth"),
+        (Self::BURST, "burst"),
+        (Self::BYPASS, "bypass"),
+        (Self::DROP, "DROP"),
+    ];
+
+    /// Adds `effect` to the set.
+    pub fn insert(&mut self, effect: Self) {
+        self.0 |= effect.0;
+    }
+
+    /// Returns whether every effect in `effect` is in the set.
+    pub fn contains(self, effect: Self) -> bool {
+        self.0 & effect.0 == effect.0
+    }
+
+    /// Returns whether no module acted on the packet.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl fmt::Display for PacketEffects {
+    /// Formats the effects as a comma-separated list of module names.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+
+        let names: Vec<&str> = Self::NAMES
+            .iter()
+            .filter(|(effect, _)| self.contains(*effect))
+            .map(|(_, name)| *name)
+            .collect();
+
+        f.write_str(&names.join(", "))
+
+    }
+}
 
 /// Represents a network packet with metadata for processing.
 ///
-/// This structure wraps a `WinDivert` packet and associates it with
-/// timing information, which is crucial for implementing various
-/// network condition simulations like delays and bandwidth limits.
+/// This structure owns the raw IP packet bytes and associates them with
+/// direction, interface and timing information, which is crucial for
+/// implementing various network condition simulations like delays and
+/// bandwidth limits. It is independent of any capture driver; backends
+/// convert to and from their own packet types at the I/O boundary.
 #[derive(Debug, Clone)]
-pub struct PacketData<'a> {
-    /// The actual network packet from `WinDivert`
-    pub packet: WinDivertPacket<'a, NetworkLayer>,
-    /// Timestamp when the packet was captured
-    pub arrival_time: Instant,
+pub struct PacketData {
+    /// Unique id assigned on creation; duplicates share the id of their original
+    pub id: u64,
+    /// Raw IP packet bytes, starting at the IP header
+    pub data: Vec<u8>,
     /// Whether this packet is outbound (upload) or inbound (download)
     pub is_outbound: bool,
+    /// Index of the interface the packet arrived on or is to be sent on
+    pub interface_index: u32,
+    /// Sub-interface index for `interface_index`
+    pub subinterface_index: u32,
+    /// Checksum and routing flags reported by the capture backend
+    pub flags: PacketFlags,
+    /// Monotonic timestamp when the packet was captured
+    pub arrival_time: Instant,
+    /// Wall-clock timestamp when the packet was captured
+    pub captured_at: SystemTime,
+    /// Modules that acted on the packet so far
+    pub effects: PacketEffects,
 }
 
-impl<'a> PacketData<'a> {
-    /// Creates a `PacketData` instance from a `WinDivertPacket` with direction info.
-    pub fn new(packet: WinDivertPacket<'a, NetworkLayer>, is_outbound: bool) -> Self {
+impl PacketData {
+    /// Creates a `PacketData` instance from raw packet bytes with direction info.
+    pub fn new(data: Vec<u8>, is_outbound: bool) -> Self {
 
-        PacketData {
-            packet,
-            arrival_time: Instant::now(),
+        Self {
+            id: NEXT_PACKET_ID.fetch_add(1, Ordering::Relaxed),
+            data,
             is_outbound,
+            interface_index: 0,
+            subinterface_index: 0,
+            flags: PacketFlags::default(),
+            arrival_time: Instant::now(),
+            captured_at: SystemTime::now(),
+            effects: PacketEffects::default(),
         }
 
     }
 }
 
-impl<'a> From<WinDivertPacket<'a, NetworkLayer>> for PacketData<'a> {
-    /// Creates a `PacketData` instance from a `WinDivertPacket`,
+impl From<Vec<u8>> for PacketData {
+    /// Creates a `PacketData` instance from raw packet bytes,
     /// automatically recording the current time as arrival time.
     /// Defaults to outbound=false (inbound) when direction is unknown.
-    fn from(packet: WinDivertPacket<'a, NetworkLayer>) -> Self {
-
-        PacketData {
-            packet,
-            arrival_time: Instant::now(),
-            is_outbound: false, // Default when direction unknown
-        }
-
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data, false) // Default when direction unknown
     }
 }
 
 /// Methods for working with packet data
-impl PacketData<'_> {
+impl PacketData {
     /// Returns the size of the packet in bytes
     pub fn size(&self) -> usize {
-        self.packet.data.len()
+        self.data.len()
     }
 
     /// Returns the time elapsed since the packet was captured
     pub fn age(&self) -> std::time::Duration {
         self.arrival_time.elapsed()
     }
+
+    /// Returns a parsed view of the IP and transport headers.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(PacketHeaders)` - If the packet starts with a valid IPv4 or IPv6 header
+    /// * `None` - If the packet is truncated or not IP
+    pub fn headers(&self) -> Option<PacketHeaders> {
+        PacketHeaders::parse(&self.data)
+    }
+
+    /// Recalculates the IP and transport checksums after the payload was modified.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the checksums were recalculated, `false` if the packet could not be parsed
+    pub fn recalculate_checksums(&mut self) -> bool {
+
+        let Some(headers) = self.headers() else {
+            return false;
+        };
+
+        checksum::recalculate(&mut self.data, &headers);
+
+        self.flags.ip_checksum = true;
+        self.flags.tcp_checksum = true;
+        self.flags.udp_checksum = true;
+        true
+
+    }
 }
 
 #[cfg(test)]
@@ -65,20 +200,54 @@ mod tests {
     #[test]
     fn test_packet_data_creation() {
 
-        unsafe {
-            let dummy_packet = WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3, 4]);
-            let packet_data = PacketData::from(dummy_packet);
+        let packet_data = PacketData::from(vec![1, 2, 3, 4]);
 
-            // Assert that the packet data is correctly assigned
-            assert_eq!(packet_data.packet.data.len(), 4);
-            assert_eq!(packet_data.packet.data[..], [1, 2, 3, 4]);
+        // Assert that the packet data is correctly assigned
+        assert_eq!(packet_data.data.len(), 4);
+        assert_eq!(packet_data.data[..], [1, 2, 3, 4]);
 
-            // Check that size() returns the correct value
-            assert_eq!(packet_data.size(), 4);
+        // Check that size() returns the correct value
+        assert_eq!(packet_data.size(), 4);
 
-            // Verify that the arrival time is recent
-            assert!(packet_data.age().as_secs() < 1);
-        }
+        // Verify that the arrival time is recent
+        assert!(packet_data.age().as_secs() < 1);
+
+    }
+
+    #[test]
+    fn test_recalculate_checksums_requires_ip_packet() {
+
+        let mut packet_data = PacketData::new(vec![1, 2, 3, 4], true);
+
+        assert!(!packet_data.recalculate_checksums());
+        assert!(!packet_data.flags.ip_checksum);
+
+    }
+
+    #[test]
+    fn test_packet_ids_are_unique() {
+
+        let first = PacketData::from(vec![1]);
+        let second = PacketData::from(vec![1]);
+
+        assert_ne!(first.id, second.id);
+
+    }
+
+    #[test]
+    fn test_effects_display() {
+
+        let mut effects = PacketEffects::default();
+
+        assert!(effects.is_empty());
+        assert_eq!(effects.to_string(), "");
+
+        effects.insert(PacketEffects::DUPLICATE);
+        effects.insert(PacketEffects::LAG);
+
+        assert!(effects.contains(PacketEffects::LAG));
+        assert!(!effects.contains(PacketEffects::BURST));
+        assert_eq!(effects.to_string(), "lag, duplicate");
 
     }
 }
@@ -0,0 +1,195 @@
+//! Process selection for process-based filtering.
+//!
+//! A [`ProcessSelector`] names processes by PID or executable name, and can
+//! extend to everything they spawn. It is resolved against a snapshot of the
+//! running processes, so a game that restarts under a new PID, or a helper it
+//! launches later, is picked up by resolving again.
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+/// Processes to target.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessSelector {
+    /// Process IDs
+    #[serde(default)]
+    pub pids: Vec<u32>,
+    /// Executable names, matched without case and with `.exe` optional
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Also target every descendant of a matched process
+    #[serde(default)]
+    pub include_children: bool,
+}
+
+/// A running process, as seen by a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub parent: Option<u32>,
+    pub name: String,
+}
+
+impl ProcessSelector {
+    /// Creates a selector for a single process.
+    pub fn pid(pid: u32) -> Self {
+        Self {
+            pids: vec![pid],
+            ..Self::default()
+        }
+    }
+
+    /// Returns whether the selector names no process.
+    pub fn is_empty(&self) -> bool {
+        self.pids.is_empty() && self.names.iter().all(|name| name.trim().is_empty())
+    }
+
+    /// Returns the PIDs of the running processes the selector targets.
+    ///
+    /// # Arguments
+    ///
+    /// * `processes` - The running processes
+    pub fn resolve(&self, processes: &[ProcessEntry]) -> HashSet<u32> {
+
+        let names: Vec<String> = self
+            .names
+            .iter()
+            .map(|name| executable_stem(name))
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        let mut matched: HashSet<u32> = processes
+            .iter()
+            .filter(|process| {
+                self.pids.contains(&process.pid) || names.contains(&executable_stem(&process.name))
+            })
+            .map(|process| process.pid)
+            .collect();
+
+        if !self.include_children {
+            return matched;
+        }
+
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+
+        for process in processes {
+            if let Some(parent) = process.parent.filter(|&parent| parent != process.pid) {
+                children.entry(parent).or_default().push(process.pid);
+            }
+        }
+
+        let mut pending: Vec<u32> = matched.iter().copied().collect();
+
+        while let Some(pid) = pending.pop() {
+            for &child in children.get(&pid).into_iter().flatten() {
+                if matched.insert(child) {
+                    pending.push(child);
+                }
+            }
+        }
+
+        matched
+
+    }
+}
+
+/// Returns the running processes.
+pub fn snapshot_processes() -> Vec<ProcessEntry> {
+
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let mut system = System::new();
+
+    system.refresh_processes_specifics(ProcessesToUpdate::All, true, ProcessRefreshKind::nothing());
+
+    system
+        .processes()
+        .iter()
+        .map(|(pid, process)| ProcessEntry {
+            pid: pid.as_u32(),
+            parent: process.parent().map(sysinfo::Pid::as_u32),
+            name: process.name().to_string_lossy().to_string(),
+        })
+        .collect()
+
+}
+
+/// Lowercases an executable name and drops its `.exe` extension.
+fn executable_stem(name: &str) -> String {
+
+    let name = name.trim().to_lowercase();
+
+    name.strip_suffix(".exe").unwrap_or(&name).to_string()
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, parent: Option<u32>, name: &str) -> ProcessEntry {
+        ProcessEntry {
+            pid,
+            parent,
+            name: name.to_string(),
+        }
+    }
+
+    fn processes() -> Vec<ProcessEntry> {
+        vec![
+            process(1, None, "launcher.exe"),
+            process(10, Some(1), "Game.exe"),
+            process(11, Some(10), "crashpad.exe"),
+            process(12, Some(11), "helper.exe"),
+            process(20, Some(1), "updater.exe"),
+            process(30, Some(30), "loop.exe"),
+        ]
+    }
+
+    fn sorted(pids: HashSet<u32>) -> Vec<u32> {
+
+        let mut pids: Vec<u32> = pids.into_iter().collect();
+
+        pids.sort_unstable();
+        pids
+
+    }
+
+    #[test]
+    fn test_resolve_by_pid_and_name() {
+
+        let selector = ProcessSelector {
+            pids: vec![20, 99],
+            names: vec!["game".to_string(), " ".to_string()],
+            include_children: false,
+        };
+
+        assert_eq!(sorted(selector.resolve(&processes())), [10, 20]);
+        assert!(ProcessSelector::default().is_empty());
+        assert!(!ProcessSelector::pid(1).is_empty());
+
+    }
+
+    #[test]
+    fn test_resolve_process_tree() {
+
+        let selector = ProcessSelector {
+            names: vec!["GAME.EXE".to_string(), "loop.exe".to_string()],
+            include_children: true,
+            ..ProcessSelector::default()
+        };
+
+        assert_eq!(sorted(selector.resolve(&processes())), [10, 11, 12, 30]);
+        assert_eq!(
+            sorted(
+                ProcessSelector {
+                    include_children: true,
+                    ..ProcessSelector::pid(1)
+                }
+                .resolve(&processes())
+            ),
+            [1, 10, 11, 12, 20]
+        );
+
+    }
+}
@@ -0,0 +1,242 @@
+//! Syntax tree of a filter expression.
+//!
+//! Printing an [`Expr`] gives the canonical form of the filter: documented
+//! field spellings, `and`/`or`/`not`, and only the parentheses precedence
+//! requires. The printed form parses back to the same tree.
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::network::filter::fields::Field;
+
+/// A value a field is compared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Value {
+    Number(u128),
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+}
+
+impl Value {
+    /// Returns the value as the number fields are compared as.
+    pub fn to_u128(self) -> u128 {
+        match self {
+            Self::Number(number) => number,
+            Self::Ipv4(addr) => u128::from(u32::from(addr)),
+            Self::Ipv6(addr) => u128::from(addr),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{}", number),
+            Self::Ipv4(addr) => write!(f, "{}", addr),
+            Self::Ipv6(addr) => write!(f, "{}", addr),
+        }
+    }
+}
+
+/// Comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    /// Returns the operator as written in a filter.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    /// Returns whether `left <op> right` holds.
+    pub fn holds(self, left: u128, right: u128) -> bool {
+        match self {
+            Self::Eq => left == right,
+            Self::Ne => left != right,
+            Self::Lt => left < right,
+            Self::Le => left <= right,
+            Self::Gt => left > right,
+            Self::Ge => left >= right,
+        }
+    }
+
+    /// Returns the operator that holds exactly when this one does not.
+    pub fn negate(self) -> Self {
+        match self {
+            Self::Eq => Self::Ne,
+            Self::Ne => Self::Eq,
+            Self::Lt => Self::Ge,
+            Self::Le => Self::Gt,
+            Self::Gt => Self::Le,
+            Self::Ge => Self::Lt,
+        }
+    }
+}
+
+/// A filter expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    /// `true` or `false`
+    Bool(bool),
+    /// A field on its own, which holds when the packet has it and it is not zero
+    Test(Field),
+    /// A field compared with a value; never holds when the packet lacks the field
+    Compare(Field, CompareOp, Value),
+    Not(Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+    /// `test ? then : otherwise`
+    If(Box<Self>, Box<Self>, Box<Self>),
+}
+
+/// Binding strength of each kind of expression, loosest first.
+const PREC_IF: u8 = 0;
+const PREC_OR: u8 = 1;
+const PREC_AND: u8 = 2;
+const PREC_UNARY: u8 = 3;
+
+impl Expr {
+    /// Returns `left and right`.
+    pub fn and(left: Self, right: Self) -> Self {
+        Self::And(Box::new(left), Box::new(right))
+    }
+
+    /// Returns `left or right`.
+    pub fn or(left: Self, right: Self) -> Self {
+        Self::Or(Box::new(left), Box::new(right))
+    }
+
+    /// Joins `exprs` with `and`; `true` if there are none.
+    pub fn all(exprs: impl IntoIterator<Item = Self>) -> Self {
+        exprs
+            .into_iter()
+            .reduce(Self::and)
+            .unwrap_or(Self::Bool(true))
+    }
+
+    /// Joins `exprs` with `or`; `false` if there are none.
+    pub fn any(exprs: impl IntoIterator<Item = Self>) -> Self {
+        exprs
+            .into_iter()
+            .reduce(Self::or)
+            .unwrap_or(Self::Bool(false))
+    }
+
+    /// Returns `not expr`.
+    pub fn negate(expr: Self) -> Self {
+        Self::Not(Box::new(expr))
+    }
+
+    /// Returns `field <op> value`.
+    pub fn compare(field: impl Into<Field>, op: CompareOp, value: Value) -> Self {
+        Self::Compare(field.into(), op, value)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::If(..) => PREC_IF,
+            Self::Or(..) => PREC_OR,
+            Self::And(..) => PREC_AND,
+            Self::Bool(_) | Self::Test(_) | Self::Compare(..) | Self::Not(_) => PREC_UNARY,
+        }
+    }
+
+    /// Writes the expression, in parentheses if it binds looser than `min`.
+    fn write(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+
+        if self.precedence() < min {
+            f.write_str("(")?;
+            self.write(f, PREC_IF)?;
+            return f.write_str(")");
+        }
+
+        match self {
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Test(field) => write!(f, "{}", field),
+            Self::Compare(field, op, value) => write!(f, "{} {} {}", field, op.symbol(), value),
+            Self::Not(expr) => {
+                f.write_str("not ")?;
+                expr.write(f, PREC_UNARY)
+            }
+            Self::And(left, right) => {
+                left.write(f, PREC_AND)?;
+                f.write_str(" and ")?;
+                right.write(f, PREC_AND)
+            }
+            Self::Or(left, right) => {
+                left.write(f, PREC_OR)?;
+                f.write_str(" or ")?;
+                right.write(f, PREC_OR)
+            }
+            Self::If(test, then, otherwise) => {
+                test.write(f, PREC_OR)?;
+                f.write_str(" ? ")?;
+                then.write(f, PREC_OR)?;
+                f.write_str(" : ")?;
+                otherwise.write(f, PREC_IF)
+            }
+        }
+
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, PREC_IF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::filter::fields::NamedField;
+
+    #[test]
+    fn test_display_adds_only_needed_parentheses() {
+
+        let tcp = Expr::Test(NamedField::Tcp.into());
+        let port = Expr::compare(NamedField::TcpDstPort, CompareOp::Eq, Value::Number(80));
+        let outbound = Expr::Test(NamedField::Outbound.into());
+
+        let expr = Expr::and(Expr::or(tcp.clone(), port.clone()), Expr::negate(outbound));
+
+        assert_eq!(
+            expr.to_string(),
+            "(tcp or tcp.DstPort == 80) and not outbound"
+        );
+
+        let expr = Expr::or(
+            Expr::and(tcp.clone(), port.clone()),
+            Expr::negate(Expr::or(tcp, port)),
+        );
+
+        assert_eq!(
+            expr.to_string(),
+            "tcp and tcp.DstPort == 80 or not (tcp or tcp.DstPort == 80)"
+        );
+
+    }
+
+    #[test]
+    fn test_values_compare_as_numbers() {
+
+        assert_eq!(Value::Ipv4(Ipv4Addr::new(0, 0, 1, 2)).to_u128(), 258);
+        assert_eq!(Value::Ipv6(Ipv6Addr::LOCALHOST).to_u128(), 1);
+        assert!(CompareOp::Le.holds(3, 3));
+        assert!(!CompareOp::Le.negate().holds(3, 3));
+
+    }
+}
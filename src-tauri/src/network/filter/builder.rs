@@ -0,0 +1,317 @@
+//! Typed construction of capture filters.
+//!
+//! A [`FilterBuilder`] collects targets, such as hosts and the ports or
+//! connections of a process, and restrictions on direction, protocol and
+//! remote port. The filter captures traffic of any target that passes every
+//! restriction. Host addresses pick the IPv4 or IPv6 header fields by their
+//! family, and the side of the packet they are compared with by direction.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use crate::network::filter::builder::{FilterBuilder, Protocol};
+//!
+//! let filter = FilterBuilder::new()
+//!     .directions(false, true)  // outbound only
+//!     .protocol(Protocol::Udp)
+//!     .remote_ports("27000-27100".parse().unwrap())
+//!     .host("10.0.0.5".parse().unwrap())
+//!     .build();
+//! ```
+use std::net::{IpAddr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::network::filter::ast::{CompareOp, Expr, Value};
+use crate::network::filter::fields::NamedField;
+use crate::settings::rules::PortRange;
+
+/// Returns `field` compared with the ports in `range`.
+fn port_range_expr(range: PortRange, field: NamedField) -> Expr {
+
+    let port = |port: u16| Value::Number(port.into());
+
+    if range.start == range.end {
+        return Expr::compare(field, CompareOp::Eq, port(range.start));
+    }
+
+    Expr::and(
+        Expr::compare(field, CompareOp::Ge, port(range.start)),
+        Expr::compare(field, CompareOp::Le, port(range.end)),
+    )
+
+}
+
+/// A transport protocol a filter can be restricted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    /// ICMP over IPv4 or IPv6
+    Icmp,
+}
+
+impl Protocol {
+    fn expr(self) -> Expr {
+
+        let test = |field: NamedField| Expr::Test(field.into());
+
+        match self {
+            Self::Tcp => test(NamedField::Tcp),
+            Self::Udp => test(NamedField::Udp),
+            Self::Icmp => Expr::or(test(NamedField::Icmp), test(NamedField::Icmpv6)),
+        }
+
+    }
+}
+
+/// Traffic a filter captures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// Traffic exchanged with a host
+    Host(IpAddr),
+    /// Traffic on a local port, such as one a process has bound
+    LocalPort(u16),
+    /// One connection, by its local port and remote end
+    Connection { local_port: u16, remote: SocketAddr },
+    /// Traffic matching an expression
+    Expr(Expr),
+}
+
+/// Builder for capture filters.
+///
+/// Captures both directions until [`FilterBuilder::directions`] says otherwise.
+#[derive(Debug, Clone)]
+pub struct FilterBuilder {
+    inbound: bool,
+    outbound: bool,
+    protocols: Vec<Protocol>,
+    remote_ports: Vec<PortRange>,
+    targets: Vec<Target>,
+}
+
+impl Default for FilterBuilder {
+    fn default() -> Self {
+        Self {
+            inbound: true,
+            outbound: true,
+            protocols: Vec::new(),
+            remote_ports: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl FilterBuilder {
+    /// Creates a builder that captures all traffic.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets which directions are captured; neither captures nothing.
+    pub fn directions(mut self, inbound: bool, outbound: bool) -> Self {
+        self.inbound = inbound;
+        self.outbound = outbound;
+        self
+    }
+
+    /// Restricts capture to `protocol`, or adds it to the protocols allowed.
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+
+        if !self.protocols.contains(&protocol) {
+            self.protocols.push(protocol);
+        }
+        self
+
+    }
+
+    /// Restricts capture to remote ports in `range`, or adds it to the ranges allowed.
+    pub fn remote_ports(mut self, range: PortRange) -> Self {
+
+        if !self.remote_ports.contains(&range) {
+            self.remote_ports.push(range);
+        }
+        self
+
+    }
+
+    /// Captures traffic of `target`, along with the targets added before.
+    pub fn target(mut self, target: Target) -> Self {
+
+        if !self.targets.contains(&target) {
+            self.targets.push(target);
+        }
+        self
+
+    }
+
+    /// Captures traffic exchanged with `addr`.
+    pub fn host(self, addr: IpAddr) -> Self {
+        self.target(Target::Host(addr))
+    }
+
+    /// Captures traffic on the local `port`.
+    pub fn local_port(self, port: u16) -> Self {
+        self.target(Target::LocalPort(port))
+    }
+
+    /// Captures the connection from the local `local_port` to `remote`.
+    pub fn connection(self, local_port: u16, remote: SocketAddr) -> Self {
+        self.target(Target::Connection { local_port, remote })
+    }
+
+    /// Captures traffic matching `expr`.
+    pub fn expr(self, expr: Expr) -> Self {
+        self.target(Target::Expr(expr))
+    }
+
+    /// Returns whether any target was added.
+    pub fn has_targets(&self) -> bool {
+        !self.targets.is_empty()
+    }
+
+    /// Builds the filter expression.
+    ///
+    /// # Returns
+    ///
+    /// * `Expr` - The restrictions joined with `and`, followed by the targets
+    ///   joined with `or`; all traffic in the chosen directions without targets
+    pub fn build(&self) -> Expr {
+
+        let direction = match (self.inbound, self.outbound) {
+            (false, false) => return Expr::Bool(false),
+            (true, true) => None,
+            (false, true) => Some(Expr::Test(NamedField::Outbound.into())),
+            (true, false) => Some(Expr::Test(NamedField::Inbound.into())),
+        };
+
+        let mut terms: Vec<Expr> = direction.into_iter().collect();
+
+        if !self.protocols.is_empty() {
+            terms.push(Expr::any(
+                self.protocols.iter().copied().map(Protocol::expr),
+            ));
+        }
+
+        if !self.remote_ports.is_empty() {
+            terms.push(Expr::any(
+                self.remote_ports
+                    .iter()
+                    .map(|&range| port_range_expr(range, NamedField::RemotePort)),
+            ));
+        }
+
+        if !self.targets.is_empty() {
+            terms.push(Expr::any(
+                self.targets.iter().map(|target| self.target_expr(target)),
+            ));
+        }
+
+        Expr::all(terms)
+
+    }
+
+    fn target_expr(&self, target: &Target) -> Expr {
+
+        let port = |field: NamedField, port: u16| {
+            Expr::compare(field, CompareOp::Eq, Value::Number(port.into()))
+        };
+
+        match target {
+            Target::Host(addr) => self.host_expr(*addr),
+            Target::LocalPort(local_port) => port(NamedField::LocalPort, *local_port),
+            Target::Connection { local_port, remote } => Expr::all([
+                self.host_expr(remote.ip()),
+                port(NamedField::LocalPort, *local_port),
+                port(NamedField::RemotePort, remote.port()),
+            ]),
+            Target::Expr(expr) => expr.clone(),
+        }
+
+    }
+
+    /// Matches packets exchanged with `addr`: sent to it when outbound,
+    /// received from it when inbound.
+    fn host_expr(&self, addr: IpAddr) -> Expr {
+
+        let (src, dst, value) = match addr {
+            IpAddr::V4(addr) => (
+                NamedField::IpSrcAddr,
+                NamedField::IpDstAddr,
+                Value::Ipv4(addr),
+            ),
+            IpAddr::V6(addr) => (
+                NamedField::Ipv6SrcAddr,
+                NamedField::Ipv6DstAddr,
+                Value::Ipv6(addr),
+            ),
+        };
+        let to = Expr::compare(dst, CompareOp::Eq, value);
+        let from = Expr::compare(src, CompareOp::Eq, value);
+
+        match (self.inbound, self.outbound) {
+            (true, true) => Expr::or(
+                Expr::and(Expr::Test(NamedField::Outbound.into()), to),
+                Expr::and(Expr::Test(NamedField::Inbound.into()), from),
+            ),
+            (true, false) => from,
+            _ => to,
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::filter::Filter;
+
+    #[test]
+    fn test_directions() {
+
+        let addr: IpAddr = "192.168.1.20".parse().unwrap();
+        let host = |inbound, outbound| {
+            FilterBuilder::new()
+                .directions(inbound, outbound)
+                .host(addr)
+                .build()
+                .to_string()
+        };
+
+        assert_eq!(FilterBuilder::new().build().to_string(), "true");
+        assert_eq!(host(false, false), "false");
+        assert_eq!(host(true, false), "inbound and ip.SrcAddr == 192.168.1.20");
+        assert_eq!(
+            host(true, true),
+            "outbound and ip.DstAddr == 192.168.1.20 or inbound and ip.SrcAddr == 192.168.1.20"
+        );
+
+    }
+
+    #[test]
+    fn test_targets_and_restrictions() {
+
+        let filter = FilterBuilder::new()
+            .directions(false, true)
+            .protocol(Protocol::Udp)
+            .protocol(Protocol::Icmp)
+            .remote_ports("27000-27100".parse().unwrap())
+            .local_port(50000)
+            .host("fe80::1".parse().unwrap())
+            .connection(50001, "10.0.0.1:443".parse().unwrap())
+            .build();
+
+        let text = filter.to_string();
+
+        assert_eq!(
+            text,
+            "outbound and (udp or icmp or icmpv6) \
+             and remotePort >= 27000 and remotePort <= 27100 \
+             and (localPort == 50000 or ipv6.DstAddr == fe80::1 \
+             or ip.DstAddr == 10.0.0.1 and localPort == 50001 and remotePort == 443)"
+        );
+        assert_eq!(Filter::parse(&text).unwrap().to_string(), text);
+
+    }
+}
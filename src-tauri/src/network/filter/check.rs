@@ -0,0 +1,388 @@
+//! Finds terms of a filter that can never hold together.
+//!
+//! Each `and` chain is reduced to the range of values it allows for every
+//! field it tests. A range that ends up empty, or headers that exclude each
+//! other (TCP and UDP, IPv4 and IPv6), make the chain unsatisfiable. Terms
+//! under `or`, `? :` or a negated group are checked on their own, so the
+//! check finds contradictions but does not prove a filter satisfiable.
+use std::collections::{BTreeSet, HashMap};
+
+use crate::network::filter::ast::{CompareOp, Expr};
+use crate::network::filter::fields::{Field, Layer, NamedField};
+
+/// Headers a packet has at most one of, with how to describe a clash.
+const EXCLUSIVE: [(&[NamedField], &str); 2] = [
+    (
+        &[
+            NamedField::Tcp,
+            NamedField::Udp,
+            NamedField::Icmp,
+            NamedField::Icmpv6,
+        ],
+        "a packet has only one transport protocol",
+    ),
+    (
+        &[NamedField::Ip, NamedField::Ipv6],
+        "a packet is either IPv4 or IPv6",
+    ),
+];
+
+/// Why an expression never matches.
+enum Unsatisfiable {
+    /// It says `false`
+    Literal,
+    /// Its terms contradict each other, as explained
+    Conflict(String),
+}
+
+/// Explains why `expr` never matches, if its terms contradict each other.
+pub fn contradiction(expr: &Expr) -> Option<String> {
+    match unsatisfiable(expr)? {
+        Unsatisfiable::Literal => None,
+        Unsatisfiable::Conflict(reason) => Some(reason),
+    }
+}
+
+fn unsatisfiable(expr: &Expr) -> Option<Unsatisfiable> {
+    match expr {
+        Expr::Bool(value) => (!value).then_some(Unsatisfiable::Literal),
+        Expr::Or(left, right) => Some(prefer_conflict(unsatisfiable(left)?, unsatisfiable(right)?)),
+        Expr::If(_, then, otherwise) => Some(prefer_conflict(
+            unsatisfiable(then)?,
+            unsatisfiable(otherwise)?,
+        )),
+        Expr::Not(inner) => match inner.as_ref() {
+            Expr::Bool(value) => value.then_some(Unsatisfiable::Literal),
+            Expr::Not(inner) => unsatisfiable(inner),
+            _ if constraint(expr).is_some() => conjunction(&[expr]),
+            _ => None,
+        },
+        Expr::And(..) | Expr::Test(_) | Expr::Compare(..) => {
+            let mut terms = Vec::new();
+
+            flatten_and(expr, &mut terms);
+            conjunction(&terms)
+        }
+    }
+}
+
+fn prefer_conflict(left: Unsatisfiable, right: Unsatisfiable) -> Unsatisfiable {
+    match (left, right) {
+        (conflict @ Unsatisfiable::Conflict(_), _) | (_, conflict) => conflict,
+    }
+}
+
+fn flatten_and<'a>(expr: &'a Expr, terms: &mut Vec<&'a Expr>) {
+    match expr {
+        Expr::And(left, right) => {
+            flatten_and(left, terms);
+            flatten_and(right, terms);
+        }
+        _ => terms.push(expr),
+    }
+}
+
+/// Checks the terms of one `and` chain together.
+fn conjunction(terms: &[&Expr]) -> Option<Unsatisfiable> {
+
+    let mut ranges = Ranges::default();
+    let mut literal = false;
+
+    for term in terms {
+        let Some((field, op, value)) = constraint(term) else {
+            match unsatisfiable(term) {
+                Some(Unsatisfiable::Literal) => literal = true,
+                Some(conflict) => return Some(conflict),
+                None => {}
+            }
+            continue;
+        };
+
+        if let Some(reason) = ranges.constrain(field, op, value, term) {
+            return Some(Unsatisfiable::Conflict(reason));
+        }
+    }
+
+    ranges
+        .exclusive_headers()
+        .map(Unsatisfiable::Conflict)
+        .or(literal.then_some(Unsatisfiable::Literal))
+
+}
+
+/// Reduces a term to a comparison that must hold, if it is that simple.
+///
+/// Negations only reduce where the field is on every packet; `not tcp.Syn`
+/// also holds for packets without TCP, which no range of `tcp.Syn` says.
+fn constraint(term: &Expr) -> Option<(Field, CompareOp, u128)> {
+    match term {
+        Expr::Test(field) => Some((*field, CompareOp::Ne, 0)),
+        Expr::Compare(field, op, value) => Some((*field, *op, value.to_u128())),
+        Expr::Not(inner) => {
+            let (field, op, value) = constraint(inner)?;
+
+            (field.layer() == Layer::Packet && is_always_present(field)).then_some((
+                field,
+                op.negate(),
+                value,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether every packet has `field`, even one without IP headers.
+fn is_always_present(field: Field) -> bool {
+    !matches!(
+        field,
+        Field::Bytes { .. }
+            | Field::Named(
+                NamedField::Timestamp
+                    | NamedField::Protocol
+                    | NamedField::LocalAddr
+                    | NamedField::LocalPort
+                    | NamedField::RemoteAddr
+                    | NamedField::RemotePort
+            )
+    )
+}
+
+/// Values each field may still take, with the terms that narrowed them.
+#[derive(Default)]
+struct Ranges {
+    ranges: HashMap<Field, Range>,
+}
+
+struct Range {
+    min: u128,
+    max: u128,
+    excluded: BTreeSet<u128>,
+    terms: Vec<String>,
+}
+
+impl Ranges {
+    /// Narrows `field` to values where `field <op> value`, along with the
+    /// headers the term requires.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<String>` - Why the terms so far never hold, if they do not
+    fn constrain(
+        &mut self,
+        field: Field,
+        op: CompareOp,
+        value: u128,
+        term: &Expr,
+    ) -> Option<String> {
+
+        // `inbound` is narrowed as the opposite of `outbound`
+        let (field, op, value) = match field {
+            Field::Named(NamedField::Inbound) => {
+                let allowed: Vec<u128> = [0, 1]
+                    .into_iter()
+                    .filter(|&flag| op.holds(flag, value))
+                    .collect();
+
+                match allowed[..] {
+                    [flag] => (Field::Named(NamedField::Outbound), CompareOp::Eq, 1 - flag),
+                    [] => (Field::Named(NamedField::Outbound), CompareOp::Gt, 1),
+                    _ => return None,
+                }
+            }
+            _ => (field, op, value),
+        };
+
+        if let Some(reason) = self.narrow(field, op, value, term) {
+            return Some(reason);
+        }
+
+        // A header field is only on packets with that header, and a header
+        // forced on is only in packets that can carry it
+        let header = match (field, field.layer().field()) {
+            (_, Some(header)) => Some(header),
+            (Field::Named(named), None) if !op.holds(0, value) => Some(named),
+            _ => None,
+        };
+        let required = header
+            .filter(|header| Field::Named(*header) != field)
+            .into_iter()
+            .chain(header.and_then(carrier));
+
+        for required in required {
+            if let Some(reason) = self.narrow(Field::Named(required), CompareOp::Eq, 1, term) {
+                return Some(reason);
+            }
+        }
+        None
+
+    }
+
+    /// Narrows the range of `field`; returns why it became empty, if it did.
+    fn narrow(&mut self, field: Field, op: CompareOp, value: u128, term: &Expr) -> Option<String> {
+
+        let range = self.ranges.entry(field).or_insert_with(|| Range {
+            min: 0,
+            max: field.field_type().max(),
+            excluded: BTreeSet::new(),
+            terms: Vec::new(),
+        });
+        let term = term.to_string();
+
+        if !range.terms.contains(&term) {
+            range.terms.push(term);
+        }
+
+        let (min, max) = match op {
+            CompareOp::Eq => (value, value),
+            CompareOp::Ne => {
+                range.excluded.insert(value);
+                (0, u128::MAX)
+            }
+            CompareOp::Lt => match value.checked_sub(1) {
+                Some(max) => (0, max),
+                None => return Some(never(&range.terms)),
+            },
+            CompareOp::Le => (0, value),
+            CompareOp::Gt => match value.checked_add(1) {
+                Some(min) => (min, u128::MAX),
+                None => return Some(never(&range.terms)),
+            },
+            CompareOp::Ge => (value, u128::MAX),
+        };
+
+        range.min = range.min.max(min);
+        range.max = range.max.min(max);
+
+        // Step past excluded values at either end
+        while range.min <= range.max && range.excluded.contains(&range.min) {
+            let Some(min) = range.min.checked_add(1) else {
+                return Some(never(&range.terms));
+            };
+            range.min = min;
+        }
+        while range.min <= range.max && range.excluded.contains(&range.max) {
+            let Some(max) = range.max.checked_sub(1) else {
+                return Some(never(&range.terms));
+            };
+            range.max = max;
+        }
+
+        (range.min > range.max).then(|| never(&range.terms))
+
+    }
+
+    /// Returns why the required headers clash, if two exclusive ones are.
+    fn exclusive_headers(&self) -> Option<String> {
+
+        for (headers, reason) in EXCLUSIVE {
+            let required: Vec<&Range> = headers
+                .iter()
+                .filter_map(|header| self.ranges.get(&Field::Named(*header)))
+                .filter(|range| range.min == 1)
+                .collect();
+
+            if required.len() > 1 {
+                let terms: Vec<String> = required
+                    .iter()
+                    .flat_map(|range| range.terms.iter().cloned())
+                    .collect();
+
+                return Some(format!("{}: {}", never(&terms), reason));
+            }
+        }
+        None
+
+    }
+}
+
+/// Returns the header `header` is carried in, if only one can carry it.
+fn carrier(header: NamedField) -> Option<NamedField> {
+    match header {
+        NamedField::Icmp => Some(NamedField::Ip),
+        NamedField::Icmpv6 => Some(NamedField::Ipv6),
+        _ => None,
+    }
+}
+
+/// Describes terms that never hold together.
+fn never(terms: &[String]) -> String {
+
+    let mut unique: Vec<&String> = Vec::new();
+
+    for term in terms {
+        if !unique.contains(&term) {
+            unique.push(term);
+        }
+    }
+
+    let joined: Vec<&str> = unique.iter().map(|term| term.as_str()).collect();
+
+    format!("`{}` never matches", joined.join(" and "))
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::filter::parser::parse;
+
+    fn check(filter: &str) -> Option<String> {
+        contradiction(&parse(filter).unwrap())
+    }
+
+    #[test]
+    fn test_conflicting_ranges() {
+
+        assert_eq!(
+            check("tcp.DstPort == 80 and tcp.DstPort == 443").as_deref(),
+            Some("`tcp.DstPort == 80 and tcp.DstPort == 443` never matches")
+        );
+        assert_eq!(
+            check("outbound and (tcp or udp) and inbound").as_deref(),
+            Some("`outbound and inbound` never matches")
+        );
+        assert!(check("ip.TTL > 10 and ip.TTL < 11").is_some());
+        assert!(
+            check(
+                "udp.DstPort >= 1 and udp.DstPort <= 2 and udp.DstPort != 1 and udp.DstPort != 2"
+            )
+            .is_some()
+        );
+        assert!(check("tcp.DstPort >= 80 and tcp.DstPort <= 81 and tcp.DstPort != 80").is_none());
+        assert!(check("not loopback and loopback").is_some());
+
+    }
+
+    #[test]
+    fn test_exclusive_headers() {
+
+        assert_eq!(
+            check("tcp and udp").as_deref(),
+            Some("`tcp and udp` never matches: a packet has only one transport protocol")
+        );
+        assert_eq!(
+            check("icmp.Type == 8 and ipv6").as_deref(),
+            Some("`icmp.Type == 8 and ipv6` never matches: a packet is either IPv4 or IPv6")
+        );
+        assert!(check("tcp.Syn and udp.DstPort == 53").is_some());
+        assert!(check("(tcp and udp) or (ip and ipv6)").is_some());
+
+    }
+
+    #[test]
+    fn test_satisfiable_filters() {
+
+        for filter in [
+            "false",
+            "tcp and not udp",
+            "(tcp and udp) or udp",
+            "tcp.Syn or not tcp.Syn",
+            "not (tcp and udp)",
+            "tcp ? tcp.DstPort == 80 : udp",
+            "localPort != 1420 and remotePort != 1420",
+        ] {
+            assert_eq!(check(filter), None, "{}", filter);
+        }
+
+    }
+}
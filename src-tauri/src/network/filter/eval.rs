@@ -0,0 +1,373 @@
+//! Matching packets against a filter expression.
+//!
+//! Follows `WinDivert`: a comparison or test of a field the packet does not
+//! have is false, so `tcp.DstPort != 80` does not match UDP packets while
+//! `not tcp.DstPort == 80` does.
+use std::net::IpAddr;
+
+use crate::network::core::headers::{PROTOCOL_ICMP, PROTOCOL_ICMPV6};
+use crate::network::core::{PacketData, PacketHeaders};
+use crate::network::filter::ast::Expr;
+use crate::network::filter::fields::{ByteBase, Field, NamedField};
+
+/// IPv6 next header value of a fragment header
+const IPV6_FRAGMENT: u8 = 44;
+
+impl Expr {
+    /// Returns whether the expression matches `packet`.
+    pub fn matches(&self, packet: &PacketData) -> bool {
+        self.eval(&PacketView::new(packet))
+    }
+
+    fn eval(&self, view: &PacketView<'_>) -> bool {
+        match self {
+            Self::Bool(value) => *value,
+            Self::Test(field) => view.field(*field).is_some_and(|value| value != 0),
+            Self::Compare(field, op, value) => view
+                .field(*field)
+                .is_some_and(|field| op.holds(field, value.to_u128())),
+            Self::Not(expr) => !expr.eval(view),
+            Self::And(left, right) => left.eval(view) && right.eval(view),
+            Self::Or(left, right) => left.eval(view) || right.eval(view),
+            Self::If(test, then, otherwise) => {
+                if test.eval(view) {
+                    then.eval(view)
+                } else {
+                    otherwise.eval(view)
+                }
+            }
+        }
+    }
+}
+
+/// The fields of one packet.
+struct PacketView<'a> {
+    packet: &'a PacketData,
+    headers: Option<PacketHeaders>,
+}
+
+impl<'a> PacketView<'a> {
+    fn new(packet: &'a PacketData) -> Self {
+        Self {
+            packet,
+            headers: PacketHeaders::parse(&packet.data),
+        }
+    }
+
+    /// Returns the IPv4 header.
+    fn ipv4(&self) -> Option<&'a [u8]> {
+        let headers = self.headers.filter(|headers| headers.ip_version == 4)?;
+
+        Some(&self.packet.data[..headers.ip_header_len])
+    }
+
+    /// Returns the IPv6 header.
+    fn ipv6(&self) -> Option<&'a [u8]> {
+        let headers = self.headers.filter(|headers| headers.ip_version == 6)?;
+
+        Some(&self.packet.data[..headers.ip_header_len])
+    }
+
+    /// Returns the first 8 bytes after the IP header of an ICMP packet.
+    fn icmp(&self, version: u8, protocol: u8) -> Option<&'a [u8]> {
+
+        let headers = self
+            .headers
+            .filter(|headers| headers.ip_version == version && headers.protocol == protocol)?;
+
+        self.packet
+            .data
+            .get(headers.ip_header_len..headers.ip_header_len + 8)
+
+    }
+
+    /// Returns the TCP header.
+    fn tcp(&self) -> Option<&'a [u8]> {
+        let headers = self.headers.filter(PacketHeaders::is_tcp)?;
+
+        Some(&self.packet.data[headers.ip_header_len..headers.payload_offset()])
+    }
+
+    /// Returns the UDP header.
+    fn udp(&self) -> Option<&'a [u8]> {
+        let headers = self.headers.filter(PacketHeaders::is_udp)?;
+
+        Some(&self.packet.data[headers.ip_header_len..headers.payload_offset()])
+    }
+
+    /// Returns the bytes after the transport header.
+    fn payload(&self) -> Option<&'a [u8]> {
+        let headers = self.headers?;
+
+        self.packet.data.get(headers.payload_offset()..)
+    }
+
+    /// Returns the local and remote address and port, by direction.
+    fn endpoints(&self) -> Option<((IpAddr, u16), (IpAddr, u16))> {
+
+        let headers = self.headers?;
+        let src = (headers.src_addr, headers.src_port.unwrap_or(0));
+        let dst = (headers.dst_addr, headers.dst_port.unwrap_or(0));
+
+        Some(if self.packet.is_outbound {
+            (src, dst)
+        } else {
+            (dst, src)
+        })
+
+    }
+
+    /// Returns the value of `field`, or None if the packet does not have it.
+    fn field(&self, field: Field) -> Option<u128> {
+        match field {
+            Field::Named(field) => self.named(field),
+            Field::Bytes { base, width, index } => {
+                let bytes = match base {
+                    ByteBase::Packet => &self.packet.data[..],
+                    ByteBase::TcpPayload => self.tcp().and(self.payload())?,
+                    ByteBase::UdpPayload => self.udp().and(self.payload())?,
+                };
+                let start = if index < 0 {
+                    bytes.len().checked_sub(usize::try_from(-index).ok()?)?
+                } else {
+                    usize::try_from(index).ok()?
+                };
+
+                bytes.get(start..start + usize::from(width)).map(be)
+            }
+        }
+    }
+
+    fn named(&self, field: NamedField) -> Option<u128> {
+
+        let packet = self.packet;
+        let flag = |set: bool| Some(u128::from(set));
+
+        match field {
+            NamedField::Zero => Some(0),
+            // Performance counter ticks only mean something to the driver
+            NamedField::Timestamp => None,
+            NamedField::Outbound => flag(packet.is_outbound),
+            NamedField::Inbound => flag(!packet.is_outbound),
+            NamedField::IfIdx => Some(u128::from(packet.interface_index)),
+            NamedField::SubIfIdx => Some(u128::from(packet.subinterface_index)),
+            NamedField::Loopback => flag(packet.flags.loopback),
+            NamedField::Impostor => flag(packet.flags.impostor),
+            NamedField::Fragment => flag(
+                self.ipv4().is_some_and(|ip| be(&ip[6..8]) & 0x3FFF != 0)
+                    || self.ipv6().is_some_and(|ip| ip[6] == IPV6_FRAGMENT),
+            ),
+            NamedField::Length => Some(packet.data.len() as u128),
+            NamedField::Random8 => Some(u128::from(random(packet.id) as u8)),
+            NamedField::Random16 => Some(u128::from(random(packet.id) as u16)),
+            NamedField::Random32 => Some(u128::from(random(packet.id) as u32)),
+            NamedField::Ip => flag(self.ipv4().is_some()),
+            NamedField::Ipv6 => flag(self.ipv6().is_some()),
+            NamedField::Icmp => flag(self.icmp(4, PROTOCOL_ICMP).is_some()),
+            NamedField::Icmpv6 => flag(self.icmp(6, PROTOCOL_ICMPV6).is_some()),
+            NamedField::Tcp => flag(self.tcp().is_some()),
+            NamedField::Udp => flag(self.udp().is_some()),
+            NamedField::Protocol => self.headers.map(|headers| u128::from(headers.protocol)),
+            NamedField::LocalAddr => self.endpoints().map(|(local, _)| address(local.0)),
+            NamedField::LocalPort => self.endpoints().map(|(local, _)| u128::from(local.1)),
+            NamedField::RemoteAddr => self.endpoints().map(|(_, remote)| address(remote.0)),
+            NamedField::RemotePort => self.endpoints().map(|(_, remote)| u128::from(remote.1)),
+            NamedField::IpHdrLength => self.ipv4().map(|ip| u128::from(ip[0] & 0x0F)),
+            NamedField::IpTos => self.ipv4().map(|ip| u128::from(ip[1])),
+            NamedField::IpLength => self.ipv4().map(|ip| be(&ip[2..4])),
+            NamedField::IpId => self.ipv4().map(|ip| be(&ip[4..6])),
+            NamedField::IpDf => self.ipv4().map(|ip| u128::from(ip[6] >> 6 & 1)),
+            NamedField::IpMf => self.ipv4().map(|ip| u128::from(ip[6] >> 5 & 1)),
+            NamedField::IpFragOff => self.ipv4().map(|ip| be(&ip[6..8]) & 0x1FFF),
+            NamedField::IpTtl => self.ipv4().map(|ip| u128::from(ip[8])),
+            NamedField::IpProtocol => self.ipv4().map(|ip| u128::from(ip[9])),
+            NamedField::IpChecksum => self.ipv4().map(|ip| be(&ip[10..12])),
+            NamedField::IpSrcAddr => self.ipv4().map(|ip| be(&ip[12..16])),
+            NamedField::IpDstAddr => self.ipv4().map(|ip| be(&ip[16..20])),
+            NamedField::Ipv6TrafficClass => self.ipv6().map(|ip| be(&ip[0..2]) >> 4 & 0xFF),
+            NamedField::Ipv6FlowLabel => self.ipv6().map(|ip| be(&ip[1..4]) & 0xF_FFFF),
+            NamedField::Ipv6Length => self.ipv6().map(|ip| be(&ip[4..6])),
+            NamedField::Ipv6NextHdr => self.ipv6().map(|ip| u128::from(ip[6])),
+            NamedField::Ipv6HopLimit => self.ipv6().map(|ip| u128::from(ip[7])),
+            NamedField::Ipv6SrcAddr => self.ipv6().map(|ip| be(&ip[8..24])),
+            NamedField::Ipv6DstAddr => self.ipv6().map(|ip| be(&ip[24..40])),
+            NamedField::IcmpType => self.icmp(4, PROTOCOL_ICMP).map(|icmp| u128::from(icmp[0])),
+            NamedField::IcmpCode => self.icmp(4, PROTOCOL_ICMP).map(|icmp| u128::from(icmp[1])),
+            NamedField::IcmpChecksum => self.icmp(4, PROTOCOL_ICMP).map(|icmp| be(&icmp[2..4])),
+            NamedField::IcmpBody => self.icmp(4, PROTOCOL_ICMP).map(|icmp| be(&icmp[4..8])),
+            NamedField::Icmpv6Type => self
+                .icmp(6, PROTOCOL_ICMPV6)
+                .map(|icmp| u128::from(icmp[0])),
+            NamedField::Icmpv6Code => self
+                .icmp(6, PROTOCOL_ICMPV6)
+                .map(|icmp| u128::from(icmp[1])),
+            NamedField::Icmpv6Checksum => self.icmp(6, PROTOCOL_ICMPV6).map(|icmp| be(&icmp[2..4])),
+            NamedField::Icmpv6Body => self.icmp(6, PROTOCOL_ICMPV6).map(|icmp| be(&icmp[4..8])),
+            NamedField::TcpSrcPort => self.tcp().map(|tcp| be(&tcp[0..2])),
+            NamedField::TcpDstPort => self.tcp().map(|tcp| be(&tcp[2..4])),
+            NamedField::TcpSeqNum => self.tcp().map(|tcp| be(&tcp[4..8])),
+            NamedField::TcpAckNum => self.tcp().map(|tcp| be(&tcp[8..12])),
+            NamedField::TcpHdrLength => self.tcp().map(|tcp| u128::from(tcp[12] >> 4)),
+            NamedField::TcpUrg => self.tcp().map(|tcp| u128::from(tcp[13] >> 5 & 1)),
+            NamedField::TcpAck => self.tcp().map(|tcp| u128::from(tcp[13] >> 4 & 1)),
+            NamedField::TcpPsh => self.tcp().map(|tcp| u128::from(tcp[13] >> 3 & 1)),
+            NamedField::TcpRst => self.tcp().map(|tcp| u128::from(tcp[13] >> 2 & 1)),
+            NamedField::TcpSyn => self.tcp().map(|tcp| u128::from(tcp[13] >> 1 & 1)),
+            NamedField::TcpFin => self.tcp().map(|tcp| u128::from(tcp[13] & 1)),
+            NamedField::TcpWindow => self.tcp().map(|tcp| be(&tcp[14..16])),
+            NamedField::TcpChecksum => self.tcp().map(|tcp| be(&tcp[16..18])),
+            NamedField::TcpUrgPtr => self.tcp().map(|tcp| be(&tcp[18..20])),
+            NamedField::TcpPayloadLength => self
+                .tcp()
+                .and(self.payload())
+                .map(|payload| payload.len() as u128),
+            NamedField::UdpSrcPort => self.udp().map(|udp| be(&udp[0..2])),
+            NamedField::UdpDstPort => self.udp().map(|udp| be(&udp[2..4])),
+            NamedField::UdpLength => self.udp().map(|udp| be(&udp[4..6])),
+            NamedField::UdpChecksum => self.udp().map(|udp| be(&udp[6..8])),
+            NamedField::UdpPayloadLength => self
+                .udp()
+                .and(self.payload())
+                .map(|payload| payload.len() as u128),
+        }
+
+    }
+}
+
+/// Reads `bytes` as a big-endian number.
+fn be(bytes: &[u8]) -> u128 {
+    bytes
+        .iter()
+        .fold(0, |value, &byte| value << 8 | u128::from(byte))
+}
+
+/// Returns an address as the number it compares as.
+fn address(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(addr) => u128::from(u32::from(addr)),
+        IpAddr::V6(addr) => u128::from(addr),
+    }
+}
+
+/// Mixes a packet id into a pseudo-random number (splitmix64).
+///
+/// Stands in for the driver's random fields, so a packet and its
+/// duplicates always fall on the same side of `random8 < 128`.
+fn random(id: u64) -> u64 {
+
+    let mut z = id.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::core::PacketData;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+    use crate::network::filter::Filter;
+
+    fn matches(filter: &str, packet: &PacketData) -> bool {
+        Filter::parse(filter).unwrap().matches(packet)
+    }
+
+    /// Builds an IPv4/TCP SYN from 192.168.1.10:50000 to 10.0.0.1:443.
+    fn tcp_syn() -> PacketData {
+
+        let mut data = vec![0u8; 40];
+
+        data[0] = 0x45;
+        data[2..4].copy_from_slice(&40u16.to_be_bytes());
+        data[6] = 0x40; // DF
+        data[8] = 128;
+        data[9] = 6;
+        data[12..16].copy_from_slice(&[192, 168, 1, 10]);
+        data[16..20].copy_from_slice(&[10, 0, 0, 1]);
+        data[20..22].copy_from_slice(&50000u16.to_be_bytes());
+        data[22..24].copy_from_slice(&443u16.to_be_bytes());
+        data[32] = 5 << 4;
+        data[33] = 0x02;
+
+        PacketData::new(data, true)
+
+    }
+
+    #[test]
+    fn test_header_fields() {
+
+        let syn = tcp_syn();
+
+        assert!(matches("outbound and ip and tcp", &syn));
+        assert!(matches("tcp.Syn and !tcp.Ack and tcp.DstPort == 443", &syn));
+        assert!(matches(
+            "ip.DstAddr == 10.0.0.1 and ip.TTL > 64 and ip.DF",
+            &syn
+        ));
+        assert!(matches(
+            "localPort == 50000 and remoteAddr == 10.0.0.1",
+            &syn
+        ));
+        assert!(matches("tcp.PayloadLength == 0 and length == 40", &syn));
+        assert!(matches(
+            "packet[0] == 0x45 and packet16[-20] == 50000",
+            &syn
+        ));
+        assert!(!matches("udp or ipv6 or inbound or fragment", &syn));
+
+        // The same fields read from the other side for inbound packets
+        let inbound = PacketData::new(tcp_syn().data, false);
+
+        assert!(matches(
+            "inbound and localPort == 443 and remotePort == 50000",
+            &inbound
+        ));
+
+    }
+
+    #[test]
+    fn test_missing_fields_never_match() {
+
+        let udp = PacketData::new(udp_ipv4_packet(5000, 27015, b"hi"), true);
+
+        assert!(matches(
+            "udp.DstPort == 27015 and udp.Payload16[0] == 0x6869",
+            &udp
+        ));
+        assert!(!matches("tcp.DstPort != 80", &udp));
+        assert!(matches("not tcp.DstPort == 80", &udp));
+        assert!(!matches("tcp.Payload[0] == 0 or packet[100] == 0", &udp));
+        assert!(!matches("timestamp > 0", &udp));
+
+        // Garbage has no headers, but still has metadata
+        let garbage = PacketData::new(vec![1, 2, 3], true);
+
+        assert!(matches(
+            "outbound and not ip and not ipv6 and length == 3",
+            &garbage
+        ));
+        assert!(!matches("localPort != 1420", &garbage));
+
+    }
+
+    #[test]
+    fn test_conditional_and_random() {
+
+        let syn = tcp_syn();
+
+        assert!(matches("tcp ? tcp.Syn : udp.DstPort == 53", &syn));
+        assert!(!matches("udp ? true : tcp.Fin", &syn));
+
+        let random = matches("random8 < 128", &syn);
+
+        assert_eq!(matches("random8 < 128", &syn), random);
+
+        let sampled = (0..1000)
+            .filter(|_| matches("random8 < 64", &tcp_syn()))
+            .count();
+
+        assert!((150..350).contains(&sampled), "{}", sampled);
+
+    }
+}
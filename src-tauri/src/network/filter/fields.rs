@@ -0,0 +1,388 @@
+//! Fields of the `WinDivert` network layer.
+//!
+//! Field names match case-insensitively, as in `WinDivert`, and are printed
+//! in their documented spelling.
+use std::fmt;
+
+/// Header a field is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Layer {
+    /// Metadata and whole-packet fields, which every packet has
+    Packet,
+    Ip,
+    Ipv6,
+    Icmp,
+    Icmpv6,
+    Tcp,
+    Udp,
+}
+
+impl Layer {
+    /// Returns the field that tests whether a packet has this header.
+    pub fn field(self) -> Option<NamedField> {
+        match self {
+            Self::Packet => None,
+            Self::Ip => Some(NamedField::Ip),
+            Self::Ipv6 => Some(NamedField::Ipv6),
+            Self::Icmp => Some(NamedField::Icmp),
+            Self::Icmpv6 => Some(NamedField::Icmpv6),
+            Self::Tcp => Some(NamedField::Tcp),
+            Self::Udp => Some(NamedField::Udp),
+        }
+    }
+}
+
+/// Values a field takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    /// 0 or 1
+    Bool,
+    /// Unsigned number of the given bit width
+    Number(u32),
+    /// IPv4 address, or a number of up to 32 bits
+    Ipv4,
+    /// IPv6 address, or any number
+    Ipv6,
+    /// Address of either family
+    Address,
+}
+
+impl FieldType {
+    /// Returns the largest value of the type.
+    pub fn max(self) -> u128 {
+        match self {
+            Self::Bool => 1,
+            Self::Number(bits) => (1u128 << bits) - 1,
+            Self::Ipv4 => u128::from(u32::MAX),
+            Self::Ipv6 | Self::Address => u128::MAX,
+        }
+    }
+}
+
+/// A field that is referred to by name alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedField {
+    Zero,
+    Timestamp,
+    Outbound,
+    Inbound,
+    IfIdx,
+    SubIfIdx,
+    Loopback,
+    Impostor,
+    Fragment,
+    Length,
+    Random8,
+    Random16,
+    Random32,
+    Ip,
+    Ipv6,
+    Icmp,
+    Icmpv6,
+    Tcp,
+    Udp,
+    Protocol,
+    LocalAddr,
+    LocalPort,
+    RemoteAddr,
+    RemotePort,
+    IpHdrLength,
+    IpTos,
+    IpLength,
+    IpId,
+    IpDf,
+    IpMf,
+    IpFragOff,
+    IpTtl,
+    IpProtocol,
+    IpChecksum,
+    IpSrcAddr,
+    IpDstAddr,
+    Ipv6TrafficClass,
+    Ipv6FlowLabel,
+    Ipv6Length,
+    Ipv6NextHdr,
+    Ipv6HopLimit,
+    Ipv6SrcAddr,
+    Ipv6DstAddr,
+    IcmpType,
+    IcmpCode,
+    IcmpChecksum,
+    IcmpBody,
+    Icmpv6Type,
+    Icmpv6Code,
+    Icmpv6Checksum,
+    Icmpv6Body,
+    TcpSrcPort,
+    TcpDstPort,
+    TcpSeqNum,
+    TcpAckNum,
+    TcpHdrLength,
+    TcpUrg,
+    TcpAck,
+    TcpPsh,
+    TcpRst,
+    TcpSyn,
+    TcpFin,
+    TcpWindow,
+    TcpChecksum,
+    TcpUrgPtr,
+    TcpPayloadLength,
+    UdpSrcPort,
+    UdpDstPort,
+    UdpLength,
+    UdpChecksum,
+    UdpPayloadLength,
+}
+
+/// Every named field with its spelling, header and values.
+const NAMED_FIELDS: &[(NamedField, &str, Layer, FieldType)] = &[
+    (NamedField::Zero, "zero", Layer::Packet, FieldType::Number(32)),
+    (NamedField::Timestamp, "timestamp", Layer::Packet, FieldType::Number(64)),
+    (NamedField::Outbound, "outbound", Layer::Packet, FieldType::Bool),
+    (NamedField::Inbound, "inbound", Layer::Packet, FieldType::Bool),
+    (NamedField::IfIdx, "ifIdx", Layer::Packet, FieldType::Number(32)),
+    (NamedField::SubIfIdx, "subIfIdx", Layer::Packet, FieldType::Number(32)),
+    (NamedField::Loopback, "loopback", Layer::Packet, FieldType::Bool),
+    (NamedField::Impostor, "impostor", Layer::Packet, FieldType::Bool),
+    (NamedField::Fragment, "fragment", Layer::Packet, FieldType::Bool),
+    (NamedField::Length, "length", Layer::Packet, FieldType::Number(32)),
+    (NamedField::Random8, "random8", Layer::Packet, FieldType::Number(8)),
+    (NamedField::Random16, "random16", Layer::Packet, FieldType::Number(16)),
+    (NamedField::Random32, "random32", Layer::Packet, FieldType::Number(32)),
+    (NamedField::Ip, "ip", Layer::Packet, FieldType::Bool),
+    (NamedField::Ipv6, "ipv6", Layer::Packet, FieldType::Bool),
+    (NamedField::Icmp, "icmp", Layer::Packet, FieldType::Bool),
+    (NamedField::Icmpv6, "icmpv6", Layer::Packet, FieldType::Bool),
+    (NamedField::Tcp, "tcp", Layer::Packet, FieldType::Bool),
+    (NamedField::Udp, "udp", Layer::Packet, FieldType::Bool),
+    (NamedField::Protocol, "protocol", Layer::Packet, FieldType::Number(8)),
+    (NamedField::LocalAddr, "localAddr", Layer::Packet, FieldType::Address),
+    (NamedField::LocalPort, "localPort", Layer::Packet, FieldType::Number(16)),
+    (NamedField::RemoteAddr, "remoteAddr", Layer::Packet, FieldType::Address),
+    (NamedField::RemotePort, "remotePort", Layer::Packet, FieldType::Number(16)),
+    (NamedField::IpHdrLength, "ip.HdrLength", Layer::Ip, FieldType::Number(4)),
+    (NamedField::IpTos, "ip.TOS", Layer::Ip, FieldType::Number(8)),
+    (NamedField::IpLength, "ip.Length", Layer::Ip, FieldType::Number(16)),
+    (NamedField::IpId, "ip.Id", Layer::Ip, FieldType::Number(16)),
+    (NamedField::IpDf, "ip.DF", Layer::Ip, FieldType::Bool),
+    (NamedField::IpMf, "ip.MF", Layer::Ip, FieldType::Bool),
+    (NamedField::IpFragOff, "ip.FragOff", Layer::Ip, FieldType::Number(13)),
+    (NamedField::IpTtl, "ip.TTL", Layer::Ip, FieldType::Number(8)),
+    (NamedField::IpProtocol, "ip.Protocol", Layer::Ip, FieldType::Number(8)),
+    (NamedField::IpChecksum, "ip.Checksum", Layer::Ip, FieldType::Number(16)),
+    (NamedField::IpSrcAddr, "ip.SrcAddr", Layer::Ip, FieldType::Ipv4),
+    (NamedField::IpDstAddr, "ip.DstAddr", Layer::Ip, FieldType::Ipv4),
+    (NamedField::Ipv6TrafficClass, "ipv6.TrafficClass", Layer::Ipv6, FieldType::Number(8)),
+    (NamedField::Ipv6FlowLabel, "ipv6.FlowLabel", Layer::Ipv6, FieldType::Number(20)),
+    (NamedField::Ipv6Length, "ipv6.Length", Layer::Ipv6, FieldType::Number(16)),
+    (NamedField::Ipv6NextHdr, "ipv6.NextHdr", Layer::Ipv6, FieldType::Number(8)),
+    (NamedField::Ipv6HopLimit, "ipv6.HopLimit", Layer::Ipv6, FieldType::Number(8)),
+    (NamedField::Ipv6SrcAddr, "ipv6.SrcAddr", Layer::Ipv6, FieldType::Ipv6),
+    (NamedField::Ipv6DstAddr, "ipv6.DstAddr", Layer::Ipv6, FieldType::Ipv6),
+    (NamedField::IcmpType, "icmp.Type", Layer::Icmp, FieldType::Number(8)),
+    (NamedField::IcmpCode, "icmp.Code", Layer::Icmp, FieldType::Number(8)),
+    (NamedField::IcmpChecksum, "icmp.Checksum", Layer::Icmp, FieldType::Number(16)),
+    (NamedField::IcmpBody, "icmp.Body", Layer::Icmp, FieldType::Number(32)),
+    (NamedField::Icmpv6Type, "icmpv6.Type", Layer::Icmpv6, FieldType::Number(8)),
+    (NamedField::Icmpv6Code, "icmpv6.Code", Layer::Icmpv6, FieldType::Number(8)),
+    (NamedField::Icmpv6Checksum, "icmpv6.Checksum", Layer::Icmpv6, FieldType::Number(16)),
+    (NamedField::Icmpv6Body, "icmpv6.Body", Layer::Icmpv6, FieldType::Number(32)),
+    (NamedField::TcpSrcPort, "tcp.SrcPort", Layer::Tcp, FieldType::Number(16)),
+    (NamedField::TcpDstPort, "tcp.DstPort", Layer::Tcp, FieldType::Number(16)),
+    (NamedField::TcpSeqNum, "tcp.SeqNum", Layer::Tcp, FieldType::Number(32)),
+    (NamedField::TcpAckNum, "tcp.AckNum", Layer::Tcp, FieldType::Number(32)),
+    (NamedField::TcpHdrLength, "tcp.HdrLength", Layer::Tcp, FieldType::Number(4)),
+    (NamedField::TcpUrg, "tcp.Urg", Layer::Tcp, FieldType::Bool),
+    (NamedField::TcpAck, "tcp.Ack", Layer::Tcp, FieldType::Bool),
+    (NamedField::TcpPsh, "tcp.Psh", Layer::Tcp, FieldType::Bool),
+    (NamedField::TcpRst, "tcp.Rst", Layer::Tcp, FieldType::Bool),
+    (NamedField::TcpSyn, "tcp.Syn", Layer::Tcp, FieldType::Bool),
+    (NamedField::TcpFin, "tcp.Fin", Layer::Tcp, FieldType::Bool),
+    (NamedField::TcpWindow, "tcp.Window", Layer::Tcp, FieldType::Number(16)),
+    (NamedField::TcpChecksum, "tcp.Checksum", Layer::Tcp, FieldType::Number(16)),
+    (NamedField::TcpUrgPtr, "tcp.UrgPtr", Layer::Tcp, FieldType::Number(16)),
+    (NamedField::TcpPayloadLength, "tcp.PayloadLength", Layer::Tcp, FieldType::Number(16)),
+    (NamedField::UdpSrcPort, "udp.SrcPort", Layer::Udp, FieldType::Number(16)),
+    (NamedField::UdpDstPort, "udp.DstPort", Layer::Udp, FieldType::Number(16)),
+    (NamedField::UdpLength, "udp.Length", Layer::Udp, FieldType::Number(16)),
+    (NamedField::UdpChecksum, "udp.Checksum", Layer::Udp, FieldType::Number(16)),
+    (NamedField::UdpPayloadLength, "udp.PayloadLength", Layer::Udp, FieldType::Number(16)),
+];
+
+impl NamedField {
+    /// Finds the field called `name`, ignoring case.
+    pub fn lookup(name: &str) -> Option<Self> {
+        NAMED_FIELDS
+            .iter()
+            .find(|(_, spelling, _, _)| spelling.eq_ignore_ascii_case(name))
+            .map(|&(field, _, _, _)| field)
+    }
+
+    /// Returns the documented spelling of the field.
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Returns the header the field is read from.
+    pub fn layer(self) -> Layer {
+        self.entry().2
+    }
+
+    /// Returns the values the field takes.
+    pub fn field_type(self) -> FieldType {
+        self.entry().3
+    }
+
+    fn entry(self) -> &'static (Self, &'static str, Layer, FieldType) {
+        NAMED_FIELDS
+            .iter()
+            .find(|(field, _, _, _)| *field == self)
+            .expect("every field is listed in NAMED_FIELDS")
+    }
+}
+
+/// Bytes an indexed field is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteBase {
+    /// The whole packet, from the IP header
+    Packet,
+    /// The TCP payload
+    TcpPayload,
+    /// The UDP payload
+    UdpPayload,
+}
+
+impl ByteBase {
+    const ALL: [Self; 3] = [Self::Packet, Self::TcpPayload, Self::UdpPayload];
+
+    /// Finds the indexed field called `name`, ignoring case.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<(ByteBase, u8)>` - The bytes and the width read, in bytes
+    pub fn lookup(name: &str) -> Option<(Self, u8)> {
+
+        for base in Self::ALL {
+            for width in [1, 2, 4] {
+                if indexed_name(base, width).eq_ignore_ascii_case(name) {
+                    return Some((base, width));
+                }
+            }
+        }
+        None
+
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Packet => "packet",
+            Self::TcpPayload => "tcp.Payload",
+            Self::UdpPayload => "udp.Payload",
+        }
+    }
+
+    fn layer(self) -> Layer {
+        match self {
+            Self::Packet => Layer::Packet,
+            Self::TcpPayload => Layer::Tcp,
+            Self::UdpPayload => Layer::Udp,
+        }
+    }
+}
+
+/// Returns the name of an indexed field, such as `packet16` or `tcp.Payload`.
+fn indexed_name(base: ByteBase, width: u8) -> String {
+    match width {
+        1 => base.name().to_string(),
+        width => format!("{}{}", base.name(), u32::from(width) * 8),
+    }
+}
+
+/// A field of a filter expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    /// A field referred to by name
+    Named(NamedField),
+    /// `width` bytes at `index`, read big-endian, such as `packet16[4]`;
+    /// negative indices count back from the end
+    Bytes { base: ByteBase, width: u8, index: i64 },
+}
+
+impl Field {
+    /// Returns the header the field is read from.
+    pub fn layer(self) -> Layer {
+        match self {
+            Self::Named(field) => field.layer(),
+            Self::Bytes { base, .. } => base.layer(),
+        }
+    }
+
+    /// Returns the values the field takes.
+    pub fn field_type(self) -> FieldType {
+        match self {
+            Self::Named(field) => field.field_type(),
+            Self::Bytes { width, .. } => FieldType::Number(u32::from(width) * 8),
+        }
+    }
+}
+
+impl From<NamedField> for Field {
+    fn from(field: NamedField) -> Self {
+        Self::Named(field)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Named(field) => f.write_str(field.name()),
+            Self::Bytes { base, width, index } => {
+                write!(f, "{}[{}]", indexed_name(*base, *width), index)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_ignores_case() {
+
+        assert_eq!(NamedField::lookup("tcp.dstport"), Some(NamedField::TcpDstPort));
+        assert_eq!(NamedField::lookup("IP.DSTADDR"), Some(NamedField::IpDstAddr));
+        assert_eq!(NamedField::lookup("tcp.Port"), None);
+        assert_eq!(NamedField::TcpDstPort.name(), "tcp.DstPort");
+
+        assert_eq!(ByteBase::lookup("Packet32"), Some((ByteBase::Packet, 4)));
+        assert_eq!(ByteBase::lookup("udp.payload"), Some((ByteBase::UdpPayload, 1)));
+        assert_eq!(ByteBase::lookup("packet8"), None);
+
+    }
+
+    #[test]
+    fn test_every_field_is_listed_once() {
+
+        for (field, name, _, _) in NAMED_FIELDS {
+            assert_eq!(NamedField::lookup(name), Some(*field));
+        }
+
+    }
+
+    #[test]
+    fn test_display() {
+
+        let field = Field::Bytes {
+            base: ByteBase::TcpPayload,
+            width: 2,
+            index: -2,
+        };
+
+        assert_eq!(field.to_string(), "tcp.Payload16[-2]");
+        assert_eq!(field.field_type().max(), 65535);
+        assert_eq!(Field::from(NamedField::IpTtl).to_string(), "ip.TTL");
+
+    }
+}
@@ -0,0 +1,285 @@
+//! Splits a filter into tokens.
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::network::filter::FilterError;
+use crate::network::filter::ast::{CompareOp, Value};
+
+/// A token of a filter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// A field name, `true` or `false`
+    Word(String),
+    Value(Value),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Not,
+    And,
+    Or,
+    Question,
+    Colon,
+    Minus,
+    Compare(CompareOp),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "`{}`", word),
+            Self::Value(value) => write!(f, "`{}`", value),
+            Self::LParen => f.write_str("`(`"),
+            Self::RParen => f.write_str("`)`"),
+            Self::LBracket => f.write_str("`[`"),
+            Self::RBracket => f.write_str("`]`"),
+            Self::Not => f.write_str("`not`"),
+            Self::And => f.write_str("`and`"),
+            Self::Or => f.write_str("`or`"),
+            Self::Question => f.write_str("`?`"),
+            Self::Colon => f.write_str("`:`"),
+            Self::Minus => f.write_str("`-`"),
+            Self::Compare(op) => write!(f, "`{}`", op.symbol()),
+            Self::End => f.write_str("the end of the filter"),
+        }
+    }
+}
+
+/// A token with the bytes of the filter it was read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned {
+    pub token: Token,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits `text` into tokens, ending with [`Token::End`].
+pub fn tokenize(text: &str) -> Result<Vec<Spanned>, FilterError> {
+
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+
+    while start < bytes.len() {
+        let next = bytes.get(start + 1).copied();
+
+        let (token, len) = match bytes[start] {
+            byte if byte.is_ascii_whitespace() => {
+                start += 1;
+                continue;
+            }
+            b'(' => (Token::LParen, 1),
+            b')' => (Token::RParen, 1),
+            b'[' => (Token::LBracket, 1),
+            b']' => (Token::RBracket, 1),
+            b'?' => (Token::Question, 1),
+            b'-' => (Token::Minus, 1),
+            b':' if next != Some(b':') => (Token::Colon, 1),
+            b'!' if next == Some(b'=') => (Token::Compare(CompareOp::Ne), 2),
+            b'!' => (Token::Not, 1),
+            b'=' if next == Some(b'=') => (Token::Compare(CompareOp::Eq), 2),
+            b'=' => (Token::Compare(CompareOp::Eq), 1),
+            b'<' if next == Some(b'=') => (Token::Compare(CompareOp::Le), 2),
+            b'<' => (Token::Compare(CompareOp::Lt), 1),
+            b'>' if next == Some(b'=') => (Token::Compare(CompareOp::Ge), 2),
+            b'>' => (Token::Compare(CompareOp::Gt), 1),
+            b'&' if next == Some(b'&') => (Token::And, 2),
+            b'|' if next == Some(b'|') => (Token::Or, 2),
+            b'&' | b'|' => {
+                let expected = if bytes[start] == b'&' { "&&" } else { "||" };
+
+                return Err(FilterError::new(
+                    text,
+                    start,
+                    start + 1,
+                    format!("expected `{}`", expected),
+                ));
+            }
+            byte if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b':' => {
+                word(text, start)?
+            }
+            _ => {
+                let c = text[start..].chars().next().unwrap_or_default();
+
+                return Err(FilterError::new(
+                    text,
+                    start,
+                    start + c.len_utf8(),
+                    format!("unexpected character `{}`", c),
+                ));
+            }
+        };
+
+        tokens.push(Spanned {
+            token,
+            start,
+            end: start + len,
+        });
+        start += len;
+    }
+
+    tokens.push(Spanned {
+        token: Token::End,
+        start: bytes.len(),
+        end: bytes.len(),
+    });
+    Ok(tokens)
+
+}
+
+/// Reads a keyword, field name, number or address starting at `start`.
+///
+/// IPv6 addresses contain colons, so the longest run that parses as one
+/// wins over a shorter name or number followed by the `:` of a conditional.
+fn word(text: &str, start: usize) -> Result<(Token, usize), FilterError> {
+
+    let rest = &text[start..];
+    let word_len = run_len(rest, |byte| {
+        byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.'
+    });
+    let addr_len = run_len(rest, |byte| {
+        byte.is_ascii_hexdigit() || byte == b':' || byte == b'.'
+    });
+
+    if addr_len >= word_len && rest[..addr_len].contains(':') {
+        if let Ok(addr) = rest[..addr_len].parse::<Ipv6Addr>() {
+            return Ok((Token::Value(Value::Ipv6(addr)), addr_len));
+        }
+    }
+
+    let word = &rest[..word_len];
+
+    if word.is_empty() {
+        return Err(FilterError::new(text, start, start + 1, "unexpected `:`"));
+    }
+
+    if word.starts_with(|c: char| c.is_ascii_digit()) {
+        let value = parse_value(word).ok_or_else(|| {
+            FilterError::new(
+                text,
+                start,
+                start + word_len,
+                format!("invalid value `{}`", word),
+            )
+        })?;
+
+        return Ok((Token::Value(value), word_len));
+    }
+
+    let token = match word.to_ascii_lowercase().as_str() {
+        "and" => Token::And,
+        "or" => Token::Or,
+        "not" => Token::Not,
+        _ => Token::Word(word.to_string()),
+    };
+
+    Ok((token, word_len))
+
+}
+
+/// Returns how many leading bytes of `text` satisfy `accept`.
+fn run_len(text: &str, accept: impl Fn(u8) -> bool) -> usize {
+    text.bytes().take_while(|&byte| accept(byte)).count()
+}
+
+/// Parses a decimal or `0x` hexadecimal number, or an IPv4 address.
+fn parse_value(word: &str) -> Option<Value> {
+
+    if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+        return u128::from_str_radix(hex, 16).ok().map(Value::Number);
+    }
+
+    if word.contains('.') {
+        return word.parse::<Ipv4Addr>().ok().map(Value::Ipv4);
+    }
+
+    word.parse::<u128>().ok().map(Value::Number)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<Token> {
+        tokenize(text)
+            .unwrap()
+            .into_iter()
+            .map(|spanned| spanned.token)
+            .collect()
+    }
+
+    #[test]
+    fn test_operators_and_keywords() {
+
+        assert_eq!(
+            tokens("!tcp && (udp.DstPort>=0x10 || NOT ip)"),
+            [
+                Token::Not,
+                Token::Word("tcp".to_string()),
+                Token::And,
+                Token::LParen,
+                Token::Word("udp.DstPort".to_string()),
+                Token::Compare(CompareOp::Ge),
+                Token::Value(Value::Number(16)),
+                Token::Or,
+                Token::Not,
+                Token::Word("ip".to_string()),
+                Token::RParen,
+                Token::End,
+            ]
+        );
+
+    }
+
+    #[test]
+    fn test_addresses() {
+
+        assert_eq!(
+            tokens("ip.DstAddr = 10.0.0.1"),
+            [
+                Token::Word("ip.DstAddr".to_string()),
+                Token::Compare(CompareOp::Eq),
+                Token::Value(Value::Ipv4(Ipv4Addr::new(10, 0, 0, 1))),
+                Token::End,
+            ]
+        );
+
+        for addr in ["::1", "fe80::1", "::ffff:10.0.0.1"] {
+            assert_eq!(
+                tokens(addr),
+                [Token::Value(Value::Ipv6(addr.parse().unwrap())), Token::End]
+            );
+        }
+
+        // A colon that does not start an address belongs to a conditional
+        assert_eq!(
+            tokens("tcp ? 1:udp")[2..5],
+            [
+                Token::Value(Value::Number(1)),
+                Token::Colon,
+                Token::Word("udp".to_string()),
+            ]
+        );
+
+    }
+
+    #[test]
+    fn test_errors_point_at_the_character() {
+
+        let error = tokenize("tcp & udp").unwrap_err();
+
+        assert_eq!(error.offset, 4);
+        assert_eq!(error.message, "expected `&&`");
+
+        let error = tokenize("ip.TTL == 1.2.3").unwrap_err();
+
+        assert_eq!((error.offset, error.len), (10, 5));
+
+        let error = tokenize("tcp é").unwrap_err();
+
+        assert_eq!((error.offset, error.len), (4, 1));
+
+    }
+}
@@ -0,0 +1,121 @@
+//! `WinDivert` filter language.
+//!
+//! Parses filter expressions without a capture driver, so a filter can be
+//! validated offline, printed back in a canonical form and evaluated against
+//! packets by backends that cannot hand it to `WinDivert`. The grammar is
+//! that of the `WinDivert` 2.x network layer: fields on their own or compared
+//! with numbers and addresses, `and`/`or`/`not` (also `&&`, `||` and `!`),
+//! the `test ? then : otherwise` conditional, and indexed fields such as
+//! `packet[0]` or `tcp.Payload16[-2]`.
+//!
+//! Beyond the syntax, parsing rejects unknown fields and values a field can
+//! never take, and [`Filter::contradiction`] finds terms that can never hold
+//! together, such as `inbound and outbound`.
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::network::core::PacketData;
+
+pub mod ast;
+pub mod builder;
+mod check;
+mod eval;
+pub mod fields;
+mod lexer;
+mod parser;
+
+pub use ast::{CompareOp, Expr, Value};
+pub use builder::{FilterBuilder, Protocol, Target};
+pub use fields::{ByteBase, Field, FieldType, Layer, NamedField};
+
+/// A filter that could not be parsed, with the part of it at fault.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message} at column {}", .offset + 1)]
+pub struct FilterError {
+    /// What is wrong
+    pub message: String,
+    /// Characters before the part at fault
+    pub offset: usize,
+    /// Characters of the part at fault
+    pub len: usize,
+}
+
+impl FilterError {
+    /// Creates an error for the bytes `start..end` of `text`.
+    fn new(text: &str, start: usize, end: usize, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            offset: text[..start].chars().count(),
+            len: text[start..end].chars().count(),
+        }
+    }
+}
+
+/// A parsed filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    expr: Expr,
+}
+
+impl Filter {
+    /// Parses a filter expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The filter, in `WinDivert` syntax
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Filter)` - If the filter is well-formed
+    /// * `Err(FilterError)` - If it is not, with the position of the first error
+    pub fn parse(text: &str) -> Result<Self, FilterError> {
+        parser::parse(text).map(Self::new)
+    }
+
+    /// Creates a filter from an expression.
+    pub fn new(expr: Expr) -> Self {
+        Self { expr }
+    }
+
+    /// Returns the expression of the filter.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Returns the expression of the filter, consuming it.
+    pub fn into_expr(self) -> Expr {
+        self.expr
+    }
+
+    /// Returns whether the filter matches `packet`.
+    ///
+    /// Fields only the driver knows, like `timestamp`, are treated as missing
+    /// from the packet. `random8` and friends are derived from the packet id.
+    pub fn matches(&self, packet: &PacketData) -> bool {
+        self.expr.matches(packet)
+    }
+
+    /// Explains why the filter can never match, if that is because of terms
+    /// that contradict each other.
+    ///
+    /// A filter that is plainly `false` does not count as a contradiction.
+    pub fn contradiction(&self) -> Option<String> {
+        check::contradiction(&self.expr)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = FilterError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.expr.fmt(f)
+    }
+}
@@ -0,0 +1,390 @@
+//! Recursive descent parser for filter expressions.
+//!
+//! Precedence from loosest to tightest: `? :`, `or`, `and`, `not`, then
+//! comparisons and parentheses.
+use crate::network::filter::FilterError;
+use crate::network::filter::ast::{Expr, Value};
+use crate::network::filter::fields::{ByteBase, Field, FieldType, NamedField};
+use crate::network::filter::lexer::{Spanned, Token, tokenize};
+
+/// Parses `text` into an expression.
+pub fn parse(text: &str) -> Result<Expr, FilterError> {
+
+    let tokens = tokenize(text)?;
+
+    if tokens.len() == 1 {
+        return Err(FilterError::new(text, 0, 0, "filter is empty"));
+    }
+
+    let mut parser = Parser {
+        text,
+        tokens,
+        pos: 0,
+    };
+    let expr = parser.conditional()?;
+    let rest = parser.peek();
+
+    if rest.token != Token::End {
+        return Err(parser.error_at(rest, format!("unexpected {}", rest.token)));
+    }
+
+    Ok(expr)
+
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Spanned {
+        &self.tokens[self.pos]
+    }
+
+    /// Returns the next token and moves past it, staying on the end.
+    fn advance(&mut self) -> Spanned {
+
+        let spanned = self.tokens[self.pos].clone();
+
+        if spanned.token != Token::End {
+            self.pos += 1;
+        }
+        spanned
+
+    }
+
+    /// Moves past the next token if it is `token`.
+    fn eat(&mut self, token: &Token) -> bool {
+
+        if self.peek().token == *token {
+            self.pos += 1;
+            return true;
+        }
+        false
+
+    }
+
+    fn error_at(&self, spanned: &Spanned, message: impl Into<String>) -> FilterError {
+        FilterError::new(self.text, spanned.start, spanned.end, message)
+    }
+
+    fn conditional(&mut self) -> Result<Expr, FilterError> {
+
+        let test = self.or()?;
+
+        if !self.eat(&Token::Question) {
+            return Ok(test);
+        }
+
+        let then = self.conditional()?;
+        let colon = self.advance();
+
+        if colon.token != Token::Colon {
+            return Err(self.error_at(&colon, format!("expected `:`, found {}", colon.token)));
+        }
+
+        let otherwise = self.conditional()?;
+
+        Ok(Expr::If(
+            Box::new(test),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+
+        let mut expr = self.and()?;
+
+        while self.eat(&Token::Or) {
+            expr = Expr::or(expr, self.and()?);
+        }
+        Ok(expr)
+
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+
+        let mut expr = self.unary()?;
+
+        while self.eat(&Token::And) {
+            expr = Expr::and(expr, self.unary()?);
+        }
+        Ok(expr)
+
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+
+        if self.eat(&Token::Not) {
+            return Ok(Expr::negate(self.unary()?));
+        }
+        self.primary()
+
+    }
+
+    fn primary(&mut self) -> Result<Expr, FilterError> {
+
+        let spanned = self.advance();
+
+        match &spanned.token {
+            Token::LParen => {
+                let expr = self.conditional()?;
+                let close = self.advance();
+
+                match close.token {
+                    Token::RParen => Ok(expr),
+                    Token::End => Err(self.error_at(&spanned, "unclosed `(`")),
+                    _ => Err(self.error_at(&close, format!("expected `)`, found {}", close.token))),
+                }
+            }
+            Token::Word(word) if word.eq_ignore_ascii_case("true") => Ok(Expr::Bool(true)),
+            Token::Word(word) if word.eq_ignore_ascii_case("false") => Ok(Expr::Bool(false)),
+            Token::Word(word) => {
+                let field = self.field(word, &spanned)?;
+                let Token::Compare(op) = self.peek().token else {
+                    return Ok(Expr::Test(field));
+                };
+
+                self.advance();
+
+                let value = self.advance();
+                let Token::Value(number) = value.token else {
+                    return Err(
+                        self.error_at(&value, format!("expected a value, found {}", value.token))
+                    );
+                };
+
+                self.check_value(field, number, &value)?;
+                Ok(Expr::Compare(field, op, number))
+            }
+            token => Err(self.error_at(
+                &spanned,
+                format!("expected a field, `not` or `(`, found {}", token),
+            )),
+        }
+
+    }
+
+    /// Reads the field called `name`, with its index if it takes one.
+    fn field(&mut self, name: &str, spanned: &Spanned) -> Result<Field, FilterError> {
+
+        let indexed = self.peek().token == Token::LBracket;
+
+        if let Some((base, width)) = ByteBase::lookup(name) {
+            if !indexed {
+                return Err(self.error_at(
+                    spanned,
+                    format!("`{}` needs an index, as in `{}[0]`", name, name),
+                ));
+            }
+
+            let index = self.index()?;
+
+            return Ok(Field::Bytes { base, width, index });
+        }
+
+        let Some(field) = NamedField::lookup(name) else {
+            return Err(self.error_at(spanned, format!("unknown field `{}`", name)));
+        };
+
+        if indexed {
+            return Err(self.error_at(self.peek(), format!("`{}` cannot be indexed", field.name())));
+        }
+
+        Ok(Field::Named(field))
+
+    }
+
+    /// Reads `[index]`, where the index may be negative.
+    fn index(&mut self) -> Result<i64, FilterError> {
+
+        self.advance();
+
+        let negative = self.eat(&Token::Minus);
+        let number = self.advance();
+        let Token::Value(Value::Number(index)) = number.token else {
+            return Err(self.error_at(
+                &number,
+                format!("expected an index, found {}", number.token),
+            ));
+        };
+        let index = i64::try_from(index)
+            .ok()
+            .filter(|&index| index <= i64::from(u16::MAX))
+            .ok_or_else(|| self.error_at(&number, "index is out of range"))?;
+
+        let close = self.advance();
+
+        if close.token != Token::RBracket {
+            return Err(self.error_at(&close, format!("expected `]`, found {}", close.token)));
+        }
+
+        Ok(if negative { -index } else { index })
+
+    }
+
+    /// Rejects values `field` can never take.
+    fn check_value(
+        &self,
+        field: Field,
+        value: Value,
+        spanned: &Spanned,
+    ) -> Result<(), FilterError> {
+
+        let field_type = field.field_type();
+        let is_address = matches!(value, Value::Ipv4(_) | Value::Ipv6(_));
+
+        let message = match (field_type, value) {
+            (FieldType::Bool | FieldType::Number(_), _) if is_address => {
+                format!("`{}` takes a number, not an address", field)
+            }
+            (FieldType::Ipv4, Value::Ipv6(_)) => format!("`{}` takes an IPv4 address", field),
+            (FieldType::Ipv6, Value::Ipv4(_)) => format!("`{}` takes an IPv6 address", field),
+            (FieldType::Bool, Value::Number(number)) if number > 1 => {
+                format!("`{}` is either 0 or 1", field)
+            }
+            (_, Value::Number(number)) if number > field_type.max() => {
+                format!(
+                    "{} is out of range for `{}`, which is at most {}",
+                    number,
+                    field,
+                    field_type.max()
+                )
+            }
+            _ => return Ok(()),
+        };
+
+        Err(self.error_at(spanned, message))
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::filter::ast::CompareOp;
+
+    fn error(text: &str) -> (String, usize, usize) {
+
+        let error = parse(text).unwrap_err();
+
+        (error.message, error.offset, error.len)
+
+    }
+
+    #[test]
+    fn test_precedence() {
+
+        let expr = parse("not tcp or udp and outbound ? ip : ipv6").unwrap();
+
+        assert_eq!(expr.to_string(), "not tcp or udp and outbound ? ip : ipv6");
+
+        let Expr::If(test, _, _) = expr else {
+            panic!("`? :` binds loosest");
+        };
+        let Expr::Or(left, right) = *test else {
+            panic!("`or` binds looser than `and` and `not`");
+        };
+
+        assert!(matches!(*left, Expr::Not(_)));
+        assert!(matches!(*right, Expr::And(..)));
+
+    }
+
+    #[test]
+    fn test_fields_and_values() {
+
+        assert_eq!(
+            parse("TCP.dstport = 0x50").unwrap(),
+            Expr::compare(NamedField::TcpDstPort, CompareOp::Eq, Value::Number(80))
+        );
+        assert_eq!(
+            parse("packet16[-2] != 0").unwrap(),
+            Expr::Compare(
+                Field::Bytes {
+                    base: ByteBase::Packet,
+                    width: 2,
+                    index: -2,
+                },
+                CompareOp::Ne,
+                Value::Number(0),
+            )
+        );
+        assert_eq!(parse("((true))").unwrap(), Expr::Bool(true));
+
+    }
+
+    #[test]
+    fn test_printed_filters_parse_back() {
+
+        for text in [
+            "outbound and !loopback and (tcp.DstPort == 443 || udp.DstPort >= 27000)",
+            "ip.DstAddr == 10.0.0.1 or ipv6.DstAddr == fe80::1",
+            "(tcp ? tcp.Syn : udp) and not (localPort == 1420 or remotePort == 1420)",
+            "tcp.Payload32[0] == 0x16030100 and packet[-1] < 10",
+        ] {
+            let expr = parse(text).unwrap();
+
+            assert_eq!(parse(&expr.to_string()).unwrap(), expr, "{}", expr);
+        }
+
+    }
+
+    #[test]
+    fn test_errors_point_at_the_token() {
+
+        assert_eq!(error(""), ("filter is empty".to_string(), 0, 0));
+        assert_eq!(error("tcp and (udp"), ("unclosed `(`".to_string(), 8, 1));
+        assert_eq!(error("tcp)"), ("unexpected `)`".to_string(), 3, 1));
+        assert_eq!(
+            error("tcp.Port == 80"),
+            ("unknown field `tcp.Port`".to_string(), 0, 8)
+        );
+        assert_eq!(
+            error("tcp.DstPort == 70000"),
+            (
+                "70000 is out of range for `tcp.DstPort`, which is at most 65535".to_string(),
+                15,
+                5
+            )
+        );
+        assert_eq!(
+            error("ip.SrcAddr == ::1"),
+            ("`ip.SrcAddr` takes an IPv4 address".to_string(), 14, 3)
+        );
+        assert_eq!(
+            error("tcp.Syn == 2"),
+            ("`tcp.Syn` is either 0 or 1".to_string(), 11, 1)
+        );
+        assert_eq!(
+            error("tcp and"),
+            (
+                "expected a field, `not` or `(`, found the end of the filter".to_string(),
+                7,
+                0
+            )
+        );
+        assert_eq!(
+            error("packet == 1"),
+            (
+                "`packet` needs an index, as in `packet[0]`".to_string(),
+                0,
+                6
+            )
+        );
+        assert_eq!(
+            error("tcp ? udp"),
+            (
+                "expected `:`, found the end of the filter".to_string(),
+                9,
+                0
+            )
+        );
+        assert_eq!(error("ip.TTL == tcp").0, "expected a value, found `tcp`");
+
+    }
+}
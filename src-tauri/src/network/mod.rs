@@ -2,9 +2,13 @@
 //!
 //! This module contains components for capturing, processing, and
 //! manipulating network traffic using WinDivert.
+pub mod backend;
 pub mod classic;
 pub mod core;
+pub mod filter;
 pub mod modules;
+pub mod pcap;
 pub mod processing;
+pub mod shaper;
 pub mod types;
 pub mod wfp_throttle;
@@ -1,16 +1,13 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::bandwidth_stats::BandwidthStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
+use crate::network::shaper::Shaper;
 use crate::settings::bandwidth::BandwidthOptions;
-use std::collections::VecDeque;
+use crate::settings::shaper::ShaperOptions;
+use rand::Rng;
 use std::time::Instant;
 
-/// Maximum size of the packet buffer in bytes (100 MB)
-/// Increased from 10MB to prevent packet drops during heavy throttling
-/// When this limit is exceeded, oldest packets will be dropped from the buffer
-const MAX_BUFFER_SIZE: usize = 100 * 1024 * 1024; // 100 MB in bytes
-
 /// Unit struct for the Bandwidth packet module.
 ///
 /// This module simulates bandwidth limitations using a token bucket
@@ -21,23 +18,22 @@ pub struct BandwidthModule;
 /// State maintained by the bandwidth module between processing calls.
 #[derive(Debug)]
 pub struct BandwidthState {
-    pub buffer: VecDeque<PacketData<'static>>,
-    pub total_buffer_size: usize,
-    pub last_send_time: Instant,
-    /// For packet pacing mode: tracks when the next packet can be released
-    pub next_release_time: Instant,
+    /// Token bucket and queue of the packets waiting to be sent
+    pub shaper: Shaper<PacketData>,
 }
 
-impl Default for BandwidthState {
-    fn default() -> Self {
-
+impl BandwidthState {
+    /// Creates an empty state whose token bucket starts filling at `now`.
+    pub fn starting_at(now: Instant) -> Self {
         Self {
-            buffer: VecDeque::new(),
-            total_buffer_size: 0,
-            last_send_time: Instant::now(),
-            next_release_time: Instant::now(),
+            shaper: Shaper::new(0.0, &ShaperOptions::default(), now),
         }
+    }
+}
 
+impl Default for BandwidthState {
+    fn default() -> Self {
+        Self::starting_at(Instant::now())
     }
 }
 
@@ -62,9 +58,9 @@ impl PacketModule for BandwidthModule {
         options.limit == 0 || options.use_wfp
     }
 
-    fn process<'a>(
+    fn process(
         &self,
-        packets: &mut Vec<PacketData<'a>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         state: &mut Self::State,
         ctx: &mut ModuleContext,
@@ -72,21 +68,14 @@ impl PacketModule for BandwidthModule {
 
         let mut stats = ctx.write_stats(self.name())?;
 
-        // Safety: We need to transmute lifetimes here because the buffer persists
-        // across processing calls.
-        let buffer: &mut VecDeque<PacketData<'a>> =
-            unsafe { std::mem::transmute(&mut state.buffer) };
-
         bandwidth_limiter(
             packets,
-            buffer,
-            &mut state.total_buffer_size,
-            &mut state.next_release_time,
-            options.limit,
-            options.inbound,
-            options.outbound,
-            options.passthrough_threshold,
+            &mut state.shaper,
+            options,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.bandwidth_stats,
+            ctx.dropped,
         );
         Ok(())
 
@@ -95,215 +84,101 @@ impl PacketModule for BandwidthModule {
 
 /// Limits network bandwidth by controlling the rate at which packets are released
 ///
-/// This function implements a token bucket algorithm to limit bandwidth. It buffers incoming
-/// packets and releases them at a rate determined by the specified bandwidth limit.
+/// Packets of the limited directions are queued in a token bucket shaper and
+/// released as fast as the limit allows. The queue limits and discipline in
+/// `options.shaper` decide which packets are dropped when the link cannot
+/// keep up, and so how much delay builds up under load.
 ///
 /// # Arguments
 ///
 /// * `packets` - Mutable vector that initially contains incoming packets and will contain outgoing packets after the function runs
-/// * `buffer` - Queue used to store packets that exceed the current bandwidth allowance
-/// * `total_buffer_size` - Running total of the buffer size in bytes
-/// * `last_send_time` - The time when packets were last sent, used to calculate allowable bytes
-/// * `bandwidth_limit_kbps` - The maximum bandwidth allowed in kilobits per second
-/// * `apply_inbound` - Whether to apply bandwidth limiting to inbound (download) traffic
-/// * `apply_outbound` - Whether to apply bandwidth limiting to outbound (upload) traffic
+/// * `shaper` - Token bucket and queue of the packets waiting to be sent
+/// * `options` - Limit in KB/s, directions, passthrough threshold and shaper options
+/// * `now` - Current time from the module clock
+/// * `rng` - Random number generator for RED drops
 /// * `stats` - Statistics tracker for bandwidth usage
+/// * `dropped` - Receives the packets the shaper dropped, marked with [`PacketEffects::DROP`]
 ///
 /// # Example
 ///
-/// ```
+/// ```rust,ignore
 /// let mut packets = vec![packet1, packet2];
-/// let mut buffer = VecDeque::new();
-/// let mut total_buffer_size = 0;
-/// let mut last_send_time = Instant::now();
-/// let bandwidth_limit_kbps = 1000; // 1 Mbps
+/// let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), Instant::now());
+/// let options = BandwidthOptions {
+///     limit: 1000, // 1000 KB/s
+///     ..Default::default()
+/// };
 /// let mut stats = BandwidthStats::new(0.5);
 ///
 /// bandwidth_limiter(
 ///     &mut packets,
-///     &mut buffer,
-///     &mut total_buffer_size,
-///     &mut last_send_time,
-///     bandwidth_limit_kbps,
-///     true,  // apply to inbound
-///     true,  // apply to outbound
+///     &mut shaper,
+///     &options,
+///     Instant::now(),
+///     &mut rand::rng(),
 ///     &mut stats,
+///     &mut Vec::new(),
 /// );
 /// ```
-pub fn bandwidth_limiter<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    buffer: &mut VecDeque<PacketData<'a>>,
-    total_buffer_size: &mut usize,
-    last_send_time: &mut Instant,
-    bandwidth_limit_kbps: usize,
-    apply_inbound: bool,
-    apply_outbound: bool,
-    passthrough_threshold: usize,
-    stats: &mut BandwidthStats,
-) {
-
-    bandwidth_limiter_paced(
-        packets,
-        buffer,
-        total_buffer_size,
-        last_send_time,
-        bandwidth_limit_kbps,
-        apply_inbound,
-        apply_outbound,
-        passthrough_threshold,
-        stats,
-    );
-
-}
-
-/// Releases packets one at a time at smooth intervals based on rate limit
-fn bandwidth_limiter_paced<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    buffer: &mut VecDeque<PacketData<'a>>,
-    total_buffer_size: &mut usize,
-    next_release_time: &mut Instant,
-    bandwidth_limit_kbps: usize,
-    apply_inbound: bool,
-    apply_outbound: bool,
-    passthrough_threshold: usize,
+pub fn bandwidth_limiter(
+    packets: &mut Vec<PacketData>,
+    shaper: &mut Shaper<PacketData>,
+    options: &BandwidthOptions,
+    now: Instant,
+    rng: &mut impl Rng,
     stats: &mut BandwidthStats,
+    dropped: &mut Vec<PacketData>,
 ) {
 
+    let queued_before = shaper.len();
+    let dropped_before = dropped.len();
     let mut passthrough = Vec::new();
-    let mut to_buffer = Vec::new();
 
-    for packet in packets.drain(..) {
-        let packet_size = packet.packet.data.len();
+    shaper.configure(options.limit as f64 * 1024.0, &options.shaper);
+
+    for mut packet in packets.drain(..) {
+        let packet_size = packet.data.len();
         let matches_direction =
-            (packet.is_outbound && apply_outbound) || (!packet.is_outbound && apply_inbound);
+            (packet.is_outbound && options.outbound) || (!packet.is_outbound && options.inbound);
 
-        let is_small = passthrough_threshold > 0 && packet_size <= passthrough_threshold;
+        let is_small =
+            options.passthrough_threshold > 0 && packet_size <= options.passthrough_threshold;
 
         if !matches_direction || is_small {
             passthrough.push(packet);
             continue;
         }
-        to_buffer.push(packet);
-    }
 
-    stats.storage_packet_count += to_buffer.len();
+        packet.effects.insert(PacketEffects::BANDWIDTH);
 
-    add_packets_to_buffer(buffer, &mut to_buffer, total_buffer_size);
-    maintain_buffer_size(buffer, total_buffer_size, stats);
+        if let Err(packet) = shaper.enqueue(packet, packet_size, now, rng) {
+            dropped.push(packet);
+        }
+    }
 
-    let now = Instant::now();
     let mut to_send = Vec::new();
-    let mut bytes_sent = 0;
 
-    if now >= *next_release_time {
-        if let Some(packet) = remove_packet_from_buffer(buffer, total_buffer_size, stats) {
-            let packet_size = packet.packet.data.len();
+    shaper.release(now, &mut to_send, dropped);
 
-            bytes_sent = packet_size;
-
-            let bytes_per_sec = (bandwidth_limit_kbps as f64) * 1024.0;
-            let transmission_time_secs = if bytes_per_sec > 0.0 {
-                packet_size as f64 / bytes_per_sec
-            } else {
-                1.0
-            };
+    for packet in &mut dropped[dropped_before..] {
+        packet.effects.insert(PacketEffects::DROP);
+    }
 
-            let transmission_duration = std::time::Duration::from_secs_f64(transmission_time_secs);
+    let bytes_sent: usize = to_send.iter().map(|packet| packet.data.len()).sum();
 
-            *next_release_time = now + transmission_duration;
+    stats.storage_packet_count = (stats.storage_packet_count + shaper.len())
+        .saturating_sub(queued_before);
+    stats.dropped_packet_count += dropped.len() - dropped_before;
 
-            to_send.push(packet);
-        }
+    if !to_send.is_empty() {
+        stats.queue_delay = shaper.last_sojourn();
     }
 
     packets.extend(passthrough);
     packets.extend(to_send);
 
     if bytes_sent > 0 {
-        stats.record(bytes_sent);
-    }
-
-}
-
-/// Adds a single packet to the buffer and updates the total buffer size
-///
-/// # Arguments
-///
-/// * `buffer` - The packet buffer
-/// * `packet` - The packet to add
-/// * `total_size` - Running total of the buffer size in bytes
-fn add_packet_to_buffer<'a>(
-    buffer: &mut VecDeque<PacketData<'a>>,
-    packet: PacketData<'a>,
-    total_size: &mut usize,
-) {
-    *total_size += packet.packet.data.len();
-    buffer.push_back(packet);
-}
-
-/// Moves all packets from the input vector to the buffer
-///
-/// This function consumes the packets from the input vector by popping them one by one
-/// and adding them to the buffer. The input vector will be empty after this operation.
-///
-/// # Arguments
-///
-/// * `buffer` - The packet buffer
-/// * `packets` - Vector of packets to add to the buffer
-/// * `total_size` - Running total of the buffer size in bytes
-fn add_packets_to_buffer<'a>(
-    buffer: &mut VecDeque<PacketData<'a>>,
-    packets: &mut Vec<PacketData<'a>>,
-    total_size: &mut usize,
-) {
-    while let Some(packet) = packets.pop() {
-        add_packet_to_buffer(buffer, packet, total_size);
-    }
-}
-
-/// Removes a packet from the front of the buffer and updates the total buffer size
-///
-/// # Arguments
-///
-/// * `buffer` - The packet buffer
-/// * `total_size` - Running total of the buffer size in bytes
-/// * `stats` - Statistics tracker to update
-///
-/// # Returns
-///
-/// * `Option<PacketData<'a>>` - The removed packet, or None if the buffer is empty
-fn remove_packet_from_buffer<'a>(
-    buffer: &mut VecDeque<PacketData<'a>>,
-    total_size: &mut usize,
-    stats: &mut BandwidthStats,
-) -> Option<PacketData<'a>> {
-
-    let packet = buffer.pop_front()?;
-
-    *total_size -= packet.packet.data.len();
-    stats.storage_packet_count = stats.storage_packet_count.saturating_sub(1);
-
-    Some(packet)
-
-}
-
-/// Ensures the buffer doesn't exceed the maximum size by removing packets if necessary
-///
-/// # Arguments
-///
-/// * `buffer` - The packet buffer
-/// * `total_size` - Running total of the buffer size in bytes
-/// * `stats` - Statistics tracker to update
-fn maintain_buffer_size(
-    buffer: &mut VecDeque<PacketData<'_>>,
-    total_size: &mut usize,
-    stats: &mut BandwidthStats,
-) {
-
-    while *total_size > MAX_BUFFER_SIZE {
-        if remove_packet_from_buffer(buffer, total_size, stats).is_none() {
-            break;
-        }
+        stats.record(bytes_sent, now);
     }
 
 }
@@ -312,21 +187,27 @@ fn maintain_buffer_size(
 mod tests {
     use super::*;
     use crate::network::core::packet::PacketData;
-    use crate::network::modules::bandwidth::{
-        MAX_BUFFER_SIZE, add_packet_to_buffer, add_packets_to_buffer, bandwidth_limiter,
-        remove_packet_from_buffer,
-    };
-    use std::collections::VecDeque;
+    use crate::network::modules::bandwidth::bandwidth_limiter;
+    use crate::network::types::clock::{Clock, ManualClock};
+    use crate::network::types::rng::module_rng;
     use std::time::Duration;
-    use windivert::layer::NetworkLayer;
-    use windivert::packet::WinDivertPacket;
 
     /// Safely creates a dummy packet with a specified length.
     /// Assumes the vector created with the specified length is valid for packet creation.
-    fn create_dummy_packet<'a>(length: usize) -> WinDivertPacket<'a, NetworkLayer> {
-        let data = vec![1; length];
+    fn create_dummy_packet(length: usize) -> Vec<u8> {
+        vec![1; length]
+    }
 
-        unsafe { WinDivertPacket::<NetworkLayer>::new(data) }
+    /// Options limiting both directions to `limit` KB/s.
+    fn limited(limit: usize) -> BandwidthOptions {
+        BandwidthOptions {
+            enabled: true,
+            inbound: true,
+            outbound: true,
+            limit,
+            passthrough_threshold: 0,
+            ..Default::default()
+        }
     }
 
     #[test]
@@ -337,25 +218,26 @@ mod tests {
             PacketData::from(create_dummy_packet(1000)),
         ];
 
-        let mut buffer = VecDeque::new();
-        let total_buffer_size: &mut usize = &mut 0usize;
-        let mut last_send_time = Instant::now() - Duration::from_secs(1);
-        let bandwidth_limit = 1; // 1 KB/s
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
+
+        clock.advance(Duration::from_secs(1));
+
         let mut stats = BandwidthStats::new(0.5);
 
         bandwidth_limiter(
             &mut packets,
-            &mut buffer,
-            total_buffer_size,
-            &mut last_send_time,
-            bandwidth_limit,
-            true, // apply inbound
-            true, // apply outbound
-            0,    // no passthrough threshold
+            &mut shaper,
+            &limited(1), // 1 KB/s
+            clock.now(),
+            &mut module_rng(Some(1), "bandwidth"),
             &mut stats,
+            &mut Vec::new(),
         );
 
         assert!(packets.len() <= 1);
+        assert_eq!(shaper.len() + packets.len(), 2);
+        assert!(packets.iter().all(|p| p.effects.contains(PacketEffects::BANDWIDTH)));
 
     }
 
@@ -363,36 +245,39 @@ mod tests {
     fn test_exceeding_buffer_size() {
 
         let mut packets = Vec::new();
-        let mut buffer = VecDeque::new();
-        let mut total_buffer_size = 0;
 
-        // Fill the buffer with packets to exceed the max total size
-        while total_buffer_size < MAX_BUFFER_SIZE + 10_000 {
-            let packet = PacketData::from(create_dummy_packet(1000));
-
-            total_buffer_size += packet.packet.data.len();
-            buffer.push_back(packet);
+        // More packets than fit in the queue arrive at once
+        for _ in 0..20 {
+            packets.push(PacketData::from(create_dummy_packet(1000)));
         }
 
-        let mut last_send_time = Instant::now();
-        let bandwidth_limit = 100; // High enough to not limit the test
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
+        let options = BandwidthOptions {
+            shaper: ShaperOptions {
+                queue_bytes: 10_000,
+                ..Default::default()
+            },
+            ..limited(1)
+        };
         let mut stats = BandwidthStats::new(0.5);
+        let mut dropped = Vec::new();
 
         bandwidth_limiter(
             &mut packets,
-            &mut buffer,
-            &mut total_buffer_size,
-            &mut last_send_time,
-            bandwidth_limit,
-            true,
-            true,
-            0,
+            &mut shaper,
+            &options,
+            clock.now(),
+            &mut module_rng(Some(1), "bandwidth"),
             &mut stats,
+            &mut dropped,
         );
 
-        let actual_total_size: usize = buffer.iter().map(|p| p.packet.data.len()).sum();
-
-        assert!(actual_total_size <= MAX_BUFFER_SIZE);
+        assert!(shaper.queued_bytes() <= 10_000);
+        assert_eq!(stats.dropped_packets() + shaper.len() + packets.len(), 20);
+        assert!(stats.dropped_packets() >= 9);
+        assert_eq!(dropped.len(), stats.dropped_packets());
+        assert!(dropped.iter().all(|p| p.effects.contains(PacketEffects::DROP)));
 
     }
 
@@ -404,25 +289,25 @@ mod tests {
             PacketData::from(create_dummy_packet(1000)),
         ];
 
-        let mut buffer = VecDeque::new();
-        let mut total_buffer_size = 0;
-        let mut last_send_time = Instant::now() - Duration::from_secs(1);
-        let bandwidth_limit = 10_000; // 10 MB/s
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
+
+        clock.advance(Duration::from_secs(1));
+
         let mut stats = BandwidthStats::new(0.5);
 
         bandwidth_limiter(
             &mut packets,
-            &mut buffer,
-            &mut total_buffer_size,
-            &mut last_send_time,
-            bandwidth_limit,
-            true,
-            true,
-            0,
+            &mut shaper,
+            &limited(10_000), // 10 MB/s
+            clock.now(),
+            &mut module_rng(Some(1), "bandwidth"),
             &mut stats,
+            &mut Vec::new(),
         );
 
         assert_eq!(packets.len(), 2);
+        assert_eq!(stats.total_bytes(), 2000);
 
     }
 
@@ -434,26 +319,23 @@ mod tests {
             PacketData::from(create_dummy_packet(1000)),
         ];
 
-        let mut buffer = VecDeque::new();
-        let mut total_buffer_size = 0;
-        let mut last_send_time = Instant::now();
-        let bandwidth_limit = 0; // 0 KB/s
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
         let mut stats = BandwidthStats::new(0.5);
 
         bandwidth_limiter(
             &mut packets,
-            &mut buffer,
-            &mut total_buffer_size,
-            &mut last_send_time,
-            bandwidth_limit,
-            true,
-            true,
-            0,
+            &mut shaper,
+            &limited(0), // 0 KB/s
+            clock.now(),
+            &mut module_rng(Some(1), "bandwidth"),
             &mut stats,
+            &mut Vec::new(),
         );
 
         assert!(packets.is_empty());
-        assert_eq!(buffer.len(), 2);
+        assert_eq!(shaper.len(), 2);
+        assert_eq!(stats.buffered_packets(), 2);
 
     }
 
@@ -461,95 +343,94 @@ mod tests {
     fn test_empty_packet_vector() {
 
         let mut packets = Vec::new();
-        let mut buffer = VecDeque::new();
-        let mut total_buffer_size = 0;
-        let mut last_send_time = Instant::now();
-        let bandwidth_limit = 10_000; // 10 MB/s
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
         let mut stats = BandwidthStats::new(0.5);
 
         bandwidth_limiter(
             &mut packets,
-            &mut buffer,
-            &mut total_buffer_size,
-            &mut last_send_time,
-            bandwidth_limit,
-            true,
-            true,
-            0,
+            &mut shaper,
+            &limited(10_000), // 10 MB/s
+            clock.now(),
+            &mut module_rng(Some(1), "bandwidth"),
             &mut stats,
+            &mut Vec::new(),
         );
 
-        // Since the packets vector was empty, buffer should remain empty and nothing should be sent
+        // Since the packets vector was empty, the queue should remain empty and nothing should be sent
         assert!(packets.is_empty());
-        assert!(buffer.is_empty());
+        assert!(shaper.is_empty());
 
     }
 
     #[test]
-    fn test_add_packet_to_buffer() {
-
-        let mut buffer = VecDeque::new();
-        let mut total_size = 0;
-        let packet = PacketData::from(create_dummy_packet(1000));
+    fn test_small_and_unlimited_direction_packets_pass() {
 
-        add_packet_to_buffer(&mut buffer, packet.clone(), &mut total_size);
-
-        assert_eq!(buffer.len(), 1);
-        assert_eq!(total_size, 1000);
-        assert_eq!(buffer.front().unwrap().packet.data.len(), 1000);
-
-    }
+        let mut outbound = PacketData::from(create_dummy_packet(1000));
+        outbound.is_outbound = true;
 
-    #[test]
-    fn test_add_packets_to_buffer() {
-
-        let mut buffer = VecDeque::new();
-        let mut total_size = 0;
         let mut packets = vec![
             PacketData::from(create_dummy_packet(1000)),
-            PacketData::from(create_dummy_packet(2000)),
+            PacketData::from(create_dummy_packet(60)),
+            outbound,
         ];
 
-        add_packets_to_buffer(&mut buffer, &mut packets, &mut total_size);
-
-        assert_eq!(buffer.len(), 2);
-        assert_eq!(total_size, 3000);
-        assert_eq!(buffer.pop_front().unwrap().packet.data.len(), 2000);
-        assert_eq!(buffer.pop_front().unwrap().packet.data.len(), 1000);
-
-    }
-
-    #[test]
-    fn test_remove_packet_from_buffer() {
-
-        let mut buffer = VecDeque::new();
-        let mut total_size = 0;
-        let packet = PacketData::from(create_dummy_packet(1000));
-
-        add_packet_to_buffer(&mut buffer, packet.clone(), &mut total_size);
-
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
+        let options = BandwidthOptions {
+            outbound: false,
+            passthrough_threshold: 64,
+            ..limited(0)
+        };
         let mut stats = BandwidthStats::new(0.5);
 
-        let removed_packet = remove_packet_from_buffer(&mut buffer, &mut total_size, &mut stats);
+        bandwidth_limiter(
+            &mut packets,
+            &mut shaper,
+            &options,
+            clock.now(),
+            &mut module_rng(Some(1), "bandwidth"),
+            &mut stats,
+            &mut Vec::new(),
+        );
 
-        assert_eq!(removed_packet.unwrap().packet.data.len(), 1000);
-        assert_eq!(buffer.len(), 0);
-        assert_eq!(total_size, 0);
+        assert_eq!(packets.len(), 2);
+        assert!(packets[1].is_outbound);
+        assert_eq!(shaper.len(), 1);
 
     }
 
     #[test]
-    fn test_remove_packet_from_empty_buffer() {
+    fn test_released_packets_keep_their_order() {
 
-        let mut buffer = VecDeque::new();
-        let mut total_size = 0;
+        let clock = ManualClock::new();
+        let mut shaper = Shaper::new(0.0, &ShaperOptions::default(), clock.now());
         let mut stats = BandwidthStats::new(0.5);
+        let mut rng = module_rng(Some(1), "bandwidth");
+        let mut packets: Vec<_> = (1..=5)
+            .map(|length| PacketData::from(create_dummy_packet(1000 + length)))
+            .collect();
+
+        let mut released = Vec::new();
+
+        for _ in 0..10 {
+            bandwidth_limiter(
+                &mut packets,
+                &mut shaper,
+                &limited(1),
+                clock.now(),
+                &mut rng,
+                &mut stats,
+                &mut Vec::new(),
+            );
+            released.append(&mut packets);
+            clock.advance(Duration::from_secs(1));
+        }
 
-        let removed_packet = remove_packet_from_buffer(&mut buffer, &mut total_size, &mut stats);
+        let lengths: Vec<_> = released.iter().map(|p| p.data.len()).collect();
 
-        assert!(removed_packet.is_none());
-        assert_eq!(buffer.len(), 0);
-        assert_eq!(total_size, 0);
+        assert_eq!(lengths, vec![1001, 1002, 1003, 1004, 1005]);
+        assert_eq!(stats.buffered_packets(), 0);
 
     }
 }
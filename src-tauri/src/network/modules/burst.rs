@@ -1,11 +1,11 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::burst_stats::BurstStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::probability::Probability;
 use crate::settings::burst::BurstOptions;
 use log::debug;
-use rand::{Rng, rng};
+use rand::Rng;
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -21,7 +21,7 @@ pub struct BurstModule;
 #[derive(Debug, Default)]
 pub struct BurstState {
     /// Queue of buffered packets with their capture time
-    pub buffer: VecDeque<(PacketData<'static>, Instant)>,
+    pub buffer: VecDeque<(PacketData, Instant)>,
     /// When the current burst cycle started
     pub cycle_start: Option<Instant>,
     /// When the last keepalive packet was sent
@@ -44,9 +44,9 @@ pub struct BurstState {
         options.duration_ms
     }
 
-                          fn process<'a>(
+                          fn process(
                           &self,
-                          packets: &mut Vec<PacketData<'a>>,
+                          packets: &mut Vec<PacketData>,
                           options: &Self::Options,
                           state: &mut Self::State,
                           ctx: &mut ModuleContext,
@@ -54,16 +54,9 @@ pub struct BurstState {
 
         let mut stats = ctx.write_stats(self.name())?;
 
-        // SAFETY: The buffer outlives each processing call. Packets are moved into
-        // the buffer and only released when the burst timer expires. The transmute
-        // extends the lifetime to 'static for storage, but packets are always consumed
-        // or released before the buffer is dropped.
-        let buffer: &mut VecDeque<(PacketData<'a>, Instant)> =
-            unsafe { std::mem::transmute(&mut state.buffer) };
-
         burst_packets(
             packets,
-            buffer,
+            &mut state.buffer,
             &mut state.cycle_start,
             &mut state.last_keepalive,
             Duration::from_millis(options.buffer_ms),
@@ -72,6 +65,8 @@ pub struct BurstState {
             options.inbound,
             options.outbound,
             options.reverse,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.burst_stats,
         );
         Ok(())
@@ -102,9 +97,9 @@ pub struct BurstState {
 ///
 /// This creates the "teleport" effect - your actions are recorded locally,
 /// then all sent at once when the buffer releases.
-pub fn burst_packets<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    buffer: &mut VecDeque<(PacketData<'a>, Instant)>,
+pub fn burst_packets(
+    packets: &mut Vec<PacketData>,
+    buffer: &mut VecDeque<(PacketData, Instant)>,
                            cycle_start: &mut Option<Instant>,
                            last_keepalive: &mut Option<Instant>,
                            buffer_duration: Duration,
@@ -113,11 +108,11 @@ pub fn burst_packets<'a>(
                            apply_inbound: bool,
                            apply_outbound: bool,
                            reverse: bool,
+                           now: Instant,
+                           rng: &mut impl Rng,
                            stats: &mut BurstStats,
                            ) {
 
-    let now = Instant::now();
-    let mut rng = rng();
 
     // Initialize cycle if not started
     if cycle_start.is_none() {
@@ -170,10 +165,10 @@ pub fn burst_packets<'a>(
             continue;
         }
 
-        let packet = packets.remove(i);
-        let static_packet: PacketData<'static> = unsafe { std::mem::transmute(packet) };
+        let mut packet = packets.remove(i);
 
-        buffer.push_back((static_packet, now));
+        packet.effects.insert(PacketEffects::BURST);
+        buffer.push_back((packet, now));
         stats.record_buffer(1);
     }
 
@@ -229,9 +224,9 @@ pub fn burst_packets<'a>(
 /// * `buffer` - The buffer containing packets to flush
 /// * `cycle_start` - Cycle start time to reset
 /// * `reverse` - If true, release packets in reverse order (rewind effect)
-pub fn flush_buffer<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    buffer: &mut VecDeque<(PacketData<'a>, Instant)>,
+pub fn flush_buffer(
+    packets: &mut Vec<PacketData>,
+    buffer: &mut VecDeque<(PacketData, Instant)>,
                            cycle_start: &mut Option<Instant>,
                            reverse: bool,
                            ) {
@@ -276,43 +271,41 @@ pub fn flush_buffer<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use windivert::layer::NetworkLayer;
-    use windivert::packet::WinDivertPacket;
 
     #[test]
     fn test_packet_buffering() {
 
-        unsafe {
-            // Create outbound packets for testing
-            let mut packets = vec![
-                PacketData::new(WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]), true),
-                PacketData::new(WinDivertPacket::<NetworkLayer>::new(vec![4, 5, 6]), true),
-            ];
-
-            let mut buffer = VecDeque::new();
-            let mut cycle_start = None;
-            let mut last_keepalive = None;
-            let mut stats = BurstStats::new(0.05);
-
-            // Buffer with 100% probability, both directions
-            burst_packets(
-                &mut packets,
-                &mut buffer,
-                &mut cycle_start,
-                &mut last_keepalive,
-                Duration::from_millis(1000),
-                Duration::from_millis(0), // No keepalive
-                Probability::new(1.0).unwrap(),
-                true,  // apply_inbound
-                true,  // apply_outbound
-                false, // reverse
-                &mut stats,
-            );
+        // Create outbound packets for testing
+        let mut packets = vec![
+            PacketData::new(vec![1, 2, 3], true),
+            PacketData::new(vec![4, 5, 6], true),
+        ];
 
-            // All packets should be buffered
-            assert_eq!(packets.len(), 0);
-            assert_eq!(buffer.len(), 2);
-        }
+        let mut buffer = VecDeque::new();
+        let mut cycle_start = None;
+        let mut last_keepalive = None;
+        let mut stats = BurstStats::new(0.05);
+
+        // Buffer with 100% probability, both directions
+        burst_packets(
+            &mut packets,
+            &mut buffer,
+            &mut cycle_start,
+            &mut last_keepalive,
+            Duration::from_millis(1000),
+            Duration::from_millis(0), // No keepalive
+            Probability::new(1.0).unwrap(),
+            true,  // apply_inbound
+            true,  // apply_outbound
+            false, // reverse
+            Instant::now(),
+            &mut rand::rng(),
+            &mut stats,
+        );
+
+        // All packets should be buffered
+        assert_eq!(packets.len(), 0);
+        assert_eq!(buffer.len(), 2);
 
     }
 }
@@ -1,13 +1,13 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::corruption_stats::CorruptionStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::probability::Probability;
 use crate::settings::corruption::CorruptionOptions;
 use log::error;
-use rand::{Rng, rng};
+use rand::Rng;
 use std::collections::HashSet;
-use windivert_sys::ChecksumFlags;
+use std::time::Instant;
 
 /// Unit struct for the Corruption packet module.
 ///
@@ -34,7 +34,7 @@ impl PacketModule for CorruptionModule {
 
     fn process(
         &self,
-        packets: &mut Vec<PacketData<'_>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         _state: &mut Self::State,
         ctx: &mut ModuleContext,
@@ -49,6 +49,8 @@ impl PacketModule for CorruptionModule {
             options.recalculate_checksums.unwrap_or(true),
             options.inbound,
             options.outbound,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.corruption_stats,
         );
         Ok(())
@@ -68,6 +70,8 @@ impl PacketModule for CorruptionModule {
 /// * `corruption_probability` - Probability of corruptioning with each packet
 /// * `corruption_amount` - Proportion of bytes to corruption with in each selected packet
 /// * `recalculate_checksums` - Whether to recalculate packet checksums after corruptioning
+/// * `now` - Current time from the module clock
+/// * `rng` - Random number generator choosing packets and corrupted bytes
 /// * `stats` - Statistics collector for corruptioning operations
 ///
 /// # Example
@@ -84,6 +88,9 @@ impl PacketModule for CorruptionModule {
 ///     corruption_probability,
 ///     corruption_amount,
 ///     recalculate_checksums,
+///     true,
+///     true,
+///     &mut rand::rng(),
 ///     &mut stats,
 /// );
 /// ```
@@ -94,11 +101,12 @@ pub fn corruption_packets(
     recalculate_checksums: bool,
     apply_inbound: bool,
     apply_outbound: bool,
+    now: Instant,
+    rng: &mut impl Rng,
     stats: &mut CorruptionStats,
 ) {
 
-    let should_update_stats = stats.should_update();
-    let mut rng = rng();
+    let should_update_stats = stats.should_update(now);
 
     for packet_data in packets.iter_mut() {
         // Check if this packet's direction should be affected
@@ -116,24 +124,13 @@ pub fn corruption_packets(
             continue;
         }
 
-        let data = packet_data.packet.data.to_mut();
-
-        let (ip_header_len, protocol) = match get_ip_version(data) {
-            Some((4, data)) => parse_ipv4_header(data),
-            Some((6, data)) => parse_ipv6_header(data),
-            _ => {
-                error!("Unsupported IP version");
-                continue;
-            }
-        };
-
-        let total_header_len = match protocol {
-            17 => parse_udp_header(data, ip_header_len),
-            6 => parse_tcp_header(data, ip_header_len),
-            _ => ip_header_len,
+        let Some(headers) = packet_data.headers() else {
+            error!("Unsupported or truncated IP packet");
+            continue;
         };
 
-        let payload_offset = total_header_len;
+        let data = &mut packet_data.data;
+        let payload_offset = headers.payload_offset();
         let payload_length = data.len() - payload_offset;
 
         if should_skip {
@@ -144,14 +141,14 @@ pub fn corruption_packets(
             stats.data = data[payload_offset..].to_owned();
             stats.corruption_flags = vec![false; stats.data.len()];
             stats.checksum_valid = true;
-            stats.updated();
+            stats.updated(now);
             continue;
         }
 
         if payload_length > 0 {
             let bytes_to_corruption = (payload_length as f64 * corruption_amount.value()) as usize;
             let corruptioned_indices =
-                apply_corruptioning(&mut data[payload_offset..], bytes_to_corruption);
+                apply_corruptioning(&mut data[payload_offset..], bytes_to_corruption, rng);
 
             if should_update_stats {
                 let corruptioned_flags =
@@ -159,27 +156,24 @@ pub fn corruption_packets(
 
                 stats.corruption_flags = corruptioned_flags;
                 stats.data = data[payload_offset..].to_owned();
-                stats.updated();
+                stats.updated(now);
             }
         }
 
-        if recalculate_checksums {
-            if let Err(e) = packet_data
-                .packet
-                .recalculate_checksums(ChecksumFlags::new())
-            {
-                error!("Error recalculating checksums: {}", e);
-            }
+        packet_data.effects.insert(PacketEffects::CORRUPTION);
+
+        if recalculate_checksums && !packet_data.recalculate_checksums() {
+            error!("Error recalculating checksums");
         }
 
         if !should_update_stats {
             continue;
         }
 
-        stats.checksum_valid = packet_data.packet.address.ip_checksum()
-            && packet_data.packet.address.tcp_checksum()
-            && packet_data.packet.address.udp_checksum();
-        stats.updated();
+        stats.checksum_valid = packet_data.flags.ip_checksum
+            && packet_data.flags.tcp_checksum
+            && packet_data.flags.udp_checksum;
+        stats.updated(now);
     }
 
 }
@@ -193,16 +187,20 @@ pub fn corruption_packets(
 ///
 /// * `data` - The data slice to be corruptioned with
 /// * `bytes_to_corruption` - The number of bytes to corruption with
+/// * `rng` - Random number generator choosing bytes and modifications
 ///
 /// # Returns
 ///
 /// A `HashSet` containing the indices of all modified bytes
-fn apply_corruptioning(data: &mut [u8], bytes_to_corruption: usize) -> HashSet<usize> {
+fn apply_corruptioning(
+    data: &mut [u8],
+    bytes_to_corruption: usize,
+    rng: &mut impl Rng,
+) -> HashSet<usize> {
 
     let mut corruptioned_indices = HashSet::new();
     let mut corruptioned_count = 0;
     let data_len = data.len();
-    let mut rng = rng();
 
     while corruptioned_count < bytes_to_corruption && corruptioned_count < data_len {
         let index = rng.random_range(0..data.len());
@@ -251,95 +249,6 @@ fn calculate_corruptioned_flags(
 
 }
 
-/// Extracts the IP version from a packet data slice
-///
-/// # Arguments
-///
-/// * `data` - Packet data slice
-///
-/// # Returns
-///
-/// Option containing a tuple of (IP version, data slice reference) if successful
-fn get_ip_version(data: &[u8]) -> Option<(u8, &[u8])> {
-
-    if data.is_empty() {
-        return None;
-    }
-
-    let version = data[0] >> 4;
-
-    Some((version, data))
-
-}
-
-/// Parses an IPv4 header to extract header length and protocol
-///
-/// # Arguments
-///
-/// * `data` - Packet data slice starting at the IPv4 header
-///
-/// # Returns
-///
-/// A tuple of (header length in bytes, protocol number)
-fn parse_ipv4_header(data: &[u8]) -> (usize, u8) {
-
-    let header_length = ((data[0] & 0x0F) * 4) as usize;
-    let protocol = data[9];
-
-    (header_length, protocol)
-
-}
-
-/// Parses an IPv6 header to extract header length and next header type
-///
-/// # Arguments
-///
-/// * `data` - Packet data slice starting at the IPv6 header
-///
-/// # Returns
-///
-/// A tuple of (header length in bytes, next header type)
-fn parse_ipv6_header(data: &[u8]) -> (usize, u8) {
-
-    let header_length = 40; // IPv6 header is always 40 bytes
-    let next_header = data[6];
-
-    (header_length, next_header)
-
-}
-
-/// Calculates the total header length for a UDP packet
-///
-/// # Arguments
-///
-/// * `_data` - Packet data slice (unused but kept for consistency)
-/// * `ip_header_len` - Length of the IP header in bytes
-///
-/// # Returns
-///
-/// Total header length (IP header + UDP header) in bytes
-fn parse_udp_header(_data: &[u8], ip_header_len: usize) -> usize {
-    let udp_header_len = 8; // UDP header is always 8 bytes
-
-    ip_header_len + udp_header_len
-}
-
-/// Calculates the total header length for a TCP packet
-///
-/// # Arguments
-///
-/// * `data` - Packet data slice
-/// * `ip_header_len` - Length of the IP header in bytes
-///
-/// # Returns
-///
-/// Total header length (IP header + TCP header) in bytes
-fn parse_tcp_header(data: &[u8], ip_header_len: usize) -> usize {
-    let tcp_data_offset = (data[ip_header_len + 12] >> 4) * 4;
-
-    ip_header_len + tcp_data_offset as usize
-}
-
 /// Manipulates a specific bit in a byte to a specified value
 ///
 /// # Arguments
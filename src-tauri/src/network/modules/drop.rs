@@ -1,10 +1,10 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::drop_stats::DropStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::probability::Probability;
-use crate::settings::drop::DropOptions;
-use rand::{Rng, rng};
+use crate::settings::drop::{DropModel, DropOptions, GilbertElliottOptions};
+use rand::Rng;
 
 /// Unit struct for the Drop packet module.
 ///
@@ -13,9 +13,16 @@ use rand::{Rng, rng};
 #[derive(Debug, Default)]
 pub struct DropModule;
 
+/// State maintained by the drop module between processing calls.
+#[derive(Debug, Default)]
+pub struct DropState {
+    /// Whether the Gilbert-Elliott model is in its bad (lossy) state
+    pub bad: bool,
+}
+
 impl PacketModule for DropModule {
     type Options = DropOptions;
-    type State = ();
+    type State = DropState;
 
     fn name(&self) -> &'static str {
         "drop"
@@ -31,21 +38,35 @@ impl PacketModule for DropModule {
 
     fn process(
         &self,
-        packets: &mut Vec<PacketData<'_>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
-        _state: &mut Self::State,
+        state: &mut Self::State,
         ctx: &mut ModuleContext,
     ) -> Result<()> {
 
         let mut stats = ctx.write_stats(self.name())?;
 
-        drop_packets(
-            packets,
-            options.probability,
-            options.inbound,
-            options.outbound,
-            &mut stats.drop_stats,
-        );
+        match options.model {
+            DropModel::Uniform => drop_packets(
+                packets,
+                options.probability,
+                options.inbound,
+                options.outbound,
+                ctx.rng,
+                &mut stats.drop_stats,
+                ctx.dropped,
+            ),
+            DropModel::GilbertElliott => drop_packets_gilbert_elliott(
+                packets,
+                &options.gilbert_elliott,
+                options.inbound,
+                options.outbound,
+                state,
+                ctx.rng,
+                &mut stats.drop_stats,
+                ctx.dropped,
+            ),
+        }
         Ok(())
 
     }
@@ -60,7 +81,9 @@ impl PacketModule for DropModule {
 ///
 /// * `packets` - Mutable vector of packets that will be filtered
 /// * `drop_probability` - Probability (0.0-1.0) of dropping each packet
+/// * `rng` - Random number generator deciding which packets are dropped
 /// * `stats` - Statistics tracker that will be updated with drop information
+/// * `dropped` - Receives the dropped packets, marked with [`PacketEffects::DROP`]
 ///
 /// # Example
 ///
@@ -69,107 +92,252 @@ impl PacketModule for DropModule {
 /// let probability = Probability::new(0.3).unwrap(); // 30% chance to drop
 /// let mut stats = DropStats::new(0.1); // With EWMA alpha of 0.1
 ///
-/// drop_packets(&mut packets, probability, &mut stats);
+/// drop_packets(&mut packets, probability, true, true, &mut rand::rng(), &mut stats, &mut vec![]);
 /// ```
 pub fn drop_packets(
-    packets: &mut Vec<PacketData<'_>>,
+    packets: &mut Vec<PacketData>,
     drop_probability: Probability,
     apply_inbound: bool,
     apply_outbound: bool,
+    rng: &mut impl Rng,
     stats: &mut DropStats,
+    dropped: &mut Vec<PacketData>,
 ) {
 
-    let mut rng = rng();
-
-    packets.retain(|packet| {
-
+    for mut packet in std::mem::take(packets) {
         // Check if this packet's direction should be affected
         let matches_direction =
             (packet.is_outbound && apply_outbound) || (!packet.is_outbound && apply_inbound);
 
         if !matches_direction {
             // Direction doesn't match - keep packet unchanged
-            return true;
+            packets.push(packet);
+            continue;
         }
         let drop = rng.random::<f64>() < drop_probability.value();
 
+        stats.record(drop);
+
         if drop {
-            stats.record(true);
-            return false;
+            packet.effects.insert(PacketEffects::DROP);
+            dropped.push(packet);
+        } else {
+            packets.push(packet);
+        }
+    }
+
+}
+
+/// Drops packets in bursts using the two-state Gilbert-Elliott model.
+///
+/// Before each packet the model may change state, then the packet is
+/// dropped with the loss rate of the current state. The state is kept in
+/// `state` so bursts continue across processing cycles.
+///
+/// # Arguments
+///
+/// * `packets` - Mutable vector of packets that will be filtered
+/// * `params` - Transition probabilities and per-state loss rates
+/// * `state` - Current state of the model, updated in place
+/// * `rng` - Random number generator driving transitions and drops
+/// * `stats` - Statistics tracker that will be updated with drop information
+/// * `dropped` - Receives the dropped packets, marked with [`PacketEffects::DROP`]
+pub fn drop_packets_gilbert_elliott(
+    packets: &mut Vec<PacketData>,
+    params: &GilbertElliottOptions,
+    apply_inbound: bool,
+    apply_outbound: bool,
+    state: &mut DropState,
+    rng: &mut impl Rng,
+    stats: &mut DropStats,
+    dropped: &mut Vec<PacketData>,
+) {
+
+    for mut packet in std::mem::take(packets) {
+        let matches_direction =
+            (packet.is_outbound && apply_outbound) || (!packet.is_outbound && apply_inbound);
+
+        if !matches_direction {
+            packets.push(packet);
+            continue;
+        }
+
+        let transition = if state.bad { params.bad_to_good } else { params.good_to_bad };
+
+        if rng.random::<f64>() < transition.value() {
+            state.bad = !state.bad;
         }
-        stats.record(false);
-        true
 
-    });
+        let loss = if state.bad { params.bad_loss } else { params.good_loss };
+        let drop = rng.random::<f64>() < loss.value();
+
+        stats.record(drop);
+
+        if drop {
+            packet.effects.insert(PacketEffects::DROP);
+            dropped.push(packet);
+        } else {
+            packets.push(packet);
+        }
+    }
 
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use windivert::layer::NetworkLayer;
-    use windivert::packet::WinDivertPacket;
 
     #[test]
     fn test_drop_all_packets() {
 
-        unsafe {
-            // Create a test packet
-            let mut packets = vec![PacketData::from(WinDivertPacket::<NetworkLayer>::new(
-                vec![1, 2, 3],
-            ))];
+        // Create a test packet
+        let mut packets = vec![PacketData::from(vec![1, 2, 3],)];
 
-            // Initialize drop statistics with EWMA alpha=0.3
-            let mut drop_stats = DropStats::new(0.3);
+        // Initialize drop statistics with EWMA alpha=0.3
+        let mut drop_stats = DropStats::new(0.3);
+        let mut dropped = Vec::new();
 
-            // Use 100% drop probability to ensure all packets are dropped
-            drop_packets(
-                &mut packets,
-                Probability::new(1.0).unwrap(),
-                true, // apply_inbound
-                true, // apply_outbound
-                &mut drop_stats,
-            );
+        // Use 100% drop probability to ensure all packets are dropped
+        drop_packets(
+            &mut packets,
+            Probability::new(1.0).unwrap(),
+            true, // apply_inbound
+            true, // apply_outbound
+            &mut rand::rng(),
+            &mut drop_stats,
+            &mut dropped,
+        );
 
-            // Verify that all packets were dropped
-            assert!(packets.is_empty());
-            assert_eq!(drop_stats.total_packets, 1);
-            assert_eq!(drop_stats.total_dropped, 1);
-            assert_eq!(drop_stats.total_drop_rate(), 1.0);
-        }
+        // Verify that all packets were dropped
+        assert!(packets.is_empty());
+        assert_eq!(dropped.len(), 1);
+        assert!(dropped[0].effects.contains(PacketEffects::DROP));
+        assert_eq!(drop_stats.total_packets, 1);
+        assert_eq!(drop_stats.total_dropped, 1);
+        assert_eq!(drop_stats.total_drop_rate(), 1.0);
 
     }
 
     #[test]
     fn test_drop_no_packets() {
 
-        unsafe {
-            // Create multiple test packets
-            let mut packets = vec![
-                PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3])),
-                PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![4, 5, 6])),
-            ];
+        // Create multiple test packets
+        let mut packets = vec![
+            PacketData::from(vec![1, 2, 3]),
+            PacketData::from(vec![4, 5, 6]),
+        ];
+
+        let initial_count = packets.len();
+
+        // Initialize drop statistics
+        let mut drop_stats = DropStats::new(0.3);
 
-            let initial_count = packets.len();
+        // Use 0% drop probability to ensure no packets are dropped
+        drop_packets(
+            &mut packets,
+            Probability::new(0.0).unwrap(),
+            true, // apply_inbound
+            true, // apply_outbound
+            &mut rand::rng(),
+            &mut drop_stats,
+            &mut Vec::new(),
+        );
 
-            // Initialize drop statistics
-            let mut drop_stats = DropStats::new(0.3);
+        // Verify that no packets were dropped
+        assert_eq!(packets.len(), initial_count);
+        assert_eq!(drop_stats.total_packets, 2);
+        assert_eq!(drop_stats.total_dropped, 0);
+        assert_eq!(drop_stats.total_drop_rate(), 0.0);
+
+    }
+
+    #[test]
+    fn test_same_seed_drops_same_packets() {
+
+        let run = |seed| {
+            let mut packets: Vec<PacketData> =
+                (0..100u8).map(|i| PacketData::from(vec![i])).collect();
+            let mut rng = crate::network::types::rng::module_rng(Some(seed), "drop");
 
-            // Use 0% drop probability to ensure no packets are dropped
             drop_packets(
                 &mut packets,
-                Probability::new(0.0).unwrap(),
-                true, // apply_inbound
-                true, // apply_outbound
-                &mut drop_stats,
+                Probability::new(0.5).unwrap(),
+                true,
+                true,
+                &mut rng,
+                &mut DropStats::new(0.3),
+                &mut Vec::new(),
             );
 
-            // Verify that no packets were dropped
-            assert_eq!(packets.len(), initial_count);
-            assert_eq!(drop_stats.total_packets, 2);
-            assert_eq!(drop_stats.total_dropped, 0);
-            assert_eq!(drop_stats.total_drop_rate(), 0.0);
+            packets.into_iter().map(|p| p.data[0]).collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(1234), run(1234));
+        assert_ne!(run(1234), run(4321));
+
+    }
+
+    #[test]
+    fn test_gilbert_elliott_drops_in_bursts() {
+
+        let params = GilbertElliottOptions {
+            good_to_bad: Probability::new(0.05).unwrap(),
+            bad_to_good: Probability::new(0.25).unwrap(),
+            good_loss: Probability::new(0.0).unwrap(),
+            bad_loss: Probability::new(1.0).unwrap(),
+        };
+        let mut state = DropState::default();
+        let mut rng = crate::network::types::rng::module_rng(Some(99), "drop");
+        let mut stats = DropStats::new(0.3);
+
+        for _ in 0..200 {
+            let mut packets: Vec<PacketData> =
+                (0..100u8).map(|i| PacketData::from(vec![i])).collect();
+
+            drop_packets_gilbert_elliott(
+                &mut packets,
+                &params,
+                true,
+                true,
+                &mut state,
+                &mut rng,
+                &mut stats,
+                &mut Vec::new(),
+            );
         }
 
+        // Stationary loss is p / (p + r) = 1/6, mean burst length is 1 / r = 4
+        assert!((stats.total_drop_rate() - 1.0 / 6.0).abs() < 0.02);
+        assert!((stats.mean_burst_length() - 4.0).abs() < 0.3);
+        assert!(stats.burst_length_distribution().keys().any(|&len| len >= 8));
+
+    }
+
+    #[test]
+    fn test_gilbert_elliott_skips_other_direction() {
+
+        let params = GilbertElliottOptions {
+            good_to_bad: Probability::new(1.0).unwrap(),
+            ..GilbertElliottOptions::default()
+        };
+        let mut packets = vec![PacketData::from(vec![1, 2, 3])];
+        let mut state = DropState::default();
+        let mut stats = DropStats::new(0.3);
+
+        drop_packets_gilbert_elliott(
+            &mut packets,
+            &params,
+            false,
+            false,
+            &mut state,
+            &mut rand::rng(),
+            &mut stats,
+            &mut Vec::new(),
+        );
+
+        assert_eq!(packets.len(), 1);
+        assert!(!state.bad);
+        assert_eq!(stats.total_packets, 0);
+
     }
 }
@@ -1,10 +1,11 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::duplicate_stats::DuplicateStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::probability::Probability;
 use crate::settings::duplicate::DuplicateOptions;
 use rand::Rng;
+use std::time::Instant;
 use std::vec::Vec;
 
 /// Unit struct for the Duplicate packet module.
@@ -36,7 +37,7 @@ impl PacketModule for DuplicateModule {
 
     fn process(
         &self,
-        packets: &mut Vec<PacketData<'_>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         _state: &mut Self::State,
         ctx: &mut ModuleContext,
@@ -50,6 +51,8 @@ impl PacketModule for DuplicateModule {
             options.probability,
             options.inbound,
             options.outbound,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.duplicate_stats,
         );
         Ok(())
@@ -67,6 +70,8 @@ impl PacketModule for DuplicateModule {
 /// * `packets` - Vector of packets to process
 /// * `count` - Number of duplicates to create for each selected packet
 /// * `probability` - Probability of duplicating a packet
+/// * `now` - Current time from the module clock
+/// * `rng` - Random number generator deciding which packets are duplicated
 /// * `stats` - Statistics tracker to update
 pub fn duplicate_packets(
     packets: &mut Vec<PacketData>,
@@ -74,10 +79,11 @@ pub fn duplicate_packets(
     probability: Probability,
     apply_inbound: bool,
     apply_outbound: bool,
+    now: Instant,
+    rng: &mut impl Rng,
     stats: &mut DuplicateStats,
 ) {
 
-    let mut rng = rand::rng();
     let mut duplicate_packets_vec = Vec::with_capacity(packets.len() * count);
 
     for packet_data in packets.iter() {
@@ -97,7 +103,13 @@ pub fn duplicate_packets(
         }
 
         for _ in 1..=count {
-            duplicate_packets_vec.push(PacketData::from(packet_data.packet.clone()));
+            let mut duplicate = PacketData {
+                arrival_time: now,
+                ..packet_data.clone()
+            };
+
+            duplicate.effects.insert(PacketEffects::DUPLICATE);
+            duplicate_packets_vec.push(duplicate);
         }
 
         stats.record(1 + count);
@@ -109,44 +121,66 @@ pub fn duplicate_packets(
 
 #[cfg(test)]
 mod tests {
-    use crate::network::core::packet::PacketData;
+    use crate::network::core::packet::{PacketData, PacketEffects};
     use crate::network::modules::duplicate::duplicate_packets;
     use crate::network::modules::stats::duplicate_stats::DuplicateStats;
     use crate::network::types::probability::Probability;
-    use windivert::layer::NetworkLayer;
-    use windivert::packet::WinDivertPacket;
+    use std::time::Instant;
 
     #[test]
     fn test_packet_duplication() {
 
-        unsafe {
-            let original_packets = vec![PacketData::from(WinDivertPacket::<NetworkLayer>::new(
-                vec![1, 2, 3],
-            ))];
-
-            let original_len = original_packets.len();
-            let mut packets = original_packets.clone();
-            let mut stats = DuplicateStats::new(0.05);
-
-            duplicate_packets(
-                &mut packets,
-                3,
-                Probability::new(1.0).unwrap(),
-                true, // apply_inbound
-                true, // apply_outbound
-                &mut stats,
-            );
-
-            // Ensure three times as many packets
-            assert_eq!(packets.len(), original_len * 4);
-
-            // Ensure data consistency
-            for chunk in packets.chunks(original_len) {
-                for packet_data in chunk.iter() {
-                    assert_eq!(packet_data.packet.data[..], [1, 2, 3]);
-                }
+        let original_packets = vec![PacketData::from(vec![1, 2, 3],)];
+
+        let original_len = original_packets.len();
+        let mut packets = original_packets;
+        let mut stats = DuplicateStats::new(0.05);
+
+        duplicate_packets(
+            &mut packets,
+            3,
+            Probability::new(1.0).unwrap(),
+            true, // apply_inbound
+            true, // apply_outbound
+            Instant::now(),
+            &mut rand::rng(),
+            &mut stats,
+        );
+
+        // Ensure three times as many packets
+        assert_eq!(packets.len(), original_len * 4);
+
+        // Ensure data consistency
+        for chunk in packets.chunks(original_len) {
+            for packet_data in chunk.iter() {
+                assert_eq!(packet_data.data[..], [1, 2, 3]);
             }
         }
 
     }
+
+    #[test]
+    fn test_duplicates_are_marked() {
+
+        let mut packets = vec![PacketData::from(vec![1, 2, 3])];
+        let original_id = packets[0].id;
+        let mut stats = DuplicateStats::new(0.05);
+
+        duplicate_packets(
+            &mut packets,
+            1,
+            Probability::new(1.0).unwrap(),
+            true, // apply_inbound
+            true, // apply_outbound
+            Instant::now(),
+            &mut rand::rng(),
+            &mut stats,
+        );
+
+        assert_eq!(packets.len(), 2);
+        assert!(packets[0].effects.is_empty());
+        assert!(packets[1].effects.contains(PacketEffects::DUPLICATE));
+        assert_eq!(packets[1].id, original_id);
+
+    }
 }
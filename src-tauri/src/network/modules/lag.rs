@@ -1,12 +1,15 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{FlowKey, PacketData, PacketEffects};
 use crate::network::modules::stats::lag_stats::LagStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::probability::Probability;
-use crate::settings::lag::LagOptions;
-use rand::{Rng, rng};
-use std::collections::VecDeque;
-use std::time::Duration;
+use crate::settings::lag::{JitterDistribution, LagOptions};
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Shape of the Pareto distribution used for jitter, matching netem's tables.
+const PARETO_SHAPE: f64 = 3.0;
 
 /// Unit struct for the Lag packet module.
 ///
@@ -16,8 +19,59 @@ use std::time::Duration;
 #[derive(Debug, Default)]
 pub struct LagModule;
 
+/// A packet held by the lag module.
+#[derive(Debug)]
+pub struct LaggedPacket {
+    /// The held packet
+    pub packet: PacketData,
+    /// Time at which the packet may be released
+    pub release_at: Instant,
+}
+
 /// State maintained by the lag module between processing calls.
-pub type LagState = VecDeque<PacketData<'static>>;
+#[derive(Debug, Default)]
+pub struct LagState {
+    /// Held packets, ordered by release time
+    pub queue: VecDeque<LaggedPacket>,
+    /// Previous jitter value, which correlated jitter is drawn towards
+    last_jitter: f64,
+    /// Latest release time given to each flow, used to preserve order
+    flow_release: HashMap<Option<FlowKey>, Instant>,
+}
+
+impl LagState {
+    /// Returns the number of held packets.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns whether no packets are held.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/// Random variation added to the lag of each packet.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Jitter {
+    /// Half-width (uniform) or standard deviation (other distributions) of the jitter
+    pub amount: Duration,
+    /// Distribution the jitter is drawn from
+    pub distribution: JitterDistribution,
+    /// Weight of the previous jitter value in the next one (0.0-1.0)
+    pub correlation: f64,
+}
+
+impl Jitter {
+    /// Creates the jitter described by the lag options.
+    pub fn from_options(options: &LagOptions) -> Self {
+        Self {
+            amount: Duration::from_millis(options.jitter_ms),
+            distribution: options.distribution,
+            correlation: options.correlation.value(),
+        }
+    }
+}
 
 impl PacketModule for LagModule {
     type Options = LagOptions;
@@ -35,9 +89,9 @@ impl PacketModule for LagModule {
         options.duration_ms
     }
 
-    fn process<'a>(
+    fn process(
         &self,
-        packets: &mut Vec<PacketData<'a>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         state: &mut Self::State,
         ctx: &mut ModuleContext,
@@ -45,19 +99,17 @@ impl PacketModule for LagModule {
 
         let mut stats = ctx.write_stats(self.name())?;
 
-        // SAFETY: The storage outlives each processing call. Packets are moved into
-        // storage and only released when their lag duration has elapsed. The transmute
-        // extends the lifetime to 'static for storage, but packets are always consumed
-        // or released before the storage is dropped.
-        let storage: &mut VecDeque<PacketData<'a>> = unsafe { std::mem::transmute(state) };
-
         lag_packets(
             packets,
-            storage,
+            state,
             Duration::from_millis(options.delay_ms),
+            &Jitter::from_options(options),
+            options.preserve_order,
             options.probability,
             options.inbound,
             options.outbound,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.lag_stats,
         );
         Ok(())
@@ -68,52 +120,75 @@ impl PacketModule for LagModule {
 /// Simulates network lag by holding packets for a specified duration.
 ///
 /// This function holds incoming packets in a buffer and only releases them
-/// after the specified lag time has elapsed.
+/// after their lag time has elapsed.
 /// With probability set to 1.0 (100%, the default), all traffic is lagged.
 ///
 /// # How it works
 ///
-/// 1. Incoming packets are moved to the lag storage queue based on probability
-/// 2. On each processing cycle, packets that have been in the queue for at least
-///    the lag duration are moved back to the outgoing packets vector
-/// 3. Statistics are updated with the number of packets still being lagged
+/// 1. Incoming packets are given a lag time of `lag` plus a random jitter and
+///    moved to the lag queue based on probability
+/// 2. With `preserve_order`, a packet is never released before an earlier
+///    packet of the same flow
+/// 3. On each processing cycle, packets whose lag time has elapsed are moved
+///    back to the outgoing packets vector
+/// 4. Statistics are updated with the number of packets still being lagged
+///    and the delays of the released ones
 ///
 /// # Arguments
 ///
 /// * `packets` - Mutable vector of packets that will be processed
-/// * `storage` - Persistent queue for storing lagged packets
-/// * `lag` - The duration to lag each packet
+/// * `state` - Persistent lag queue and jitter state
+/// * `lag` - The base duration to lag each packet
+/// * `jitter` - Random variation added to `lag`
+/// * `preserve_order` - Whether packets of a flow keep their order despite jitter
 /// * `probability` - Probability of lagging each packet (default 1.0 = 100%)
+/// * `now` - Current time from the module clock
+/// * `rng` - Random number generator deciding which packets are lagged and their jitter
 /// * `stats` - Statistics tracker that will be updated with lag information
 ///
 /// # Example
 ///
 /// ```
 /// let mut packets = vec![packet1, packet2];
-/// let mut storage = VecDeque::new();
+/// let mut state = LagState::default();
 /// let lag = Duration::from_millis(100);
 /// let probability = Probability::new(1.0).unwrap(); // 100% - all packets lagged
 /// let mut stats = LagStats::new();
 ///
-/// lag_packets(&mut packets, &mut storage, lag, probability, &mut stats);
+/// lag_packets(
+///     &mut packets,
+///     &mut state,
+///     lag,
+///     &Jitter::default(),
+///     false,
+///     probability,
+///     true,
+///     true,
+///     Instant::now(),
+///     &mut rand::rng(),
+///     &mut stats,
+/// );
 /// ```
-pub fn lag_packets<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    storage: &mut VecDeque<PacketData<'a>>,
+pub fn lag_packets(
+    packets: &mut Vec<PacketData>,
+    state: &mut LagState,
     lag: Duration,
+    jitter: &Jitter,
+    preserve_order: bool,
     probability: Probability,
     apply_inbound: bool,
     apply_outbound: bool,
+    now: Instant,
+    rng: &mut impl Rng,
     stats: &mut LagStats,
 ) {
 
-    let mut rng = rng();
     let mut passthrough_packets = Vec::new();
     let prob_value = probability.value();
 
     // Move packets to the lag buffer based on probability and direction
     // With default probability of 1.0, ALL matching packets are lagged
-    for packet in packets.drain(..) {
+    for mut packet in packets.drain(..) {
         // Check if this packet's direction should be affected
         let matches_direction =
             (packet.is_outbound && apply_outbound) || (!packet.is_outbound && apply_inbound);
@@ -128,28 +203,101 @@ pub fn lag_packets<'a>(
             passthrough_packets.push(packet);
             continue;
         }
-        storage.push_back(packet);
-    }
+        packet.effects.insert(PacketEffects::LAG);
 
-    // Collect packets that have been lagged long enough
-    // Check packets from the front (oldest first) and release those that have waited long enough
-    while let Some(packet_data) = storage.front() {
-        if packet_data.arrival_time.elapsed() < lag {
-            // Since packets are ordered by arrival time, if this one isn't ready,
-            // none of the following ones will be either
-            break;
+        let mut release_at =
+            packet.arrival_time + packet_delay(lag, jitter, &mut state.last_jitter, rng);
+
+        if preserve_order {
+            let flow = packet.headers().map(|headers| headers.flow_key());
+            let flow_release = state.flow_release.entry(flow).or_insert(release_at);
+
+            release_at = release_at.max(*flow_release);
+            *flow_release = release_at;
         }
 
-        let Some(packet) = storage.pop_front() else {
+        // Packets with the same release time stay in arrival order
+        let index = state.queue.partition_point(|held| held.release_at <= release_at);
+        state.queue.insert(index, LaggedPacket { packet, release_at });
+    }
+
+    // Release packets from the front of the queue once their lag has elapsed
+    while state.queue.front().is_some_and(|held| held.release_at <= now) {
+        let Some(held) = state.queue.pop_front() else {
             break;
         };
 
-        passthrough_packets.push(packet);
+        stats.record_delay(now.saturating_duration_since(held.packet.arrival_time));
+        passthrough_packets.push(held.packet);
     }
 
+    // Forget flows that no longer have packets waiting
+    state.flow_release.retain(|_, release_at| *release_at > now);
+
     // Put all packets (passthrough + released) back into the output
     packets.extend(passthrough_packets);
-    stats.lagged_package_count(storage.len());
+    stats.lagged_package_count(state.queue.len());
+
+}
+
+/// Returns the lag of one packet: `lag` plus a jitter drawn from `jitter`.
+///
+/// The jitter value is blended with the previous one by `jitter.correlation`,
+/// the same way netem correlates its delays. The result is never negative.
+fn packet_delay(
+    lag: Duration,
+    jitter: &Jitter,
+    last_jitter: &mut f64,
+    rng: &mut impl Rng,
+) -> Duration {
+
+    if jitter.amount.is_zero() {
+        return lag;
+    }
+
+    let fresh = sample_jitter(jitter.distribution, rng);
+    let value = jitter
+        .correlation
+        .mul_add(*last_jitter, (1.0 - jitter.correlation) * fresh);
+
+    *last_jitter = value;
+
+    let delay = jitter.amount.as_secs_f64().mul_add(value, lag.as_secs_f64());
+
+    Duration::from_secs_f64(delay.max(0.0))
+
+}
+
+/// Draws a jitter value with zero mean, where 1.0 stands for the full jitter amount.
+fn sample_jitter(distribution: JitterDistribution, rng: &mut impl Rng) -> f64 {
+    match distribution {
+        JitterDistribution::Uniform => rng.random_range(-1.0..=1.0),
+        JitterDistribution::Normal => standard_normal(rng),
+        JitterDistribution::Pareto => standard_pareto(rng),
+        JitterDistribution::ParetoNormal => {
+            0.25f64.mul_add(standard_normal(rng), 0.75 * standard_pareto(rng))
+        }
+    }
+}
+
+/// Draws from the standard normal distribution using the Box-Muller transform.
+fn standard_normal(rng: &mut impl Rng) -> f64 {
+
+    // Keep the first sample in (0, 1] so the logarithm stays finite
+    let radius = (-2.0 * (1.0 - rng.random::<f64>()).ln()).sqrt();
+    let angle = std::f64::consts::TAU * rng.random::<f64>();
+
+    radius * angle.cos()
+
+}
+
+/// Draws from a Pareto distribution rescaled to zero mean and unit standard deviation.
+fn standard_pareto(rng: &mut impl Rng) -> f64 {
+
+    // Pareto II (Lomax) with shape 3 has mean 1/2 and standard deviation sqrt(3)/2
+    let value = (1.0 - rng.random::<f64>()).powf(-1.0 / PARETO_SHAPE) - 1.0;
+
+    (value - 0.5) / (3.0f64.sqrt() / 2.0)
 
 }
 
@@ -157,106 +305,293 @@ pub fn lag_packets<'a>(
 mod tests {
     use super::*;
     use crate::network::modules::stats::lag_stats::LagStats;
-    use std::time::{Duration, Instant};
-    use windivert::layer::NetworkLayer;
-    use windivert::packet::WinDivertPacket;
+    use crate::network::types::clock::{Clock, ManualClock};
 
     #[test]
     fn test_lag_packets_immediate_release_after_lag() {
 
-        unsafe {
-            // Create test packet with an arrival time in the past
-            let mut old_packet =
-                PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]));
+        // Create test packet with an arrival time in the past
+        let mut old_packet = PacketData::from(vec![1, 2, 3]);
 
-            // Manually set arrival time to be in the past by enough to bypass lag
-            let now = Instant::now();
-            let past = now - Duration::from_millis(200);
+        // Let 200ms pass on the clock after the packet arrived, enough to bypass lag
+        let clock = ManualClock::new();
 
-            std::ptr::write(&mut old_packet.arrival_time as *mut Instant, past);
+        old_packet.arrival_time = clock.now();
+        clock.advance(Duration::from_millis(200));
 
-            let mut packets = vec![old_packet];
-            let mut storage = VecDeque::new();
-            let mut stats = LagStats::new();
+        let mut packets = vec![old_packet];
+        let mut state = LagState::default();
+        let mut stats = LagStats::new();
 
-            // Lag of 100ms with 100% probability (should be immediately released because arrival was 200ms ago)
-            lag_packets(
-                &mut packets,
-                &mut storage,
-                Duration::from_millis(100),
-                Probability::new(1.0).unwrap(),
-                true, // apply_inbound
-                true, // apply_outbound
-                &mut stats,
-            );
+        // Lag of 100ms with 100% probability (should be immediately released because arrival was 200ms ago)
+        lag_packets(
+            &mut packets,
+            &mut state,
+            Duration::from_millis(100),
+            &Jitter::default(),
+            false,
+            Probability::new(1.0).unwrap(),
+            true, // apply_inbound
+            true, // apply_outbound
+            clock.now(),
+            &mut rand::rng(),
+            &mut stats,
+        );
 
-            // Packet should have passed through immediately (it was already lagged 200ms)
-            assert_eq!(packets.len(), 1);
-            assert_eq!(storage.len(), 0);
-            assert_eq!(stats.current_lagged(), 0);
-        }
+        // Packet should have passed through immediately (it was already lagged 200ms)
+        assert_eq!(packets.len(), 1);
+        assert_eq!(state.len(), 0);
+        assert_eq!(stats.current_lagged(), 0);
 
     }
 
     #[test]
     fn test_lag_packets_held_until_lag_elapsed() {
 
-        unsafe {
-            // Create a new packet (will have recent arrival time)
-            let packet = PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]));
+        let clock = ManualClock::new();
+        let mut packet = PacketData::from(vec![1, 2, 3]);
+
+        packet.arrival_time = clock.now();
 
-            let mut packets = vec![packet];
-            let mut storage = VecDeque::new();
-            let mut stats = LagStats::new();
+        let mut packets = vec![packet];
+        let mut state = LagState::default();
+        let mut stats = LagStats::new();
 
-            // Apply a long lag with 100% probability (ensuring the packet will be held)
+        let mut run = |packets: &mut Vec<PacketData>, now| {
             lag_packets(
-                &mut packets,
-                &mut storage,
+                packets,
+                &mut state,
                 Duration::from_millis(1000),
+                &Jitter::default(),
+                false,
                 Probability::new(1.0).unwrap(),
                 true, // apply_inbound
                 true, // apply_outbound
+                now,
+                &mut rand::rng(),
                 &mut stats,
             );
+        };
 
-            // ALL packets should be held in storage with 100% probability
-            assert_eq!(packets.len(), 0);
-            assert_eq!(storage.len(), 1);
-            assert_eq!(stats.current_lagged(), 1);
-        }
+        // Apply a long lag with 100% probability (ensuring the packet will be held)
+        run(&mut packets, clock.now());
+        assert_eq!(packets.len(), 0);
+
+        // Still held just before the lag elapses
+        clock.advance(Duration::from_millis(999));
+        run(&mut packets, clock.now());
+        assert_eq!(packets.len(), 0);
+
+        // Released once the full lag has passed
+        clock.advance(Duration::from_millis(1));
+        run(&mut packets, clock.now());
+        assert_eq!(packets.len(), 1);
+        assert_eq!(state.len(), 0);
+        assert_eq!(stats.current_lagged(), 0);
 
     }
 
     #[test]
     fn test_all_packets_lagged_with_100_percent() {
 
-        unsafe {
-            // Create multiple packets
-            let packet1 = PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]));
-            let packet2 = PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![4, 5, 6]));
-            let packet3 = PacketData::from(WinDivertPacket::<NetworkLayer>::new(vec![7, 8, 9]));
+        // Create multiple packets
+        let packet1 = PacketData::from(vec![1, 2, 3]);
+        let packet2 = PacketData::from(vec![4, 5, 6]);
+        let packet3 = PacketData::from(vec![7, 8, 9]);
+
+        let mut packets = vec![packet1, packet2, packet3];
+        let mut state = LagState::default();
+        let mut stats = LagStats::new();
 
-            let mut packets = vec![packet1, packet2, packet3];
-            let mut storage = VecDeque::new();
-            let mut stats = LagStats::new();
+        // Apply lag with 100% probability - ALL packets should be lagged
+        lag_packets(
+            &mut packets,
+            &mut state,
+            Duration::from_millis(1000),
+            &Jitter::default(),
+            false,
+            Probability::new(1.0).unwrap(),
+            true, // apply_inbound
+            true, // apply_outbound
+            Instant::now(),
+            &mut rand::rng(),
+            &mut stats,
+        );
 
-            // Apply lag with 100% probability - ALL packets should be lagged
+        // ALL packets should be in storage, none passed through
+        assert_eq!(packets.len(), 0);
+        assert_eq!(state.len(), 3);
+        assert_eq!(stats.current_lagged(), 3);
+
+    }
+
+    /// Samples the lag of `count` packets with the given jitter, in milliseconds.
+    fn sample_delays(jitter: &Jitter, count: usize) -> Vec<f64> {
+
+        let mut rng = crate::network::types::rng::module_rng(Some(5), "lag");
+        let mut last_jitter = 0.0;
+
+        (0..count)
+            .map(|_| {
+                packet_delay(Duration::from_millis(100), jitter, &mut last_jitter, &mut rng)
+                    .as_secs_f64()
+                    * 1000.0
+            })
+            .collect()
+
+    }
+
+    fn mean_and_deviation(values: &[f64]) -> (f64, f64) {
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance =
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+
+        (mean, variance.sqrt())
+
+    }
+
+    /// Lags 100 packets arriving together and returns their payloads in release order.
+    fn release_order(jitter: &Jitter, preserve_order: bool, stats: &mut LagStats) -> Vec<u8> {
+
+        let clock = ManualClock::new();
+        let mut rng = crate::network::types::rng::module_rng(Some(11), "lag");
+        let mut state = LagState::default();
+        let mut released = Vec::new();
+
+        let mut packets: Vec<PacketData> = (0..100u8)
+            .map(|i| {
+                let mut packet = PacketData::from(vec![i]);
+                packet.arrival_time = clock.now();
+                packet
+            })
+            .collect();
+
+        for _ in 0..200 {
             lag_packets(
                 &mut packets,
-                &mut storage,
-                Duration::from_millis(1000),
+                &mut state,
+                Duration::from_millis(100),
+                jitter,
+                preserve_order,
                 Probability::new(1.0).unwrap(),
-                true, // apply_inbound
-                true, // apply_outbound
-                &mut stats,
+                true,
+                true,
+                clock.now(),
+                &mut rng,
+                stats,
             );
-
-            // ALL packets should be in storage, none passed through
-            assert_eq!(packets.len(), 0);
-            assert_eq!(storage.len(), 3);
-            assert_eq!(stats.current_lagged(), 3);
+            released.extend(std::mem::take(&mut packets).into_iter().map(|p| p.data[0]));
+            clock.advance(Duration::from_millis(1));
         }
 
+        assert!(state.is_empty());
+        assert!(state.flow_release.is_empty());
+        released
+
+    }
+
+    #[test]
+    fn test_uniform_jitter_stays_in_range() {
+
+        let jitter = Jitter {
+            amount: Duration::from_millis(20),
+            ..Jitter::default()
+        };
+
+        let delays = sample_delays(&jitter, 10_000);
+
+        assert!(delays.iter().all(|&d| (80.0..=120.0).contains(&d)));
+        assert!((mean_and_deviation(&delays).0 - 100.0).abs() < 1.0);
+
+    }
+
+    #[test]
+    fn test_normal_jitter_matches_deviation() {
+
+        let jitter = Jitter {
+            amount: Duration::from_millis(10),
+            distribution: JitterDistribution::Normal,
+            correlation: 0.0,
+        };
+
+        let (mean, deviation) = mean_and_deviation(&sample_delays(&jitter, 20_000));
+
+        assert!((mean - 100.0).abs() < 0.5);
+        assert!((deviation - 10.0).abs() < 0.5);
+
+    }
+
+    #[test]
+    fn test_pareto_jitter_has_long_tail() {
+
+        let jitter = Jitter {
+            amount: Duration::from_millis(10),
+            distribution: JitterDistribution::Pareto,
+            correlation: 0.0,
+        };
+
+        let delays = sample_delays(&jitter, 20_000);
+        let (mean, _) = mean_and_deviation(&delays);
+        let shortest = delays.iter().copied().fold(f64::MAX, f64::min);
+        let longest = delays.iter().copied().fold(0.0, f64::max);
+
+        // Packets are rarely much early, but some are very late
+        assert!((mean - 100.0).abs() < 1.0);
+        assert!(shortest > 94.0);
+        assert!(longest > 150.0);
+
+    }
+
+    #[test]
+    fn test_correlated_jitter_changes_slowly() {
+
+        let mean_step = |correlation| {
+            let jitter = Jitter {
+                amount: Duration::from_millis(20),
+                distribution: JitterDistribution::Uniform,
+                correlation,
+            };
+            let delays = sample_delays(&jitter, 5_000);
+
+            delays.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / delays.len() as f64
+        };
+
+        assert!(mean_step(0.9) < mean_step(0.0) / 3.0);
+
+    }
+
+    #[test]
+    fn test_jitter_reorders_packets() {
+
+        let jitter = Jitter {
+            amount: Duration::from_millis(50),
+            ..Jitter::default()
+        };
+
+        let released = release_order(&jitter, false, &mut LagStats::new());
+
+        assert_eq!(released.len(), 100);
+        assert_ne!(released, (0..100u8).collect::<Vec<_>>());
+
+    }
+
+    #[test]
+    fn test_preserve_order_within_flow() {
+
+        let jitter = Jitter {
+            amount: Duration::from_millis(50),
+            ..Jitter::default()
+        };
+        let mut stats = LagStats::new();
+
+        let released = release_order(&jitter, true, &mut stats);
+
+        assert_eq!(released, (0..100u8).collect::<Vec<_>>());
+
+        // Realized delays are recorded in the histogram
+        assert_eq!(stats.delay_histogram().values().sum::<usize>(), 100);
+        assert!(stats.delay_histogram().keys().all(|&bucket| (50..=150).contains(&bucket)));
+
     }
 }
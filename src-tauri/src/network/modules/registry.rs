@@ -18,9 +18,9 @@
 //!     name: "jitter",
 //!     display_name: "Packet Jitter",
 //!     get_options: |s| s.jitter.as_ref(),
-//!     process: |packets, settings, state, stats, effect_start, has_packets| {
+//!     process: |packets, settings, state, stats, effect_start, rng, clock, has_packets| {
 //!         process_module(&JitterModule, settings.jitter.as_ref(), packets,
-//!                        &mut state.jitter, effect_start, stats, has_packets)
+//!                        &mut state.jitter, effect_start, rng, clock, stats, has_packets)
 //!     },
 //! });
 //! ```
@@ -34,10 +34,11 @@ use crate::network::modules::{
     ReorderModule, ThrottleModule,
 };
 use crate::network::processing::module_state::ModuleProcessingState;
+use crate::network::types::clock::Clock;
+use crate::network::types::rng::ModuleRng;
 use crate::settings::Settings;
 use crate::utils::is_effect_active;
 use log::info;
-use std::collections::VecDeque;
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
@@ -153,6 +154,34 @@ pub fn get_enabled_modules(settings: &Settings) -> Vec<&'static str> {
 
 }
 
+/// Checks if a specific module has separate options for a direction.
+pub fn is_module_asymmetric(settings: &Settings, name: &str) -> bool {
+
+    match name {
+        "drop" => settings.drop.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "lag" => settings.lag.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "throttle" => settings.throttle.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "reorder" => settings.reorder.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "corruption" => settings.corruption.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "duplicate" => settings.duplicate.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "bandwidth" => settings.bandwidth.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        "burst" => settings.burst.as_ref().is_some_and(ModuleOptions::is_asymmetric),
+        _ => false,
+    }
+
+}
+
+/// Returns the names of the modules with separate options for a direction.
+pub fn asymmetric_modules(settings: &Settings) -> Vec<&'static str> {
+
+    MODULES
+        .iter()
+        .filter(|m| is_module_asymmetric(settings, m.name))
+        .map(|m| m.name)
+        .collect()
+
+}
+
 /// Generic module processor that handles common logic.
 ///
 /// This function wraps the module-specific processing with:
@@ -160,13 +189,23 @@ pub fn get_enabled_modules(settings: &Settings) -> Vec<&'static str> {
 /// - Duration-based auto-disable
 /// - Skip conditions
 /// - Effect start time reset
+/// - Separate options for each direction
+///
+/// When the options are asymmetric, outbound and inbound packets are processed
+/// apart with the options of their direction. Inbound packets then use
+/// `inbound_state`, so buffers and rate limits are kept per direction.
+/// Packets the module drops are moved to `dropped`.
 pub fn process_module<M>(
     module: &M,
     options: Option<&M::Options>,
-    packets: &mut Vec<PacketData<'_>>,
+    packets: &mut Vec<PacketData>,
     state: &mut M::State,
+    inbound_state: Option<&mut M::State>,
     effect_start: &mut Instant,
+    rng: &mut ModuleRng,
+    clock: &dyn Clock,
     statistics: &Arc<RwLock<PacketProcessingStatistics>>,
+    dropped: &mut Vec<PacketData>,
     has_packets: bool,
 ) -> Result<()>
 where
@@ -181,48 +220,114 @@ where
     }
 
     let duration = module.get_duration_ms(opts);
+    let now = clock.now();
 
-    if duration > 0 && !is_effect_active(duration, *effect_start) {
+    if duration > 0 && !is_effect_active(duration, *effect_start, now) {
         return Ok(());
     }
 
-    if module.should_skip(opts) {
-        return Ok(());
-    }
-
-    if has_packets && *effect_start == Instant::now() {
-        *effect_start = Instant::now();
+    if has_packets && *effect_start == now {
+        *effect_start = now;
     }
 
     let mut ctx = ModuleContext {
         statistics,
         has_packets,
         effect_start,
+        rng,
+        clock,
+        dropped,
     };
 
-    module.process(packets, opts, state, &mut ctx)
+    if !opts.is_asymmetric() {
+        return process_direction(module, packets, opts, state, &mut ctx);
+    }
+
+    let (mut outbound, mut inbound): (Vec<_>, Vec<_>) =
+        packets.drain(..).partition(|packet| packet.is_outbound);
+
+    ctx.has_packets = !outbound.is_empty();
+    process_direction(module, &mut outbound, &opts.for_direction(true), state, &mut ctx)?;
+
+    ctx.has_packets = !inbound.is_empty();
+    let inbound_state = inbound_state.unwrap_or(state);
+    process_direction(module, &mut inbound, &opts.for_direction(false), inbound_state, &mut ctx)?;
+
+    packets.append(&mut outbound);
+    packets.append(&mut inbound);
+
+    Ok(())
+}
+
+/// Runs a module over `packets` unless its options say to skip them.
+fn process_direction<M>(
+    module: &M,
+    packets: &mut Vec<PacketData>,
+    options: &M::Options,
+    state: &mut M::State,
+    ctx: &mut ModuleContext,
+) -> Result<()>
+where
+    M: PacketModule,
+{
+    if module.should_skip(options) {
+        return Ok(());
+    }
+
+    module.process(packets, options, state, ctx)
 }
 
 /// Process all registered modules in order.
 ///
 /// This is the main entry point that replaces the manual `process_module` calls
 /// in processor.rs. It handles all modules automatically based on the registry.
+/// The caller is responsible for seeding `state.rngs`.
 pub fn process_all_modules(
     settings: &Settings,
-    packets: &mut Vec<PacketData<'_>>,
+    packets: &mut Vec<PacketData>,
     state: &mut ModuleProcessingState,
     statistics: &Arc<RwLock<PacketProcessingStatistics>>,
 ) -> Result<()> {
 
+    let asymmetric = asymmetric_modules(settings);
+    let mut released = Vec::new();
+
+    if asymmetric != state.asymmetric_modules {
+        // Packets held for inbound under the previous options are sent as they are
+        if asymmetric.is_empty() {
+            if let Some(mut inbound) = state.inbound.take() {
+                released = inbound.take_held_packets();
+            }
+        } else if let Some(inbound) = state.inbound.as_deref_mut() {
+            // Only modules that lost their inbound options give up their inbound state
+            for name in state
+                .asymmetric_modules
+                .iter()
+                .filter(|name| !asymmetric.contains(name))
+            {
+                released.extend(inbound.reset_module(name));
+            }
+        } else {
+            state.inbound = Some(Box::new(state.for_inbound()));
+        }
+        state.asymmetric_modules = asymmetric;
+    }
+
     let has_packets = !packets.is_empty();
 
+    let clock = Arc::clone(&state.clock);
+
     process_module(
         &DropModule,
         settings.drop.as_ref(),
         packets,
-        &mut (),
+        &mut state.drop,
+        state.inbound.as_deref_mut().map(|inbound| &mut inbound.drop),
         &mut state.effect_start_times.drop,
+        &mut state.rngs.drop,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -231,8 +336,12 @@ pub fn process_all_modules(
         settings.lag.as_ref(),
         packets,
         &mut state.lag,
+        state.inbound.as_deref_mut().map(|inbound| &mut inbound.lag),
         &mut state.effect_start_times.lag,
+        &mut state.rngs.lag,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -241,8 +350,12 @@ pub fn process_all_modules(
         settings.throttle.as_ref(),
         packets,
         &mut state.throttle,
+        state.inbound.as_deref_mut().map(|inbound| &mut inbound.throttle),
         &mut state.effect_start_times.throttle,
+        &mut state.rngs.throttle,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -251,8 +364,12 @@ pub fn process_all_modules(
         settings.reorder.as_ref(),
         packets,
         &mut state.reorder,
+        state.inbound.as_deref_mut().map(|inbound| &mut inbound.reorder),
         &mut state.effect_start_times.reorder,
+        &mut state.rngs.reorder,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -261,8 +378,12 @@ pub fn process_all_modules(
         settings.corruption.as_ref(),
         packets,
         &mut (),
+        None,
         &mut state.effect_start_times.corruption,
+        &mut state.rngs.corruption,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -271,8 +392,12 @@ pub fn process_all_modules(
         settings.duplicate.as_ref(),
         packets,
         &mut (),
+        None,
         &mut state.effect_start_times.duplicate,
+        &mut state.rngs.duplicate,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -281,8 +406,12 @@ pub fn process_all_modules(
         settings.bandwidth.as_ref(),
         packets,
         &mut state.bandwidth,
+        state.inbound.as_deref_mut().map(|inbound| &mut inbound.bandwidth),
         &mut state.effect_start_times.bandwidth,
+        &mut state.rngs.bandwidth,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
@@ -297,9 +426,21 @@ pub fn process_all_modules(
             buffer_size, reverse
         );
 
-        let buffer: &mut VecDeque<(PacketData<'_>, Instant)> =
-            unsafe { std::mem::transmute(&mut state.burst.buffer) };
-        flush_buffer(packets, buffer, &mut state.burst.cycle_start, reverse);
+        flush_buffer(
+            packets,
+            &mut state.burst.buffer,
+            &mut state.burst.cycle_start,
+            reverse,
+        );
+
+        if let Some(inbound) = state.inbound.as_deref_mut() {
+            flush_buffer(
+                packets,
+                &mut inbound.burst.buffer,
+                &mut inbound.burst.cycle_start,
+                reverse,
+            );
+        }
     }
     state.burst_was_enabled = burst_enabled;
 
@@ -308,11 +449,17 @@ pub fn process_all_modules(
         settings.burst.as_ref(),
         packets,
         &mut state.burst,
+        state.inbound.as_deref_mut().map(|inbound| &mut inbound.burst),
         &mut state.effect_start_times.burst,
+        &mut state.rngs.burst,
+        clock.as_ref(),
         statistics,
+        &mut state.dropped,
         has_packets,
     )?;
 
+    packets.append(&mut released);
+
     Ok(())
 
 }
@@ -320,6 +467,9 @@ pub fn process_all_modules(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::types::clock::ManualClock;
+    use crate::settings::SettingsBuilder;
+    use std::time::Duration;
 
     #[test]
     fn test_module_count() {
@@ -350,4 +500,74 @@ mod tests {
         assert!(names.contains(&"burst"));
 
     }
+
+    #[test]
+    fn test_asymmetric_lag_uses_the_options_of_each_direction() {
+
+        let clock = Arc::new(ManualClock::new());
+        let mut state = ModuleProcessingState::with_clock(clock.clone());
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+        let settings = SettingsBuilder::new()
+            .lag(100)
+            .with_outbound(|outbound| outbound.lag(10))
+            .build();
+
+        let mut packets = vec![PacketData::new(vec![1], true), PacketData::new(vec![2], false)];
+        for packet in &mut packets {
+            packet.arrival_time = clock.now();
+        }
+        process_all_modules(&settings, &mut packets, &mut state, &statistics).unwrap();
+
+        assert!(packets.is_empty());
+        assert_eq!(state.held_packets(), 2);
+        assert_eq!(state.inbound.as_ref().map(|inbound| inbound.lag.len()), Some(1));
+
+        clock.advance(Duration::from_millis(10));
+        process_all_modules(&settings, &mut packets, &mut state, &statistics).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert!(packets[0].is_outbound);
+
+        // Going back to symmetric options sends what the inbound state held
+        packets.clear();
+        let symmetric = SettingsBuilder::new().lag(100).build();
+        process_all_modules(&symmetric, &mut packets, &mut state, &statistics).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert!(!packets[0].is_outbound);
+        assert!(state.inbound.is_none());
+
+    }
+
+    #[test]
+    fn test_dropping_one_asymmetric_module_keeps_the_inbound_state_of_others() {
+
+        let clock = Arc::new(ManualClock::new());
+        let mut state = ModuleProcessingState::with_clock(clock.clone());
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+        let settings = SettingsBuilder::new()
+            .lag(100)
+            .drop(0.0)
+            .with_outbound(|outbound| outbound.lag(10).drop(0.0))
+            .build();
+
+        let mut packets = vec![PacketData::new(vec![1], false)];
+        packets[0].arrival_time = clock.now();
+        process_all_modules(&settings, &mut packets, &mut state, &statistics).unwrap();
+        assert_eq!(state.inbound.as_ref().map(|inbound| inbound.lag.len()), Some(1));
+
+        // Drop going back to symmetric options leaves the inbound lag queue alone
+        let lag_only = SettingsBuilder::new()
+            .lag(100)
+            .drop(0.0)
+            .with_outbound(|outbound| outbound.lag(10))
+            .build();
+        process_all_modules(&lag_only, &mut packets, &mut state, &statistics).unwrap();
+        assert!(packets.is_empty());
+        assert_eq!(state.asymmetric_modules, ["lag"]);
+        assert_eq!(state.inbound.as_ref().map(|inbound| inbound.lag.len()), Some(1));
+
+        clock.advance(Duration::from_millis(100));
+        process_all_modules(&lag_only, &mut packets, &mut state, &statistics).unwrap();
+        assert_eq!(packets.len(), 1);
+
+    }
 }
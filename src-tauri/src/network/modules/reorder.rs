@@ -1,12 +1,12 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::reorder_stats::ReorderStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::delayed_packet::DelayedPacket;
 use crate::network::types::probability::Probability;
 use crate::settings::reorder::ReorderOptions;
 use log::{debug, error, warn};
-use rand::{Rng, rng};
+use rand::Rng;
 use std::collections::BinaryHeap;
 use std::time::{Duration, Instant};
 
@@ -18,7 +18,7 @@ use std::time::{Duration, Instant};
 pub struct ReorderModule;
 
 /// State maintained by the reorder module between processing calls.
-pub type ReorderState = BinaryHeap<DelayedPacket<'static>>;
+pub type ReorderState = BinaryHeap<DelayedPacket>;
 
 impl PacketModule for ReorderModule {
     type Options = ReorderOptions;
@@ -36,9 +36,9 @@ impl PacketModule for ReorderModule {
         options.duration_ms
     }
 
-    fn process<'a>(
+    fn process(
         &self,
-        packets: &mut Vec<PacketData<'a>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         state: &mut Self::State,
         ctx: &mut ModuleContext,
@@ -46,17 +46,15 @@ impl PacketModule for ReorderModule {
 
         let mut stats = ctx.write_stats(self.name())?;
 
-        // Safety: We need to transmute lifetimes here because the storage persists
-        // across processing calls.
-        let storage: &mut BinaryHeap<DelayedPacket<'a>> = unsafe { std::mem::transmute(state) };
-
         reorder_packets(
             packets,
-            storage,
+            state,
             options.probability,
             Duration::from_millis(options.max_delay),
             options.inbound,
             options.outbound,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.reorder_stats,
         );
         Ok(())
@@ -78,14 +76,18 @@ impl PacketModule for ReorderModule {
 /// * `max_delay` - Maximum delay duration
 /// * `apply_inbound` - Whether to affect inbound packets
 /// * `apply_outbound` - Whether to affect outbound packets
+/// * `now` - Current time from the module clock
+/// * `rng` - Random number generator for reorder selection and delays
 /// * `stats` - Statistics tracker to update
-pub fn reorder_packets<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    storage: &mut BinaryHeap<DelayedPacket<'a>>,
+pub fn reorder_packets(
+    packets: &mut Vec<PacketData>,
+    storage: &mut BinaryHeap<DelayedPacket>,
     reorder_probability: Probability,
     max_delay: Duration,
     apply_inbound: bool,
     apply_outbound: bool,
+    now: Instant,
+    rng: &mut impl Rng,
     stats: &mut ReorderStats,
 ) {
 
@@ -103,10 +105,9 @@ pub fn reorder_packets<'a>(
     );
 
     let mut skipped_packets = Vec::new();
-    let mut rng = rng();
     let mut delayed_count = 0;
 
-    for packet in packets.drain(..) {
+    for mut packet in packets.drain(..) {
         // Check if this packet's direction should be affected
         let matches_direction =
             (packet.is_outbound && apply_outbound) || (!packet.is_outbound && apply_inbound);
@@ -126,7 +127,10 @@ pub fn reorder_packets<'a>(
         let delay_max = max_delay.as_millis() as u64;
         let delay_millis = rng.random_range(0..delay_max);
         let delay = Duration::from_millis(delay_millis);
-        let delayed_packet = DelayedPacket::new(packet, delay);
+
+        packet.effects.insert(PacketEffects::REORDER);
+
+        let delayed_packet = DelayedPacket::new(packet, delay, now);
 
         storage.push(delayed_packet);
         stats.record(true);
@@ -145,7 +149,6 @@ pub fn reorder_packets<'a>(
 
     packets.append(&mut skipped_packets);
 
-    let now = Instant::now();
     let mut released_count = 0;
     let mut released_packets = Vec::new();
 
@@ -6,6 +6,7 @@ use std::time::{Duration, Instant};
 /// This struct tracks statistics related to bandwidth throttling, including:
 /// - The number of packets currently in storage
 /// - The total number of bytes that have been sent
+/// - The packets dropped by the queue and how long released packets waited
 /// - Recent throughput calculated using an exponential weighted moving average (EWMA)
 ///
 /// It provides methods to record traffic and retrieve statistics about recent throughput.
@@ -15,12 +16,16 @@ pub struct BandwidthStats {
     pub(crate) storage_packet_count: usize,
     /// Total number of bytes sent since this stats tracker was created
     pub(crate) total_byte_count: usize,
+    /// Number of packets the queue dropped, because it was full or by its discipline
+    pub(crate) dropped_packet_count: usize,
+    /// How long the last released packet waited in the queue
+    pub(crate) queue_delay: Duration,
     /// EWMA calculator for smoothing throughput measurements
     ewma: Ewma,
     /// Bytes sent since the last EWMA update
     recent_byte_sent: usize,
-    /// Timer used to determine when to update the EWMA
-    recent_timer: Instant,
+    /// Start of the current measurement period, by the module clock
+    recent_timer: Option<Instant>,
     /// Interval at which to update the EWMA
     update_interval: Duration,
 }
@@ -46,9 +51,11 @@ impl BandwidthStats {
         Self {
             storage_packet_count: 0,
             total_byte_count: 0,
+            dropped_packet_count: 0,
+            queue_delay: Duration::ZERO,
             ewma: Ewma::new(alpha),
             recent_byte_sent: 0,
-            recent_timer: Instant::now(),
+            recent_timer: None,
             update_interval: Duration::from_millis(100),
         }
 
@@ -64,17 +71,21 @@ impl BandwidthStats {
     /// # Arguments
     ///
     /// * `bytes_sent` - The number of bytes sent in this operation
-    pub fn record(&mut self, bytes_sent: usize) {
+    /// * `now` - Current time from the module clock
+    pub fn record(&mut self, bytes_sent: usize, now: Instant) {
 
         self.total_byte_count += bytes_sent;
         self.recent_byte_sent += bytes_sent;
 
-        if self.recent_timer.elapsed() >= self.update_interval {
+        // The first record starts the measurement period
+        let started = *self.recent_timer.get_or_insert(now);
+
+        if now.duration_since(started) >= self.update_interval {
             self.ewma.update(
                 (self.recent_byte_sent as f64 / 1024f64) / self.update_interval.as_secs_f64(),
             );
             self.recent_byte_sent = 0;
-            self.recent_timer = Instant::now();
+            self.recent_timer = Some(now);
         }
 
     }
@@ -97,6 +108,33 @@ impl BandwidthStats {
         self.storage_packet_count
     }
 
+    /// Returns the number of packets dropped by the queue
+    ///
+    /// # Returns
+    ///
+    /// The number of packets dropped because the queue was full or by its discipline
+    pub fn dropped_packets(&self) -> usize {
+        self.dropped_packet_count
+    }
+
+    /// Returns the recent throughput of the bandwidth limiter
+    ///
+    /// # Returns
+    ///
+    /// The moving average of the bytes released, in KB/s
+    pub fn recent_throughput(&self) -> f64 {
+        self.ewma.get().unwrap_or(0.0)
+    }
+
+    /// Returns the queueing delay of the last released packet
+    ///
+    /// # Returns
+    ///
+    /// How long the last packet released by the bandwidth limiter waited in its queue
+    pub fn queue_delay(&self) -> Duration {
+        self.queue_delay
+    }
+
     /// Resets all statistics to zero
     ///
     /// This resets the packet count, byte count, and EWMA calculations.
@@ -104,9 +142,11 @@ impl BandwidthStats {
 
         self.storage_packet_count = 0;
         self.total_byte_count = 0;
+        self.dropped_packet_count = 0;
+        self.queue_delay = Duration::ZERO;
         self.recent_byte_sent = 0;
         self.ewma.reset();
-        self.recent_timer = Instant::now();
+        self.recent_timer = None;
 
     }
 }
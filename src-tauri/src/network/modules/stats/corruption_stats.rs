@@ -1,4 +1,3 @@
-use std::ops::Sub;
 use std::time::{Duration, Instant};
 
 /// Statistics for network packet corruptioning operations
@@ -18,8 +17,8 @@ pub struct CorruptionStats {
     pub(crate) corruption_flags: Vec<bool>,
     /// Indicates whether packet checksums are still valid after corruptioning
     pub(crate) checksum_valid: bool,
-    /// When statistics were last updated
-    pub last_update: Instant,
+    /// When statistics were last updated, by the module clock
+    pub last_update: Option<Instant>,
     /// How often statistics should be updated
     pub update_interval: Duration,
 }
@@ -46,7 +45,7 @@ impl CorruptionStats {
             data: vec![],
             corruption_flags: vec![],
             checksum_valid: true,
-            last_update: Instant::now().sub(refresh_interval),
+            last_update: None,
             update_interval: refresh_interval,
         }
 
@@ -57,18 +56,23 @@ impl CorruptionStats {
     /// This method helps control the frequency of statistics updates
     /// to avoid excessive processing on high-traffic networks.
     ///
+    /// # Arguments
+    ///
+    /// * `now` - Current time from the module clock
+    ///
     /// # Returns
     ///
-    /// `true` if enough time has passed since the last update, `false` otherwise
-    pub fn should_update(&mut self) -> bool {
-        self.last_update.elapsed() >= self.update_interval
+    /// `true` if there was no update yet or enough time has passed since the last one
+    pub fn should_update(&mut self, now: Instant) -> bool {
+        self.last_update
+            .map_or(true, |last| now.duration_since(last) >= self.update_interval)
     }
 
     /// Records that statistics have been updated
     ///
     /// Call this method after updating the statistics to reset the update timer.
-    pub fn updated(&mut self) {
-        self.last_update = Instant::now();
+    pub fn updated(&mut self, now: Instant) {
+        self.last_update = Some(now);
     }
 
     /// Returns the raw payload data from the most recently corruptioned packet
@@ -100,13 +104,14 @@ impl CorruptionStats {
 
     /// Resets all statistics
     ///
-    /// Clears the data and corruption flags and resets the checksum status.
+    /// Clears the data and corruption flags and resets the checksum status,
+    /// so the next packet updates the statistics again.
     pub fn reset(&mut self) {
 
         self.data.clear();
         self.corruption_flags.clear();
         self.checksum_valid = true;
-        self.last_update = Instant::now();
+        self.last_update = None;
 
     }
 
@@ -143,15 +148,17 @@ mod tests {
     #[test]
     fn test_should_update() {
 
-        // Create with a refresh interval that's already elapsed
-        let mut stats = CorruptionStats::new(Duration::from_millis(0));
+        let mut stats = CorruptionStats::new(Duration::from_secs(1));
+
+        let now = Instant::now();
 
-        assert!(stats.should_update());
+        // The first packet always updates
+        assert!(stats.should_update(now));
 
         // Update and check again immediately
-        stats.updated();
-        stats.update_interval = Duration::from_secs(1);
-        assert!(!stats.should_update());
+        stats.updated(now);
+        assert!(!stats.should_update(now));
+        assert!(stats.should_update(now + Duration::from_secs(1)));
 
     }
 
@@ -1,4 +1,5 @@
 use crate::network::modules::stats::util::ewma::Ewma;
+use std::collections::BTreeMap;
 
 /// Statistics for tracking packet dropping behavior.
 ///
@@ -10,6 +11,7 @@ use crate::network::modules::stats::util::ewma::Ewma;
 /// * `total_packets` - Total number of packets processed
 /// * `total_dropped` - Total number of packets dropped
 /// * `ewma` - Exponentially weighted moving average for smoothing recent drop rates
+/// * `burst_lengths` - Number of completed bursts of each length
 #[derive(Debug)]
 pub struct DropStats {
     /// Total number of packets processed
@@ -18,6 +20,10 @@ pub struct DropStats {
     pub total_dropped: usize,
     /// EWMA for recent drop rate calculations
     ewma: Ewma,
+    /// Length of the run of consecutive drops still in progress
+    current_burst: usize,
+    /// Completed runs of consecutive drops, keyed by length
+    burst_lengths: BTreeMap<usize, usize>,
 }
 
 impl DropStats {
@@ -43,6 +49,8 @@ impl DropStats {
             total_packets: 0,
             total_dropped: 0,
             ewma: Ewma::new(alpha),
+            current_burst: 0,
+            burst_lengths: BTreeMap::new(),
         }
 
     }
@@ -66,6 +74,10 @@ impl DropStats {
 
         if dropped {
             self.total_dropped += 1;
+            self.current_burst += 1;
+        } else if self.current_burst > 0 {
+            *self.burst_lengths.entry(self.current_burst).or_insert(0) += 1;
+            self.current_burst = 0;
         }
 
         // Update the EWMA with the new drop status (1.0 if dropped, 0.0 if not)
@@ -113,13 +125,49 @@ impl DropStats {
         self.ewma.get().unwrap_or(0.0)
     }
 
+    /// Gets the distribution of burst lengths.
+    ///
+    /// A burst is a run of consecutive dropped packets. It is counted once
+    /// a packet gets through, so a burst still in progress is not included.
+    ///
+    /// # Returns
+    ///
+    /// A map from burst length to the number of bursts of that length.
+    pub fn burst_length_distribution(&self) -> &BTreeMap<usize, usize> {
+        &self.burst_lengths
+    }
+
+    /// Calculates the mean length of completed bursts.
+    ///
+    /// # Returns
+    ///
+    /// The average number of packets per burst, or 0.0 if no burst has completed.
+    pub fn mean_burst_length(&self) -> f64 {
+
+        let (bursts, packets) = self
+            .burst_lengths
+            .iter()
+            .fold((0, 0), |(bursts, packets), (&length, &count)| {
+                (bursts + count, packets + length * count)
+            });
+
+        if bursts == 0 {
+            return 0.0;
+        }
+
+        packets as f64 / bursts as f64
+
+    }
+
     /// Resets all statistics to zero.
     ///
-    /// This clears both the total counters and resets the EWMA.
+    /// This clears the total counters, the burst lengths and resets the EWMA.
     pub fn reset(&mut self) {
 
         self.total_packets = 0;
         self.total_dropped = 0;
+        self.current_burst = 0;
+        self.burst_lengths.clear();
         // Reset the EWMA to its initial state
         self.ewma.reset();
 
@@ -172,6 +220,25 @@ mod tests {
         assert_eq!(stats.total_packets, 0);
         assert_eq!(stats.total_dropped, 0);
         assert_eq!(stats.total_drop_rate(), 0.0);
+        assert!(stats.burst_length_distribution().is_empty());
+
+    }
+
+    #[test]
+    fn test_burst_lengths() {
+
+        let mut stats = DropStats::new(0.5);
+
+        // Bursts of 2, 1 and 2, the last one still in progress
+        for dropped in [true, true, false, true, false, false, true, true, false, true] {
+            stats.record(dropped);
+        }
+
+        let distribution = stats.burst_length_distribution();
+        assert_eq!(distribution.get(&1), Some(&1));
+        assert_eq!(distribution.get(&2), Some(&2));
+        assert_eq!(distribution.len(), 2);
+        assert!((stats.mean_burst_length() - 5.0 / 3.0).abs() < f64::EPSILON);
 
     }
 }
@@ -42,4 +42,19 @@ impl DuplicateStats {
         self.ewma.update(current_duplication_multiplier);
 
     }
+
+    /// Returns the number of packets that reached the duplicate module
+    pub fn incoming_packets(&self) -> usize {
+        self.incoming_packet_count
+    }
+
+    /// Returns the number of packets sent out by the duplicate module
+    pub fn outgoing_packets(&self) -> usize {
+        self.outgoing_packet_count
+    }
+
+    /// Returns the recent average number of packets sent per incoming packet
+    pub fn recent_multiplier(&self) -> f64 {
+        self.ewma.get().unwrap_or(1.0)
+    }
 }
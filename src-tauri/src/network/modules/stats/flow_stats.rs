@@ -0,0 +1,530 @@
+use crate::network::core::{FlowKey, PacketData, PacketHeaders};
+use crate::network::modules::stats::traffic_stats::TrafficCount;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Most flows kept; the flow idle the longest makes room for a new one
+const MAX_FLOWS: usize = 1024;
+
+/// Flows without packets for this long are forgotten
+const FLOW_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Weight of each new sample in the smoothed RTT, as in RFC 6298
+const RTT_WEIGHT: f64 = 0.125;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_ACK: u8 = 0x10;
+
+/// The TCP fields the RTT estimate needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TcpSegment {
+    /// First sequence number of the segment
+    seq: u32,
+    /// Sequence numbers the segment takes up: its payload, SYN and FIN
+    len: u32,
+    /// Acknowledgment number, if the ACK flag is set
+    ack: Option<u32>,
+}
+
+/// A packet as seen by the flow statistics.
+#[derive(Debug, Clone, Copy)]
+pub struct FlowPacket {
+    id: u64,
+    /// Flow from the local to the remote endpoint, whatever the direction
+    key: FlowKey,
+    outbound: bool,
+    size: usize,
+    arrival_time: Instant,
+    /// When the packet was captured or sent
+    seen_at: Instant,
+    tcp: Option<TcpSegment>,
+}
+
+impl FlowPacket {
+    /// Reads the flow of `packet`.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - A captured or sent packet
+    /// * `seen_at` - When the packet was captured or sent
+    ///
+    /// # Returns
+    ///
+    /// * `Option<FlowPacket>` - The flow of the packet, or None if its headers are incomplete
+    pub fn parse(packet: &PacketData, seen_at: Instant) -> Option<Self> {
+
+        let headers = PacketHeaders::parse(&packet.data)?;
+        let key = if packet.is_outbound {
+            headers.flow_key()
+        } else {
+            headers.flow_key().reversed()
+        };
+
+        Some(Self {
+            id: packet.id,
+            key,
+            outbound: packet.is_outbound,
+            size: packet.size(),
+            arrival_time: packet.arrival_time,
+            seen_at,
+            tcp: tcp_segment(&headers, &packet.data),
+        })
+
+    }
+}
+
+/// Reads the sequence space and acknowledgment of a TCP segment.
+fn tcp_segment(headers: &PacketHeaders, data: &[u8]) -> Option<TcpSegment> {
+
+    if !headers.is_tcp() {
+        return None;
+    }
+
+    let tcp = &data[headers.ip_header_len..];
+    let flags = tcp[13];
+    let payload = data.len().saturating_sub(headers.payload_offset());
+    let len = u32::try_from(payload).unwrap_or(u32::MAX)
+        + u32::from(flags & TCP_SYN != 0)
+        + u32::from(flags & TCP_FIN != 0);
+
+    Some(TcpSegment {
+        seq: u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]),
+        len,
+        ack: (flags & TCP_ACK != 0).then(|| u32::from_be_bytes([tcp[8], tcp[9], tcp[10], tcp[11]])),
+    })
+
+}
+
+/// Returns whether sequence number `a` comes before `b`, allowing for wraparound.
+fn seq_before(a: u32, b: u32) -> bool {
+    a.wrapping_sub(b) >= 1 << 31
+}
+
+/// Statistics of one direction of a flow
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FlowDirection {
+    /// Packets captured
+    pub captured: TrafficCount,
+    /// Packets sent after processing, extra copies included
+    pub delivered: TrafficCount,
+    /// Packets that were captured but never sent
+    pub dropped_packets: usize,
+    /// Longest time a packet took from capture to being sent
+    pub max_delay: Duration,
+    total_delay: Duration,
+    delayed_packets: u32,
+}
+
+impl FlowDirection {
+    /// Returns the mean time packets took from capture to being sent.
+    pub fn mean_delay(&self) -> Duration {
+
+        if self.delayed_packets == 0 {
+            return Duration::ZERO;
+        }
+        self.total_delay / self.delayed_packets
+
+    }
+}
+
+/// Statistics of one flow, from the local endpoint's point of view.
+#[derive(Debug, Clone)]
+pub struct Flow {
+    /// Flow from the local to the remote endpoint
+    pub key: FlowKey,
+    /// Packets from the remote endpoint
+    pub inbound: FlowDirection,
+    /// Packets to the remote endpoint
+    pub outbound: FlowDirection,
+    /// When the last packet of the flow was seen
+    pub last_seen: Instant,
+    rtt: Option<Duration>,
+    /// Sequence number after the last new outbound segment
+    next_seq: Option<u32>,
+    /// End of the outbound segment being timed, and when it was sent by the application
+    timed_segment: Option<(u32, Instant)>,
+}
+
+impl Flow {
+    fn new(key: FlowKey, now: Instant) -> Self {
+        Self {
+            key,
+            inbound: FlowDirection::default(),
+            outbound: FlowDirection::default(),
+            last_seen: now,
+            rtt: None,
+            next_seq: None,
+            timed_segment: None,
+        }
+    }
+
+    /// Returns the smoothed round-trip time of a TCP flow.
+    ///
+    /// Measured from when the local application sent a segment to when the
+    /// acknowledgment reached it, so the impairments of both directions count.
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt
+    }
+
+    /// Returns the bytes captured in both directions.
+    pub fn total_bytes(&self) -> usize {
+        self.inbound.captured.bytes + self.outbound.captured.bytes
+    }
+
+    fn direction_mut(&mut self, outbound: bool) -> &mut FlowDirection {
+        if outbound {
+            &mut self.outbound
+        } else {
+            &mut self.inbound
+        }
+    }
+
+    /// Times a new outbound segment unless one is being timed already.
+    ///
+    /// A retransmission stops the timing, as its acknowledgment cannot be told
+    /// apart from that of the original (Karn's algorithm).
+    fn time_segment(&mut self, segment: TcpSegment, sent_at: Instant) {
+
+        if segment.len == 0 {
+            return;
+        }
+
+        if self
+            .next_seq
+            .is_some_and(|next| seq_before(segment.seq, next))
+        {
+            self.timed_segment = None;
+            return;
+        }
+
+        let end = segment.seq.wrapping_add(segment.len);
+
+        self.next_seq = Some(end);
+        self.timed_segment.get_or_insert((end, sent_at));
+
+    }
+
+    /// Takes an RTT sample if `ack` covers the timed segment.
+    fn sample_rtt(&mut self, ack: u32, received_at: Instant) {
+
+        let Some((end, sent_at)) = self.timed_segment else {
+            return;
+        };
+
+        if seq_before(ack, end) {
+            return;
+        }
+
+        let sample = received_at.saturating_duration_since(sent_at);
+
+        self.rtt = Some(self.rtt.map_or(sample, |rtt| {
+            rtt.mul_f64(1.0 - RTT_WEIGHT) + sample.mul_f64(RTT_WEIGHT)
+        }));
+        self.timed_segment = None;
+
+    }
+}
+
+/// Statistics per flow
+///
+/// Keyed by the 5-tuple seen from the local endpoint, so both directions of
+/// a connection share one entry. A packet counts as dropped once it was
+/// captured, has not been sent and no module holds it any more.
+#[derive(Debug, Default)]
+pub struct FlowStats {
+    flows: HashMap<FlowKey, Flow>,
+    /// Captured packets not sent yet, with their flow and direction
+    pending: HashMap<u64, (FlowKey, bool)>,
+}
+
+impl FlowStats {
+    /// Creates a new `FlowStats` instance without flows
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one processing cycle
+    ///
+    /// # Arguments
+    ///
+    /// * `captured` - Packets received from the capture this cycle
+    /// * `delivered` - Packets sent back out this cycle
+    /// * `held_packets` - Packets held by modules at the end of the cycle
+    /// * `held_ids` - Returns the ids of the held packets; only called when packets went missing
+    /// * `now` - Current time
+    pub fn record_cycle(
+        &mut self,
+        captured: &[FlowPacket],
+        delivered: &[FlowPacket],
+        held_packets: usize,
+        held_ids: impl FnOnce() -> HashSet<u64>,
+        now: Instant,
+    ) {
+
+        for packet in captured {
+            self.record_captured(packet);
+        }
+
+        for packet in delivered {
+            self.record_delivered(packet);
+        }
+
+        // Every pending packet is held unless some went missing
+        if self.pending.len() > held_packets {
+            let held = held_ids();
+            let flows = &mut self.flows;
+
+            self.pending.retain(|id, (key, outbound)| {
+                if held.contains(id) {
+                    return true;
+                }
+
+                if let Some(flow) = flows.get_mut(key) {
+                    flow.direction_mut(*outbound).dropped_packets += 1;
+                }
+                false
+            });
+        }
+
+        self.flows
+            .retain(|_, flow| now.saturating_duration_since(flow.last_seen) < FLOW_IDLE_TIMEOUT);
+
+    }
+
+    /// Returns the flows with the most bytes captured, most first.
+    pub fn top(&self, limit: usize) -> Vec<&Flow> {
+
+        let mut flows: Vec<&Flow> = self.flows.values().collect();
+
+        flows.sort_by_key(|flow| Reverse(flow.total_bytes()));
+        flows.truncate(limit);
+        flows
+
+    }
+
+    /// Returns the number of flows.
+    pub fn len(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Returns whether no flows were seen.
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty()
+    }
+
+    fn record_captured(&mut self, packet: &FlowPacket) {
+
+        if !self.flows.contains_key(&packet.key) && self.flows.len() >= MAX_FLOWS {
+            self.evict_idlest();
+        }
+
+        let flow = self
+            .flows
+            .entry(packet.key)
+            .or_insert_with(|| Flow::new(packet.key, packet.seen_at));
+        let direction = flow.direction_mut(packet.outbound);
+
+        direction.captured.packets += 1;
+        direction.captured.bytes += packet.size;
+        flow.last_seen = flow.last_seen.max(packet.seen_at);
+
+        if let (Some(segment), true) = (packet.tcp, packet.outbound) {
+            flow.time_segment(segment, packet.seen_at);
+        }
+
+        self.pending
+            .insert(packet.id, (packet.key, packet.outbound));
+
+    }
+
+    fn record_delivered(&mut self, packet: &FlowPacket) {
+
+        let was_pending = self.pending.remove(&packet.id).is_some();
+
+        let Some(flow) = self.flows.get_mut(&packet.key) else {
+            return;
+        };
+        let direction = flow.direction_mut(packet.outbound);
+
+        direction.delivered.packets += 1;
+        direction.delivered.bytes += packet.size;
+
+        // Extra copies share the id of the original and were not delayed on their own
+        if was_pending {
+            let delay = packet
+                .seen_at
+                .saturating_duration_since(packet.arrival_time);
+
+            direction.total_delay += delay;
+            direction.delayed_packets += 1;
+            direction.max_delay = direction.max_delay.max(delay);
+        }
+
+        flow.last_seen = flow.last_seen.max(packet.seen_at);
+
+        if let (Some(ack), false) = (packet.tcp.and_then(|segment| segment.ack), packet.outbound) {
+            flow.sample_rtt(ack, packet.seen_at);
+        }
+
+    }
+
+    fn evict_idlest(&mut self) {
+
+        let idlest = self
+            .flows
+            .values()
+            .min_by_key(|flow| flow.last_seen)
+            .map(|flow| flow.key);
+
+        if let Some(key) = idlest {
+            self.flows.remove(&key);
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+
+    const LOCAL: ([u8; 4], u16) = ([192, 168, 1, 10], 5000);
+    const REMOTE: ([u8; 4], u16) = ([10, 0, 0, 1], 443);
+
+    /// Builds an IPv4/TCP segment between `LOCAL` and `REMOTE`.
+    fn tcp_packet(seq: u32, ack: Option<u32>, payload: usize, outbound: bool) -> PacketData {
+
+        let mut data = vec![0u8; 40 + payload];
+        let (src, dst) = if outbound {
+            (LOCAL, REMOTE)
+        } else {
+            (REMOTE, LOCAL)
+        };
+
+        data[0] = 0x45;
+        data[9] = 6;
+        data[12..16].copy_from_slice(&src.0);
+        data[16..20].copy_from_slice(&dst.0);
+        data[20..22].copy_from_slice(&src.1.to_be_bytes());
+        data[22..24].copy_from_slice(&dst.1.to_be_bytes());
+        data[24..28].copy_from_slice(&seq.to_be_bytes());
+        data[28..32].copy_from_slice(&ack.unwrap_or(0).to_be_bytes());
+        data[32] = 5 << 4;
+        data[33] = if ack.is_some() { TCP_ACK } else { 0 };
+
+        PacketData::new(data, outbound)
+
+    }
+
+    /// Sends a segment and receives its acknowledgment `rtt_ms` later.
+    fn exchange(stats: &mut FlowStats, start: Instant, seq: u32, ack: u32, rtt_ms: u64) {
+
+        let acked_at = start + Duration::from_millis(rtt_ms);
+        let data = FlowPacket::parse(&tcp_packet(seq, None, 100, true), start).unwrap();
+        let reply = FlowPacket::parse(&tcp_packet(1, Some(ack), 0, false), acked_at).unwrap();
+
+        stats.record_cycle(&[data], &[data], 0, HashSet::new, start);
+        stats.record_cycle(&[reply], &[reply], 0, HashSet::new, acked_at);
+
+    }
+
+    #[test]
+    fn test_both_directions_share_a_flow() {
+
+        let now = Instant::now();
+        let outbound = PacketData::new(udp_ipv4_packet(5000, 27015, &[0; 10]), true);
+        let mut inbound_data = udp_ipv4_packet(27015, 5000, &[0; 50]);
+
+        inbound_data[12..16].copy_from_slice(&[10, 0, 0, 1]);
+        inbound_data[16..20].copy_from_slice(&[192, 168, 1, 10]);
+
+        let inbound = PacketData::new(inbound_data, false);
+        let packets: Vec<FlowPacket> = [&outbound, &inbound]
+            .iter()
+            .filter_map(|packet| FlowPacket::parse(packet, now))
+            .collect();
+
+        let mut stats = FlowStats::new();
+        stats.record_cycle(&packets, &packets, 0, HashSet::new, now);
+
+        assert_eq!(stats.len(), 1);
+
+        let flow = stats.top(10)[0];
+
+        assert_eq!(flow.key.src_port, 5000);
+        assert_eq!(flow.outbound.captured.bytes, 38);
+        assert_eq!(flow.inbound.delivered.bytes, 78);
+        assert_eq!(flow.total_bytes(), 116);
+
+    }
+
+    #[test]
+    fn test_missing_packets_count_as_dropped_unless_held() {
+
+        let start = Instant::now();
+        let packets: Vec<PacketData> = (0..3)
+            .map(|_| PacketData::new(udp_ipv4_packet(5000, 27015, &[]), true))
+            .collect();
+        let captured: Vec<FlowPacket> = packets
+            .iter()
+            .filter_map(|packet| FlowPacket::parse(packet, start))
+            .collect();
+        let held_id = packets[1].id;
+
+        let mut stats = FlowStats::new();
+
+        // One packet is sent, one held by a module and one dropped
+        stats.record_cycle(
+            &captured,
+            &captured[..1],
+            1,
+            || HashSet::from([held_id]),
+            start,
+        );
+
+        let flow = stats.top(1)[0].clone();
+
+        assert_eq!(flow.outbound.dropped_packets, 1);
+        assert_eq!(flow.outbound.delivered.packets, 1);
+
+        // The held packet goes out 100 ms later
+        let later = start + Duration::from_millis(100);
+        let released = FlowPacket::parse(&packets[1], later).unwrap();
+
+        stats.record_cycle(&[], &[released], 0, HashSet::new, later);
+
+        let flow = stats.top(1)[0];
+
+        assert_eq!(flow.outbound.dropped_packets, 1);
+        assert_eq!(
+            flow.outbound.max_delay,
+            released.seen_at - released.arrival_time
+        );
+
+        // Idle flows are forgotten
+        stats.record_cycle(&[], &[], 0, HashSet::new, later + FLOW_IDLE_TIMEOUT);
+        assert!(stats.is_empty());
+
+    }
+
+    #[test]
+    fn test_rtt_is_taken_from_acknowledged_segments() {
+
+        let start = Instant::now();
+        let mut stats = FlowStats::new();
+
+        exchange(&mut stats, start, 1000, 1100, 80);
+        assert_eq!(stats.top(1)[0].rtt(), Some(Duration::from_millis(80)));
+
+        // A retransmission is not timed
+        exchange(&mut stats, start + Duration::from_secs(1), 1000, 1100, 400);
+        assert_eq!(stats.top(1)[0].rtt(), Some(Duration::from_millis(80)));
+
+        // Later samples are smoothed in
+        exchange(&mut stats, start + Duration::from_secs(2), 1100, 1200, 160);
+        assert_eq!(stats.top(1)[0].rtt(), Some(Duration::from_millis(90)));
+
+    }
+}
@@ -1,3 +1,9 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Width of a bucket in the delay histogram, in milliseconds.
+pub const DELAY_BUCKET_MS: u64 = 5;
+
 /// Statistics for tracking packet lag behavior.
 ///
 /// This struct maintains statistics about packets currently being lagged
@@ -10,6 +16,8 @@ pub struct LagStats {
     max_lagged: usize,
     /// Total number of packets that have been processed by the lag module
     total_processed: usize,
+    /// Number of released packets per delay bucket, keyed by bucket start in ms
+    delay_histogram: BTreeMap<u64, usize>,
 }
 
 impl Default for LagStats {
@@ -37,6 +45,7 @@ impl LagStats {
             lagged_package_count: 0,
             max_lagged: 0,
             total_processed: 0,
+            delay_histogram: BTreeMap::new(),
         }
 
     }
@@ -95,11 +104,36 @@ impl LagStats {
         self.total_processed
     }
 
+    /// Records the time a released packet actually spent in the lag queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - Time between the packet's arrival and its release
+    pub fn record_delay(&mut self, delay: Duration) {
+
+        let millis = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX);
+        let bucket = millis - millis % DELAY_BUCKET_MS;
+
+        *self.delay_histogram.entry(bucket).or_insert(0) += 1;
+
+    }
+
+    /// Returns the histogram of realized delays.
+    ///
+    /// # Returns
+    ///
+    /// A map from bucket start in milliseconds to the number of packets whose
+    /// delay fell in `[start, start + DELAY_BUCKET_MS)`.
+    pub fn delay_histogram(&self) -> &BTreeMap<u64, usize> {
+        &self.delay_histogram
+    }
+
     /// Resets all statistics to zero.
     pub fn reset(&mut self) {
         self.lagged_package_count = 0;
         self.max_lagged = 0;
         self.total_processed = 0;
+        self.delay_histogram.clear();
     }
 }
 
@@ -150,6 +184,7 @@ mod tests {
 
         // Add some data
         stats.lagged_package_count(5);
+        stats.record_delay(Duration::from_millis(100));
 
         // Reset
         stats.reset();
@@ -158,6 +193,21 @@ mod tests {
         assert_eq!(stats.current_lagged(), 0);
         assert_eq!(stats.max_lagged(), 0);
         assert_eq!(stats.total_processed(), 0);
+        assert!(stats.delay_histogram().is_empty());
+
+    }
+
+    #[test]
+    fn test_delay_histogram_buckets() {
+
+        let mut stats = LagStats::new();
+
+        stats.record_delay(Duration::from_millis(100));
+        stats.record_delay(Duration::from_micros(104_900));
+        stats.record_delay(Duration::from_millis(105));
+
+        assert_eq!(stats.delay_histogram().get(&100), Some(&2));
+        assert_eq!(stats.delay_histogram().get(&105), Some(&1));
 
     }
 }
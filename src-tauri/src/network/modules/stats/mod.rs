@@ -3,9 +3,12 @@ use crate::network::modules::stats::burst_stats::BurstStats;
 use crate::network::modules::stats::corruption_stats::CorruptionStats;
 use crate::network::modules::stats::drop_stats::DropStats;
 use crate::network::modules::stats::duplicate_stats::DuplicateStats;
+use crate::network::modules::stats::flow_stats::FlowStats;
 use crate::network::modules::stats::lag_stats::LagStats;
 use crate::network::modules::stats::reorder_stats::ReorderStats;
+use crate::network::modules::stats::tap_stats::TapStats;
 use crate::network::modules::stats::throttle_stats::ThrottleStats;
+use crate::network::modules::stats::traffic_stats::TrafficStats;
 use std::time::Duration;
 
 pub mod bandwidth_stats;
@@ -13,9 +16,12 @@ pub mod burst_stats;
 pub mod corruption_stats;
 pub mod drop_stats;
 pub mod duplicate_stats;
+pub mod flow_stats;
 pub mod lag_stats;
 pub mod reorder_stats;
+pub mod tap_stats;
 pub mod throttle_stats;
+pub mod traffic_stats;
 pub mod util;
 
 /// Statistics collection for all packet processing modules
@@ -40,6 +46,12 @@ pub struct PacketProcessingStatistics {
     pub bandwidth_stats: BandwidthStats,
     /// Statistics for packet bursting
     pub burst_stats: BurstStats,
+    /// Statistics for tapping
+    pub tap_stats: TapStats,
+    /// Statistics for the traffic passing through the processor
+    pub traffic_stats: TrafficStats,
+    /// Statistics per flow
+    pub flow_stats: FlowStats,
 }
 
 impl Default for PacketProcessingStatistics {
@@ -54,6 +66,9 @@ impl Default for PacketProcessingStatistics {
             duplicate_stats: DuplicateStats::new(0.005),
             bandwidth_stats: BandwidthStats::new(0.005),
             burst_stats: BurstStats::new(0.005),
+            tap_stats: TapStats::new(),
+            traffic_stats: TrafficStats::new(),
+            flow_stats: FlowStats::new(),
         }
 
     }
@@ -0,0 +1,39 @@
+use crate::network::processing::tap::TapPhase;
+
+/// Statistics for tapping
+///
+/// Tracks the phase the tap is in and how many taps have started.
+#[derive(Debug, Default)]
+pub struct TapStats {
+    /// Phase of the last processing cycle
+    pub(crate) phase: TapPhase,
+    /// Number of taps started this session
+    pub(crate) taps: usize,
+}
+
+impl TapStats {
+    /// Creates a new `TapStats` instance with tapping off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the phase of a processing cycle, counting each new tap
+    pub fn record(&mut self, phase: TapPhase) {
+
+        if phase == TapPhase::Tapping && self.phase != TapPhase::Tapping {
+            self.taps += 1;
+        }
+        self.phase = phase;
+
+    }
+
+    /// Returns the phase of the last processing cycle
+    pub fn phase(&self) -> TapPhase {
+        self.phase
+    }
+
+    /// Returns the number of taps started this session
+    pub fn taps(&self) -> usize {
+        self.taps
+    }
+}
@@ -0,0 +1,162 @@
+use crate::network::core::PacketData;
+
+/// Packets and bytes counted in one direction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrafficCount {
+    /// Number of packets
+    pub packets: usize,
+    /// Total size of the packets in bytes
+    pub bytes: usize,
+}
+
+/// Packets and bytes counted per direction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DirectionalCount {
+    /// Traffic arriving at this machine
+    pub inbound: TrafficCount,
+    /// Traffic leaving this machine
+    pub outbound: TrafficCount,
+}
+
+impl DirectionalCount {
+    /// Counts `packet` in its direction
+    pub fn add(&mut self, packet: &PacketData) {
+
+        let count = if packet.is_outbound {
+            &mut self.outbound
+        } else {
+            &mut self.inbound
+        };
+
+        count.packets += 1;
+        count.bytes += packet.size();
+
+    }
+
+    /// Adds the counts of `other`
+    pub fn merge(&mut self, other: &Self) {
+
+        self.inbound.packets += other.inbound.packets;
+        self.inbound.bytes += other.inbound.bytes;
+        self.outbound.packets += other.outbound.packets;
+        self.outbound.bytes += other.outbound.bytes;
+
+    }
+}
+
+/// Statistics for the traffic passing through the processor
+///
+/// Counts what was captured and what was delivered after the modules ran,
+/// and how many packets the modules held back. The held packet peak is kept
+/// until it is taken, so a burst between two samples is not missed.
+#[derive(Debug, Default)]
+pub struct TrafficStats {
+    /// Packets taken from the network
+    pub(crate) captured: DirectionalCount,
+    /// Packets sent back out after processing
+    pub(crate) delivered: DirectionalCount,
+    /// Packets held by modules at the end of the last cycle
+    pub(crate) held_packets: usize,
+    /// Most packets held at the end of a cycle since the peak was last taken
+    pub(crate) peak_held_packets: usize,
+}
+
+impl TrafficStats {
+    /// Creates a new `TrafficStats` instance with all counters at zero
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records one processing cycle
+    ///
+    /// # Arguments
+    ///
+    /// * `captured` - Packets received from the capture this cycle
+    /// * `delivered` - Packets sent back out this cycle
+    /// * `held_packets` - Packets held by modules at the end of the cycle
+    pub fn record_cycle(
+        &mut self,
+        captured: &DirectionalCount,
+        delivered: &DirectionalCount,
+        held_packets: usize,
+    ) {
+
+        self.captured.merge(captured);
+        self.delivered.merge(delivered);
+        self.held_packets = held_packets;
+        self.peak_held_packets = self.peak_held_packets.max(held_packets);
+
+    }
+
+    /// Returns the packets captured so far
+    pub fn captured(&self) -> DirectionalCount {
+        self.captured
+    }
+
+    /// Returns the packets delivered so far
+    pub fn delivered(&self) -> DirectionalCount {
+        self.delivered
+    }
+
+    /// Returns the number of packets held by modules
+    pub fn held_packets(&self) -> usize {
+        self.held_packets
+    }
+
+    /// Returns the most packets held since the last call, and starts a new peak
+    pub fn take_peak_held_packets(&mut self) -> usize {
+
+        let peak = self.peak_held_packets.max(self.held_packets);
+
+        self.peak_held_packets = self.held_packets;
+        peak
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traffic_is_counted_per_direction() {
+
+        let mut cycle = DirectionalCount::default();
+
+        cycle.add(&PacketData::new(vec![0; 100], true));
+        cycle.add(&PacketData::new(vec![0; 60], false));
+        cycle.add(&PacketData::new(vec![0; 40], true));
+
+        let mut stats = TrafficStats::new();
+
+        stats.record_cycle(&cycle, &DirectionalCount::default(), 0);
+        stats.record_cycle(&cycle, &cycle, 0);
+
+        assert_eq!(
+            stats.captured().outbound,
+            TrafficCount {
+                packets: 4,
+                bytes: 280
+            }
+        );
+        assert_eq!(stats.captured().inbound.packets, 2);
+        assert_eq!(stats.delivered().inbound.bytes, 60);
+
+    }
+
+    #[test]
+    fn test_peak_held_packets_survives_until_taken() {
+
+        let mut stats = TrafficStats::new();
+        let none = DirectionalCount::default();
+
+        stats.record_cycle(&none, &none, 3);
+        stats.record_cycle(&none, &none, 50);
+        stats.record_cycle(&none, &none, 7);
+
+        assert_eq!(stats.held_packets(), 7);
+        assert_eq!(stats.take_peak_held_packets(), 50);
+        assert_eq!(stats.take_peak_held_packets(), 7);
+
+    }
+}
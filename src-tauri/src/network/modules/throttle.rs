@@ -1,5 +1,5 @@
 use crate::error::Result;
-use crate::network::core::PacketData;
+use crate::network::core::{PacketData, PacketEffects};
 use crate::network::modules::stats::throttle_stats::ThrottleStats;
 use crate::network::modules::traits::{ModuleContext, PacketModule};
 use crate::network::types::probability::Probability;
@@ -22,7 +22,7 @@ pub struct ThrottleModule;
 #[derive(Debug, Default)]
 pub struct ThrottleState {
     /// Queue of buffered packets
-    pub buffer: VecDeque<PacketData<'static>>,
+    pub buffer: VecDeque<PacketData>,
     /// When the current throttle cycle started (None = not throttling)
     pub cycle_start: Option<Instant>,
     /// When the last flush occurred (for cooldown period)
@@ -47,9 +47,9 @@ impl PacketModule for ThrottleModule {
         options.duration_ms
     }
 
-    fn process<'a>(
+    fn process(
         &self,
-        packets: &mut Vec<PacketData<'a>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         state: &mut Self::State,
         ctx: &mut ModuleContext,
@@ -57,12 +57,9 @@ impl PacketModule for ThrottleModule {
 
         let mut stats = ctx.write_stats(self.name())?;
 
-        let buffer: &mut VecDeque<PacketData<'a>> =
-            unsafe { std::mem::transmute(&mut state.buffer) };
-
         throttle_packets(
             packets,
-            buffer,
+            &mut state.buffer,
             &mut state.cycle_start,
             &mut state.last_flush,
             &mut state.last_leak,
@@ -73,7 +70,10 @@ impl PacketModule for ThrottleModule {
             options.inbound,
             options.outbound,
             options.freeze_mode,
+            ctx.clock.now(),
+            ctx.rng,
             &mut stats.throttle_stats,
+            ctx.dropped,
         );
         Ok(())
 
@@ -107,10 +107,13 @@ impl PacketModule for ThrottleModule {
 /// * `apply_outbound` - Apply to outbound (upload) packets
 /// * `freeze_mode` - If true, disable cooldown for continuous buffering (freeze effect)
 /// * `last_leak` - When we last let a packet through as keepalive
+/// * `now` - Current time from the module clock
+/// * `rng` - Random number generator deciding when a throttle cycle starts
 /// * `stats` - Statistics tracker
-pub fn throttle_packets<'a>(
-    packets: &mut Vec<PacketData<'a>>,
-    buffer: &mut VecDeque<PacketData<'a>>,
+/// * `dropped` - Receives the packets dropped in drop mode, marked with [`PacketEffects::DROP`]
+pub fn throttle_packets(
+    packets: &mut Vec<PacketData>,
+    buffer: &mut VecDeque<PacketData>,
     cycle_start: &mut Option<Instant>,
     last_flush: &mut Option<Instant>,
     last_leak: &mut Option<Instant>,
@@ -121,10 +124,12 @@ pub fn throttle_packets<'a>(
     apply_inbound: bool,
     apply_outbound: bool,
     freeze_mode: bool,
+    now: Instant,
+    rng: &mut impl Rng,
     stats: &mut ThrottleStats,
+    dropped: &mut Vec<PacketData>,
 ) {
 
-    let now = Instant::now();
     let cooldown = Duration::from_millis(40);
     let _ = last_leak; // Reserved for future use
 
@@ -150,7 +155,10 @@ pub fn throttle_packets<'a>(
         if drop {
             // Drop Throttled mode - discard all buffered packets
             info!("THROTTLE: Dropping {} buffered packets", count);
-            buffer.clear();
+            dropped.extend(buffer.drain(..).map(|mut packet| {
+                packet.effects.insert(PacketEffects::DROP);
+                packet
+            }));
             stats.dropped_count += count;
         } else {
             // Release mode - send all buffered packets at once
@@ -181,7 +189,7 @@ pub fn throttle_packets<'a>(
         cycle_start.is_none() && !in_cooldown && !just_flushed
     };
 
-    if can_start_new_cycle && rand::rng().random_bool(probability.value()) {
+    if can_start_new_cycle && rng.random_bool(probability.value()) {
         *cycle_start = Some(now);
         info!(
             "THROTTLE: Starting new {}ms throttle cycle",
@@ -222,7 +230,7 @@ pub fn throttle_packets<'a>(
                 continue;
             }
 
-            let packet_size = packet.packet.data.len();
+            let packet_size = packet.data.len();
 
             if packet_size <= KEEPALIVE_THRESHOLD {
                 // Small packet - let it through as keepalive
@@ -237,10 +245,10 @@ pub fn throttle_packets<'a>(
                 break;
             }
 
-            let packet = packets.remove(i);
-            let static_packet: PacketData<'static> = unsafe { std::mem::transmute(packet) };
+            let mut packet = packets.remove(i);
 
-            buffer.push_back(static_packet);
+            packet.effects.insert(PacketEffects::THROTTLE);
+            buffer.push_back(packet);
             buffered_this_cycle += 1;
         }
 
@@ -262,135 +270,151 @@ pub fn throttle_packets<'a>(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::types::clock::{Clock, ManualClock};
     use std::time::Duration;
-    use windivert::layer::NetworkLayer;
-    use windivert::packet::WinDivertPacket;
 
     #[test]
     fn test_throttle_buffering() {
 
-        unsafe {
-            let mut packets = vec![
-                PacketData::new(WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]), true),
-                PacketData::new(WinDivertPacket::<NetworkLayer>::new(vec![4, 5, 6]), true),
-                PacketData::new(WinDivertPacket::<NetworkLayer>::new(vec![7, 8, 9]), true),
-            ];
-
-            let mut buffer = VecDeque::new();
-            let mut cycle_start = Some(Instant::now());
-            let mut last_flush = None;
-            let mut last_leak = None;
-            let mut stats = ThrottleStats::new();
-
-            throttle_packets(
-                &mut packets,
-                &mut buffer,
-                &mut cycle_start,
-                &mut last_flush,
-                &mut last_leak,
-                Probability::new(1.0).unwrap(),
-                Duration::from_secs(10), // Long timeframe
-                false,
-                2000,
-                true,
-                true,
-                false, // freeze_mode
-                &mut stats,
-            );
+        let mut packets = vec![
+            PacketData::new(vec![1, 2, 3], true),
+            PacketData::new(vec![4, 5, 6], true),
+            PacketData::new(vec![7, 8, 9], true),
+        ];
 
-            // All packets should be buffered
-            assert_eq!(packets.len(), 0);
-            assert_eq!(buffer.len(), 3);
-            assert!(stats.is_throttling);
-        }
+        let mut buffer = VecDeque::new();
+        let clock = ManualClock::new();
+        let mut cycle_start = Some(clock.now());
+        let mut last_flush = None;
+        let mut last_leak = None;
+        let mut stats = ThrottleStats::new();
+        let mut dropped = Vec::new();
+
+        throttle_packets(
+            &mut packets,
+            &mut buffer,
+            &mut cycle_start,
+            &mut last_flush,
+            &mut last_leak,
+            Probability::new(1.0).unwrap(),
+            Duration::from_secs(10), // Long timeframe
+            false,
+            2000,
+            true,
+            true,
+            false, // freeze_mode
+            clock.now(),
+            &mut rand::rng(),
+            &mut stats,
+            &mut dropped,
+        );
+
+        // All packets should be buffered
+        assert_eq!(packets.len(), 0);
+        assert_eq!(buffer.len(), 3);
+        assert!(stats.is_throttling);
 
     }
 
     #[test]
     fn test_throttle_release_on_timeframe_end() {
 
-        unsafe {
-            let mut packets = Vec::new();
-            let mut buffer = VecDeque::new();
-
-            buffer.push_back(PacketData::new(
-                WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]),
-                true,
-            ));
-            buffer.push_back(PacketData::new(
-                WinDivertPacket::<NetworkLayer>::new(vec![4, 5, 6]),
-                true,
-            ));
-
-            // Set cycle start in the past so timeframe has elapsed
-            let mut cycle_start = Some(Instant::now() - Duration::from_secs(10));
-            let mut last_flush = None;
-            let mut last_leak = None;
-            let mut stats = ThrottleStats::new();
-
-            throttle_packets(
-                &mut packets,
-                &mut buffer,
-                &mut cycle_start,
-                &mut last_flush,
-                &mut last_leak,
-                Probability::new(0.0).unwrap(), // Don't start new cycle
-                Duration::from_millis(100),     // Short timeframe (already elapsed)
-                false,                          // Release mode
-                2000,
-                true,
-                true,
-                false, // freeze_mode
-                &mut stats,
-            );
+        let mut packets = Vec::new();
+        let mut buffer = VecDeque::new();
 
-            // Buffered packets should be released
-            assert_eq!(packets.len(), 2);
-            assert_eq!(buffer.len(), 0);
-            // last_flush should be set after release
-            assert!(last_flush.is_some());
-        }
+        buffer.push_back(PacketData::new(
+            vec![1, 2, 3],
+            true,
+        ));
+        buffer.push_back(PacketData::new(
+            vec![4, 5, 6],
+            true,
+        ));
+
+        // Start the cycle 10s ago so the timeframe has elapsed
+        let clock = ManualClock::new();
+        let mut cycle_start = Some(clock.now());
+
+        clock.advance(Duration::from_secs(10));
+
+        let mut last_flush = None;
+        let mut last_leak = None;
+        let mut stats = ThrottleStats::new();
+        let mut dropped = Vec::new();
+
+        throttle_packets(
+            &mut packets,
+            &mut buffer,
+            &mut cycle_start,
+            &mut last_flush,
+            &mut last_leak,
+            Probability::new(0.0).unwrap(), // Don't start new cycle
+            Duration::from_millis(100),     // Short timeframe (already elapsed)
+            false,                          // Release mode
+            2000,
+            true,
+            true,
+            false, // freeze_mode
+            clock.now(),
+            &mut rand::rng(),
+            &mut stats,
+            &mut dropped,
+        );
+
+        // Buffered packets should be released
+        assert_eq!(packets.len(), 2);
+        assert_eq!(buffer.len(), 0);
+        // last_flush should be set after release
+        assert!(last_flush.is_some());
 
     }
 
     #[test]
     fn test_throttle_drop_mode() {
 
-        unsafe {
-            let mut packets = Vec::new();
-            let mut buffer = VecDeque::new();
-
-            buffer.push_back(PacketData::new(
-                WinDivertPacket::<NetworkLayer>::new(vec![1, 2, 3]),
-                true,
-            ));
-
-            let mut cycle_start = Some(Instant::now() - Duration::from_secs(10));
-            let mut last_flush = None;
-            let mut last_leak = None;
-            let mut stats = ThrottleStats::new();
-
-            throttle_packets(
-                &mut packets,
-                &mut buffer,
-                &mut cycle_start,
-                &mut last_flush,
-                &mut last_leak,
-                Probability::new(0.0).unwrap(),
-                Duration::from_millis(100),
-                true, // Drop mode
-                2000,
-                true,
-                true,
-                false, // freeze_mode
-                &mut stats,
-            );
+        let mut packets = Vec::new();
+        let mut buffer = VecDeque::new();
 
-            // Packets should be dropped, not released
-            assert_eq!(packets.len(), 0);
-            assert_eq!(buffer.len(), 0);
-            assert_eq!(stats.dropped_count, 1);
-        }
+        buffer.push_back(PacketData::new(
+            vec![1, 2, 3],
+            true,
+        ));
+
+        // Start the cycle 10s ago so the timeframe has elapsed
+        let clock = ManualClock::new();
+        let mut cycle_start = Some(clock.now());
+
+        clock.advance(Duration::from_secs(10));
+
+        let mut last_flush = None;
+        let mut last_leak = None;
+        let mut stats = ThrottleStats::new();
+        let mut dropped = Vec::new();
+
+        throttle_packets(
+            &mut packets,
+            &mut buffer,
+            &mut cycle_start,
+            &mut last_flush,
+            &mut last_leak,
+            Probability::new(0.0).unwrap(),
+            Duration::from_millis(100),
+            true, // Drop mode
+            2000,
+            true,
+            true,
+            false, // freeze_mode
+            clock.now(),
+            &mut rand::rng(),
+            &mut stats,
+            &mut dropped,
+        );
+
+        // Packets should be dropped, not released
+        assert_eq!(packets.len(), 0);
+        assert_eq!(buffer.len(), 0);
+        assert_eq!(stats.dropped_count, 1);
+        assert!(dropped[0].effects.contains(PacketEffects::DROP));
 
     }
 }
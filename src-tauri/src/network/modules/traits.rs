@@ -5,13 +5,28 @@
 use crate::error::{MyraError, Result};
 use crate::network::core::PacketData;
 use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::types::clock::Clock;
+use crate::network::types::rng::ModuleRng;
+use std::borrow::Cow;
 use std::sync::{Arc, RwLock};
 use std::time::Instant;
 
 /// Trait for module options that can be enabled/disabled.
-pub trait ModuleOptions {
+pub trait ModuleOptions: Clone {
     /// Returns whether this module is enabled.
     fn is_enabled(&self) -> bool;
+
+    /// Switches this module on or off, keeping its options.
+    fn set_enabled(&mut self, enabled: bool);
+
+    /// Returns whether inbound or outbound packets have options of their own.
+    fn is_asymmetric(&self) -> bool;
+
+    /// Returns the options in effect for packets of one direction.
+    ///
+    /// Options given for the direction replace these, except for whether the
+    /// module is enabled, which directions it applies to and its duration.
+    fn for_direction(&self, outbound: bool) -> Cow<'_, Self>;
 }
 
 /// Context passed to packet modules during processing.
@@ -25,9 +40,15 @@ pub struct ModuleContext<'a, 'b> {
     pub has_packets: bool,
     /// Reference to effect start time for duration tracking
     pub effect_start: &'b mut Instant,
+    /// Random number generator owned by this module
+    pub rng: &'b mut ModuleRng,
+    /// Source of the current time
+    pub clock: &'b dyn Clock,
+    /// Receives the packets the module drops, so the recording can list them
+    pub dropped: &'b mut Vec<PacketData>,
 }
 
-impl ModuleContext<'_, '_> {
+impl<'a> ModuleContext<'a, '_> {
     /// Acquires a write lock on the statistics, returning a Result instead of panicking.
     ///
     /// # Arguments
@@ -40,7 +61,7 @@ impl ModuleContext<'_, '_> {
     pub fn write_stats(
         &self,
         module_name: &str,
-    ) -> Result<std::sync::RwLockWriteGuard<'_, PacketProcessingStatistics>> {
+    ) -> Result<std::sync::RwLockWriteGuard<'a, PacketProcessingStatistics>> {
         self.statistics
             .write()
             .map_err(|_| MyraError::stats_lock(module_name))
@@ -65,9 +86,9 @@ impl ModuleContext<'_, '_> {
 ///         "my_module"
 ///     }
 ///
-///     fn process<'a>(
+///     fn process(
 ///         &self,
-///         packets: &mut Vec<PacketData<'a>>,
+///         packets: &mut Vec<PacketData>,
 ///         options: &Self::Options,
 ///         state: &mut Self::State,
 ///         ctx: &mut ModuleContext,
@@ -106,7 +127,7 @@ pub trait PacketModule {
     /// `Ok(())` on success, or a `MyraError` if processing fails.
     fn process(
         &self,
-        packets: &mut Vec<PacketData<'_>>,
+        packets: &mut Vec<PacketData>,
         options: &Self::Options,
         state: &mut Self::State,
         ctx: &mut ModuleContext,
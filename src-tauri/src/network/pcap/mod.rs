@@ -0,0 +1,12 @@
+//! Capture file support.
+//!
+//! Reads `.pcap`/`.pcapng` files into raw IP packets and writes processed
+//! packets back out as pcapng, used for offline replay through the module
+//! chain and for recording live sessions.
+pub mod reader;
+pub mod recorder;
+pub mod writer;
+
+pub use reader::{CaptureReader, CapturedPacket};
+pub use recorder::{PacketRecorder, RecordingSummary, SharedRecorder, record_with};
+pub use writer::PcapngWriter;
@@ -0,0 +1,762 @@
+//! Reader for `.pcap` and `.pcapng` capture files.
+//!
+//! Frames are stripped of their link-layer header so every returned packet
+//! starts at the IP header, matching what the capture backends produce.
+//! Frames that do not carry IPv4/IPv6 are skipped.
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{MyraError, Result};
+
+/// Magic number of a classic pcap file with microsecond timestamps.
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+/// Magic number of a classic pcap file with nanosecond timestamps.
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+
+/// pcapng Section Header Block type.
+pub(crate) const BLOCK_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+/// pcapng Interface Description Block type.
+pub(crate) const BLOCK_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+/// pcapng Simple Packet Block type.
+const BLOCK_SIMPLE_PACKET: u32 = 0x0000_0003;
+/// pcapng Enhanced Packet Block type.
+pub(crate) const BLOCK_ENHANCED_PACKET: u32 = 0x0000_0006;
+/// pcapng byte-order magic stored in every Section Header Block.
+pub(crate) const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+/// pcapng option code terminating an option list.
+pub(crate) const OPT_END: u16 = 0;
+/// pcapng option code for a free-form comment.
+pub(crate) const OPT_COMMENT: u16 = 1;
+/// Enhanced Packet Block option code carrying direction flags.
+pub(crate) const OPT_EPB_FLAGS: u16 = 2;
+/// Interface Description Block option code for the timestamp resolution.
+pub(crate) const OPT_IF_TSRESOL: u16 = 9;
+
+/// Link type for BSD loopback encapsulation.
+const LINKTYPE_NULL: u32 = 0;
+/// Link type for Ethernet.
+const LINKTYPE_ETHERNET: u32 = 1;
+/// Link types for raw IP without a link-layer header.
+const LINKTYPE_RAW_LEGACY: [u32; 2] = [12, 14];
+/// Link type for OpenBSD loopback encapsulation.
+const LINKTYPE_LOOP: u32 = 108;
+/// Link type for raw IP without a link-layer header.
+pub(crate) const LINKTYPE_RAW: u32 = 101;
+/// Link type for Linux "cooked" capture v1.
+const LINKTYPE_LINUX_SLL: u32 = 113;
+/// Link type for raw IPv4.
+const LINKTYPE_IPV4: u32 = 228;
+/// Link type for raw IPv6.
+const LINKTYPE_IPV6: u32 = 229;
+/// Link type for Linux "cooked" capture v2.
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+/// Largest block or record the reader accepts, to bound allocations.
+const MAX_RECORD_SIZE: usize = 16 * 1024 * 1024;
+
+/// A packet read from a capture file.
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    /// Capture timestamp
+    pub timestamp: SystemTime,
+    /// Raw IP packet bytes, starting at the IP header
+    pub data: Vec<u8>,
+    /// Direction recorded in the capture, if any (`true` = outbound)
+    pub is_outbound: Option<bool>,
+}
+
+/// Interface described by a pcapng Interface Description Block.
+#[derive(Debug, Clone, Copy)]
+struct Interface {
+    link_type: u32,
+    ticks_per_second: u64,
+}
+
+/// Container format detected from the file header.
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        big_endian: bool,
+        ticks_per_second: u64,
+        link_type: u32,
+    },
+    Pcapng {
+        big_endian: bool,
+        interfaces: Vec<Interface>,
+    },
+}
+
+/// Streaming reader for pcap and pcapng files.
+///
+/// The format is detected from the magic number, so either file type can be
+/// passed to [`CaptureReader::open`].
+pub struct CaptureReader<R: Read> {
+    reader: R,
+    format: Format,
+    skipped: usize,
+    last_timestamp: SystemTime,
+}
+
+impl CaptureReader<BufReader<File>> {
+    /// Opens a capture file from disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path to a `.pcap` or `.pcapng` file
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(CaptureReader)` - If the file header was recognized
+    /// * `Err(MyraError)` - If the file could not be opened or is not a capture file
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Creates a reader over any byte stream containing a capture file.
+    pub fn new(mut reader: R) -> Result<Self> {
+
+        let mut magic = [0u8; 4];
+
+        reader.read_exact(&mut magic)?;
+
+        let format = if u32::from_le_bytes(magic) == BLOCK_SECTION_HEADER {
+            let big_endian = read_section_header(&mut reader)?;
+
+            Format::Pcapng {
+                big_endian,
+                interfaces: Vec::new(),
+            }
+        } else {
+            read_pcap_header(&mut reader, magic)?
+        };
+
+        Ok(Self {
+            reader,
+            format,
+            skipped: 0,
+            last_timestamp: UNIX_EPOCH,
+        })
+
+    }
+
+    /// Returns the number of frames skipped because they did not carry IP.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Reads the next IP packet from the capture.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(CapturedPacket))` - The next packet
+    /// * `Ok(None)` - At the end of the file
+    /// * `Err(MyraError)` - If the file is truncated or malformed
+    pub fn next_packet(&mut self) -> Result<Option<CapturedPacket>> {
+
+        loop {
+            let frame = match self.format {
+                Format::Pcap { .. } => self.next_pcap_frame()?,
+                Format::Pcapng { .. } => self.next_pcapng_frame()?,
+            };
+
+            let Some((link_type, timestamp, frame, is_outbound)) = frame else {
+                return Ok(None);
+            };
+
+            self.last_timestamp = timestamp;
+
+            match strip_link_layer(link_type, &frame) {
+                Some((data, link_direction)) => {
+                    return Ok(Some(CapturedPacket {
+                        timestamp,
+                        data: data.to_vec(),
+                        is_outbound: is_outbound.or(link_direction),
+                    }));
+                }
+                None => self.skipped += 1,
+            }
+        }
+
+    }
+
+    /// Reads the next record of a classic pcap file.
+    fn next_pcap_frame(&mut self) -> Result<Option<Frame>> {
+
+        let Format::Pcap {
+            big_endian,
+            ticks_per_second,
+            link_type,
+        } = self.format
+        else {
+            return Ok(None);
+        };
+
+        let mut header = [0u8; 16];
+
+        if !read_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let seconds = read_u32(&header[0..4], big_endian);
+        let fraction = read_u32(&header[4..8], big_endian);
+        let captured_len = read_u32(&header[8..12], big_endian) as usize;
+
+        if captured_len > MAX_RECORD_SIZE {
+            return Err(capture_error(format!(
+                "pcap record of {} bytes exceeds limit",
+                captured_len
+            )));
+        }
+
+        let mut frame = vec![0u8; captured_len];
+
+        self.reader.read_exact(&mut frame)?;
+
+        let ticks = u64::from(seconds) * ticks_per_second + u64::from(fraction);
+
+        Ok(Some((link_type, ticks_to_time(ticks, ticks_per_second)?, frame, None)))
+
+    }
+
+    /// Reads blocks of a pcapng file until the next packet block.
+    fn next_pcapng_frame(&mut self) -> Result<Option<Frame>> {
+
+        loop {
+            let Format::Pcapng { big_endian, .. } = self.format else {
+                return Ok(None);
+            };
+
+            let mut header = [0u8; 8];
+
+            if !read_or_eof(&mut self.reader, &mut header)? {
+                return Ok(None);
+            }
+
+            if u32::from_le_bytes([header[0], header[1], header[2], header[3]])
+                == BLOCK_SECTION_HEADER
+            {
+                self.start_section(header)?;
+                continue;
+            }
+
+            let block_type = read_u32(&header[0..4], big_endian);
+            let block_len = read_u32(&header[4..8], big_endian) as usize;
+            let body = read_block_body(&mut self.reader, block_len)?;
+
+            match block_type {
+                BLOCK_INTERFACE_DESCRIPTION => self.add_interface(&body, big_endian)?,
+                BLOCK_ENHANCED_PACKET => return self.enhanced_packet(&body, big_endian).map(Some),
+                BLOCK_SIMPLE_PACKET => return self.simple_packet(&body, big_endian).map(Some),
+                _ => {}
+            }
+        }
+
+    }
+
+    /// Handles a Section Header Block that starts a new section mid-file.
+    fn start_section(&mut self, header: [u8; 8]) -> Result<()> {
+
+        let mut magic = [0u8; 4];
+
+        self.reader.read_exact(&mut magic)?;
+
+        let big_endian = byte_order(magic)?;
+        let block_len = read_u32(&header[4..8], big_endian) as usize;
+
+        // The byte-order magic was already consumed from the body
+        skip_bytes(&mut self.reader, block_len.saturating_sub(12))?;
+
+        self.format = Format::Pcapng {
+            big_endian,
+            interfaces: Vec::new(),
+        };
+        Ok(())
+
+    }
+
+    /// Records an Interface Description Block.
+    fn add_interface(&mut self, body: &[u8], big_endian: bool) -> Result<()> {
+
+        if body.len() < 8 {
+            return Err(capture_error("truncated interface description block"));
+        }
+
+        let link_type = u32::from(read_u16(&body[0..2], big_endian));
+        let mut ticks_per_second = 1_000_000;
+
+        for (code, value) in options(&body[8..], big_endian) {
+            if code == OPT_IF_TSRESOL {
+                if let Some(&resolution) = value.first() {
+                    ticks_per_second = tsresol_to_ticks(resolution)?;
+                }
+            }
+        }
+
+        if let Format::Pcapng { interfaces, .. } = &mut self.format {
+            interfaces.push(Interface {
+                link_type,
+                ticks_per_second,
+            });
+        }
+        Ok(())
+
+    }
+
+    /// Decodes an Enhanced Packet Block.
+    fn enhanced_packet(&self, body: &[u8], big_endian: bool) -> Result<Frame> {
+
+        if body.len() < 20 {
+            return Err(capture_error("truncated enhanced packet block"));
+        }
+
+        let interface = self.interface(read_u32(&body[0..4], big_endian))?;
+        let ticks = (u64::from(read_u32(&body[4..8], big_endian)) << 32)
+            | u64::from(read_u32(&body[8..12], big_endian));
+        let captured_len = read_u32(&body[12..16], big_endian) as usize;
+        let data_end = 20 + captured_len;
+
+        if body.len() < data_end {
+            return Err(capture_error("enhanced packet block data exceeds block length"));
+        }
+
+        let options_start = (data_end + 3) & !3;
+        let mut is_outbound = None;
+
+        for (code, value) in options(body.get(options_start..).unwrap_or(&[]), big_endian) {
+            if code == OPT_EPB_FLAGS && value.len() >= 4 {
+                is_outbound = match read_u32(&value[0..4], big_endian) & 0x3 {
+                    1 => Some(false),
+                    2 => Some(true),
+                    _ => None,
+                };
+            }
+        }
+
+        Ok((
+            interface.link_type,
+            ticks_to_time(ticks, interface.ticks_per_second)?,
+            body[20..data_end].to_vec(),
+            is_outbound,
+        ))
+
+    }
+
+    /// Decodes a Simple Packet Block, which carries no timestamp.
+    fn simple_packet(&self, body: &[u8], big_endian: bool) -> Result<Frame> {
+
+        if body.len() < 4 {
+            return Err(capture_error("truncated simple packet block"));
+        }
+
+        let interface = self.interface(0)?;
+        let original_len = read_u32(&body[0..4], big_endian) as usize;
+        let data_end = body.len().min(4 + original_len);
+
+        Ok((
+            interface.link_type,
+            self.last_timestamp,
+            body[4..data_end].to_vec(),
+            None,
+        ))
+
+    }
+
+    /// Looks up an interface of the current section.
+    fn interface(&self, id: u32) -> Result<Interface> {
+
+        let Format::Pcapng { interfaces, .. } = &self.format else {
+            return Err(capture_error("packet block outside of a pcapng section"));
+        };
+
+        interfaces
+            .get(id as usize)
+            .copied()
+            .ok_or_else(|| capture_error(format!("packet references unknown interface {}", id)))
+
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_packet().transpose()
+    }
+}
+
+/// Link type, timestamp, raw frame and recorded direction of one capture record.
+type Frame = (u32, SystemTime, Vec<u8>, Option<bool>);
+
+/// Parses the rest of a classic pcap global header after the magic number.
+fn read_pcap_header<R: Read>(reader: &mut R, magic: [u8; 4]) -> Result<Format> {
+
+    let (big_endian, ticks_per_second) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic))
+    {
+        (PCAP_MAGIC_MICROS, _) => (false, 1_000_000),
+        (PCAP_MAGIC_NANOS, _) => (false, 1_000_000_000),
+        (_, PCAP_MAGIC_MICROS) => (true, 1_000_000),
+        (_, PCAP_MAGIC_NANOS) => (true, 1_000_000_000),
+        _ => return Err(capture_error("not a pcap or pcapng file")),
+    };
+
+    let mut header = [0u8; 20];
+
+    reader.read_exact(&mut header)?;
+
+    // Upper bits of the link type field carry FCS information
+    let link_type = read_u32(&header[16..20], big_endian) & 0xFFFF;
+
+    Ok(Format::Pcap {
+        big_endian,
+        ticks_per_second,
+        link_type,
+    })
+
+}
+
+/// Parses the first Section Header Block after its block type.
+///
+/// # Returns
+///
+/// Whether the section is big-endian
+fn read_section_header<R: Read>(reader: &mut R) -> Result<bool> {
+
+    let mut header = [0u8; 8];
+
+    reader.read_exact(&mut header)?;
+
+    let big_endian = byte_order([header[4], header[5], header[6], header[7]])?;
+    let block_len = read_u32(&header[0..4], big_endian) as usize;
+
+    skip_bytes(reader, block_len.saturating_sub(12))?;
+
+    Ok(big_endian)
+
+}
+
+/// Determines section endianness from the byte-order magic.
+fn byte_order(magic: [u8; 4]) -> Result<bool> {
+
+    if u32::from_le_bytes(magic) == BYTE_ORDER_MAGIC {
+        Ok(false)
+    } else if u32::from_be_bytes(magic) == BYTE_ORDER_MAGIC {
+        Ok(true)
+    } else {
+        Err(capture_error("invalid pcapng byte-order magic"))
+    }
+
+}
+
+/// Reads the body of a block whose 8-byte header was already consumed.
+///
+/// The trailing block length is read and discarded.
+fn read_block_body<R: Read>(reader: &mut R, block_len: usize) -> Result<Vec<u8>> {
+
+    if block_len < 12 || block_len % 4 != 0 || block_len > MAX_RECORD_SIZE {
+        return Err(capture_error(format!("invalid pcapng block length {}", block_len)));
+    }
+
+    let mut body = vec![0u8; block_len - 8];
+
+    reader.read_exact(&mut body)?;
+    body.truncate(block_len - 12);
+
+    Ok(body)
+
+}
+
+/// Iterates over the `(code, value)` pairs of a pcapng option list.
+fn options(mut data: &[u8], big_endian: bool) -> impl Iterator<Item = (u16, &[u8])> {
+
+    std::iter::from_fn(move || {
+        if data.len() < 4 {
+            return None;
+        }
+
+        let code = read_u16(&data[0..2], big_endian);
+        let len = usize::from(read_u16(&data[2..4], big_endian));
+
+        if code == OPT_END || data.len() < 4 + len {
+            return None;
+        }
+
+        let value = &data[4..4 + len];
+
+        data = data.get(4 + ((len + 3) & !3)..).unwrap_or(&[]);
+        Some((code, value))
+    })
+
+}
+
+/// Converts an `if_tsresol` option value into ticks per second.
+fn tsresol_to_ticks(resolution: u8) -> Result<u64> {
+
+    let exponent = u32::from(resolution & 0x7F);
+    let ticks = if resolution & 0x80 == 0 {
+        10u64.checked_pow(exponent)
+    } else {
+        1u64.checked_shl(exponent)
+    };
+
+    ticks.ok_or_else(|| capture_error(format!("unsupported timestamp resolution {}", resolution)))
+
+}
+
+/// Converts a tick count since the epoch into a `SystemTime`.
+///
+/// # Returns
+///
+/// * `Ok(SystemTime)` - The timestamp
+/// * `Err(MyraError)` - If the timestamp lies beyond what `SystemTime` can represent
+fn ticks_to_time(ticks: u64, ticks_per_second: u64) -> Result<SystemTime> {
+
+    let seconds = ticks / ticks_per_second;
+    let nanos = u128::from(ticks % ticks_per_second) * 1_000_000_000 / u128::from(ticks_per_second);
+
+    UNIX_EPOCH
+        .checked_add(Duration::new(seconds, nanos as u32))
+        .ok_or_else(|| capture_error(format!("packet timestamp of {} s is out of range", seconds)))
+
+}
+
+/// Removes the link-layer header of a frame.
+///
+/// # Returns
+///
+/// * `Some((ip_packet, direction))` - The IP packet and any direction the link layer records
+/// * `None` - If the frame does not carry IPv4/IPv6 or the link type is unsupported
+fn strip_link_layer(link_type: u32, frame: &[u8]) -> Option<(&[u8], Option<bool>)> {
+
+    let (payload, direction) = match link_type {
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => (frame, None),
+        t if LINKTYPE_RAW_LEGACY.contains(&t) => (frame, None),
+        LINKTYPE_NULL | LINKTYPE_LOOP => (frame.get(4..)?, None),
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+
+            // Skip 802.1Q / 802.1ad VLAN tags
+            while matches!(ethertype(frame, offset)?, 0x8100 | 0x88A8) {
+                offset += 4;
+            }
+
+            (frame.get(offset + 2..)?, None)
+        }
+        LINKTYPE_LINUX_SLL => {
+            let packet_type = ethertype(frame, 0)?;
+
+            (frame.get(16..)?, sll_direction(packet_type))
+        }
+        LINKTYPE_LINUX_SLL2 => {
+            let packet_type = u16::from(*frame.get(10)?);
+
+            (frame.get(20..)?, sll_direction(packet_type))
+        }
+        _ => return None,
+    };
+
+    match payload.first()? >> 4 {
+        4 | 6 => Some((payload, direction)),
+        _ => None,
+    }
+
+}
+
+/// Reads a big-endian 16-bit field at `offset`.
+fn ethertype(frame: &[u8], offset: usize) -> Option<u16> {
+    let bytes = frame.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Maps a Linux cooked-capture packet type to a direction.
+fn sll_direction(packet_type: u16) -> Option<bool> {
+    match packet_type {
+        0 => Some(false), // Addressed to this host
+        4 => Some(true),  // Sent by this host
+        _ => None,
+    }
+}
+
+/// Fills `buffer`, returning `false` on a clean end of file.
+fn read_or_eof<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<bool> {
+
+    match reader.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+
+}
+
+/// Discards `count` bytes from `reader`.
+fn skip_bytes<R: Read>(reader: &mut R, count: usize) -> Result<()> {
+
+    let skipped = std::io::copy(&mut reader.take(count as u64), &mut std::io::sink())?;
+
+    if skipped < count as u64 {
+        return Err(capture_error("unexpected end of capture file"));
+    }
+    Ok(())
+
+}
+
+fn read_u16(bytes: &[u8], big_endian: bool) -> u16 {
+
+    let bytes = [bytes[0], bytes[1]];
+
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+
+}
+
+fn read_u32(bytes: &[u8], big_endian: bool) -> u32 {
+
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+
+}
+
+fn capture_error(message: impl Into<String>) -> MyraError {
+    MyraError::Capture(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a classic little-endian pcap file with Ethernet framing.
+    fn ethernet_pcap(packets: &[(u32, u32, &[u8])]) -> Vec<u8> {
+
+        let mut file = Vec::new();
+
+        file.extend_from_slice(&PCAP_MAGIC_MICROS.to_le_bytes());
+        file.extend_from_slice(&2u16.to_le_bytes());
+        file.extend_from_slice(&4u16.to_le_bytes());
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&65535u32.to_le_bytes());
+        file.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+        for (seconds, micros, ip) in packets {
+            let mut frame = vec![0u8; 12];
+
+            frame.extend_from_slice(&0x0800u16.to_be_bytes());
+            frame.extend_from_slice(ip);
+
+            file.extend_from_slice(&seconds.to_le_bytes());
+            file.extend_from_slice(&micros.to_le_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            file.extend_from_slice(&frame);
+        }
+        file
+
+    }
+
+    #[test]
+    fn test_read_pcap_ethernet() {
+
+        let ip = [0x45, 1, 2, 3];
+        let file = ethernet_pcap(&[(10, 500, &ip), (11, 0, &ip)]);
+        let mut reader = CaptureReader::new(&file[..]).unwrap();
+
+        let first = reader.next_packet().unwrap().unwrap();
+
+        assert_eq!(first.data, ip);
+        assert_eq!(first.is_outbound, None);
+        assert_eq!(
+            first.timestamp,
+            UNIX_EPOCH + Duration::from_secs(10) + Duration::from_micros(500)
+        );
+
+        assert!(reader.next_packet().unwrap().is_some());
+        assert!(reader.next_packet().unwrap().is_none());
+
+    }
+
+    #[test]
+    fn test_skips_non_ip_frames() {
+
+        let file = ethernet_pcap(&[(1, 0, &[0x00, 0x01]), (2, 0, &[0x60, 0, 0, 0])]);
+        let mut reader = CaptureReader::new(&file[..]).unwrap();
+
+        let packet = reader.next_packet().unwrap().unwrap();
+
+        assert_eq!(packet.data[0], 0x60);
+        assert_eq!(reader.skipped(), 1);
+
+    }
+
+    #[test]
+    fn test_rejects_unknown_format() {
+        assert!(CaptureReader::new(&b"not a capture"[..]).is_err());
+    }
+
+    #[test]
+    fn test_tsresol_to_ticks() {
+
+        assert_eq!(tsresol_to_ticks(6).unwrap(), 1_000_000);
+        assert_eq!(tsresol_to_ticks(9).unwrap(), 1_000_000_000);
+        assert_eq!(tsresol_to_ticks(0x8A).unwrap(), 1024);
+
+    }
+
+    /// Appends a little-endian pcapng block with the given body.
+    fn push_block(file: &mut Vec<u8>, block_type: u32, body: &[u8]) {
+
+        let len = (12 + body.len()) as u32;
+
+        file.extend_from_slice(&block_type.to_le_bytes());
+        file.extend_from_slice(&len.to_le_bytes());
+        file.extend_from_slice(body);
+        file.extend_from_slice(&len.to_le_bytes());
+
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_pcapng_timestamp() {
+
+        let mut file = Vec::new();
+
+        let mut section = BYTE_ORDER_MAGIC.to_le_bytes().to_vec();
+        section.extend_from_slice(&[1, 0, 0, 0]);
+        section.extend_from_slice(&u64::MAX.to_le_bytes());
+        push_block(&mut file, BLOCK_SECTION_HEADER, &section);
+
+        // One tick per second makes the largest timestamp overflow `SystemTime`
+        let mut interface = (LINKTYPE_RAW as u16).to_le_bytes().to_vec();
+        interface.extend_from_slice(&[0; 2]);
+        interface.extend_from_slice(&65535u32.to_le_bytes());
+        interface.extend_from_slice(&OPT_IF_TSRESOL.to_le_bytes());
+        interface.extend_from_slice(&1u16.to_le_bytes());
+        interface.extend_from_slice(&[0x80, 0, 0, 0]);
+        interface.extend_from_slice(&[0; 4]);
+        push_block(&mut file, BLOCK_INTERFACE_DESCRIPTION, &interface);
+
+        let mut packet = 0u32.to_le_bytes().to_vec();
+        packet.extend_from_slice(&u32::MAX.to_le_bytes());
+        packet.extend_from_slice(&u32::MAX.to_le_bytes());
+        packet.extend_from_slice(&4u32.to_le_bytes());
+        packet.extend_from_slice(&4u32.to_le_bytes());
+        packet.extend_from_slice(&[0x45, 0, 0, 0]);
+        push_block(&mut file, BLOCK_ENHANCED_PACKET, &packet);
+
+        let mut reader = CaptureReader::new(&file[..]).unwrap();
+
+        let error = reader.next_packet().unwrap_err();
+
+        assert!(error.to_string().contains("out of range"));
+
+    }
+}
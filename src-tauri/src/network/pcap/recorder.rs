@@ -0,0 +1,289 @@
+//! Live session recording.
+//!
+//! Writes two pcapng files while packet processing runs: one with packets as
+//! they were captured and one with packets as they were actually sent. Each
+//! packet carries a comment with its id, and sent packets also list the
+//! modules that acted on them, so the two files can be lined up in Wireshark.
+//! Packets a module dropped are written to the impaired file as well, at the
+//! time they were dropped and with `DROP` in their comment.
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use log::{error, info};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::network::core::PacketData;
+use crate::network::pcap::PcapngWriter;
+
+/// Recorder shared between the receiving and processing threads.
+///
+/// Every packet passes through [`record_with`], so whether a recording is
+/// active is kept in an atomic flag and the lock is only taken while it is.
+#[derive(Clone, Default)]
+pub struct SharedRecorder {
+    inner: Arc<RecorderSlot>,
+}
+
+#[derive(Default)]
+struct RecorderSlot {
+    active: AtomicBool,
+    recorder: Mutex<Option<PacketRecorder>>,
+}
+
+impl SharedRecorder {
+    /// Makes `recorder` the active recording.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the recording was started
+    /// * `Err(String)` - If a recording is already active
+    pub fn start(&self, recorder: PacketRecorder) -> std::result::Result<(), String> {
+
+        let mut slot = self
+            .inner
+            .recorder
+            .lock()
+            .map_err(|e| format!("Failed to lock recorder mutex: {}", e))?;
+
+        if slot.is_some() {
+            return Err("Recording already running".to_string());
+        }
+
+        *slot = Some(recorder);
+        self.inner.active.store(true, Ordering::SeqCst);
+        Ok(())
+
+    }
+
+    /// Removes and returns the active recording, if any.
+    pub fn take(&self) -> std::result::Result<Option<PacketRecorder>, String> {
+
+        let mut slot = self
+            .inner
+            .recorder
+            .lock()
+            .map_err(|e| format!("Failed to lock recorder mutex: {}", e))?;
+
+        self.inner.active.store(false, Ordering::SeqCst);
+        Ok(slot.take())
+
+    }
+
+    /// Returns whether a recording is active.
+    pub fn is_active(&self) -> bool {
+        self.inner.active.load(Ordering::SeqCst)
+    }
+}
+
+/// Packet counts and file locations of a finished recording.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingSummary {
+    /// File containing packets as captured
+    pub original_path: PathBuf,
+    /// File containing packets as sent
+    pub impaired_path: PathBuf,
+    /// Packets written to the original capture
+    pub original_packets: u64,
+    /// Packets written to the impaired capture, including dropped ones
+    pub impaired_packets: u64,
+    /// Packets written to the impaired capture as dropped
+    pub dropped_packets: u64,
+}
+
+/// Writes the original and impaired pcapng streams of a session.
+pub struct PacketRecorder {
+    original: PcapngWriter<BufWriter<File>>,
+    impaired: PcapngWriter<BufWriter<File>>,
+    summary: RecordingSummary,
+}
+
+impl PacketRecorder {
+    /// Creates both capture files.
+    ///
+    /// # Arguments
+    ///
+    /// * `original_path` - File for packets as captured
+    /// * `impaired_path` - File for packets as sent
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PacketRecorder)` - If both files were created
+    /// * `Err(MyraError)` - If either file could not be written
+    pub fn create(original_path: &Path, impaired_path: &Path) -> Result<Self> {
+
+        let original = PcapngWriter::create(original_path)?;
+        let impaired = PcapngWriter::create(impaired_path)?;
+
+        info!(
+            "Recording packets to {} (original) and {} (impaired)",
+            original_path.display(),
+            impaired_path.display()
+        );
+
+        Ok(Self {
+            original,
+            impaired,
+            summary: RecordingSummary {
+                original_path: original_path.to_path_buf(),
+                impaired_path: impaired_path.to_path_buf(),
+                original_packets: 0,
+                impaired_packets: 0,
+                dropped_packets: 0,
+            },
+        })
+
+    }
+
+    /// Records a packet as it was captured.
+    pub fn record_original(&mut self, packet: &PacketData) -> Result<()> {
+
+        let comment = format!("#{}", packet.id);
+
+        self.original.write_packet(
+            &packet.data,
+            packet.captured_at,
+            packet.is_outbound,
+            Some(&comment),
+        )?;
+        self.summary.original_packets += 1;
+        Ok(())
+
+    }
+
+    /// Records a packet as it was sent, noting the modules that acted on it.
+    pub fn record_impaired(&mut self, packet: &PacketData) -> Result<()> {
+
+        let comment = if packet.effects.is_empty() {
+            format!("#{}", packet.id)
+        } else {
+            format!("#{} {}", packet.id, packet.effects)
+        };
+
+        self.impaired.write_packet(
+            &packet.data,
+            SystemTime::now(),
+            packet.is_outbound,
+            Some(&comment),
+        )?;
+        self.summary.impaired_packets += 1;
+        Ok(())
+
+    }
+
+    /// Records a packet a module dropped instead of sending.
+    ///
+    /// The packet goes to the impaired file with the time it was dropped; its
+    /// effects include `PacketEffects::DROP`, which marks it in the comment.
+    pub fn record_dropped(&mut self, packet: &PacketData) -> Result<()> {
+
+        self.record_impaired(packet)?;
+        self.summary.dropped_packets += 1;
+        Ok(())
+
+    }
+
+    /// Flushes both files and returns the packet counts.
+    pub fn finish(mut self) -> Result<RecordingSummary> {
+
+        self.original.flush()?;
+        self.impaired.flush()?;
+
+        info!(
+            "Recording finished: {} original, {} impaired packets",
+            self.summary.original_packets, self.summary.impaired_packets
+        );
+
+        Ok(self.summary)
+
+    }
+}
+
+/// Runs `record` against the active recorder, if any.
+///
+/// Returns without locking when no recording is active. A write error stops
+/// the recording so a full disk does not flood the log with one error per
+/// packet.
+pub fn record_with<F>(recorder: &SharedRecorder, record: F)
+where
+    F: FnOnce(&mut PacketRecorder) -> Result<()>,
+{
+    if !recorder.is_active() {
+        return;
+    }
+
+    let Ok(mut guard) = recorder.inner.recorder.lock() else {
+        return;
+    };
+
+    let Some(active) = guard.as_mut() else {
+        return;
+    };
+
+    if let Err(e) = record(active) {
+        error!("Stopping packet recording after write error: {}", e);
+        recorder.inner.active.store(false, Ordering::SeqCst);
+
+        if let Some(failed) = guard.take() {
+            let _ = failed.finish();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::PacketEffects;
+    use crate::network::pcap::CaptureReader;
+
+    #[test]
+    fn test_records_both_streams() {
+
+        let dir = std::env::temp_dir();
+        let original_path = dir.join(format!("myra-rec-orig-{}.pcapng", std::process::id()));
+        let impaired_path = dir.join(format!("myra-rec-imp-{}.pcapng", std::process::id()));
+        let recorder = SharedRecorder::default();
+
+        recorder
+            .start(PacketRecorder::create(&original_path, &impaired_path).unwrap())
+            .unwrap();
+
+        let mut packet = PacketData::new(vec![0x45, 0, 0, 0], true);
+
+        record_with(&recorder, |r| r.record_original(&packet));
+        packet.effects.insert(PacketEffects::LAG);
+        record_with(&recorder, |r| r.record_impaired(&packet));
+        packet.effects.insert(PacketEffects::DROP);
+        record_with(&recorder, |r| r.record_dropped(&packet));
+
+        let summary = recorder.take().unwrap().unwrap().finish().unwrap();
+
+        assert!(!recorder.is_active());
+        assert_eq!(summary.original_packets, 1);
+        assert_eq!(summary.impaired_packets, 2);
+        assert_eq!(summary.dropped_packets, 1);
+        assert_eq!(CaptureReader::open(&original_path).unwrap().count(), 1);
+        assert_eq!(CaptureReader::open(&impaired_path).unwrap().count(), 2);
+
+        let _ = std::fs::remove_file(original_path);
+        let _ = std::fs::remove_file(impaired_path);
+
+    }
+
+    #[test]
+    fn test_record_without_active_recorder() {
+
+        let recorder = SharedRecorder::default();
+        let packet = PacketData::new(vec![0x45], false);
+
+        record_with(&recorder, |r| r.record_original(&packet));
+
+        assert!(!recorder.is_active());
+        assert!(recorder.take().unwrap().is_none());
+
+    }
+}
@@ -0,0 +1,219 @@
+//! Writer for `.pcapng` capture files.
+//!
+//! Packets are written as raw IP (`LINKTYPE_RAW`) on a single interface with
+//! nanosecond timestamps, direction flags and optional per-packet comments.
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Result;
+use crate::network::pcap::reader::{
+    BLOCK_ENHANCED_PACKET, BLOCK_INTERFACE_DESCRIPTION, BLOCK_SECTION_HEADER, BYTE_ORDER_MAGIC,
+    LINKTYPE_RAW, OPT_COMMENT, OPT_END, OPT_EPB_FLAGS, OPT_IF_TSRESOL,
+};
+
+/// Section Header Block option code naming the writing application.
+const OPT_SHB_USERAPPL: u16 = 4;
+
+/// `if_tsresol` value for nanosecond timestamps.
+const TSRESOL_NANOS: u8 = 9;
+
+/// Streaming pcapng writer.
+pub struct PcapngWriter<W: Write> {
+    writer: W,
+}
+
+impl PcapngWriter<BufWriter<File>> {
+    /// Creates (or truncates) a pcapng file on disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Destination file path
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PcapngWriter)` - If the file was created and the headers written
+    /// * `Err(MyraError)` - If the file could not be written
+    pub fn create(path: &Path) -> Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write> PcapngWriter<W> {
+    /// Creates a writer and emits the section and interface headers.
+    pub fn new(writer: W) -> Result<Self> {
+
+        let mut pcapng = Self { writer };
+
+        let mut section = Vec::new();
+
+        section.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        section.extend_from_slice(&1u16.to_le_bytes()); // Major version
+        section.extend_from_slice(&0u16.to_le_bytes()); // Minor version
+        section.extend_from_slice(&(-1i64).to_le_bytes()); // Section length unknown
+        push_option(
+            &mut section,
+            OPT_SHB_USERAPPL,
+            concat!("Myra ", env!("CARGO_PKG_VERSION")).as_bytes(),
+        );
+        end_options(&mut section);
+        pcapng.write_block(BLOCK_SECTION_HEADER, &section)?;
+
+        let mut interface = Vec::new();
+
+        interface.extend_from_slice(&(LINKTYPE_RAW as u16).to_le_bytes());
+        interface.extend_from_slice(&0u16.to_le_bytes()); // Reserved
+        interface.extend_from_slice(&0u32.to_le_bytes()); // No snap length limit
+        push_option(&mut interface, OPT_IF_TSRESOL, &[TSRESOL_NANOS]);
+        end_options(&mut interface);
+        pcapng.write_block(BLOCK_INTERFACE_DESCRIPTION, &interface)?;
+
+        Ok(pcapng)
+
+    }
+
+    /// Appends a packet to the capture.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Raw IP packet bytes
+    /// * `timestamp` - Time to record for the packet
+    /// * `is_outbound` - Packet direction, stored in the `epb_flags` option
+    /// * `comment` - Optional comment shown next to the packet in Wireshark
+    pub fn write_packet(
+        &mut self,
+        data: &[u8],
+        timestamp: SystemTime,
+        is_outbound: bool,
+        comment: Option<&str>,
+    ) -> Result<()> {
+
+        let nanos = timestamp
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+
+        let mut body = Vec::with_capacity(data.len() + 48);
+
+        body.extend_from_slice(&0u32.to_le_bytes()); // Interface id
+        body.extend_from_slice(&((nanos >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(nanos as u32).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+        pad(&mut body);
+
+        let direction: u32 = if is_outbound { 2 } else { 1 };
+
+        push_option(&mut body, OPT_EPB_FLAGS, &direction.to_le_bytes());
+
+        if let Some(comment) = comment {
+            push_option(&mut body, OPT_COMMENT, truncate_option(comment));
+        }
+
+        end_options(&mut body);
+        self.write_block(BLOCK_ENHANCED_PACKET, &body)
+
+    }
+
+    /// Flushes buffered output to the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying writer.
+    pub fn into_inner(mut self) -> Result<W> {
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Writes a block with its leading and trailing length fields.
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<()> {
+
+        let block_len = (body.len() + 12) as u32;
+
+        self.writer.write_all(&block_type.to_le_bytes())?;
+        self.writer.write_all(&block_len.to_le_bytes())?;
+        self.writer.write_all(body)?;
+        self.writer.write_all(&block_len.to_le_bytes())?;
+        Ok(())
+
+    }
+}
+
+/// Appends an option with its value padded to 32 bits.
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    pad(body);
+}
+
+/// Terminates an option list.
+fn end_options(body: &mut Vec<u8>) {
+    body.extend_from_slice(&OPT_END.to_le_bytes());
+    body.extend_from_slice(&0u16.to_le_bytes());
+}
+
+/// Pads `body` with zeros to a multiple of four bytes.
+fn pad(body: &mut Vec<u8>) {
+    body.resize((body.len() + 3) & !3, 0);
+}
+
+/// Limits a string option to the maximum option length on a character boundary.
+fn truncate_option(value: &str) -> &[u8] {
+
+    let mut end = value.len().min(usize::from(u16::MAX));
+
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value.as_bytes()[..end]
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::pcap::CaptureReader;
+    use std::time::Duration;
+
+    #[test]
+    fn test_round_trip() {
+
+        let timestamp = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let mut writer = PcapngWriter::new(Vec::new()).unwrap();
+
+        writer
+            .write_packet(&[0x45, 0, 0, 1, 2], timestamp, true, Some("lag"))
+            .unwrap();
+        writer
+            .write_packet(&[0x60, 9], timestamp, false, None)
+            .unwrap();
+
+        let file = writer.into_inner().unwrap();
+        let packets: Vec<_> = CaptureReader::new(&file[..])
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].data, [0x45, 0, 0, 1, 2]);
+        assert_eq!(packets[0].timestamp, timestamp);
+        assert_eq!(packets[0].is_outbound, Some(true));
+        assert_eq!(packets[1].data, [0x60, 9]);
+        assert_eq!(packets[1].is_outbound, Some(false));
+
+    }
+
+    #[test]
+    fn test_blocks_are_aligned() {
+
+        let writer = PcapngWriter::new(Vec::new()).unwrap();
+        let file = writer.into_inner().unwrap();
+
+        assert_eq!(file.len() % 4, 0);
+
+    }
+}
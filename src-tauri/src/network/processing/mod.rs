@@ -1,6 +1,10 @@
 pub mod module_state;
 pub mod processor;
 pub mod receiver;
+pub mod replay;
+pub mod rules;
+pub mod tap;
 
 pub use processor::start_packet_processing;
 pub use receiver::receive_packets;
+pub use replay::{ReplayConfig, ReplaySummary, replay_capture};
@@ -1,8 +1,16 @@
+use crate::network::core::PacketData;
 use crate::network::modules::bandwidth::BandwidthState;
 use crate::network::modules::burst::BurstState;
+use crate::network::modules::drop::DropState;
 use crate::network::modules::lag::LagState;
 use crate::network::modules::reorder::ReorderState;
 use crate::network::modules::throttle::ThrottleState;
+use crate::network::processing::tap::TapState;
+use crate::network::types::clock::{SharedClock, SystemClock};
+use crate::network::types::rng::{ModuleRng, module_rng};
+use crate::settings::rules::{ImpairmentRule, PacketMatch};
+use std::collections::HashSet;
+use std::sync::Arc;
 use std::time::Instant;
 
 /// Maintains state for the packet processing modules.
@@ -11,6 +19,8 @@ use std::time::Instant;
 /// between processing iterations, such as queued packets and timing info.
 #[derive(Debug)]
 pub struct ModuleProcessingState {
+    /// State for the drop module
+    pub drop: DropState,
     /// State for the lag module
     pub lag: LagState,
     /// State for the reorder module
@@ -27,6 +37,22 @@ pub struct ModuleProcessingState {
     pub burst_release_delay_us: u64,
     /// Time when each module's effect was started
     pub effect_start_times: ModuleEffectStartTimes,
+    /// Random number generator of each module
+    pub rngs: ModuleRngs,
+    /// Source of the current time for all modules
+    pub clock: SharedClock,
+    /// Module state of each impairment rule, indexed like `Settings::rules`
+    pub rule_states: Vec<Self>,
+    /// Name and match of the impairment rule this state belongs to
+    pub rule_key: Option<(String, PacketMatch)>,
+    /// Phase of the tap cycle
+    pub tap: TapState,
+    /// Module state for the inbound packets of asymmetric modules
+    pub inbound: Option<Box<Self>>,
+    /// Modules that had separate options per direction in the last cycle
+    pub asymmetric_modules: Vec<&'static str>,
+    /// Packets dropped by the modules since they were last taken
+    pub dropped: Vec<PacketData>,
 }
 
 /// Tracks when each module's effect was started.
@@ -53,11 +79,9 @@ pub struct ModuleEffectStartTimes {
     pub burst: Instant,
 }
 
-impl Default for ModuleEffectStartTimes {
-    fn default() -> Self {
-
-        let now = Instant::now();
-
+impl ModuleEffectStartTimes {
+    /// Creates start times with every effect starting at `now`.
+    pub fn starting_at(now: Instant) -> Self {
         Self {
             drop: now,
             lag: now,
@@ -68,22 +92,292 @@ impl Default for ModuleEffectStartTimes {
             bandwidth: now,
             burst: now,
         }
+    }
+}
+
+impl Default for ModuleEffectStartTimes {
+    fn default() -> Self {
+        Self::starting_at(Instant::now())
+    }
+}
+
+/// Holds a separate random number generator for each module.
+///
+/// Generators are derived from the session seed so the same input and seed
+/// produce the same decisions in every module.
+#[derive(Debug)]
+pub struct ModuleRngs {
+    /// Seed the generators were derived from
+    seed: Option<u64>,
+    /// Prefix of the stream names, keeping the generators of each rule independent
+    scope: String,
+    /// Generator for the drop module
+    pub drop: ModuleRng,
+    /// Generator for the lag module
+    pub lag: ModuleRng,
+    /// Generator for the throttle module
+    pub throttle: ModuleRng,
+    /// Generator for the reorder module
+    pub reorder: ModuleRng,
+    /// Generator for the corruption module
+    pub corruption: ModuleRng,
+    /// Generator for the duplicate module
+    pub duplicate: ModuleRng,
+    /// Generator for the bandwidth module
+    pub bandwidth: ModuleRng,
+    /// Generator for the burst module
+    pub burst: ModuleRng,
+}
+
+impl ModuleRngs {
+    /// Creates generators for all modules from the given seed.
+    ///
+    /// Without a seed every generator is seeded from system entropy.
+    pub fn new(seed: Option<u64>) -> Self {
+        Self::scoped(seed, String::new())
+    }
+
+    /// Creates generators whose streams are prefixed with `scope`.
+    ///
+    /// Used for the modules of impairment rules, so that each rule draws
+    /// different numbers from the same seed.
+    pub fn scoped(seed: Option<u64>, scope: String) -> Self {
+
+        let rng = |module: &str| module_rng(seed, &format!("{}{}", scope, module));
+
+        Self {
+            seed,
+            drop: rng("drop"),
+            lag: rng("lag"),
+            throttle: rng("throttle"),
+            reorder: rng("reorder"),
+            corruption: rng("corruption"),
+            duplicate: rng("duplicate"),
+            bandwidth: rng("bandwidth"),
+            burst: rng("burst"),
+            scope,
+        }
+
+    }
 
+    /// Recreates the generators if the seed differs from the current one.
+    pub fn reseed(&mut self, seed: Option<u64>) {
+        if self.seed != seed {
+            *self = Self::scoped(seed, std::mem::take(&mut self.scope));
+        }
     }
 }
 
 impl ModuleProcessingState {
     pub fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock))
+    }
+
+    /// Creates module state that reads time from `clock`.
+    ///
+    /// Used with a manual clock for tests and offline replay.
+    pub fn with_clock(clock: SharedClock) -> Self {
+
+        let now = clock.now();
 
         Self {
+            drop: DropState::default(),
             lag: LagState::default(),
             reorder: ReorderState::default(),
-            bandwidth: BandwidthState::default(),
+            bandwidth: BandwidthState::starting_at(now),
             throttle: ThrottleState::default(),
             burst: BurstState::default(),
             burst_was_enabled: false,
             burst_release_delay_us: 500, // Default 0.5ms
-            effect_start_times: ModuleEffectStartTimes::default(),
+            effect_start_times: ModuleEffectStartTimes::starting_at(now),
+            rngs: ModuleRngs::new(None),
+            clock,
+            rule_states: Vec::new(),
+            rule_key: None,
+            tap: TapState::default(),
+            inbound: None,
+            asymmetric_modules: Vec::new(),
+            dropped: Vec::new(),
+        }
+
+    }
+
+    /// Creates module state for `rule`, added at `index`.
+    ///
+    /// The rule shares the clock of `self` and gets its own random streams.
+    pub fn for_rule(&self, index: usize, rule: &ImpairmentRule) -> Self {
+        Self {
+            rngs: ModuleRngs::scoped(None, format!("rule{}/", index)),
+            rule_key: Some((rule.name.clone(), rule.matcher.clone())),
+            ..Self::with_clock(Arc::clone(&self.clock))
+        }
+    }
+
+    /// Returns whether this is the module state of `rule`.
+    ///
+    /// Rules are told apart by name and match, so editing a rule's module
+    /// options keeps its state.
+    pub fn belongs_to(&self, rule: &ImpairmentRule) -> bool {
+        self.rule_key
+            .as_ref()
+            .is_some_and(|(name, matcher)| *name == rule.name && *matcher == rule.matcher)
+    }
+
+    /// Creates the module state for inbound packets of asymmetric modules.
+    ///
+    /// The inbound state has its own buffers and rate limits, but shares the
+    /// clock of `self`.
+    pub fn for_inbound(&self) -> Self {
+        Self::with_clock(Arc::clone(&self.clock))
+    }
+
+    /// Removes and returns every packet held back by modules.
+    ///
+    /// Used when a rule is removed, so its held packets are still sent.
+    pub fn take_held_packets(&mut self) -> Vec<PacketData> {
+
+        let mut packets: Vec<PacketData> =
+            self.lag.queue.drain(..).map(|held| held.packet).collect();
+
+        packets.extend(self.reorder.drain().map(|delayed| delayed.packet));
+        packets.extend(self.bandwidth.shaper.drain());
+        packets.extend(self.throttle.buffer.drain(..));
+        packets.extend(self.burst.buffer.drain(..).map(|(packet, _)| packet));
+
+        if let Some(inbound) = self.inbound.as_deref_mut() {
+            packets.extend(inbound.take_held_packets());
+        }
+
+        for rule_state in &mut self.rule_states {
+            packets.extend(rule_state.take_held_packets());
+        }
+        packets
+
+    }
+
+    /// Resets the state of the module `name` and returns the packets it held.
+    ///
+    /// Used on the inbound state when a module gains or loses separate
+    /// options per direction, so the other modules keep their buffers.
+    pub fn reset_module(&mut self, name: &str) -> Vec<PacketData> {
+
+        match name {
+            "drop" => {
+                self.drop = DropState::default();
+                Vec::new()
+            }
+            "lag" => std::mem::take(&mut self.lag)
+                .queue
+                .into_iter()
+                .map(|held| held.packet)
+                .collect(),
+            "throttle" => std::mem::take(&mut self.throttle).buffer.into_iter().collect(),
+            "reorder" => {
+                let packets = self.reorder.drain().map(|delayed| delayed.packet).collect();
+
+                self.reorder = ReorderState::default();
+                packets
+            }
+            "bandwidth" => {
+                let packets = self.bandwidth.shaper.drain().collect();
+
+                self.bandwidth = BandwidthState::starting_at(self.clock.now());
+                packets
+            }
+            "burst" => std::mem::take(&mut self.burst)
+                .buffer
+                .into_iter()
+                .map(|(packet, _)| packet)
+                .collect(),
+            _ => Vec::new(),
+        }
+
+    }
+
+    /// Removes and returns the packets buffered by burst, including those of rules.
+    ///
+    /// Used on shutdown, when a burst cycle will not complete any more.
+    pub fn take_burst_buffers(&mut self) -> Vec<PacketData> {
+
+        let mut packets: Vec<PacketData> =
+            self.burst.buffer.drain(..).map(|(packet, _)| packet).collect();
+
+        if let Some(inbound) = self.inbound.as_deref_mut() {
+            packets.extend(inbound.take_burst_buffers());
+        }
+
+        for rule_state in &mut self.rule_states {
+            packets.extend(rule_state.take_burst_buffers());
+        }
+        packets
+
+    }
+
+    /// Removes and returns the packets dropped by modules, including those of rules.
+    pub fn take_dropped_packets(&mut self) -> Vec<PacketData> {
+
+        // Inbound packets of asymmetric modules are dropped into `self.dropped` too
+        let mut packets = std::mem::take(&mut self.dropped);
+
+        for rule_state in &mut self.rule_states {
+            packets.extend(rule_state.take_dropped_packets());
+        }
+        packets
+
+    }
+
+    /// Returns the most packets held by any one of the buffering modules.
+    ///
+    /// Auto taps start when this reaches `TapOptions::min_buffered`.
+    pub fn largest_buffer(&self) -> usize {
+        self.burst
+            .buffer
+            .len()
+            .max(self.throttle.buffer.len())
+            .max(self.lag.len())
+            .max(self.reorder.len())
+            .max(self.inbound.as_deref().map_or(0, Self::largest_buffer))
+    }
+
+    /// Returns the number of packets currently held back by modules.
+    pub fn held_packets(&self) -> usize {
+        self.lag.len()
+            + self.reorder.len()
+            + self.bandwidth.shaper.len()
+            + self.throttle.buffer.len()
+            + self.burst.buffer.len()
+            + self.inbound.as_deref().map_or(0, Self::held_packets)
+            + self
+                .rule_states
+                .iter()
+                .map(Self::held_packets)
+                .sum::<usize>()
+    }
+
+    /// Returns the ids of the packets currently held back by modules.
+    pub fn held_packet_ids(&self) -> HashSet<u64> {
+
+        let mut ids = HashSet::with_capacity(self.held_packets());
+
+        self.collect_held_ids(&mut ids);
+        ids
+
+    }
+
+    fn collect_held_ids(&self, ids: &mut HashSet<u64>) {
+
+        ids.extend(self.lag.queue.iter().map(|held| held.packet.id));
+        ids.extend(self.reorder.iter().map(|delayed| delayed.packet.id));
+        ids.extend(self.bandwidth.shaper.iter().map(|packet| packet.id));
+        ids.extend(self.throttle.buffer.iter().map(|packet| packet.id));
+        ids.extend(self.burst.buffer.iter().map(|(packet, _)| packet.id));
+
+        if let Some(inbound) = self.inbound.as_deref() {
+            inbound.collect_held_ids(ids);
+        }
+
+        for rule_state in &self.rule_states {
+            rule_state.collect_held_ids(ids);
         }
 
     }
@@ -1,8 +1,13 @@
-use crate::error::{MyraError, Result};
-use crate::network::core::PacketData;
-use crate::network::modules::registry::process_all_modules;
+use crate::error::Result;
+use crate::network::backend::{BackendHandle, PacketSink};
+use crate::network::core::{PacketData, PacketEffects, flush_wfp_cache};
 use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::modules::stats::flow_stats::FlowPacket;
+use crate::network::modules::stats::traffic_stats::DirectionalCount;
+use crate::network::pcap::{SharedRecorder, record_with};
 use crate::network::processing::module_state::ModuleProcessingState;
+use crate::network::processing::rules::process_with_rules;
+use crate::network::processing::tap::TapPhase;
 use crate::settings::Settings;
 use crate::utils::log_statistics;
 use log::{debug, error, info, warn};
@@ -10,16 +15,13 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
-use windivert::layer::NetworkLayer;
-use windivert::{CloseAction, WinDivert};
-use windivert_sys::WinDivertFlags;
 
 /// Swap source and destination IP addresses in a packet (MGO2 bypass technique).
 /// This is called when `WinDivertSend` fails - swapping IPs and retrying can bypass
 /// certain game anti-lag detection mechanisms.
 fn swap_ip_addresses(packet_data: &mut PacketData) -> bool {
 
-    let data = packet_data.packet.data.to_mut();
+    let data = &mut packet_data.data;
 
     if data.len() < 20 {
         return false;
@@ -59,14 +61,14 @@ fn swap_ip_addresses(packet_data: &mut PacketData) -> bool {
 }
 
 /// If the initial send fails, swap source/destination IPs and retry.
-fn send_with_bypass(
-    wd: &WinDivert<NetworkLayer>,
+fn send_with_bypass<S: PacketSink + ?Sized>(
+    sink: &mut S,
     packet_data: &mut PacketData,
     enable_bypass: bool,
-) -> std::result::Result<(), windivert::error::WinDivertError> {
+) -> Result<()> {
 
-    match wd.send(&packet_data.packet) {
-        Ok(_bytes_sent) => Ok(()),
+    match sink.send_packet(packet_data) {
+        Ok(()) => Ok(()),
         Err(e) => {
             if !enable_bypass {
                 return Err(e);
@@ -78,21 +80,18 @@ fn send_with_bypass(
                 return Err(e);
             }
 
-            let current = packet_data.packet.address.outbound();
-
-            packet_data.packet.address.set_outbound(!current);
+            packet_data.is_outbound = !packet_data.is_outbound;
 
-            match wd.send(&packet_data.packet) {
-                Ok(_bytes_sent) => {
+            match sink.send_packet(packet_data) {
+                Ok(()) => {
                     debug!("IP swap bypass successful");
+                    packet_data.effects.insert(PacketEffects::BYPASS);
                     Ok(())
                 }
                 Err(e2) => {
                     swap_ip_addresses(packet_data);
 
-                    let current = packet_data.packet.address.outbound();
-
-                    packet_data.packet.address.set_outbound(!current);
+                    packet_data.is_outbound = !packet_data.is_outbound;
                     Err(e2)
                 }
             }
@@ -103,7 +102,7 @@ fn send_with_bypass(
 
 /// Starts the packet processing loop that handles network packet manipulation.
 ///
-/// This function creates a `WinDivert` handle configured for sending packets only,
+/// This function creates the sink of the session's capture backend,
 /// then enters a processing loop where it:
 /// 1. Receives packets from the provided channel
 /// 2. Applies various packet manipulations based on settings
@@ -113,31 +112,73 @@ fn send_with_bypass(
 ///
 /// # Arguments
 ///
+/// * `backend` - Backend to create the packet sink from
 /// * `settings` - Shared settings that control packet manipulation behavior
 /// * `packet_receiver` - Channel receiver for incoming packet data
 /// * `running` - Atomic flag that controls when processing should stop
 /// * `statistics` - Shared statistics tracking various packet manipulations
+/// * `recorder` - Session recorder that sent packets are written to, if active
 ///
 /// # Returns
 ///
 /// Result indicating success or a `MyraError` if something fails
 pub fn start_packet_processing(
+    backend: BackendHandle,
     settings: Arc<Mutex<Settings>>,
     packet_receiver: Receiver<PacketData>,
     running: Arc<AtomicBool>,
     statistics: Arc<RwLock<PacketProcessingStatistics>>,
+    recorder: SharedRecorder,
 ) -> Result<()> {
 
-    let mut wd = WinDivert::<NetworkLayer>::network(
-        "false",
-        0,
-        WinDivertFlags::set_send_only(WinDivertFlags::new()),
-    )
-    .map_err(|e| {
-        error!("Failed to initialize WinDivert: {}", e);
-        error!("WinDivert error detailed: {:?}", e);
-        MyraError::WinDivert(e)
-    })?;
+    let mut sink = backend.sink()?;
+
+    run_packet_processing(
+        sink.as_mut(),
+        settings,
+        packet_receiver,
+        running,
+        statistics,
+        recorder,
+    )?;
+
+    if let Err(e) = sink.shutdown() {
+        error!("Failed to close the packet sink: {}", e);
+    }
+
+    flush_wfp_cache();
+    debug!("Successfully flushed WFP cache");
+
+    Ok(())
+
+}
+
+/// Runs the packet processing loop on top of an arbitrary [`PacketSink`].
+///
+/// Drains the packet channel every cycle, runs the batch through the module
+/// registry and sends whatever the modules release through `sink`. Any packets
+/// still held by the burst module are flushed when `running` becomes false.
+///
+/// # Arguments
+///
+/// * `sink` - Backend that processed packets are sent through
+/// * `settings` - Shared settings that control packet manipulation behavior
+/// * `packet_receiver` - Channel receiver for incoming packet data
+/// * `running` - Atomic flag that controls when processing should stop
+/// * `statistics` - Shared statistics tracking various packet manipulations
+/// * `recorder` - Session recorder that sent packets are written to, if active
+///
+/// # Returns
+///
+/// Result indicating success or a `MyraError` if something fails
+pub fn run_packet_processing<S: PacketSink + ?Sized>(
+    sink: &mut S,
+    settings: Arc<Mutex<Settings>>,
+    packet_receiver: Receiver<PacketData>,
+    running: Arc<AtomicBool>,
+    statistics: Arc<RwLock<PacketProcessingStatistics>>,
+    recorder: SharedRecorder,
+) -> Result<()> {
 
     let log_interval = Duration::from_secs(2);
     let mut last_log_time = Instant::now();
@@ -177,14 +218,21 @@ pub fn start_packet_processing(
     }
 
     let mut enable_bypass = false;
+    let mut next_tap_change = None;
 
     const CYCLE_TIME_MS: u64 = 40;
 
     while running.load(Ordering::SeqCst) {
         let cycle_start = Instant::now();
         let mut packets = Vec::new();
+        let mut captured = DirectionalCount::default();
+        let mut delivered = DirectionalCount::default();
+        let mut captured_flows = Vec::new();
+        let mut delivered_flows = Vec::new();
 
         while let Ok(packet_data) = packet_receiver.try_recv() {
+            captured.add(&packet_data);
+            captured_flows.extend(FlowPacket::parse(&packet_data, packet_data.arrival_time));
             packets.push(packet_data);
             received_packet_count += 1;
         }
@@ -197,6 +245,8 @@ pub fn start_packet_processing(
                 if let Err(e) = process_packets(&settings, &mut packets, &mut state, &statistics) {
                     error!("Error processing packets: {}", e);
                 }
+
+                next_tap_change = state.tap.next_change(settings.tap.as_ref());
             }
             Err(e) => {
                 error!(
@@ -206,6 +256,11 @@ pub fn start_packet_processing(
             }
         }
 
+        // Dropped packets are kept for the recording only
+        for packet in state.take_dropped_packets() {
+            record_with(&recorder, |r| r.record_dropped(&packet));
+        }
+
         let pacing_needed = packets.len() > 20;
         let release_delay = state.burst_release_delay_us;
 
@@ -218,11 +273,17 @@ pub fn start_packet_processing(
         }
 
         for mut packet_data in packets {
-            if let Err(e) = send_with_bypass(&wd, &mut packet_data, enable_bypass) {
+            // Read the flow first, as the bypass may swap the addresses
+            let flow = FlowPacket::parse(&packet_data, Instant::now());
+
+            if let Err(e) = send_with_bypass(sink, &mut packet_data, enable_bypass) {
                 error!("Failed to send packet: {e}");
                 continue;
             }
 
+            record_with(&recorder, |r| r.record_impaired(&packet_data));
+            delivered.add(&packet_data);
+            delivered_flows.extend(flow);
             sent_packet_count += 1;
 
             if pacing_needed && release_delay > 0 {
@@ -230,6 +291,24 @@ pub fn start_packet_processing(
             }
         }
 
+        match statistics.write() {
+            Ok(mut stats) => {
+                let held_packets = state.held_packets();
+
+                stats
+                    .traffic_stats
+                    .record_cycle(&captured, &delivered, held_packets);
+                stats.flow_stats.record_cycle(
+                    &captured_flows,
+                    &delivered_flows,
+                    held_packets,
+                    || state.held_packet_ids(),
+                    Instant::now(),
+                );
+            }
+            Err(e) => error!("Failed to record traffic statistics: {}", e),
+        }
+
         if last_log_time.elapsed() >= log_interval {
             log_statistics(received_packet_count, sent_packet_count);
             received_packet_count = 0;
@@ -238,46 +317,31 @@ pub fn start_packet_processing(
         }
 
         let elapsed = cycle_start.elapsed();
+        let mut cycle_time = Duration::from_millis(CYCLE_TIME_MS);
 
-        if elapsed < Duration::from_millis(CYCLE_TIME_MS) {
-            std::thread::sleep(
-                Duration::from_millis(CYCLE_TIME_MS)
-                    .checked_sub(elapsed)
-                    .unwrap(),
-            );
+        // Wake up for the next tap edge rather than at the end of the cycle
+        if let Some(change) = next_tap_change {
+            cycle_time = cycle_time.min(change.saturating_duration_since(cycle_start));
         }
-    }
 
-    if !state.burst.buffer.is_empty() {
-        while let Some((mut packet, _)) = state.burst.buffer.pop_front() {
-            if let Err(e) = send_with_bypass(&wd, &mut packet, enable_bypass) {
-                error!("Failed to send buffered packet on shutdown: {e}");
-            }
+        if elapsed < cycle_time {
+            std::thread::sleep(cycle_time.checked_sub(elapsed).unwrap());
         }
-        std::thread::sleep(Duration::from_millis(250));
     }
 
-    debug!("Closing packet processing WinDivert handle");
-
-    let close_result = wd.close(CloseAction::Nothing);
-
-    if let Err(e) = &close_result {
-        error!("Failed to close WinDivert handle: {}", e);
-    }
+    // Inbound and rule bursts go out with the rest
+    let buffered = state.take_burst_buffers();
 
-    if close_result.is_ok() {
-        debug!("Successfully closed packet processing WinDivert handle");
-    }
-
-    match WinDivert::<NetworkLayer>::network("false", 0, WinDivertFlags::new()) {
-        Ok(mut flush_handle) => {
-            let _ = flush_handle.close(CloseAction::Nothing);
+    if !buffered.is_empty() {
+        for mut packet in buffered {
+            if let Err(e) = send_with_bypass(sink, &mut packet, enable_bypass) {
+                error!("Failed to send buffered packet on shutdown: {e}");
+                continue;
+            }
 
-            debug!("Successfully flushed WFP cache");
-        }
-        Err(e) => {
-            error!("Failed to flush WFP cache: {}", e);
+            record_with(&recorder, |r| r.record_impaired(&packet));
         }
+        std::thread::sleep(Duration::from_millis(250));
     }
 
     Ok(())
@@ -286,9 +350,13 @@ pub fn start_packet_processing(
 
 /// Processes packets according to the current manipulation settings.
 ///
-/// Delegates to the module registry which handles all modules in order:
+/// Packets are first matched against the impairment rules, then each group
+/// is handed to the module registry which handles all modules in order:
 /// drop → lag → throttle → reorder → corruption → duplicate → bandwidth → burst
 ///
+/// While a tap is in progress the tapped modules are processed as disabled,
+/// which releases whatever they were holding.
+///
 /// # Arguments
 ///
 /// * `settings` - The current packet manipulation settings
@@ -301,7 +369,7 @@ pub fn start_packet_processing(
 /// `Ok(())` on success, or `MyraError` if any module fails to process.
 pub fn process_packets(
     settings: &Settings,
-    packets: &mut Vec<PacketData<'_>>,
+    packets: &mut Vec<PacketData>,
     state: &mut ModuleProcessingState,
     statistics: &Arc<RwLock<PacketProcessingStatistics>>,
 ) -> Result<()> {
@@ -313,6 +381,258 @@ pub fn process_packets(
         );
     }
 
-    process_all_modules(settings, packets, state, statistics)
+    let phase = advance_tap(settings, state, statistics);
+
+    if phase == TapPhase::Tapping {
+        if let Some(tap) = &settings.tap {
+            let mut tapped = settings.clone();
+
+            tap.apply(&mut tapped);
+            return process_with_rules(&tapped, packets, state, statistics);
+        }
+    }
+
+    process_with_rules(settings, packets, state, statistics)
 
 }
+
+/// Moves the tap cycle to the current time and records its phase.
+fn advance_tap(
+    settings: &Settings,
+    state: &mut ModuleProcessingState,
+    statistics: &Arc<RwLock<PacketProcessingStatistics>>,
+) -> TapPhase {
+
+    let now = state.clock.now();
+    let tap = settings.tap.as_ref();
+
+    let under_pressure = match tap {
+        Some(tap) if tap.auto => {
+            state.largest_buffer() >= tap.min_buffered
+                || statistics
+                    .read()
+                    .is_ok_and(|stats| stats.throttle_stats.is_throttling())
+        }
+        _ => false,
+    };
+
+    let phase = state.tap.update(tap, under_pressure, now);
+
+    match statistics.write() {
+        Ok(mut stats) => stats.tap_stats.record(phase),
+        Err(e) => error!("Failed to record tap phase: {}", e),
+    }
+
+    phase
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::backend::{ChannelSink, ChannelSource};
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+    use crate::network::processing::receiver::receive_packets_from;
+    use crate::network::types::clock::ManualClock;
+    use crate::network::types::probability::Probability;
+    use crate::settings::SettingsBuilder;
+    use crate::settings::burst::BurstOptions;
+    use crate::settings::rules::{ImpairmentRule, PacketMatch};
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Runs `input` through the receiver and processor threads using channel backends
+    /// and returns every packet that reached the sink.
+    fn run_pipeline(settings: Settings, input: Vec<Vec<u8>>) -> Vec<PacketData> {
+        run_session(settings, |inject_tx, _| {
+            for data in input {
+                inject_tx.send(PacketData::new(data, true)).unwrap();
+            }
+        })
+    }
+
+    /// Runs the receiver and processor threads using channel backends while `feed`
+    /// injects packets and changes the filter, and returns every packet that reached
+    /// the sink.
+    fn run_session(
+        settings: Settings,
+        feed: impl FnOnce(&mpsc::Sender<PacketData>, &Mutex<Option<String>>),
+    ) -> Vec<PacketData> {
+
+        let (inject_tx, inject_rx) = mpsc::channel();
+        let (output_tx, output_rx) = mpsc::channel();
+        let (packet_sender, packet_receiver) = mpsc::channel();
+
+        let running = Arc::new(AtomicBool::new(true));
+        let settings = Arc::new(Mutex::new(settings));
+        let filter = Arc::new(Mutex::new(Some("true".to_string())));
+        let session_filter = Arc::clone(&filter);
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+
+        let receiver = {
+            let running = running.clone();
+            let settings = settings.clone();
+
+            thread::spawn(move || {
+                let mut source = ChannelSource::new(inject_rx);
+
+                receive_packets_from(
+                    &mut source,
+                    packet_sender,
+                    running,
+                    settings,
+                    filter,
+                    SharedRecorder::default(),
+                )
+            })
+        };
+
+        let processor = {
+            let running = running.clone();
+
+            thread::spawn(move || {
+                let mut sink = ChannelSink::new(output_tx);
+
+                run_packet_processing(
+                    &mut sink,
+                    settings,
+                    packet_receiver,
+                    running,
+                    statistics,
+                    SharedRecorder::default(),
+                )
+            })
+        };
+
+        feed(&inject_tx, &session_filter);
+
+        thread::sleep(Duration::from_millis(200));
+        running.store(false, Ordering::SeqCst);
+
+        receiver.join().unwrap().unwrap();
+        processor.join().unwrap().unwrap();
+
+        output_rx.try_iter().collect()
+
+    }
+
+    #[test]
+    fn test_pipeline_passes_packets_through_without_modules() {
+
+        let output = run_pipeline(Settings::default(), vec![vec![1, 2, 3], vec![4, 5, 6]]);
+
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].data[..], [1, 2, 3]);
+        assert_eq!(output[1].data[..], [4, 5, 6]);
+
+    }
+
+    #[test]
+    fn test_pipeline_applies_drop_module() {
+
+        let settings = SettingsBuilder::new().drop(100.0).build();
+        let output = run_pipeline(settings, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+
+        assert!(output.is_empty());
+
+    }
+
+    #[test]
+    fn test_tap_switches_modules_off_on_schedule() {
+
+        let clock = Arc::new(ManualClock::new());
+        let mut state = ModuleProcessingState::with_clock(clock.clone());
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+        let settings = SettingsBuilder::new().drop(100.0).tap(100, 20).build();
+
+        let run_cycle = |state: &mut ModuleProcessingState| {
+            let mut packets = vec![PacketData::new(vec![1, 2, 3], true)];
+
+            process_packets(&settings, &mut packets, state, &statistics).unwrap();
+            packets.len()
+        };
+
+        assert_eq!(run_cycle(&mut state), 0);
+
+        clock.advance(Duration::from_millis(100));
+        assert_eq!(run_cycle(&mut state), 1);
+        assert_eq!(statistics.read().unwrap().tap_stats.phase(), TapPhase::Tapping);
+
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(run_cycle(&mut state), 0);
+        assert_eq!(statistics.read().unwrap().tap_stats.phase(), TapPhase::Waiting);
+        assert_eq!(statistics.read().unwrap().tap_stats.taps(), 1);
+
+    }
+
+    #[test]
+    fn test_filter_change_keeps_held_packets() {
+
+        let settings = SettingsBuilder::new().lag(100).build();
+        let output = run_session(settings, |inject_tx, filter| {
+            inject_tx.send(PacketData::new(vec![1], true)).unwrap();
+            thread::sleep(Duration::from_millis(30));
+
+            *filter.lock().unwrap() = Some("outbound".to_string());
+            thread::sleep(Duration::from_millis(30));
+
+            inject_tx.send(PacketData::new(vec![2], false)).unwrap();
+            inject_tx.send(PacketData::new(vec![3], true)).unwrap();
+        });
+        let data: Vec<&[u8]> = output.iter().map(|p| &p.data[..]).collect();
+
+        assert_eq!(data, [[1], [3]]);
+
+    }
+
+    #[test]
+    fn test_shutdown_releases_burst_held_by_rule() {
+
+        let burst = BurstOptions {
+            enabled: true,
+            inbound: true,
+            outbound: true,
+            probability: Probability::new(1.0).unwrap(),
+            release_delay_us: 0,
+            ..BurstOptions::default()
+        };
+        let mut settings = Settings::default();
+
+        settings.rules.push(ImpairmentRule {
+            name: "game".to_string(),
+            enabled: true,
+            matcher: PacketMatch {
+                dst_ports: Some("27015".parse().unwrap()),
+                ..PacketMatch::default()
+            },
+            settings: Settings {
+                burst: Some(burst),
+                ..Settings::default()
+            },
+        });
+
+        // Without a buffer time the rule's burst holds its packet until the session stops
+        let output = run_pipeline(
+            settings,
+            vec![udp_ipv4_packet(5000, 27015, b"game"), udp_ipv4_packet(5000, 443, b"web")],
+        );
+        let ports: Vec<_> = output
+            .iter()
+            .map(|p| p.headers().unwrap().dst_port)
+            .collect();
+
+        assert_eq!(ports, [Some(443), Some(27015)]);
+
+    }
+
+    #[test]
+    fn test_pipeline_applies_duplicate_module() {
+
+        let settings = SettingsBuilder::new().duplicate(2).build();
+        let output = run_pipeline(settings, vec![vec![1, 2, 3]]);
+
+        assert_eq!(output.len(), 3);
+        assert!(output.iter().all(|p| p.data[..] == [1, 2, 3]));
+
+    }
+}
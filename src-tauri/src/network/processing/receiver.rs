@@ -1,17 +1,17 @@
 //! Packet receiving module.
 //!
-//! This module handles receiving network packets using `WinDivert`
+//! This module handles receiving network packets from a capture backend
 //! and forwarding them to the processing thread.
-use crate::network::core::{
-    HandleConfig, HandleManager, PacketData, construct_filter_with_exclusions, flush_wfp_cache,
-};
+use crate::error::Result;
+use crate::network::backend::{BackendHandle, PacketSource};
+use crate::network::core::{PacketData, flush_wfp_cache};
+use crate::network::pcap::{SharedRecorder, record_with};
 use crate::settings::Settings;
 use log::{debug, error, info};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, mpsc};
-use windivert::error::WinDivertError;
 
-/// Receives network packets using `WinDivert`.
+/// Receives network packets from the session's capture backend.
 ///
 /// This function runs in a separate thread and continuously receives packets
 /// from the network. It sends these packets to the main processing thread
@@ -19,30 +19,74 @@ use windivert::error::WinDivertError;
 ///
 /// # Arguments
 ///
+/// * `backend` - Backend to create the packet source from
 /// * `packet_sender` - Channel to send received packets to the processor
 /// * `running` - Atomic flag to control thread execution
-/// * `_settings` - Shared packet manipulation settings (reserved for future use)
+/// * `settings` - Shared packet manipulation settings (reserved for future use)
 /// * `filter` - Shared filter string to determine which packets to capture
+/// * `recorder` - Session recorder that captured packets are written to, if active
 ///
 /// # Returns
 ///
 /// * `Ok(())` - If thread completes cleanly
-/// * `Err(WinDivertError)` - If there's an error with `WinDivert` operations
+/// * `Err(MyraError)` - If there's an error with the capture backend
 pub fn receive_packets(
-    packet_sender: mpsc::Sender<PacketData<'_>>,
+    backend: BackendHandle,
+    packet_sender: mpsc::Sender<PacketData>,
+    running: Arc<AtomicBool>,
+    settings: Arc<Mutex<Settings>>,
+    filter: Arc<Mutex<Option<String>>>,
+    recorder: SharedRecorder,
+) -> Result<()> {
+
+    let mut source = backend.source();
+    let result = receive_packets_from(
+        source.as_mut(),
+        packet_sender,
+        running,
+        settings,
+        filter,
+        recorder,
+    );
+
+    flush_wfp_cache();
+    result
+
+}
+
+/// Receives network packets from an arbitrary [`PacketSource`].
+///
+/// Watches the shared filter for changes, reconfigures the source when it
+/// changes and forwards every captured packet to the processing thread.
+///
+/// # Arguments
+///
+/// * `source` - Backend to capture packets from
+/// * `packet_sender` - Channel to send received packets to the processor
+/// * `running` - Atomic flag to control thread execution
+/// * `_settings` - Shared packet manipulation settings (reserved for future use)
+/// * `filter` - Shared filter string to determine which packets to capture
+/// * `recorder` - Session recorder that captured packets are written to, if active
+///
+/// # Returns
+///
+/// * `Ok(())` - If thread completes cleanly
+/// * `Err(MyraError)` - If the source fails to shut down
+pub fn receive_packets_from<S: PacketSource + ?Sized>(
+    source: &mut S,
+    packet_sender: mpsc::Sender<PacketData>,
     running: Arc<AtomicBool>,
     _settings: Arc<Mutex<Settings>>,
     filter: Arc<Mutex<Option<String>>>,
-) -> Result<(), WinDivertError> {
+    recorder: SharedRecorder,
+) -> Result<()> {
 
     info!(
         "Receiver thread started, running={}",
         running.load(Ordering::SeqCst)
     );
 
-    let mut buffer = vec![0u8; 65535]; // Max packet size
     let mut last_filter: Option<String> = None;
-    let mut handle_manager = HandleManager::new();
     let mut logged_missing_handle = false;
     let mut recv_count: u64 = 0;
 
@@ -53,50 +97,36 @@ pub fn receive_packets(
             continue;
         };
 
-        let current_filter = construct_filter_with_exclusions(&filter_guard);
+        let current_filter = filter_guard.clone();
 
         drop(filter_guard);
 
-        // If filter changed, update WinDivert handle
+        // If filter changed, reconfigure the capture backend
         if current_filter != last_filter {
             info!(
                 "Filter changed to: {}",
                 current_filter.as_deref().unwrap_or("none")
             );
 
-            match &current_filter {
-                Some(filter_str) => {
-                    let config = HandleConfig::with_filter(filter_str)
-                        .priority(0)
-                        .recv_only(false)
-                        .exclude_tauri_port(false); // Already excluded in construct_filter_with_exclusions
-
-                    if let Err(e) = handle_manager.open(config) {
-                        error!("Failed to open WinDivert handle: {}", e);
-                    }
-                }
-                None => {
-                    if let Err(e) = handle_manager.close() {
-                        error!("Failed to close WinDivert handle: {}", e);
-                    }
-                }
+            if let Err(e) = source.apply_filter(current_filter.as_deref()) {
+                error!("Failed to apply capture filter: {}", e);
             }
 
             last_filter = current_filter;
         }
 
-        // Process packets if WinDivert handle exists
-        let Some(wd_handle) = handle_manager.handle() else {
+        // Process packets if the capture is active
+        if !source.is_capturing() {
             if !logged_missing_handle {
-                debug!("WinDivert handle is not initialized. Waiting for filter.");
+                debug!("Capture handle is not initialized. Waiting for filter.");
                 logged_missing_handle = true;
             }
             continue;
-        };
+        }
 
         if logged_missing_handle {
             logged_missing_handle = false;
-            info!("WinDivert handle now active, waiting for packets ..");
+            info!("Capture handle now active, waiting for packets ..");
         }
 
         // Log once that we're about to start receiving
@@ -109,18 +139,15 @@ pub fn receive_packets(
             }
         }
 
-        match wd_handle.recv(Some(&mut buffer)) {
-            Ok(packet) => {
+        match source.recv_packet() {
+            Ok(Some(packet_data)) => {
                 recv_count += 1;
 
-                // Capture direction before taking ownership
-                let is_outbound = packet.address.outbound();
-
                 if recv_count % 100 == 1 {
                     info!("Receiver: {} packets captured so far", recv_count);
                 }
 
-                let packet_data = PacketData::new(packet.into_owned(), is_outbound);
+                record_with(&recorder, |r| r.record_original(&packet_data));
 
                 if packet_sender.send(packet_data).is_err() {
                     if should_shutdown(&running) {
@@ -129,6 +156,7 @@ pub fn receive_packets(
                     error!("Failed to send packet data to main thread");
                 }
             }
+            Ok(None) => {}
             Err(e) => {
                 error!("Failed to receive packet: {}", e);
 
@@ -139,16 +167,10 @@ pub fn receive_packets(
         }
     }
 
-    // HandleManager's Drop impl will clean up, but we explicitly close for logging
-    if handle_manager.is_active() {
-        debug!("Closing packet receiving WinDivert handle on shutdown");
-
-        if let Err(e) = handle_manager.close() {
-            error!("Failed to close WinDivert handle on shutdown: {}", e);
-        }
+    if let Err(e) = source.shutdown() {
+        error!("Failed to close capture handle on shutdown: {}", e);
     }
 
-    flush_wfp_cache();
     debug!("Shutting down packet receiving thread");
     Ok(())
 
@@ -0,0 +1,391 @@
+//! Offline capture replay.
+//!
+//! Feeds a recorded `.pcap`/`.pcapng` file through [`process_packets`] and
+//! writes whatever the modules release to a pcapng file. Dropped packets are
+//! omitted, duplicates appear as extra packets and timestamps reflect the
+//! delay each packet picked up on the way through.
+//!
+//! Modules run on a [`ManualClock`] that follows the captured timestamps, so a
+//! replay finishes as fast as the host allows and, with a seed set in the
+//! settings, produces the same output every time.
+use crate::error::Result;
+use crate::network::core::{PacketData, PacketHeaders};
+use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::pcap::{CaptureReader, CapturedPacket, PcapngWriter};
+use crate::network::processing::module_state::ModuleProcessingState;
+use crate::network::processing::processor::process_packets;
+use crate::network::types::clock::{Clock, ManualClock};
+use crate::settings::Settings;
+use log::info;
+use serde::Serialize;
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long to keep cycling after the last input packet for held packets to drain,
+/// measured on the replay clock.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How far the replay clock moves per cycle while modules hold packets.
+const HOLD_STEP: Duration = Duration::from_millis(1);
+
+/// Configuration for a capture replay.
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Addresses considered local; used for packets without recorded direction
+    pub local_addresses: Vec<IpAddr>,
+    /// Maximum time to wait for held packets after the input is exhausted
+    pub drain_timeout: Duration,
+}
+
+impl ReplayConfig {
+    /// Creates a configuration with default settings.
+    pub fn new() -> Self {
+
+        Self {
+            local_addresses: Vec::new(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+        }
+
+    }
+
+    /// Adds an address whose traffic is treated as outbound.
+    pub fn local_address(mut self, address: IpAddr) -> Self {
+        self.local_addresses.push(address);
+        self
+    }
+
+    /// Sets how long to wait for held packets after the input is exhausted.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Determines the direction of a captured packet.
+    ///
+    /// Directions recorded in the capture take precedence. Otherwise packets
+    /// sourced from a local address are outbound; with no local addresses
+    /// configured every packet is treated as outbound.
+    fn is_outbound(&self, packet: &CapturedPacket) -> bool {
+
+        if let Some(is_outbound) = packet.is_outbound {
+            return is_outbound;
+        }
+
+        if self.local_addresses.is_empty() {
+            return true;
+        }
+
+        PacketHeaders::parse(&packet.data)
+            .is_some_and(|headers| self.local_addresses.contains(&headers.src_addr))
+
+    }
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Outcome of a capture replay.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReplaySummary {
+    /// Packets read from the input capture
+    pub packets_read: usize,
+    /// Packets written to the output capture
+    pub packets_written: usize,
+    /// Input frames skipped because they did not carry IP
+    pub packets_skipped: usize,
+    /// Packets still held by modules when the drain timeout expired
+    pub packets_held: usize,
+}
+
+/// Replays a capture file through the module chain.
+///
+/// Packets are fed in at their original relative timing on a simulated clock.
+/// The clock jumps straight to the next packet's arrival and only steps by
+/// [`HOLD_STEP`] while modules hold packets. Each packet is written at its
+/// captured timestamp plus the delay the modules added to it.
+///
+/// # Arguments
+///
+/// * `input` - Path to a `.pcap` or `.pcapng` file
+/// * `output` - Path of the pcapng file to write
+/// * `settings` - Module settings to apply
+/// * `config` - Direction and drain settings
+///
+/// # Returns
+///
+/// * `Ok(ReplaySummary)` - Packet counts for the replay
+/// * `Err(MyraError)` - If either file cannot be read or written, or a module fails
+pub fn replay_capture(
+    input: &Path,
+    output: &Path,
+    settings: &Settings,
+    config: &ReplayConfig,
+) -> Result<ReplaySummary> {
+
+    let mut reader = CaptureReader::open(input)?;
+    let mut writer = PcapngWriter::create(output)?;
+    let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+    let clock = Arc::new(ManualClock::new());
+    let mut state = ModuleProcessingState::with_clock(clock.clone());
+    let mut summary = ReplaySummary::default();
+
+    state.burst_release_delay_us = settings.burst_release_delay_us;
+
+    let mut next = reader.next_packet()?;
+    let capture_start = next
+        .as_ref()
+        .map_or_else(SystemTime::now, |packet| packet.timestamp);
+    let replay_start = clock.now();
+    let mut drain_deadline = None;
+
+    info!(
+        "Replaying {} into {}",
+        input.display(),
+        output.display()
+    );
+
+    loop {
+        let elapsed = clock.elapsed(replay_start);
+        let mut packets = Vec::new();
+
+        while let Some(captured) = next.take() {
+            let offset = captured
+                .timestamp
+                .duration_since(capture_start)
+                .unwrap_or_default();
+
+            if offset > elapsed {
+                next = Some(captured);
+                break;
+            }
+
+            let is_outbound = config.is_outbound(&captured);
+            let mut packet_data = PacketData::new(captured.data, is_outbound);
+
+            packet_data.arrival_time = replay_start + offset;
+            packet_data.captured_at = captured.timestamp;
+            packets.push(packet_data);
+            summary.packets_read += 1;
+
+            next = reader.next_packet()?;
+        }
+
+        process_packets(settings, &mut packets, &mut state, &statistics)?;
+
+        // Only the released packets are written, so dropped ones are let go
+        state.take_dropped_packets();
+
+        for packet_data in &packets {
+            write_released(&mut writer, packet_data, clock.now())?;
+        }
+        summary.packets_written += packets.len();
+
+        let holding = state.held_packets() > 0;
+
+        // Without held packets nothing happens until the next one arrives
+        let step = if let Some(captured) = &next {
+            let due = captured
+                .timestamp
+                .duration_since(capture_start)
+                .unwrap_or_default()
+                .saturating_sub(clock.elapsed(replay_start));
+
+            if holding { due.min(HOLD_STEP) } else { due }
+        } else {
+            if !holding {
+                break;
+            }
+
+            let deadline =
+                *drain_deadline.get_or_insert_with(|| clock.now() + config.drain_timeout);
+
+            if clock.now() >= deadline {
+                break;
+            }
+            HOLD_STEP
+        };
+
+        clock.advance(step);
+    }
+
+    // Release whatever burst still buffers, as the live processor does on shutdown
+    // Inbound and rule bursts go out with the rest
+    for packet_data in state.take_burst_buffers() {
+        write_released(&mut writer, &packet_data, clock.now())?;
+        summary.packets_written += 1;
+    }
+
+    writer.flush()?;
+
+    summary.packets_skipped = reader.skipped();
+    summary.packets_held = state.held_packets();
+
+    info!(
+        "Replay finished: {} read, {} written, {} skipped, {} still held",
+        summary.packets_read,
+        summary.packets_written,
+        summary.packets_skipped,
+        summary.packets_held
+    );
+
+    Ok(summary)
+
+}
+
+/// Writes a packet released at `now`, noting the modules that acted on it.
+///
+/// The packet keeps its captured timestamp, moved by the time it was held.
+fn write_released<W: Write>(
+    writer: &mut PcapngWriter<W>,
+    packet_data: &PacketData,
+    now: Instant,
+) -> Result<()> {
+
+    let comment = (!packet_data.effects.is_empty()).then(|| packet_data.effects.to_string());
+    let delay = now.saturating_duration_since(packet_data.arrival_time);
+
+    writer.write_packet(
+        &packet_data.data,
+        packet_data.captured_at + delay,
+        packet_data.is_outbound,
+        comment.as_deref(),
+    )
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+    use crate::settings::SettingsBuilder;
+    use std::time::UNIX_EPOCH;
+
+    /// Writes `count` UDP packets 1ms apart into a pcapng file under the temp dir.
+    fn write_input(name: &str, count: usize) -> std::path::PathBuf {
+
+        let path = std::env::temp_dir().join(format!("myra-replay-{}-{}.pcapng", name, std::process::id()));
+        let mut writer = PcapngWriter::create(&path).unwrap();
+
+        for i in 0..count {
+            let timestamp = UNIX_EPOCH + Duration::from_secs(1_000) + Duration::from_millis(i as u64);
+
+            writer
+                .write_packet(&udp_ipv4_packet(5000, 6000, &[i as u8]), timestamp, true, None)
+                .unwrap();
+        }
+        writer.flush().unwrap();
+        path
+
+    }
+
+    fn read_output(path: &Path) -> Vec<CapturedPacket> {
+        CaptureReader::open(path)
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_replay_pass_through() {
+
+        let input = write_input("pass", 5);
+        let output = input.with_extension("out.pcapng");
+
+        let summary =
+            replay_capture(&input, &output, &Settings::default(), &ReplayConfig::new()).unwrap();
+        let packets = read_output(&output);
+
+        assert_eq!(summary.packets_read, 5);
+        assert_eq!(summary.packets_written, 5);
+        assert_eq!(packets.len(), 5);
+        assert_eq!(packets[0].is_outbound, Some(true));
+
+        // Without modules every packet keeps its captured timestamp
+        for (i, packet) in packets.iter().enumerate() {
+            let captured = UNIX_EPOCH + Duration::from_secs(1_000) + Duration::from_millis(i as u64);
+
+            assert_eq!(packet.timestamp, captured);
+        }
+
+        let _ = std::fs::remove_file(input);
+        let _ = std::fs::remove_file(output);
+
+    }
+
+    #[test]
+    fn test_replay_omits_drops_and_includes_duplicates() {
+
+        let input = write_input("impaired", 4);
+        let output = input.with_extension("out.pcapng");
+
+        let drop_all = SettingsBuilder::new().drop(100.0).build();
+
+        let summary = replay_capture(&input, &output, &drop_all, &ReplayConfig::new()).unwrap();
+
+        assert_eq!(summary.packets_written, 0);
+        assert!(read_output(&output).is_empty());
+
+        let duplicate = SettingsBuilder::new().duplicate(2).build();
+
+        let summary = replay_capture(&input, &output, &duplicate, &ReplayConfig::new()).unwrap();
+
+        assert_eq!(summary.packets_written, 12);
+        assert_eq!(read_output(&output).len(), 12);
+
+        let _ = std::fs::remove_file(input);
+        let _ = std::fs::remove_file(output);
+
+    }
+
+    #[test]
+    fn test_replay_lag_follows_capture_clock() {
+
+        let input = write_input("lag", 3);
+        let output = input.with_extension("out.pcapng");
+
+        let lag = SettingsBuilder::new().lag(500).build();
+
+        let summary = replay_capture(&input, &output, &lag, &ReplayConfig::new()).unwrap();
+        let packets = read_output(&output);
+
+        // Each packet leaves 500ms of capture time after it arrived,
+        // without the replay itself waiting that long
+        assert_eq!(summary.packets_written, 3);
+
+        for (i, packet) in packets.iter().enumerate() {
+            let arrived = UNIX_EPOCH + Duration::from_secs(1_000) + Duration::from_millis(i as u64);
+
+            assert_eq!(packet.timestamp, arrived + Duration::from_millis(500));
+        }
+
+        let _ = std::fs::remove_file(input);
+        let _ = std::fs::remove_file(output);
+
+    }
+
+    #[test]
+    fn test_replay_is_reproducible_with_seed() {
+
+        let input = write_input("seeded", 50);
+        let first = input.with_extension("first.pcapng");
+        let second = input.with_extension("second.pcapng");
+
+        let settings = SettingsBuilder::new().drop(50.0).reorder(30).seed(7).build();
+
+        replay_capture(&input, &first, &settings, &ReplayConfig::new()).unwrap();
+        replay_capture(&input, &second, &settings, &ReplayConfig::new()).unwrap();
+
+        assert_eq!(std::fs::read(&first).unwrap(), std::fs::read(&second).unwrap());
+
+        let _ = std::fs::remove_file(input);
+        let _ = std::fs::remove_file(first);
+        let _ = std::fs::remove_file(second);
+
+    }
+}
@@ -0,0 +1,228 @@
+//! Per-flow rule dispatch.
+//!
+//! Splits each cycle's packets by the first impairment rule they match and
+//! runs every group through the module registry with its own settings and
+//! state. Packets matching no rule use the top-level settings.
+use crate::error::Result;
+use crate::network::core::PacketData;
+use crate::network::modules::registry::process_all_modules;
+use crate::network::modules::stats::PacketProcessingStatistics;
+use crate::network::processing::module_state::ModuleProcessingState;
+use crate::settings::Settings;
+use crate::settings::rules::find_matching_rule;
+use std::sync::{Arc, RwLock};
+
+/// Processes packets through the modules of the rule each packet matches.
+///
+/// Every rule is processed on every cycle, even without new packets, so
+/// packets it holds back are released on time. Each rule keeps its module
+/// state when other rules are added, removed or reordered. When rules are
+/// removed, the packets their modules were holding are sent on unchanged.
+///
+/// # Arguments
+///
+/// * `settings` - Top-level settings including the rule table
+/// * `packets` - Packets of this cycle; replaced by the packets to send
+/// * `state` - Module state of the top-level settings and of each rule
+/// * `statistics` - Statistics shared by all rules
+///
+/// # Returns
+///
+/// `Ok(())` on success, or `MyraError` if any module fails to process.
+pub fn process_with_rules(
+    settings: &Settings,
+    packets: &mut Vec<PacketData>,
+    state: &mut ModuleProcessingState,
+    statistics: &Arc<RwLock<PacketProcessingStatistics>>,
+) -> Result<()> {
+
+    state.rngs.reseed(settings.seed);
+
+    let mut released = Vec::new();
+
+    let unchanged = state.rule_states.len() == settings.rules.len()
+        && state
+            .rule_states
+            .iter()
+            .zip(&settings.rules)
+            .all(|(rule_state, rule)| rule_state.belongs_to(rule));
+
+    if !unchanged {
+        let mut previous = std::mem::take(&mut state.rule_states);
+
+        for (index, rule) in settings.rules.iter().enumerate() {
+            let rule_state = previous
+                .iter()
+                .position(|s| s.belongs_to(rule))
+                .map_or_else(|| state.for_rule(index, rule), |i| previous.remove(i));
+
+            state.rule_states.push(rule_state);
+        }
+
+        for mut removed in previous {
+            released.extend(removed.take_held_packets());
+        }
+    }
+
+    if settings.rules.is_empty() {
+        process_all_modules(settings, packets, state, statistics)?;
+        packets.extend(released);
+        return Ok(());
+    }
+
+    let mut groups: Vec<Vec<PacketData>> = vec![Vec::new(); settings.rules.len()];
+    let mut unmatched = Vec::new();
+
+    for packet in packets.drain(..) {
+        match find_matching_rule(&settings.rules, packet.headers().as_ref(), packet.size()) {
+            Some(index) => groups[index].push(packet),
+            None => unmatched.push(packet),
+        }
+    }
+
+    process_all_modules(settings, &mut unmatched, state, statistics)?;
+    packets.extend(unmatched);
+
+    for ((rule, rule_state), mut group) in settings
+        .rules
+        .iter()
+        .zip(state.rule_states.iter_mut())
+        .zip(groups)
+    {
+        rule_state.rngs.reseed(settings.seed);
+        rule_state.burst_release_delay_us = rule.settings.burst_release_delay_us;
+
+        process_all_modules(&rule.settings, &mut group, rule_state, statistics)?;
+        packets.extend(group);
+    }
+
+    packets.extend(released);
+    Ok(())
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+    use crate::network::types::clock::{Clock, ManualClock};
+    use crate::settings::SettingsBuilder;
+    use crate::settings::rules::{ImpairmentRule, PacketMatch, RuleProtocol};
+    use std::time::Duration;
+
+    fn game_rule(settings: Settings) -> ImpairmentRule {
+        ImpairmentRule {
+            name: "game".to_string(),
+            enabled: true,
+            matcher: PacketMatch {
+                protocol: Some(RuleProtocol::Udp),
+                dst_ports: Some("27015".parse().unwrap()),
+                ..PacketMatch::default()
+            },
+            settings,
+        }
+    }
+
+    #[test]
+    fn test_rule_lags_only_matching_packets() {
+
+        let clock = Arc::new(ManualClock::new());
+        let mut state = ModuleProcessingState::with_clock(clock.clone());
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+
+        let mut settings = Settings::default();
+        settings.rules.push(game_rule(SettingsBuilder::new().lag(150).build()));
+
+        let mut packets = vec![
+            PacketData::new(udp_ipv4_packet(5000, 27015, b"game"), true),
+            PacketData::new(udp_ipv4_packet(5000, 443, b"https"), true),
+        ];
+
+        for packet in &mut packets {
+            packet.arrival_time = clock.now();
+        }
+
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+
+        // Only the HTTPS packet passes straight through
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].headers().unwrap().dst_port, Some(443));
+        assert_eq!(state.held_packets(), 1);
+
+        clock.advance(Duration::from_millis(150));
+        packets.clear();
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].headers().unwrap().dst_port, Some(27015));
+
+    }
+
+    #[test]
+    fn test_removed_rule_releases_held_packets() {
+
+        let clock = Arc::new(ManualClock::new());
+        let mut state = ModuleProcessingState::with_clock(clock);
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+
+        let mut settings = Settings::default();
+        settings.rules.push(game_rule(SettingsBuilder::new().lag(1000).build()));
+
+        let mut packets = vec![PacketData::new(udp_ipv4_packet(5000, 27015, b"game"), true)];
+
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+        assert!(packets.is_empty());
+
+        settings.rules.clear();
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert!(state.rule_states.is_empty());
+
+    }
+
+    #[test]
+    fn test_removing_a_rule_keeps_the_state_of_the_others() {
+
+        let clock = Arc::new(ManualClock::new());
+        let mut state = ModuleProcessingState::with_clock(clock.clone());
+        let statistics = Arc::new(RwLock::new(PacketProcessingStatistics::default()));
+
+        let mut voice = game_rule(SettingsBuilder::new().lag(300).build());
+        voice.name = "voice".to_string();
+        voice.matcher.dst_ports = Some("3478".parse().unwrap());
+
+        let mut settings = Settings::default();
+        settings.rules.push(game_rule(SettingsBuilder::new().lag(1000).build()));
+        settings.rules.push(voice);
+
+        let mut packets = vec![
+            PacketData::new(udp_ipv4_packet(5000, 27015, b"game"), true),
+            PacketData::new(udp_ipv4_packet(5000, 3478, b"voice"), true),
+        ];
+
+        for packet in &mut packets {
+            packet.arrival_time = clock.now();
+        }
+
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+        assert!(packets.is_empty());
+
+        // Only the game rule's packet is released; the voice rule keeps its lag
+        settings.rules.remove(0);
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].headers().unwrap().dst_port, Some(27015));
+        assert_eq!(state.rule_states.len(), 1);
+        assert_eq!(state.rule_states[0].lag.len(), 1);
+
+        packets.clear();
+        clock.advance(Duration::from_millis(300));
+        process_with_rules(&settings, &mut packets, &mut state, &statistics).unwrap();
+
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].headers().unwrap().dst_port, Some(3478));
+
+    }
+}
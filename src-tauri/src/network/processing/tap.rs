@@ -0,0 +1,216 @@
+//! Tap cycling.
+//!
+//! Decides on the processing thread when a tap starts and ends, using the
+//! same clock as the modules. Periodic taps are scheduled from the moment
+//! tapping was enabled, so they do not drift however late a cycle runs.
+use crate::settings::TapOptions;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Where the tap is in its cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TapPhase {
+    /// Tapping is disabled
+    #[default]
+    Off,
+    /// Modules are on until the next tap
+    Waiting,
+    /// Modules are switched off
+    Tapping,
+}
+
+/// Tap progress kept between processing cycles.
+#[derive(Debug, Default)]
+pub struct TapState {
+    /// Start of the current tap, if one is in progress
+    tap_started: Option<Instant>,
+    /// Start of the next periodic tap
+    next_tap: Option<Instant>,
+    /// End of the last tap, for the auto mode cooldown
+    last_tap_ended: Option<Instant>,
+    /// Interval `next_tap` was scheduled with
+    interval: Duration,
+}
+
+impl TapState {
+    /// Advances the tap to `now` and returns the phase in effect.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Current tap options, if any
+    /// * `under_pressure` - Whether the buffering modules hold enough packets for an auto tap
+    /// * `now` - Current time of the processing clock
+    pub fn update(
+        &mut self,
+        options: Option<&TapOptions>,
+        under_pressure: bool,
+        now: Instant,
+    ) -> TapPhase {
+
+        let Some(options) = options.filter(|options| options.is_active()) else {
+            *self = Self::default();
+            return TapPhase::Off;
+        };
+
+        if let Some(started) = self.tap_started {
+            let ends = started + options.duration();
+
+            if now < ends {
+                return TapPhase::Tapping;
+            }
+
+            self.tap_started = None;
+            self.last_tap_ended = Some(ends);
+        }
+
+        let started = if options.auto {
+            self.next_tap = None;
+
+            let cooled_down = self
+                .last_tap_ended
+                .map_or(true, |ended| now >= ended + options.cooldown());
+
+            (under_pressure && cooled_down).then_some(now)
+        } else {
+            self.next_periodic_tap(options, now)
+        };
+
+        match started {
+            Some(started) if now < started + options.duration() => {
+                self.tap_started = Some(started);
+                TapPhase::Tapping
+            }
+            _ => TapPhase::Waiting,
+        }
+
+    }
+
+    /// Moment the phase changes next without any packets arriving.
+    ///
+    /// `None` when tapping is off or an auto tap waits for packets to pile up.
+    pub fn next_change(&self, options: Option<&TapOptions>) -> Option<Instant> {
+
+        let options = options.filter(|options| options.is_active())?;
+
+        self.tap_started
+            .map_or(self.next_tap, |started| Some(started + options.duration()))
+
+    }
+
+    /// Returns the start of the periodic tap due at `now`, if one is due.
+    ///
+    /// The first tap comes one interval after tapping is enabled or its
+    /// interval changes. Taps missed entirely are skipped.
+    fn next_periodic_tap(&mut self, options: &TapOptions, now: Instant) -> Option<Instant> {
+
+        let interval = options.interval();
+
+        if self.interval != interval {
+            self.interval = interval;
+            self.next_tap = None;
+        }
+
+        let next = *self.next_tap.get_or_insert(now + interval);
+
+        if now < next {
+            return None;
+        }
+
+        let missed = now.duration_since(next).as_nanos() / interval.as_nanos();
+        let started = next + interval * u32::try_from(missed).unwrap_or(u32::MAX);
+
+        self.next_tap = Some(started + interval);
+        Some(started)
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn periodic(interval_ms: u64, duration_ms: u64) -> TapOptions {
+        TapOptions {
+            enabled: true,
+            interval_ms,
+            duration_ms,
+            ..Default::default()
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_periodic_taps_follow_the_schedule() {
+
+        let options = periodic(1000, 200);
+        let start = Instant::now();
+        let mut tap = TapState::default();
+
+        let phase_at =
+            |tap: &mut TapState, at: u64| tap.update(Some(&options), false, start + ms(at));
+
+        assert_eq!(phase_at(&mut tap, 0), TapPhase::Waiting);
+        assert_eq!(tap.next_change(Some(&options)), Some(start + ms(1000)));
+        assert_eq!(phase_at(&mut tap, 999), TapPhase::Waiting);
+        assert_eq!(phase_at(&mut tap, 1000), TapPhase::Tapping);
+        assert_eq!(tap.next_change(Some(&options)), Some(start + ms(1200)));
+        assert_eq!(phase_at(&mut tap, 1199), TapPhase::Tapping);
+        assert_eq!(phase_at(&mut tap, 1200), TapPhase::Waiting);
+
+        // A late cycle still ends the tap on schedule
+        assert_eq!(phase_at(&mut tap, 2150), TapPhase::Tapping);
+        assert_eq!(tap.next_change(Some(&options)), Some(start + ms(2200)));
+
+        // Taps missed entirely are skipped
+        assert_eq!(phase_at(&mut tap, 5500), TapPhase::Waiting);
+        assert_eq!(tap.next_change(Some(&options)), Some(start + ms(6000)));
+
+    }
+
+    #[test]
+    fn test_auto_taps_wait_for_pressure_and_cooldown() {
+
+        let options = TapOptions {
+            auto: true,
+            cooldown_ms: 500,
+            ..periodic(1000, 200)
+        };
+        let start = Instant::now();
+        let mut tap = TapState::default();
+
+        assert_eq!(tap.update(Some(&options), false, start), TapPhase::Waiting);
+        assert_eq!(tap.next_change(Some(&options)), None);
+        assert_eq!(tap.update(Some(&options), true, start + ms(10)), TapPhase::Tapping);
+        assert_eq!(tap.update(Some(&options), true, start + ms(210)), TapPhase::Waiting);
+        assert_eq!(tap.update(Some(&options), true, start + ms(709)), TapPhase::Waiting);
+        assert_eq!(tap.update(Some(&options), true, start + ms(710)), TapPhase::Tapping);
+
+    }
+
+    #[test]
+    fn test_disabling_ends_the_tap() {
+
+        let options = periodic(100, 50);
+        let start = Instant::now();
+        let mut tap = TapState::default();
+
+        tap.update(Some(&options), false, start);
+        assert_eq!(tap.update(Some(&options), false, start + ms(120)), TapPhase::Tapping);
+
+        let disabled = TapOptions {
+            enabled: false,
+            ..options.clone()
+        };
+        assert_eq!(tap.update(Some(&disabled), false, start + ms(130)), TapPhase::Off);
+        assert_eq!(tap.update(None, false, start + ms(140)), TapPhase::Off);
+
+        // Enabling again starts a fresh schedule
+        assert_eq!(tap.update(Some(&options), false, start + ms(150)), TapPhase::Waiting);
+        assert_eq!(tap.next_change(Some(&options)), Some(start + ms(250)));
+
+    }
+}
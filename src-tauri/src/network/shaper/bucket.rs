@@ -0,0 +1,130 @@
+use std::time::Instant;
+
+/// Token bucket deciding when a shaper may send.
+///
+/// Tokens are bytes. They accumulate at `rate` bytes per second up to
+/// `depth`, and sending a packet takes as many tokens as it has bytes. A
+/// packet larger than the bucket is sent once the bucket is full, leaving
+/// the bucket in debt, so such packets still get through at the set rate.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    depth: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - Rate at which tokens are added, in bytes per second
+    /// * `depth` - Most tokens the bucket holds, in bytes
+    /// * `now` - Time the bucket starts filling from
+    pub fn new(rate: f64, depth: f64, now: Instant) -> Self {
+        Self {
+            rate,
+            depth,
+            tokens: depth,
+            last_refill: now,
+        }
+    }
+
+    /// Changes the rate and depth, keeping the tokens the new depth allows.
+    pub fn configure(&mut self, rate: f64, depth: f64) {
+
+        self.rate = rate;
+        self.depth = depth;
+        self.tokens = self.tokens.min(depth);
+
+    }
+
+    /// Adds the tokens gathered since the last refill.
+    ///
+    /// A real link spends tokens as they arrive while packets are waiting,
+    /// but here they are only spent when the shaper is polled. While
+    /// `backlogged` the tokens of the whole interval are kept, so a shaper
+    /// polled every few milliseconds still sends at its full rate.
+    pub fn refill(&mut self, now: Instant, backlogged: bool) {
+
+        let elapsed = now.saturating_duration_since(self.last_refill);
+
+        self.tokens += elapsed.as_secs_f64() * self.rate;
+        self.last_refill = now;
+
+        if !backlogged {
+            self.settle();
+        }
+
+    }
+
+    /// Drops the tokens beyond the depth of the bucket.
+    pub fn settle(&mut self) {
+        self.tokens = self.tokens.min(self.depth);
+    }
+
+    /// Returns whether a packet of `size` bytes may be sent now.
+    pub fn allows(&self, size: usize) -> bool {
+        self.rate > 0.0 && self.tokens >= (size as f64).min(self.depth)
+    }
+
+    /// Takes the tokens for a packet of `size` bytes.
+    pub fn take(&mut self, size: usize) {
+        self.tokens -= size as f64;
+    }
+
+    /// Returns the rate in bytes per second.
+    pub const fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Returns the depth in bytes.
+    pub const fn depth(&self) -> f64 {
+        self.depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_bucket_refills_up_to_its_depth() {
+
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000.0, 1500.0, start);
+
+        assert!(bucket.allows(1500));
+        bucket.take(1500);
+        assert!(!bucket.allows(100));
+
+        bucket.refill(start + Duration::from_millis(500), false);
+        assert!(bucket.allows(500) && !bucket.allows(501));
+
+        bucket.refill(start + Duration::from_secs(10), false);
+        assert!(bucket.allows(1500));
+
+        // Larger than the bucket: sent from a full bucket, leaving a debt
+        bucket.take(4000);
+        bucket.refill(start + Duration::from_secs(12), false);
+        assert!(!bucket.allows(1));
+
+    }
+
+    #[test]
+    fn test_backlogged_bucket_keeps_its_tokens() {
+
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(1000.0, 100.0, start);
+
+        bucket.take(100);
+        bucket.refill(start + Duration::from_secs(1), true);
+
+        assert!(bucket.allows(100));
+        bucket.take(900);
+        assert!(bucket.allows(100));
+
+    }
+}
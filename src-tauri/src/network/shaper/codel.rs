@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+/// Size of a full Ethernet frame; `CoDel` never drops when no more than
+/// this is left queued.
+pub const MTU: usize = 1514;
+
+/// A packet taken from the head of the queue.
+#[derive(Debug)]
+pub struct Head<T> {
+    /// The packet
+    pub item: T,
+    /// How long the packet waited in the queue
+    pub sojourn: Duration,
+    /// Bytes still queued behind it
+    pub remaining_bytes: usize,
+}
+
+/// Controlled Delay queue management, following the pseudocode of RFC 8289.
+///
+/// `CoDel` watches how long packets wait rather than how many are queued.
+/// Once every packet has waited longer than `target` for a whole `interval`
+/// it drops one at the head of the queue, then drops again at intervals that
+/// shrink with the square root of the drop count until the delay falls back
+/// under the target.
+#[derive(Debug, Clone, Default)]
+pub struct Codel {
+    target: Duration,
+    interval: Duration,
+    first_above_time: Option<Instant>,
+    drop_next: Option<Instant>,
+    count: u32,
+    last_count: u32,
+    dropping: bool,
+}
+
+impl Codel {
+    /// Sets the target delay and the interval it must be exceeded for.
+    pub fn configure(&mut self, target: Duration, interval: Duration) {
+
+        self.target = target;
+        self.interval = interval;
+
+    }
+
+    /// Takes the next packet to send from the queue.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current time
+    /// * `pop` - Removes the packet at the head of the queue
+    /// * `dropped` - Receives the packets dropped on the way
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Head<T>>` - The packet to send, or None if the queue ran empty
+    pub fn dequeue<T>(
+        &mut self,
+        now: Instant,
+        mut pop: impl FnMut() -> Option<Head<T>>,
+        dropped: &mut Vec<T>,
+    ) -> Option<Head<T>> {
+
+        let (mut head, mut ok_to_drop) = self.judge(now, pop());
+
+        if self.dropping {
+            if !ok_to_drop {
+                self.dropping = false;
+            }
+
+            while self.dropping && self.drop_next.is_some_and(|next| now >= next) {
+                dropped.extend(head.map(|head| head.item));
+                self.count += 1;
+                (head, ok_to_drop) = self.judge(now, pop());
+
+                if ok_to_drop {
+                    self.drop_next = self.drop_next.map(|next| self.control_law(next));
+                } else {
+                    self.dropping = false;
+                }
+            }
+        } else if ok_to_drop {
+            dropped.extend(head.map(|head| head.item));
+            (head, _) = self.judge(now, pop());
+            self.dropping = true;
+
+            // Resume near the previous drop rate if dropping stopped only briefly
+            let delta = self.count.saturating_sub(self.last_count);
+            let recently = self
+                .drop_next
+                .is_some_and(|next| now.saturating_duration_since(next) < self.interval * 16);
+
+            self.count = if delta > 1 && recently { delta } else { 1 };
+            self.drop_next = Some(self.control_law(now));
+            self.last_count = self.count;
+        }
+        head
+
+    }
+
+    /// Returns the head packet with whether it may be dropped.
+    ///
+    /// A packet may be dropped once the delay has stayed above the target
+    /// for an interval, unless dropping it would leave the link idle.
+    fn judge<T>(&mut self, now: Instant, head: Option<Head<T>>) -> (Option<Head<T>>, bool) {
+
+        let Some(head) = head else {
+            self.first_above_time = None;
+            return (None, false);
+        };
+
+        if head.sojourn < self.target || head.remaining_bytes <= MTU {
+            self.first_above_time = None;
+            return (Some(head), false);
+        }
+
+        let Some(first_above_time) = self.first_above_time else {
+            self.first_above_time = Some(now + self.interval);
+            return (Some(head), false);
+        };
+        (Some(head), now >= first_above_time)
+
+    }
+
+    /// Returns when to drop next, sooner the more drops it has taken.
+    fn control_law(&self, from: Instant) -> Instant {
+        from + self.interval.div_f64(f64::from(self.count.max(1)).sqrt())
+    }
+}
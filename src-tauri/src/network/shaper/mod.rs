@@ -0,0 +1,14 @@
+//! Token bucket traffic shaping.
+//!
+//! The shaper behind both the inline bandwidth module and the WFP throttle.
+//! A token bucket sets the rate and how bursty the output may be, and a
+//! bounded queue with a choice of discipline decides what happens to the
+//! packets the link cannot send yet: a deep tail-drop queue reproduces
+//! bufferbloat, RED and `CoDel` keep the delay down by dropping early.
+mod bucket;
+mod codel;
+mod queue;
+mod red;
+
+pub use bucket::TokenBucket;
+pub use queue::Shaper;
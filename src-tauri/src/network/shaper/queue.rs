@@ -0,0 +1,347 @@
+use crate::network::shaper::bucket::TokenBucket;
+use crate::network::shaper::codel::{Codel, Head, MTU};
+use crate::network::shaper::red::Red;
+use crate::settings::shaper::{QueueDiscipline, ShaperOptions};
+use rand::Rng;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long the rate takes to fill the bucket when no burst size is set
+const AUTO_BURST: Duration = Duration::from_millis(10);
+
+/// A packet waiting in the queue.
+#[derive(Debug)]
+struct Queued<P> {
+    packet: P,
+    size: usize,
+    enqueued_at: Instant,
+}
+
+/// Token bucket shaper with a bounded queue.
+///
+/// Packets are queued on arrival and released in order as the token bucket
+/// allows. The queue discipline decides which packets are dropped when the
+/// link cannot keep up. The shaper does not look into its packets, so the
+/// inline bandwidth module and the WFP throttle share it.
+#[derive(Debug)]
+pub struct Shaper<P> {
+    options: ShaperOptions,
+    bucket: TokenBucket,
+    queue: VecDeque<Queued<P>>,
+    queued_bytes: usize,
+    backlogged: bool,
+    red: Red,
+    codel: Codel,
+    last_sojourn: Duration,
+}
+
+impl<P> Shaper<P> {
+    /// Creates an empty shaper with a full bucket.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - Rate limit in bytes per second
+    /// * `options` - Bucket depth, queue limits and queue discipline
+    /// * `now` - Time the bucket starts filling from
+    pub fn new(rate: f64, options: &ShaperOptions, now: Instant) -> Self {
+
+        let mut shaper = Self {
+            options: *options,
+            bucket: TokenBucket::new(rate, bucket_depth(rate, options), now),
+            queue: VecDeque::new(),
+            queued_bytes: 0,
+            backlogged: false,
+            red: Red::default(),
+            codel: Codel::default(),
+            last_sojourn: Duration::ZERO,
+        };
+
+        shaper.configure(rate, options);
+        shaper
+
+    }
+
+    /// Changes the rate and options, keeping the queued packets.
+    pub fn configure(&mut self, rate: f64, options: &ShaperOptions) {
+
+        self.options = *options;
+        self.bucket.configure(rate, bucket_depth(rate, options));
+        self.codel.configure(
+            Duration::from_millis(options.codel_target_ms),
+            Duration::from_millis(options.codel_interval_ms),
+        );
+
+    }
+
+    /// Queues a packet unless the queue discipline drops it.
+    ///
+    /// # Arguments
+    ///
+    /// * `packet` - The packet to queue
+    /// * `size` - Size of the packet in bytes
+    /// * `now` - Arrival time of the packet
+    /// * `rng` - Random number generator for RED
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the packet was queued
+    /// * `Err(P)` - The packet, if the queue discipline dropped it
+    pub fn enqueue(
+        &mut self,
+        packet: P,
+        size: usize,
+        now: Instant,
+        rng: &mut impl Rng,
+    ) -> Result<(), P> {
+
+        let full = (self.options.queue_bytes > 0
+            && self.queued_bytes + size > self.options.queue_bytes)
+            || (self.options.queue_packets > 0 && self.queue.len() >= self.options.queue_packets);
+
+        if full {
+            return Err(packet);
+        }
+
+        if self.options.discipline == QueueDiscipline::Red
+            && self.red.should_drop(self.fill(), &self.options, rng)
+        {
+            return Err(packet);
+        }
+
+        self.queued_bytes += size;
+        self.queue.push_back(Queued {
+            packet,
+            size,
+            enqueued_at: now,
+        });
+        Ok(())
+
+    }
+
+    /// Releases every queued packet the token bucket allows.
+    ///
+    /// # Arguments
+    ///
+    /// * `now` - Current time
+    /// * `released` - Receives the released packets, oldest first
+    /// * `dropped` - Receives the packets `CoDel` dropped while dequeuing
+    pub fn release(&mut self, now: Instant, released: &mut Vec<P>, dropped: &mut Vec<P>) {
+
+        let mut dropped_heads = Vec::new();
+
+        self.bucket.refill(now, self.backlogged);
+
+        while let Some(size) = self.queue.front().map(|queued| queued.size) {
+            if !self.bucket.allows(size) {
+                break;
+            }
+
+            let head = if self.options.discipline == QueueDiscipline::Codel {
+                let mut codel = std::mem::take(&mut self.codel);
+                let head = codel.dequeue(now, || self.pop(now), &mut dropped_heads);
+
+                self.codel = codel;
+                head
+            } else {
+                self.pop(now)
+            };
+
+            let Some(head) = head else {
+                break;
+            };
+
+            self.bucket.take(head.item.size);
+            self.last_sojourn = head.sojourn;
+            released.push(head.item.packet);
+        }
+
+        // Tokens left over once the queue is empty only fill the bucket
+        if self.queue.is_empty() {
+            self.bucket.settle();
+        }
+        self.backlogged = !self.queue.is_empty();
+        dropped.extend(dropped_heads.into_iter().map(|queued| queued.packet));
+
+    }
+
+    /// Removes every queued packet, oldest first.
+    pub fn drain(&mut self) -> impl Iterator<Item = P> + '_ {
+
+        self.queued_bytes = 0;
+        self.backlogged = false;
+        self.queue.drain(..).map(|queued| queued.packet)
+
+    }
+
+    /// Returns the queued packets, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &P> {
+        self.queue.iter().map(|queued| &queued.packet)
+    }
+
+    /// Returns the number of queued packets.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns whether no packets are queued.
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Returns the number of queued bytes.
+    pub const fn queued_bytes(&self) -> usize {
+        self.queued_bytes
+    }
+
+    /// Returns how long the last released packet waited in the queue.
+    pub const fn last_sojourn(&self) -> Duration {
+        self.last_sojourn
+    }
+
+    /// Returns the rate limit in bytes per second.
+    pub const fn rate(&self) -> f64 {
+        self.bucket.rate()
+    }
+
+    /// Removes the packet at the head of the queue.
+    fn pop(&mut self, now: Instant) -> Option<Head<Queued<P>>> {
+
+        let queued = self.queue.pop_front()?;
+
+        self.queued_bytes -= queued.size;
+
+        Some(Head {
+            sojourn: now.saturating_duration_since(queued.enqueued_at),
+            remaining_bytes: self.queued_bytes,
+            item: queued,
+        })
+
+    }
+
+    /// Returns how full the queue is as a fraction of its tightest limit.
+    fn fill(&self) -> f64 {
+
+        let bytes = if self.options.queue_bytes > 0 {
+            self.queued_bytes as f64 / self.options.queue_bytes as f64
+        } else {
+            0.0
+        };
+        let packets = if self.options.queue_packets > 0 {
+            self.queue.len() as f64 / self.options.queue_packets as f64
+        } else {
+            0.0
+        };
+        bytes.max(packets)
+
+    }
+}
+
+/// Returns the depth of the token bucket for `options`.
+///
+/// Without a configured burst the bucket holds 10 ms worth of the rate, and
+/// never less than one full-size packet.
+fn bucket_depth(rate: f64, options: &ShaperOptions) -> f64 {
+
+    if options.burst_bytes > 0 {
+        return options.burst_bytes as f64;
+    }
+    (rate * AUTO_BURST.as_secs_f64()).max(MTU as f64)
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::types::rng::module_rng;
+
+    /// Offers twice the rate for five seconds and returns the longest
+    /// queueing delay of the last second with the number of drops.
+    fn overload(options: &ShaperOptions) -> (Duration, usize) {
+
+        let start = Instant::now();
+        let mut rng = module_rng(Some(1), "shaper");
+        let mut shaper = Shaper::new(100_000.0, options, start);
+        let mut released = Vec::new();
+        let mut dropped = Vec::new();
+        let mut worst = Duration::ZERO;
+
+        for step in 0..500u32 {
+            let now = start + Duration::from_millis(10) * step;
+
+            for _ in 0..2 {
+                dropped.extend(shaper.enqueue(step, 1000, now, &mut rng).err());
+            }
+            shaper.release(now, &mut released, &mut dropped);
+
+            if step >= 400 {
+                worst = worst.max(shaper.last_sojourn());
+            }
+        }
+        (worst, dropped.len())
+
+    }
+
+    #[test]
+    fn test_shaper_releases_at_its_rate() {
+
+        let start = Instant::now();
+        let mut rng = module_rng(Some(1), "shaper");
+        let mut shaper = Shaper::new(10_000.0, &ShaperOptions::default(), start);
+        let mut released = Vec::new();
+
+        for index in 0..20 {
+            assert!(shaper.enqueue(index, 1000, start, &mut rng).is_ok());
+        }
+
+        // The bucket starts with one full-size packet worth of tokens
+        shaper.release(start, &mut released, &mut Vec::new());
+        assert_eq!(released, vec![0]);
+
+        // Tokens of a whole second are kept while packets wait
+        shaper.release(start + Duration::from_secs(1), &mut released, &mut Vec::new());
+        assert_eq!(released, (0..11).collect::<Vec<_>>());
+        assert_eq!(shaper.len(), 9);
+        assert_eq!(shaper.queued_bytes(), 9000);
+
+    }
+
+    #[test]
+    fn test_full_queue_drops_arriving_packets() {
+
+        let start = Instant::now();
+        let mut rng = module_rng(Some(1), "shaper");
+        let options = ShaperOptions {
+            queue_packets: 5,
+            ..Default::default()
+        };
+        let mut shaper = Shaper::new(0.0, &options, start);
+
+        let dropped: Vec<_> = (0..8)
+            .filter_map(|index| shaper.enqueue(index, 100, start, &mut rng).err())
+            .collect();
+
+        assert_eq!(dropped, vec![5, 6, 7]);
+        assert_eq!(shaper.drain().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(shaper.queued_bytes(), 0);
+
+    }
+
+    #[test]
+    fn test_codel_keeps_the_queueing_delay_down() {
+
+        // An unmanaged deep queue: the delay grows as long as the overload lasts
+        let (bloated, tail_drops) = overload(&ShaperOptions::default());
+
+        assert!(bloated > Duration::from_secs(2), "{bloated:?}");
+        assert_eq!(tail_drops, 0);
+
+        let (managed, codel_drops) = overload(&ShaperOptions {
+            discipline: QueueDiscipline::Codel,
+            ..Default::default()
+        });
+
+        assert!(managed < Duration::from_millis(500), "{managed:?}");
+        assert!(codel_drops > 0);
+
+    }
+}
@@ -0,0 +1,95 @@
+use crate::settings::shaper::ShaperOptions;
+use rand::Rng;
+
+/// Weight of each new sample in the average queue fill.
+///
+/// The value suggested by Floyd and Jacobson, so short bursts pass while a
+/// standing queue is soon noticed.
+const AVERAGE_WEIGHT: f64 = 0.002;
+
+/// Random Early Detection (Floyd and Jacobson, 1993).
+///
+/// Keeps an exponentially weighted average of the queue fill, sampled on
+/// each arrival. Between the minimum and maximum thresholds arriving packets
+/// are dropped with a chance rising linearly to the maximum probability;
+/// above the maximum threshold every arriving packet is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct Red {
+    average_fill: f64,
+    since_last_drop: u32,
+}
+
+impl Red {
+    /// Returns whether to drop an arriving packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `fill` - Current fill of the queue as a fraction of its limit
+    /// * `options` - Thresholds and maximum drop probability
+    /// * `rng` - Random number generator for the drop decision
+    pub fn should_drop(
+        &mut self,
+        fill: f64,
+        options: &ShaperOptions,
+        rng: &mut impl Rng,
+    ) -> bool {
+
+        self.average_fill += AVERAGE_WEIGHT * (fill - self.average_fill);
+
+        if self.average_fill < options.red_min_threshold {
+            self.since_last_drop = 0;
+            return false;
+        }
+
+        if self.average_fill >= options.red_max_threshold {
+            self.since_last_drop = 0;
+            return true;
+        }
+
+        // Spread drops evenly: the chance grows with each packet let through
+        let (min, max) = (options.red_min_threshold, options.red_max_threshold);
+        let base = options.red_max_probability.value() * (self.average_fill - min) / (max - min);
+        let spread = f64::from(self.since_last_drop).mul_add(-base, 1.0);
+        let chance = if spread > 0.0 { base / spread } else { 1.0 };
+
+        if rng.random_bool(chance.clamp(0.0, 1.0)) {
+            self.since_last_drop = 0;
+            true
+        } else {
+            self.since_last_drop += 1;
+            false
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::types::rng::module_rng;
+
+    #[test]
+    fn test_red_drops_more_as_the_queue_fills() {
+
+        let options = ShaperOptions::default();
+        let mut rng = module_rng(Some(1), "red");
+        let mut red = Red::default();
+
+        // A briefly full queue barely moves the average
+        assert!(!(0..10).any(|_| red.should_drop(1.0, &options, &mut rng)));
+
+        // A standing queue pushes the average past the thresholds
+        let drops = (0..2000)
+            .filter(|_| red.should_drop(1.0, &options, &mut rng))
+            .count();
+        assert!(drops > 0);
+        assert!(red.should_drop(1.0, &options, &mut rng));
+
+        // An empty queue lets the average decay again
+        for _ in 0..5000 {
+            red.should_drop(0.0, &options, &mut rng);
+        }
+        assert!(!red.should_drop(0.0, &options, &mut rng));
+
+    }
+}
@@ -0,0 +1,92 @@
+//! Time sources for packet modules.
+//!
+//! Modules read the current time from a [`Clock`] instead of calling
+//! `Instant::now()` directly. Live processing uses [`SystemClock`]; tests and
+//! offline replay use [`ManualClock`], which only moves when advanced, so
+//! time-dependent modules behave the same regardless of how fast the host runs.
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of the current time for packet processing.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns the time elapsed since `earlier`, or zero if it lies in the future.
+    fn elapsed(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// Clock shared between the processing state and its owner.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// Clock backed by the operating system's monotonic time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only advances when told to.
+///
+/// Starts at the moment it is created and stands still until
+/// [`advance`](Self::advance) is called.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<Instant>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at the current time.
+    pub fn new() -> Self {
+        Self {
+            now: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+
+        let mut now = self.now.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        *now += duration;
+
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_advances_only_when_told() {
+
+        let clock = ManualClock::new();
+        let start = clock.now();
+
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::from_millis(250));
+
+        assert_eq!(clock.elapsed(start), Duration::from_millis(250));
+        assert_eq!(clock.elapsed(clock.now() + Duration::from_secs(1)), Duration::ZERO);
+
+    }
+}
@@ -8,50 +8,51 @@ use std::time::{Duration, Instant};
 /// Used in reordering functionality to delay packet delivery.
 /// Implements `Ord` and `PartialOrd` to enable use in a priority queue.
 #[derive(Debug)]
-pub struct DelayedPacket<'a> {
+pub struct DelayedPacket {
     /// The packet data to be delivered
-    pub packet: PacketData<'a>,
+    pub packet: PacketData,
     /// Timestamp representing when this packet should be delivered
     pub delay_until: Instant,
 }
 
-impl PartialEq for DelayedPacket<'_> {
+impl PartialEq for DelayedPacket {
     fn eq(&self, other: &Self) -> bool {
         self.delay_until == other.delay_until
     }
 }
 
-impl Eq for DelayedPacket<'_> {}
+impl Eq for DelayedPacket {}
 
-impl PartialOrd for DelayedPacket<'_> {
+impl PartialOrd for DelayedPacket {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for DelayedPacket<'_> {
+impl Ord for DelayedPacket {
     fn cmp(&self, other: &Self) -> Ordering {
         // Note: We flip the ordering here to turn BinaryHeap into a min-heap based on delay_until
         other.delay_until.cmp(&self.delay_until)
     }
 }
 
-impl<'a> DelayedPacket<'a> {
+impl DelayedPacket {
     /// Creates a new delayed packet
     ///
     /// # Arguments
     ///
     /// * `packet` - The packet to be delayed
     /// * `delay` - How long to delay the packet
+    /// * `now` - Current time from the module clock
     ///
     /// # Returns
     ///
     /// A new `DelayedPacket` with delivery time set to now + delay
-    pub fn new(packet: PacketData<'a>, delay: Duration) -> Self {
+    pub fn new(packet: PacketData, delay: Duration, now: Instant) -> Self {
 
         Self {
             packet,
-            delay_until: Instant::now() + delay,
+            delay_until: now + delay,
         }
 
     }
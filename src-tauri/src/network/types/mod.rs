@@ -1,2 +1,4 @@
+pub mod clock;
 pub mod delayed_packet;
 pub mod probability;
+pub mod rng;
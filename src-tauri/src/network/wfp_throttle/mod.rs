@@ -2,6 +2,28 @@
 //!
 //! Uses Windows Filtering Platform to intercept and throttle inbound traffic.
 //! This is a user-mode approach that may have limitations compared to kernel drivers.
+//! Packets are paced by the same token bucket shaper as the inline bandwidth module.
+//! On other platforms the throttle cannot be started.
+use thiserror::Error;
+
+#[cfg(windows)]
 mod throttle;
+#[cfg(not(windows))]
+mod unsupported;
+
+#[cfg(windows)]
+pub use throttle::WfpThrottle;
+#[cfg(not(windows))]
+pub use unsupported::WfpThrottle;
 
-pub use throttle::{WfpError, WfpThrottle};
+#[derive(Error, Debug)]
+pub enum WfpError {
+    #[error("Failed to open WinDivert: {0}")]
+    OpenFailed(String),
+    #[error("Failed to start throttle thread: {0}")]
+    ThreadFailed(String),
+    #[error("Invalid parameter: {0}")]
+    InvalidParam(String),
+    #[error("WFP throttling is only available on Windows")]
+    Unsupported,
+}
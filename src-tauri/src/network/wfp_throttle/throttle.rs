@@ -1,10 +1,11 @@
+use super::WfpError;
+use crate::network::shaper::Shaper;
+use crate::settings::shaper::ShaperOptions;
 use log::{info, warn};
-use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
-use thiserror::Error;
 use windivert::CloseAction;
 use windivert::packet::WinDivertPacket;
 use windivert::prelude::*;
@@ -17,32 +18,8 @@ const MIN_PAYLOAD_THRESHOLD: usize = 52;
 // Tauri devtools port to exclude
 const TAURI_PORT: u16 = 1420;
 
-#[derive(Error, Debug)]
-pub enum WfpError {
-    #[error("Failed to open WinDivert: {0}")]
-    OpenFailed(String),
-    #[error("Failed to start throttle thread: {0}")]
-    ThreadFailed(String),
-    #[error("Invalid parameter: {0}")]
-    InvalidParam(String),
-}
-
-/// Shared buffer between receiver and sender threads
-struct SharedBuffer {
-    packets: VecDeque<(WinDivertPacket<'static, NetworkLayer>, Instant)>, // (packet, queued_time)
-    total_bytes: usize,
-}
-
-impl SharedBuffer {
-    fn new() -> Self {
-
-        Self {
-            packets: VecDeque::new(),
-            total_bytes: 0,
-        }
-
-    }
-}
+/// Shaper shared between the receiver and sender threads
+type SharedShaper = Arc<Mutex<Shaper<WinDivertPacket<'static, NetworkLayer>>>>;
 
 /// High-precision bandwidth throttle
 pub struct WfpThrottle {
@@ -58,11 +35,13 @@ impl WfpThrottle {
     ///
     /// # Arguments
     /// * `limit_kbps` - Bandwidth limit in KB/s (e.g., 0.5 = 0.5 KB/s, 10.0 = 10 KB/s)
+    /// * `shaper_options` - Token bucket depth, queue limits and queue discipline
     /// * `_process_name` - Process filter (currently not used, filters all IP traffic)
     /// * `inbound` - Throttle inbound traffic
     /// * `outbound` - Throttle outbound traffic
     pub fn new(
         limit_kbps: f64,
+        shaper_options: &ShaperOptions,
         _process_name: &str,
         inbound: bool,
         outbound: bool,
@@ -79,11 +58,15 @@ impl WfpThrottle {
         }
 
         let running = Arc::new(AtomicBool::new(true));
-        let buffer = Arc::new(Mutex::new(SharedBuffer::new()));
+        let shaper = Arc::new(Mutex::new(Shaper::new(
+            limit_kbps * 1024.0,
+            shaper_options,
+            Instant::now(),
+        )));
 
         info!(
-            "WFP Throttle: Starting {} KB/s throttle (in={}, out={})",
-            limit_kbps, inbound, outbound
+            "WFP Throttle: Starting {} KB/s throttle (in={}, out={}, {:?})",
+            limit_kbps, inbound, outbound, shaper_options.discipline
         );
 
         let filter = match (inbound, outbound) {
@@ -109,24 +92,24 @@ impl WfpThrottle {
         let wd = Arc::new(Mutex::new(Some(wd)));
 
         let running_rx = running.clone();
-        let buffer_rx = buffer.clone();
+        let shaper_rx = shaper.clone();
         let wd_rx = wd.clone();
 
         let receiver_handle = thread::Builder::new()
             .name("wfp-throttle-rx".into())
             .spawn(move || {
-                run_receiver(wd_rx, buffer_rx, running_rx);
+                run_receiver(wd_rx, shaper_rx, running_rx);
             })
             .map_err(|e| WfpError::ThreadFailed(e.to_string()))?;
 
         let running_tx = running.clone();
-        let buffer_tx = buffer;
+        let shaper_tx = shaper;
         let wd_tx = wd.clone();
 
         let sender_handle = thread::Builder::new()
             .name("wfp-throttle-tx".into())
             .spawn(move || {
-                run_sender(wd_tx, buffer_tx, running_tx, limit_kbps);
+                run_sender(wd_tx, shaper_tx, running_tx, limit_kbps);
             })
             .map_err(|e| WfpError::ThreadFailed(e.to_string()))?;
 
@@ -183,19 +166,21 @@ impl Drop for WfpThrottle {
     }
 }
 
-/// Receiver thread: captures packets and buffers them (or passes through small ones)
+/// Receiver thread: captures packets and queues them (or passes through small ones)
 fn run_receiver(
     wd: Arc<Mutex<Option<WinDivert<NetworkLayer>>>>,
-    buffer: Arc<Mutex<SharedBuffer>>,
+    shaper: SharedShaper,
     running: Arc<AtomicBool>,
 ) {
 
     info!("WFP Throttle: Receiver thread started");
 
     let mut recv_buffer = vec![0u8; 65535];
+    let mut rng = rand::rng();
     let mut packet_count: u64 = 0;
     let mut buffered_count: u64 = 0;
     let mut passthrough_count: u64 = 0;
+    let mut dropped_count: u64 = 0;
     let mut last_log = Instant::now();
 
     loop {
@@ -229,19 +214,23 @@ fn run_receiver(
                     continue;
                 }
 
-                buffered_count += 1;
-
                 let owned_packet = packet.into_owned();
 
-                if let Ok(mut buf) = buffer.lock() {
-                    buf.total_bytes += packet_size;
-                    buf.packets.push_back((owned_packet, Instant::now()));
+                if let Ok(mut shaper) = shaper.lock() {
+                    if shaper
+                        .enqueue(owned_packet, packet_size, Instant::now(), &mut rng)
+                        .is_ok()
+                    {
+                        buffered_count += 1;
+                    } else {
+                        dropped_count += 1;
+                    }
                 }
 
                 if last_log.elapsed() > Duration::from_secs(5) {
                     info!(
-                        "WFP Throttle RX: {} total, {} buffered, {} passthrough",
-                        packet_count, buffered_count, passthrough_count
+                        "WFP Throttle RX: {} total, {} buffered, {} passthrough, {} dropped",
+                        packet_count, buffered_count, passthrough_count, dropped_count
                     );
                     last_log = Instant::now();
                 }
@@ -253,16 +242,16 @@ fn run_receiver(
     }
 
     info!(
-        "WFP Throttle: Receiver exiting. Total: {} packets ({} buffered, {} passthrough)",
-        packet_count, buffered_count, passthrough_count
+        "WFP Throttle: Receiver exiting. {} packets ({} buffered, {} passthrough, {} dropped)",
+        packet_count, buffered_count, passthrough_count, dropped_count
     );
 
 }
 
-/// Sender thread: releases buffered packets at the controlled rate
+/// Sender thread: releases queued packets as the shaper allows
 fn run_sender(
     wd: Arc<Mutex<Option<WinDivert<NetworkLayer>>>>,
-    buffer: Arc<Mutex<SharedBuffer>>,
+    shaper: SharedShaper,
     running: Arc<AtomicBool>,
     limit_kbps: f64,
 ) {
@@ -276,15 +265,9 @@ fn run_sender(
         windows::Win32::Media::timeBeginPeriod(1);
     }
 
-    let bytes_per_ms = limit_kbps * 1024.0 / 1000.0;
-
-    let burst_size = limit_kbps * 1024.0 * 8.0;
-    let max_bucket = limit_kbps * 1024.0 * 4.0;
-
-    let max_packet_age = Duration::from_secs(12);
-
-    let mut bytes_credit: f64 = burst_size;
-    let mut last_time = Instant::now();
+    let mut released = Vec::new();
+    let mut dropped = Vec::new();
+    let mut dropped_count: usize = 0;
 
     while running.load(Ordering::SeqCst) {
         {
@@ -295,66 +278,43 @@ fn run_sender(
             }
         }
 
-        let now = Instant::now();
-        let elapsed_ms = now.duration_since(last_time).as_secs_f64() * 1000.0;
-
-        bytes_credit += bytes_per_ms * elapsed_ms;
-        last_time = now;
+        {
+            let Ok(mut shaper) = shaper.lock() else { break };
 
-        if bytes_credit > max_bucket {
-            bytes_credit = max_bucket;
+            shaper.release(Instant::now(), &mut released, &mut dropped);
+            dropped_count += dropped.len();
+            dropped.clear();
         }
 
-        let mut released = false;
-
-        loop {
-            let packet_to_send = {
-                let Ok(mut buf) = buffer.lock() else { break };
-
-                let Some((packet, queued_time)) = buf.packets.front() else {
-                    break;
-                };
-
-                let size = packet.data.len() as f64;
-                let packet_age = queued_time.elapsed();
-
-                let force_release = packet_age >= max_packet_age;
-
-                if !force_release && bytes_credit < size {
-                    break;
-                }
-
-                if !force_release {
-                    bytes_credit -= size;
-                }
-
-                buf.total_bytes -= packet.data.len();
-                buf.packets.pop_front()
-            };
-
-            let Some((packet, _)) = packet_to_send else {
-                break;
-            };
+        let sleep_duration = if released.is_empty() {
+            Duration::from_millis(1)
+        } else {
+            Duration::from_micros(100)
+        };
 
+        if !released.is_empty() {
             if let Ok(guard) = wd.lock() {
                 if let Some(handle) = guard.as_ref() {
-                    let _ = handle.send(&packet);
+                    for packet in &released {
+                        let _ = handle.send(packet);
+                    }
                 }
             }
-            released = true;
+            released.clear();
         }
 
-        let sleep_duration = if released {
-            Duration::from_micros(100)
-        } else {
-            Duration::from_millis(1)
-        };
-
         thread::sleep(sleep_duration);
     }
 
-    let Ok(mut buf) = buffer.lock() else {
-        warn!("WFP Throttle: Could not lock buffer for flush!");
+    if dropped_count > 0 {
+        info!(
+            "WFP Throttle: Queue discipline dropped {} packets",
+            dropped_count
+        );
+    }
+
+    let Ok(mut shaper) = shaper.lock() else {
+        warn!("WFP Throttle: Could not lock queue for flush!");
         unsafe {
             windows::Win32::Media::timeEndPeriod(1);
         }
@@ -362,7 +322,7 @@ fn run_sender(
         return;
     };
 
-    let remaining = buf.packets.len();
+    let remaining = shaper.len();
 
     if remaining > 0 {
         info!(
@@ -379,7 +339,7 @@ fn run_sender(
                     "WFP Throttle: Handle already closed, {} packets LOST!",
                     remaining
                 );
-                buf.total_bytes = 0;
+                shaper.drain().for_each(drop);
                 unsafe {
                     windows::Win32::Media::timeEndPeriod(1);
                 }
@@ -387,7 +347,7 @@ fn run_sender(
                 return;
             };
 
-            while let Some((packet, _)) = buf.packets.pop_front() {
+            for packet in shaper.drain() {
                 match handle.send(&packet) {
                     Ok(_) => sent += 1,
                     Err(_) => failed += 1,
@@ -398,7 +358,6 @@ fn run_sender(
             "WFP Throttle: Flushed {} packets (sent={}, failed={})",
             remaining, sent, failed
         );
-        buf.total_bytes = 0;
     }
 
     unsafe {
@@ -0,0 +1,31 @@
+//! Stand-in for platforms without `WinDivert`.
+use super::WfpError;
+use crate::settings::shaper::ShaperOptions;
+
+/// Bandwidth throttle that can never be started
+pub struct WfpThrottle {
+    _private: (),
+}
+
+impl WfpThrottle {
+    /// Always fails, as there is no `WinDivert` to intercept packets with
+    pub fn new(
+        _limit_kbps: f64,
+        _shaper_options: &ShaperOptions,
+        _process_name: &str,
+        _inbound: bool,
+        _outbound: bool,
+    ) -> Result<Self, WfpError> {
+        Err(WfpError::Unsupported)
+    }
+
+    pub fn is_running(&self) -> bool {
+        false
+    }
+
+    pub fn limit_kbps(&self) -> f64 {
+        0.0
+    }
+
+    pub fn stop(&mut self) {}
+}
@@ -2,7 +2,9 @@
 pub use crate::error::{MyraError, Result};
 
 // Network core
-pub use crate::network::core::{HandleConfig, HandleManager, PacketData, flush_wfp_cache};
+#[cfg(windows)]
+pub use crate::network::core::{HandleConfig, HandleManager};
+pub use crate::network::core::{PacketData, flush_wfp_cache};
 
 // Module traits
 pub use crate::network::modules::traits::{ModuleContext, ModuleOptions, PacketModule};
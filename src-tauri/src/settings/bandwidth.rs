@@ -1,5 +1,7 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
+use crate::settings::direction::PerDirection;
+use crate::settings::shaper::ShaperOptions;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +23,10 @@ pub struct BandwidthOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Maximum bandwidth limit in KB/s
     #[arg(long = "bandwidth-limit", id = "bandwidth-limit", default_value_t = 0)]
     #[serde(default)]
@@ -44,9 +50,13 @@ pub struct BandwidthOptions {
     #[serde(default = "default_passthrough_threshold")]
     pub passthrough_threshold: usize,
     /// Use WFP (`WinDivert`) token bucket algorithm for precise rate limiting
-    /// When true: Uses separate `WinDivert` handle with proper token bucket
-    /// When false: Uses inline packet pacing through the main processor
+    /// When true: Uses separate `WinDivert` handle with its own sender thread
+    /// When false: Uses the inline shaper of the main processor
     #[arg(skip)]
     #[serde(default)]
     pub use_wfp: bool,
+    /// Token bucket depth, queue limits and queue discipline of the limiter
+    #[command(flatten)]
+    #[serde(default)]
+    pub shaper: ShaperOptions,
 }
@@ -18,11 +18,15 @@
 use crate::network::types::probability::Probability;
 use crate::settings::bandwidth::BandwidthOptions;
 use crate::settings::corruption::CorruptionOptions;
-use crate::settings::drop::DropOptions;
+use crate::settings::direction::PerDirection;
+use crate::settings::drop::{DropModel, DropOptions, GilbertElliottOptions};
 use crate::settings::duplicate::DuplicateOptions;
-use crate::settings::lag::LagOptions;
+use crate::settings::lag::{JitterDistribution, LagOptions};
 use crate::settings::manipulation::Settings;
 use crate::settings::reorder::ReorderOptions;
+use crate::settings::rules::ImpairmentRule;
+use crate::settings::shaper::ShaperOptions;
+use crate::settings::tap::TapOptions;
 use crate::settings::throttle::ThrottleOptions;
 
 /// Builder for constructing `Settings`.
@@ -52,8 +56,50 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability,
             duration_ms: 0,
+            model: DropModel::Uniform,
+            gilbert_elliott: GilbertElliottOptions::default(),
+        });
+        self
+
+    }
+
+    /// Enables bursty packet loss using the Gilbert-Elliott model.
+    ///
+    /// All values are percentages (0-100).
+    ///
+    /// # Arguments
+    ///
+    /// * `good_to_bad` - Chance per packet of entering the bad state
+    /// * `bad_to_good` - Chance per packet of leaving the bad state
+    /// * `good_loss` - Loss rate while in the good state
+    /// * `bad_loss` - Loss rate while in the bad state
+    pub fn drop_gilbert_elliott(
+        mut self,
+        good_to_bad: f64,
+        bad_to_good: f64,
+        good_loss: f64,
+        bad_loss: f64,
+    ) -> Self {
+
+        let percent = |chance: f64| Probability::new(chance / 100.0).unwrap_or_default();
+
+        self.settings.drop = Some(DropOptions {
+            enabled: true,
+            inbound: true,
+            outbound: true,
+            per_direction: PerDirection::default(),
+            probability: Probability::default(),
+            duration_ms: 0,
+            model: DropModel::GilbertElliott,
+            gilbert_elliott: GilbertElliottOptions {
+                good_to_bad: percent(good_to_bad),
+                bad_to_good: percent(bad_to_good),
+                good_loss: percent(good_loss),
+                bad_loss: percent(bad_loss),
+            },
         });
         self
 
@@ -84,14 +130,49 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             delay_ms,
             probability: Probability::new(1.0).unwrap_or_default(),
             duration_ms: 0,
+            ..LagOptions::default()
         });
         self
 
     }
 
+    /// Adds random jitter to the lag time.
+    ///
+    /// # Arguments
+    ///
+    /// * `jitter_ms` - Jitter in milliseconds
+    /// * `distribution` - Distribution the jitter is drawn from
+    /// * `correlation` - Correlation with the previous jitter value as percentage (0.0 to 100.0)
+    pub fn with_lag_jitter(
+        mut self,
+        jitter_ms: u64,
+        distribution: JitterDistribution,
+        correlation: f64,
+    ) -> Self {
+
+        if let Some(ref mut lag) = self.settings.lag {
+            lag.jitter_ms = jitter_ms;
+            lag.distribution = distribution;
+            lag.correlation = Probability::new(correlation / 100.0).unwrap_or_default();
+        }
+        self
+
+    }
+
+    /// Keeps packets of the same flow in order despite jitter.
+    pub fn with_lag_order_preserved(mut self) -> Self {
+
+        if let Some(ref mut lag) = self.settings.lag {
+            lag.preserve_order = true;
+        }
+        self
+
+    }
+
     /// Sets the probability for the lag effect.
     ///
     /// # Arguments
@@ -131,6 +212,7 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::new(1.0).unwrap_or_default(),
             throttle_ms,
             duration_ms: 0,
@@ -181,6 +263,7 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::new(1.0).unwrap_or_default(),
             max_delay: max_delay_ms,
             duration_ms: 0,
@@ -214,6 +297,7 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::new(chance / 100.0).unwrap_or_default(),
             amount: Probability::new(0.5).unwrap_or_default(),
             duration_ms: 0,
@@ -262,6 +346,7 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::new(1.0).unwrap_or_default(),
             count,
             duration_ms: 0,
@@ -295,11 +380,13 @@ impl SettingsBuilder {
             enabled: true,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             limit: limit_kbps,
             probability: Probability::new(1.0).unwrap_or_default(),
             duration_ms: 0,
             passthrough_threshold: 200,
             use_wfp: false,
+            shaper: ShaperOptions::default(),
         });
         self
 
@@ -319,6 +406,133 @@ impl SettingsBuilder {
 
     }
 
+    /// Sets the token bucket and queue of the bandwidth limiter.
+    ///
+    /// # Arguments
+    ///
+    /// * `shaper` - Bucket depth, queue limits and queue discipline
+    pub fn with_bandwidth_shaper(mut self, shaper: ShaperOptions) -> Self {
+
+        if let Some(ref mut bandwidth) = self.settings.bandwidth {
+            bandwidth.shaper = shaper;
+        }
+        self
+
+    }
+
+    /// Seeds the module random number generators for reproducible runs.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Session seed shared by all modules
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.settings.seed = Some(seed);
+        self
+    }
+
+    /// Adds a per-flow impairment rule.
+    ///
+    /// Rules are checked in the order they are added.
+    ///
+    /// # Arguments
+    ///
+    /// * `rule` - Packet match and the module settings for matching packets
+    pub fn rule(mut self, rule: ImpairmentRule) -> Self {
+        self.settings.rules.push(rule);
+        self
+    }
+
+    /// Taps every module off for `duration_ms` once every `interval_ms`.
+    ///
+    /// # Arguments
+    ///
+    /// * `interval_ms` - Time between the starts of two taps
+    /// * `duration_ms` - How long the modules stay off
+    pub fn tap(mut self, interval_ms: u64, duration_ms: u64) -> Self {
+
+        self.settings.tap = Some(TapOptions {
+            enabled: true,
+            interval_ms,
+            duration_ms,
+            ..Default::default()
+        });
+        self
+
+    }
+
+    /// Uses the modules configured by `configure` for inbound packets.
+    ///
+    /// Only modules already configured on this builder are affected; they
+    /// keep their own options for outbound packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `configure` - Configures the inbound options on a fresh builder
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // 8 Mbit/s down, 1 Mbit/s up
+    /// let settings = SettingsBuilder::new()
+    ///     .bandwidth(125)
+    ///     .with_inbound(|inbound| inbound.bandwidth(1000))
+    ///     .build();
+    /// ```
+    pub fn with_inbound(self, configure: impl FnOnce(Self) -> Self) -> Self {
+        self.with_direction(false, configure(Self::new()).build())
+    }
+
+    /// Uses the modules configured by `configure` for outbound packets.
+    ///
+    /// Only modules already configured on this builder are affected; they
+    /// keep their own options for inbound packets.
+    ///
+    /// # Arguments
+    ///
+    /// * `configure` - Configures the outbound options on a fresh builder
+    pub fn with_outbound(self, configure: impl FnOnce(Self) -> Self) -> Self {
+        self.with_direction(true, configure(Self::new()).build())
+    }
+
+    /// Sets the modules of `direction` as the options for one direction.
+    fn with_direction(mut self, outbound: bool, direction: Settings) -> Self {
+
+        let settings = &mut self.settings;
+
+        if let (Some(options), Some(direction)) = (settings.drop.as_mut(), direction.drop) {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) = (settings.lag.as_mut(), direction.lag) {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) = (settings.throttle.as_mut(), direction.throttle) {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) = (settings.reorder.as_mut(), direction.reorder) {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) =
+            (settings.corruption.as_mut(), direction.corruption)
+        {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) =
+            (settings.duplicate.as_mut(), direction.duplicate)
+        {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) =
+            (settings.bandwidth.as_mut(), direction.bandwidth)
+        {
+            options.per_direction.set(outbound, direction);
+        }
+        if let (Some(options), Some(direction)) = (settings.burst.as_mut(), direction.burst) {
+            options.per_direction.set(outbound, direction);
+        }
+        self
+
+    }
+
     /// Clears all settings, resetting to default.
     pub fn clear(mut self) -> Self {
         self.settings = Settings::default();
@@ -391,6 +605,7 @@ impl Settings {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::settings::shaper::QueueDiscipline;
 
     #[test]
     fn test_builder_default() {
@@ -409,6 +624,63 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_builder_drop_gilbert_elliott() {
+
+        let settings = SettingsBuilder::new()
+            .drop_gilbert_elliott(2.0, 20.0, 0.0, 90.0)
+            .build();
+
+        let drop = settings.drop.unwrap();
+
+        assert_eq!(drop.model, DropModel::GilbertElliott);
+        assert!((drop.gilbert_elliott.good_to_bad.value() - 0.02).abs() < 0.001);
+        assert!((drop.gilbert_elliott.bad_to_good.value() - 0.2).abs() < 0.001);
+        assert!((drop.gilbert_elliott.bad_loss.value() - 0.9).abs() < 0.001);
+
+    }
+
+    #[test]
+    fn test_builder_per_direction() {
+
+        let settings = SettingsBuilder::new()
+            .lag(20)
+            .bandwidth(1000)
+            .with_outbound(|outbound| outbound.lag(60).bandwidth(125).drop(5.0))
+            .build();
+
+        let lag = settings.lag.unwrap();
+        assert_eq!(lag.delay_ms, 20);
+        assert_eq!(lag.per_direction.get(true).map(|lag| lag.delay_ms), Some(60));
+        assert!(lag.per_direction.get(false).is_none());
+
+        let bandwidth = settings.bandwidth.unwrap();
+        assert_eq!(bandwidth.per_direction.get(true).map(|bw| bw.limit), Some(125));
+
+        // Modules not configured for both directions are left alone
+        assert!(settings.drop.is_none());
+
+    }
+
+    #[test]
+    fn test_builder_bandwidth_shaper() {
+
+        let settings = SettingsBuilder::new()
+            .bandwidth(1000)
+            .with_bandwidth_shaper(ShaperOptions {
+                queue_packets: 64,
+                discipline: QueueDiscipline::Codel,
+                ..Default::default()
+            })
+            .build();
+
+        let shaper = settings.bandwidth.unwrap().shaper;
+
+        assert_eq!(shaper.queue_packets, 64);
+        assert_eq!(shaper.discipline, QueueDiscipline::Codel);
+
+    }
+
     #[test]
     fn test_builder_lag_with_chance() {
 
@@ -426,6 +698,24 @@ mod tests {
 
     }
 
+    #[test]
+    fn test_builder_lag_jitter() {
+
+        let settings = SettingsBuilder::new()
+            .lag(100)
+            .with_lag_jitter(20, JitterDistribution::Pareto, 25.0)
+            .with_lag_order_preserved()
+            .build();
+
+        let lag = settings.lag.unwrap();
+
+        assert_eq!(lag.jitter_ms, 20);
+        assert_eq!(lag.distribution, JitterDistribution::Pareto);
+        assert!((lag.correlation.value() - 0.25).abs() < 0.001);
+        assert!(lag.preserve_order);
+
+    }
+
     #[test]
     fn test_builder_multiple_modules() {
 
@@ -1,5 +1,6 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
+use crate::settings::direction::PerDirection;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,10 @@ pub struct BurstOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Buffer time in milliseconds - how long to hold packets before releasing
     /// 0 = manual mode (hold until disabled)
     #[arg(long = "burst-buffer-ms", id = "burst-buffer-ms", default_value_t = 0)]
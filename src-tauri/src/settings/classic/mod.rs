@@ -18,6 +18,8 @@ pub use throttle::ClassicThrottleOptions;
 
 use serde::{Deserialize, Serialize};
 
+use crate::settings::{ModuleKind, TapOptions};
+
 /// All Classic mode settings combined.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ClassicSettings {
@@ -39,6 +41,12 @@ pub struct ClassicSettings {
     /// Bandwidth module - rate limiting
     #[serde(default)]
     pub bandwidth: Option<ClassicBandwidthOptions>,
+    /// Seed for the module random number generators (`None` = system entropy)
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Tap options, shared with Standard mode through `set_tap`
+    #[serde(default)]
+    pub tap: Option<TapOptions>,
 }
 
 impl ClassicSettings {
@@ -53,4 +61,30 @@ impl ClassicSettings {
             || self.bandwidth.as_ref().is_some_and(|o| o.enabled)
 
     }
+
+    /// Switches off the modules tapped by `tap`.
+    ///
+    /// Standard modules map onto their Classic counterparts: lag taps latency
+    /// and corruption taps tamper. Duplicate and burst have no counterpart.
+    pub fn apply_tap(&mut self, tap: &TapOptions) {
+
+        let modules: &[ModuleKind] = if tap.modules.is_empty() {
+            &ModuleKind::ALL
+        } else {
+            &tap.modules
+        };
+
+        for module in modules {
+            match module {
+                ModuleKind::Drop => self.drop.iter_mut().for_each(|o| o.enabled = false),
+                ModuleKind::Lag => self.latency.iter_mut().for_each(|o| o.enabled = false),
+                ModuleKind::Throttle => self.throttle.iter_mut().for_each(|o| o.enabled = false),
+                ModuleKind::Reorder => self.reorder.iter_mut().for_each(|o| o.enabled = false),
+                ModuleKind::Corruption => self.tamper.iter_mut().for_each(|o| o.enabled = false),
+                ModuleKind::Bandwidth => self.bandwidth.iter_mut().for_each(|o| o.enabled = false),
+                ModuleKind::Duplicate | ModuleKind::Burst => {}
+            }
+        }
+
+    }
 }
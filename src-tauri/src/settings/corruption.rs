@@ -1,5 +1,6 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
+use crate::settings::direction::PerDirection;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,10 @@ pub struct CorruptionOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Probability of corruptioning packets, ranging from 0.0 to 1.0
     #[arg(long = "corruption-probability", id = "corruption-probability", default_value_t = Probability::default())]
     #[serde(default)]
@@ -49,6 +54,7 @@ impl Default for CorruptionOptions {
             enabled: false,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::default(),
             amount: Probability::new(0.1).unwrap(),
             duration_ms: 0,
@@ -0,0 +1,117 @@
+//! Separate module parameters for each direction.
+//!
+//! Real links are rarely symmetric: uploads usually see less bandwidth and
+//! often more delay than downloads. Every module's options can carry a
+//! [`PerDirection`] with complete options for inbound and for outbound
+//! packets, used instead of the module's own parameters for that direction.
+use serde::{Deserialize, Serialize};
+
+/// Options of a module for inbound and outbound packets.
+///
+/// A direction without options uses those of the module. Whether the module
+/// is enabled, which directions it applies to and how long its effect lasts
+/// always come from the module's own options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerDirection<T> {
+    /// Options for inbound (download) packets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inbound: Option<Box<T>>,
+    /// Options for outbound (upload) packets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outbound: Option<Box<T>>,
+}
+
+impl<T> PerDirection<T> {
+    /// Creates separate options for each direction.
+    pub fn new(inbound: Option<T>, outbound: Option<T>) -> Self {
+        Self {
+            inbound: inbound.map(Box::new),
+            outbound: outbound.map(Box::new),
+        }
+    }
+
+    /// Returns whether neither direction has options of its own.
+    pub const fn is_empty(&self) -> bool {
+        self.inbound.is_none() && self.outbound.is_none()
+    }
+
+    /// Converts the options of each direction with `convert`.
+    pub fn map<U>(&self, convert: impl Fn(&T) -> U) -> PerDirection<U> {
+        PerDirection::new(
+            self.inbound.as_deref().map(&convert),
+            self.outbound.as_deref().map(&convert),
+        )
+    }
+
+    /// Converts the options of each direction with `convert`, stopping at the first error.
+    pub fn try_map<U, E>(
+        &self,
+        convert: impl Fn(&T) -> Result<U, E>,
+    ) -> Result<PerDirection<U>, E> {
+        Ok(PerDirection::new(
+            self.inbound.as_deref().map(&convert).transpose()?,
+            self.outbound.as_deref().map(&convert).transpose()?,
+        ))
+    }
+
+    /// Sets the options for one direction.
+    pub fn set(&mut self, outbound: bool, options: T) {
+        if outbound {
+            self.outbound = Some(Box::new(options));
+        } else {
+            self.inbound = Some(Box::new(options));
+        }
+    }
+
+    /// Returns the options for one direction, if it has its own.
+    pub fn get(&self, outbound: bool) -> Option<&T> {
+        if outbound {
+            self.outbound.as_deref()
+        } else {
+            self.inbound.as_deref()
+        }
+    }
+}
+
+impl<T> Default for PerDirection<T> {
+    fn default() -> Self {
+        Self {
+            inbound: None,
+            outbound: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::lag::LagOptions;
+
+    #[test]
+    fn test_missing_directions_are_not_serialized() {
+
+        let options = LagOptions {
+            delay_ms: 20,
+            per_direction: PerDirection::new(
+                Some(LagOptions {
+                    delay_ms: 80,
+                    ..Default::default()
+                }),
+                None,
+            ),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&options).unwrap();
+        assert!(json.contains(r#""per_direction":{"inbound":{"#));
+        assert!(!json.contains(r#""outbound":{"#));
+
+        let parsed: LagOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.per_direction.get(false).map(|lag| lag.delay_ms), Some(80));
+        assert!(parsed.per_direction.get(true).is_none());
+
+        let plain: LagOptions = serde_json::from_str(r#"{"delay_ms":20}"#).unwrap();
+        assert!(plain.per_direction.is_empty());
+
+    }
+}
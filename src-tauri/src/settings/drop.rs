@@ -1,8 +1,71 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
-use clap::Parser;
+use crate::settings::direction::PerDirection;
+use clap::{Args, Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+/// Loss model used by the drop module
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DropModel {
+    /// Every packet is dropped independently with `probability`
+    #[default]
+    Uniform,
+    /// Two-state Gilbert-Elliott model that drops packets in bursts
+    GilbertElliott,
+}
+
+/// Parameters of the Gilbert-Elliott loss model.
+///
+/// The model alternates between a good and a bad state. Before each packet
+/// it may switch state, then drops the packet with the loss rate of the
+/// state it is in. The mean length of a stay in the bad state is
+/// `1 / bad_to_good` packets.
+#[derive(Args, Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct GilbertElliottOptions {
+    /// Probability of moving from the good to the bad state, per packet
+    #[arg(long = "drop-good-to-bad", id = "drop-good-to-bad", default_value = "0.01")]
+    #[serde(default = "default_good_to_bad")]
+    pub good_to_bad: Probability,
+    /// Probability of moving from the bad to the good state, per packet
+    #[arg(long = "drop-bad-to-good", id = "drop-bad-to-good", default_value = "0.25")]
+    #[serde(default = "default_bad_to_good")]
+    pub bad_to_good: Probability,
+    /// Loss rate while in the good state
+    #[arg(long = "drop-good-loss", id = "drop-good-loss", default_value_t = Probability::default())]
+    #[serde(default)]
+    pub good_loss: Probability,
+    /// Loss rate while in the bad state
+    #[arg(long = "drop-bad-loss", id = "drop-bad-loss", default_value = "1.0")]
+    #[serde(default = "default_bad_loss")]
+    pub bad_loss: Probability,
+}
+
+fn default_good_to_bad() -> Probability {
+    Probability::new(0.01).unwrap_or_default()
+}
+
+fn default_bad_to_good() -> Probability {
+    Probability::new(0.25).unwrap_or_default()
+}
+
+fn default_bad_loss() -> Probability {
+    Probability::new(1.0).unwrap_or_default()
+}
+
+impl Default for GilbertElliottOptions {
+    fn default() -> Self {
+
+        Self {
+            good_to_bad: default_good_to_bad(),
+            bad_to_good: default_bad_to_good(),
+            good_loss: Probability::default(),
+            bad_loss: default_bad_loss(),
+        }
+
+    }
+}
+
 #[derive(Parser, Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DropOptions {
     /// Whether this module is enabled
@@ -17,6 +80,10 @@ pub struct DropOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Probability of dropping packets, ranging from 0.0 to 1.0
     #[arg(long = "drop-probability", id = "drop-probability", default_value_t = Probability::default())]
     #[serde(default)]
@@ -25,4 +92,12 @@ pub struct DropOptions {
     #[arg(long = "drop-duration", id = "drop-duration", default_value_t = 0)]
     #[serde(default)]
     pub duration_ms: u64,
+    /// Loss model deciding which packets are dropped
+    #[arg(long = "drop-model", id = "drop-model", value_enum, default_value_t = DropModel::Uniform)]
+    #[serde(default)]
+    pub model: DropModel,
+    /// Parameters used when `model` is Gilbert-Elliott
+    #[command(flatten)]
+    #[serde(default)]
+    pub gilbert_elliott: GilbertElliottOptions,
 }
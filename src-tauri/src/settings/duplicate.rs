@@ -1,5 +1,6 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
+use crate::settings::direction::PerDirection;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,10 @@ pub struct DuplicateOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Probability of duplicating packets, ranging from 0.0 to 1.0
     #[arg(long = "duplicate-probability", id = "duplicate-probability", default_value_t = Probability::default())]
     #[serde(default)]
@@ -42,6 +47,7 @@ impl Default for DuplicateOptions {
             enabled: false,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             count: 1,
             probability: Probability::default(),
             duration_ms: 0,
@@ -1,8 +1,27 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
-use clap::Parser;
+use crate::settings::direction::PerDirection;
+use clap::{Parser, ValueEnum};
 use serde::{Deserialize, Serialize};
 
+/// Distribution of the random jitter added to the lag time.
+///
+/// Follows netem: `jitter_ms` is the half-width of the range for
+/// `Uniform` and the standard deviation for the other distributions.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterDistribution {
+    /// Evenly spread over `delay_ms ± jitter_ms`
+    #[default]
+    Uniform,
+    /// Bell curve centred on `delay_ms`
+    Normal,
+    /// Heavy tail of occasional long delays
+    Pareto,
+    /// Mix of 25% normal and 75% Pareto
+    ParetoNormal,
+}
+
 fn default_probability_100() -> Probability {
     Probability::new(1.0).unwrap()
 }
@@ -26,6 +45,10 @@ pub struct LagOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Lag time in milliseconds to introduce for each packet
     #[arg(long = "lag-ms", id = "lag-ms", default_value_t = 0)]
     #[serde(default)]
@@ -38,6 +61,22 @@ pub struct LagOptions {
     #[arg(long = "lag-duration", id = "lag-duration", default_value_t = 0)]
     #[serde(default)]
     pub duration_ms: u64,
+    /// Random variation of the lag time in milliseconds (0 = fixed lag)
+    #[arg(long = "lag-jitter", id = "lag-jitter", default_value_t = 0)]
+    #[serde(default)]
+    pub jitter_ms: u64,
+    /// Distribution the jitter is drawn from
+    #[arg(long = "lag-distribution", id = "lag-distribution", value_enum, default_value_t = JitterDistribution::Uniform)]
+    #[serde(default)]
+    pub distribution: JitterDistribution,
+    /// How strongly each jitter value follows the previous one, from 0.0 to 1.0
+    #[arg(long = "lag-correlation", id = "lag-correlation", default_value_t = Probability::default())]
+    #[serde(default)]
+    pub correlation: Probability,
+    /// Never release a packet before an earlier packet of the same flow
+    #[arg(long = "lag-preserve-order", id = "lag-preserve-order")]
+    #[serde(default)]
+    pub preserve_order: bool,
 }
 
 impl Default for LagOptions {
@@ -47,9 +86,14 @@ impl Default for LagOptions {
             enabled: false,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             delay_ms: 0,
             probability: default_probability_100(),
             duration_ms: 0,
+            jitter_ms: 0,
+            distribution: JitterDistribution::Uniform,
+            correlation: Probability::default(),
+            preserve_order: false,
         }
 
     }
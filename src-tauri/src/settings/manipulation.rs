@@ -5,8 +5,12 @@ use crate::settings::drop::DropOptions;
 use crate::settings::duplicate::DuplicateOptions;
 use crate::settings::lag::LagOptions;
 use crate::settings::reorder::ReorderOptions;
+use crate::settings::rules::ImpairmentRule;
+use crate::settings::scenario::ScenarioStep;
+use crate::settings::tap::TapOptions;
 use crate::settings::tc_bandwidth::TcBandwidthOptions;
 use crate::settings::throttle::ThrottleOptions;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize, Serializer};
 
 /// Custom serializer for Option<T> values in configuration.
@@ -65,6 +69,17 @@ pub struct Settings {
     /// Works at OS socket layer for true rate limiting
     #[serde(default, serialize_with = "serialize_option")]
     pub tc_bandwidth: Option<TcBandwidthOptions>,
+    /// Seed for the module random number generators
+    /// The same seed and input traffic produce the same output; `None` uses system entropy
+    #[serde(default, serialize_with = "serialize_option")]
+    pub seed: Option<u64>,
+    /// Per-flow rules, checked in order before the modules above are applied
+    /// Packets matching a rule use its module options instead of these
+    #[serde(default)]
+    pub rules: Vec<ImpairmentRule>,
+    /// Switches modules off briefly at intervals or when packets pile up
+    #[serde(default, serialize_with = "serialize_option")]
+    pub tap: Option<TapOptions>,
 }
 
 fn default_burst_release_delay() -> u64 {
@@ -86,58 +101,154 @@ impl Default for Settings {
             burst_release_delay_us: default_burst_release_delay(),
             lag_bypass: false,
             tc_bandwidth: None,
+            seed: None,
+            rules: Vec::new(),
+            tap: None,
         }
 
     }
 }
 
-// Implement ModuleOptions trait for all option types
-use crate::network::modules::traits::ModuleOptions;
-
-impl ModuleOptions for DropOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
-    }
+/// One of the packet manipulation modules of [`Settings`].
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModuleKind {
+    Drop,
+    Lag,
+    Throttle,
+    Reorder,
+    Corruption,
+    Duplicate,
+    Bandwidth,
+    Burst,
 }
 
-impl ModuleOptions for LagOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
-    }
-}
+impl ModuleKind {
+    /// Every module, in processing order.
+    pub const ALL: [Self; 8] = [
+        Self::Drop,
+        Self::Lag,
+        Self::Throttle,
+        Self::Reorder,
+        Self::Corruption,
+        Self::Duplicate,
+        Self::Bandwidth,
+        Self::Burst,
+    ];
 
-impl ModuleOptions for ThrottleOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
+    /// Clears the `enabled` flag of this module in `settings`, keeping its options.
+    pub fn disable(self, settings: &mut Settings) {
+        match self {
+            Self::Drop => settings.drop.iter_mut().for_each(|o| o.enabled = false),
+            Self::Lag => settings.lag.iter_mut().for_each(|o| o.enabled = false),
+            Self::Throttle => settings.throttle.iter_mut().for_each(|o| o.enabled = false),
+            Self::Reorder => settings.reorder.iter_mut().for_each(|o| o.enabled = false),
+            Self::Corruption => settings.corruption.iter_mut().for_each(|o| o.enabled = false),
+            Self::Duplicate => settings.duplicate.iter_mut().for_each(|o| o.enabled = false),
+            Self::Bandwidth => settings.bandwidth.iter_mut().for_each(|o| o.enabled = false),
+            Self::Burst => settings.burst.iter_mut().for_each(|o| o.enabled = false),
+        }
     }
-}
 
-impl ModuleOptions for ReorderOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
+    /// Copies the options of this module from `from` into `to`.
+    pub fn copy(self, from: &Settings, to: &mut Settings) {
+        match self {
+            Self::Drop => to.drop.clone_from(&from.drop),
+            Self::Lag => to.lag.clone_from(&from.lag),
+            Self::Throttle => to.throttle.clone_from(&from.throttle),
+            Self::Reorder => to.reorder.clone_from(&from.reorder),
+            Self::Corruption => to.corruption.clone_from(&from.corruption),
+            Self::Duplicate => to.duplicate.clone_from(&from.duplicate),
+            Self::Bandwidth => to.bandwidth.clone_from(&from.bandwidth),
+            Self::Burst => to.burst.clone_from(&from.burst),
+        }
     }
-}
 
-impl ModuleOptions for CorruptionOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
+    /// Enables this module in `to` with the options `step` gives it, if any.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the step has options for this module, `false` if `to` is left alone
+    pub fn enable_from(self, step: &ScenarioStep, to: &mut Settings) -> bool {
+        match self {
+            Self::Drop => enable_from(step.drop.as_ref(), &mut to.drop),
+            Self::Lag => enable_from(step.lag.as_ref(), &mut to.lag),
+            Self::Throttle => enable_from(step.throttle.as_ref(), &mut to.throttle),
+            Self::Reorder => enable_from(step.reorder.as_ref(), &mut to.reorder),
+            Self::Corruption => enable_from(step.corruption.as_ref(), &mut to.corruption),
+            Self::Duplicate => enable_from(step.duplicate.as_ref(), &mut to.duplicate),
+            Self::Bandwidth => enable_from(step.bandwidth.as_ref(), &mut to.bandwidth),
+            Self::Burst => enable_from(step.burst.as_ref(), &mut to.burst),
+        }
     }
 }
 
-impl ModuleOptions for DuplicateOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
-    }
-}
+/// Sets `to` to `options` with the module enabled, if there are options.
+fn enable_from<T: ModuleOptions>(options: Option<&T>, to: &mut Option<T>) -> bool {
+
+    let Some(options) = options else {
+        return false;
+    };
+    let mut enabled = options.clone();
+
+    enabled.set_enabled(true);
+    *to = Some(enabled);
+    true
 
-impl ModuleOptions for BandwidthOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
-    }
 }
 
-impl ModuleOptions for BurstOptions {
-    fn is_enabled(&self) -> bool {
-        self.enabled
-    }
+// Implement ModuleOptions trait for all option types
+use crate::network::modules::traits::ModuleOptions;
+use crate::settings::direction::PerDirection;
+use std::borrow::Cow;
+
+/// Implements [`ModuleOptions`] for options types sharing the common fields
+/// `enabled`, `inbound`, `outbound`, `duration_ms` and `per_direction`.
+///
+/// The options for one direction keep the common fields of the module and
+/// take every other field from the direction's options.
+macro_rules! impl_module_options {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl ModuleOptions for $ty {
+                fn is_enabled(&self) -> bool {
+                    self.enabled
+                }
+
+                fn set_enabled(&mut self, enabled: bool) {
+                    self.enabled = enabled;
+                }
+
+                fn is_asymmetric(&self) -> bool {
+                    !self.per_direction.is_empty()
+                }
+
+                fn for_direction(&self, outbound: bool) -> Cow<'_, Self> {
+                    self.per_direction
+                        .get(outbound)
+                        .map_or(Cow::Borrowed(self), |options| {
+                            Cow::Owned(Self {
+                                enabled: self.enabled,
+                                inbound: self.inbound,
+                                outbound: self.outbound,
+                                duration_ms: self.duration_ms,
+                                per_direction: PerDirection::default(),
+                                ..options.clone()
+                            })
+                        })
+                }
+            }
+        )*
+    };
 }
+
+impl_module_options!(
+    DropOptions,
+    LagOptions,
+    ThrottleOptions,
+    ReorderOptions,
+    CorruptionOptions,
+    DuplicateOptions,
+    BandwidthOptions,
+    BurstOptions,
+);
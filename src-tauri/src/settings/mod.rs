@@ -3,17 +3,28 @@ pub mod builder;
 pub mod burst;
 pub mod classic;
 pub mod corruption;
+pub mod direction;
 pub mod drop;
 pub mod duplicate;
 pub mod lag;
 pub mod manipulation;
+pub mod profiles;
 pub mod reorder;
+pub mod rules;
+pub mod scenario;
+pub mod shaper;
+pub mod tap;
 pub mod tc_bandwidth;
 pub mod throttle;
 
 pub use builder::SettingsBuilder;
 pub use classic::ClassicSettings;
-pub use manipulation::Settings;
+pub use direction::PerDirection;
+pub use manipulation::{ModuleKind, Settings};
+pub use profiles::{NetworkProfile, builtin_profiles, find_profile};
+pub use rules::{ImpairmentRule, PacketMatch};
+pub use scenario::{Scenario, ScenarioStep};
+pub use tap::TapOptions;
 pub use tc_bandwidth::{TcBandwidthOptions, TcDirection};
 
 /// Helper function for serde default values - returns true.
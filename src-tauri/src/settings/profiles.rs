@@ -0,0 +1,237 @@
+//! Built-in network profiles.
+//!
+//! Each profile describes a common kind of link as lag, jitter, loss and a
+//! bandwidth limit. Modules apply to both directions, so `lag` is the one-way
+//! delay, half the round trip a ping would show. The bandwidth limit is the
+//! downlink rate of the link; profiles whose source gives an uplink rate
+//! limit outbound packets to it.
+use crate::settings::SettingsBuilder;
+use crate::settings::lag::JitterDistribution;
+use crate::settings::manipulation::Settings;
+use serde::Serialize;
+
+/// A named set of module settings modelling one kind of network link.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkProfile {
+    /// Identifier used to apply the profile, e.g. `lte`
+    pub id: &'static str,
+    /// Name shown to the user
+    pub name: &'static str,
+    /// What kind of link the profile models
+    pub description: &'static str,
+    /// Where the numbers come from
+    pub source: &'static str,
+    /// Lag, drop and bandwidth settings of the link
+    pub settings: Settings,
+}
+
+impl NetworkProfile {
+    /// Applies the profile on top of `settings`.
+    ///
+    /// The lag, drop and bandwidth modules are replaced by those of the
+    /// profile; every other module, the rules, the seed and tapping are kept,
+    /// so a profile can be layered onto a saved configuration.
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.lag = self.settings.lag.clone();
+        settings.drop = self.settings.drop.clone();
+        settings.bandwidth = self.settings.bandwidth.clone();
+    }
+}
+
+/// Converts a rate in kbit/s to the KB/s used by the bandwidth module.
+fn kbit(kbit_per_second: usize) -> usize {
+    kbit_per_second / 8
+}
+
+/// Returns every built-in profile.
+pub fn builtin_profiles() -> Vec<NetworkProfile> {
+    vec![
+        NetworkProfile {
+            id: "2g",
+            name: "2G (EDGE)",
+            description: "Second generation mobile data: slow and with long delays.",
+            source: "Chrome DevTools \"Regular 2G\" throttling preset: 300 ms latency, \
+                     250 kbit/s down, 50 kbit/s up. The 2% loss is an estimate for a weak cell.",
+            settings: SettingsBuilder::new()
+                .lag(150)
+                .with_lag_jitter(40, JitterDistribution::Normal, 25.0)
+                .with_lag_order_preserved()
+                .drop(2.0)
+                .bandwidth(kbit(250))
+                .with_outbound(|outbound| outbound.bandwidth(kbit(50)))
+                .build(),
+        },
+        NetworkProfile {
+            id: "3g",
+            name: "3G",
+            description: "Third generation mobile data on an average connection.",
+            source: "Lighthouse \"mobileRegular3G\" throttling preset: 300 ms RTT, \
+                     700 kbit/s. The 1% loss is an estimate, the preset has no loss.",
+            settings: SettingsBuilder::new()
+                .lag(150)
+                .with_lag_jitter(30, JitterDistribution::Normal, 25.0)
+                .with_lag_order_preserved()
+                .drop(1.0)
+                .bandwidth(kbit(700))
+                .build(),
+        },
+        NetworkProfile {
+            id: "slow-4g",
+            name: "Slow 4G",
+            description: "LTE at the edge of a cell or in a crowded area.",
+            source: "Lighthouse \"mobileSlow4G\" throttling preset used for mobile audits: \
+                     150 ms RTT, 1.6 Mbit/s. The 0.5% loss is an estimate.",
+            settings: SettingsBuilder::new()
+                .lag(75)
+                .with_lag_jitter(20, JitterDistribution::Normal, 25.0)
+                .with_lag_order_preserved()
+                .drop(0.5)
+                .bandwidth(kbit(1600))
+                .build(),
+        },
+        NetworkProfile {
+            id: "lte",
+            name: "LTE",
+            description: "4G mobile data with good signal.",
+            source: "Chrome DevTools \"Regular 4G\" throttling preset: 20 ms latency, \
+                     4 Mbit/s down, 3 Mbit/s up. Jitter and the 0.1% loss are estimates.",
+            settings: SettingsBuilder::new()
+                .lag(10)
+                .with_lag_jitter(5, JitterDistribution::Normal, 25.0)
+                .with_lag_order_preserved()
+                .drop(0.1)
+                .bandwidth(kbit(4000))
+                .with_outbound(|outbound| outbound.bandwidth(kbit(3000)))
+                .build(),
+        },
+        NetworkProfile {
+            id: "dsl",
+            name: "DSL",
+            description: "Fixed-line ADSL broadband.",
+            source: "Chrome DevTools \"DSL\" throttling preset: 5 ms latency, 2 Mbit/s down, \
+                     1 Mbit/s up. The preset has no jitter or loss.",
+            settings: SettingsBuilder::new()
+                .lag(3)
+                .drop(0.0)
+                .bandwidth(kbit(2000))
+                .with_outbound(|outbound| outbound.bandwidth(kbit(1000)))
+                .build(),
+        },
+        NetworkProfile {
+            id: "satellite-geo",
+            name: "Satellite (GEO)",
+            description: "Geostationary satellite internet: high bandwidth, very long delay.",
+            source: "Propagation up to and down from 35,786 km takes at least 239 ms each \
+                     way (RFC 2488); ground segment adds the rest. 25 Mbit/s is a common \
+                     consumer plan speed. Loss is an estimate.",
+            settings: SettingsBuilder::new()
+                .lag(280)
+                .with_lag_jitter(10, JitterDistribution::Normal, 50.0)
+                .with_lag_order_preserved()
+                .drop(0.5)
+                .bandwidth(kbit(25_000))
+                .build(),
+        },
+        NetworkProfile {
+            id: "satellite-leo",
+            name: "Satellite (LEO)",
+            description: "Low earth orbit constellation with frequent satellite handovers.",
+            source: "Median latency of 40-60 ms and downloads of 50-100 Mbit/s in published \
+                     Starlink speed test reports. The heavy-tailed jitter stands in for \
+                     handovers; jitter and loss are estimates.",
+            settings: SettingsBuilder::new()
+                .lag(25)
+                .with_lag_jitter(10, JitterDistribution::ParetoNormal, 25.0)
+                .with_lag_order_preserved()
+                .drop(0.5)
+                .bandwidth(kbit(50_000))
+                .build(),
+        },
+        NetworkProfile {
+            id: "congested-wifi",
+            name: "Congested Wi-Fi",
+            description: "A busy 2.4 GHz network: contention delays and short bursts of loss.",
+            source: "Illustrative values, not from a published trace. Loss uses the \
+                     Gilbert-Elliott model with bad spells of about 3 packets and 2% loss \
+                     overall; Pareto jitter gives the occasional long stall.",
+            settings: SettingsBuilder::new()
+                .lag(10)
+                .with_lag_jitter(20, JitterDistribution::Pareto, 50.0)
+                .drop_gilbert_elliott(1.0, 30.0, 0.5, 50.0)
+                .bandwidth(kbit(5000))
+                .build(),
+        },
+        NetworkProfile {
+            id: "intercontinental",
+            name: "Intercontinental",
+            description: "A fast fixed line to a server on another continent.",
+            source: "London to Sydney is about 17,000 km; light in fibre covers that in \
+                     about 85 ms, and real routes are around 50% longer. Ping tables show \
+                     round trips of about 250-280 ms.",
+            settings: SettingsBuilder::new()
+                .lag(130)
+                .with_lag_jitter(3, JitterDistribution::Normal, 50.0)
+                .with_lag_order_preserved()
+                .drop(0.1)
+                .bandwidth(kbit(50_000))
+                .build(),
+        },
+    ]
+}
+
+/// Finds the built-in profile with the given id.
+pub fn find_profile(id: &str) -> Option<NetworkProfile> {
+    builtin_profiles()
+        .into_iter()
+        .find(|profile| profile.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::reorder::ReorderOptions;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_profiles_are_complete() {
+
+        let profiles = builtin_profiles();
+        let ids: HashSet<_> = profiles.iter().map(|profile| profile.id).collect();
+
+        assert_eq!(ids.len(), profiles.len());
+
+        for profile in &profiles {
+            assert!(!profile.description.is_empty() && !profile.source.is_empty());
+            assert!(profile.settings.lag.as_ref().is_some_and(|lag| lag.enabled));
+            assert!(profile.settings.drop.is_some());
+            assert!(profile.settings.bandwidth.as_ref().is_some_and(|bw| bw.limit > 0));
+        }
+
+    }
+
+    #[test]
+    fn test_apply_keeps_other_settings() {
+
+        let mut settings = Settings {
+            reorder: Some(ReorderOptions {
+                enabled: true,
+                ..Default::default()
+            }),
+            seed: Some(3),
+            ..SettingsBuilder::new().lag(500).build()
+        };
+
+        find_profile("lte").unwrap().apply(&mut settings);
+
+        assert_eq!(settings.lag.as_ref().map(|lag| lag.delay_ms), Some(10));
+        assert!(settings.bandwidth.is_some());
+        assert!(settings.reorder.is_some_and(|reorder| reorder.enabled));
+        assert_eq!(settings.seed, Some(3));
+
+    }
+
+    #[test]
+    fn test_unknown_profile() {
+        assert!(find_profile("dial-up").is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
+use crate::settings::direction::PerDirection;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +18,10 @@ pub struct ReorderOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Probability of reordering packets, ranging from 0.0 to 1.0
     #[arg(long = "reorder-probability", id = "reorder-probability", default_value_t = Probability::default())]
     #[serde(default)]
@@ -46,6 +51,7 @@ impl Default for ReorderOptions {
             enabled: false,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::default(),
             max_delay: 100,
             duration_ms: 0,
@@ -0,0 +1,456 @@
+//! Per-flow impairment rules.
+//!
+//! A rule pairs a packet match with its own module settings. Packets are
+//! checked against the rules in order and handled by the modules of the
+//! first enabled rule they match; packets matching no rule use the
+//! top-level settings.
+use crate::network::core::PacketHeaders;
+use crate::network::core::headers::{PROTOCOL_ICMP, PROTOCOL_ICMPV6, PROTOCOL_TCP, PROTOCOL_UDP};
+use crate::settings::Settings;
+use crate::settings::default_true;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Transport protocol a rule can match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleProtocol {
+    Tcp,
+    Udp,
+    /// ICMP and `ICMPv6`
+    Icmp,
+}
+
+impl RuleProtocol {
+    /// Returns whether `protocol` is an IP protocol number of this protocol.
+    pub fn matches(self, protocol: u8) -> bool {
+        match self {
+            Self::Tcp => protocol == PROTOCOL_TCP,
+            Self::Udp => protocol == PROTOCOL_UDP,
+            Self::Icmp => protocol == PROTOCOL_ICMP || protocol == PROTOCOL_ICMPV6,
+        }
+    }
+}
+
+/// IP network in CIDR notation, such as `10.0.0.0/8` or `2001:db8::/32`.
+///
+/// A bare address is a network containing only that address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNetwork {
+    /// Network address
+    pub addr: IpAddr,
+    /// Number of leading bits that must match
+    pub prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Returns whether `addr` lies in this network.
+    pub fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(network), IpAddr::V4(addr)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(addr)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+
+        let (addr, prefix_len) = s
+            .trim()
+            .split_once('/')
+            .map_or((s.trim(), None), |(a, p)| (a, Some(p)));
+
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|e| format!("Invalid IP address '{}': {}", addr, e))?;
+        let max_len = if addr.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = match prefix_len {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|&len| len <= max_len)
+                .ok_or_else(|| format!("Invalid prefix length '{}' in '{}'", p, s))?,
+            None => max_len,
+        };
+
+        Ok(Self { addr, prefix_len })
+
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl TryFrom<String> for IpNetwork {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IpNetwork> for String {
+    fn from(value: IpNetwork) -> Self {
+        value.to_string()
+    }
+}
+
+/// Inclusive range of ports, such as `27015` or `27000-27100`.
+///
+/// Serialized as text; `{"start", "end"}` objects written by older filter
+/// targets are still accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "PortRangeRepr", into = "String")]
+pub struct PortRange {
+    /// First port in the range
+    pub start: u16,
+    /// Last port in the range
+    pub end: u16,
+}
+
+impl PortRange {
+    /// Creates the range from `start` to `end`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(PortRange)` - If `start` is not after `end`
+    /// * `Err(String)` - If the bounds are reversed
+    pub fn new(start: u16, end: u16) -> Result<Self, String> {
+
+        if start > end {
+            return Err(format!(
+                "Invalid port range '{}-{}': start is after end",
+                start, end
+            ));
+        }
+
+        Ok(Self { start, end })
+
+    }
+
+    /// Creates the range of a single port.
+    pub const fn single(port: u16) -> Self {
+        Self {
+            start: port,
+            end: port,
+        }
+    }
+
+    /// Returns whether `port` lies in the range.
+    pub fn contains(&self, port: u16) -> bool {
+        (self.start..=self.end).contains(&port)
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+
+        let parse = |port: &str| {
+            port.trim()
+                .parse::<u16>()
+                .map_err(|e| format!("Invalid port '{}': {}", port.trim(), e))
+        };
+
+        let Some((start, end)) = s.split_once('-') else {
+            return parse(s).map(Self::single);
+        };
+
+        Self::new(parse(start)?, parse(end)?)
+            .map_err(|_| format!("Invalid port range '{}': start is after end", s))
+
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl TryFrom<String> for PortRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Serialized forms a [`PortRange`] is read from.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PortRangeRepr {
+    Text(String),
+    Bounds { start: u16, end: u16 },
+}
+
+impl TryFrom<PortRangeRepr> for PortRange {
+    type Error = String;
+
+    fn try_from(value: PortRangeRepr) -> Result<Self, Self::Error> {
+        match value {
+            PortRangeRepr::Text(text) => text.parse(),
+            PortRangeRepr::Bounds { start, end } => Self::new(start, end),
+        }
+    }
+}
+
+impl From<PortRange> for String {
+    fn from(value: PortRange) -> Self {
+        value.to_string()
+    }
+}
+
+/// Conditions a packet must meet to be handled by a rule.
+///
+/// Every field that is set must match; unset fields match anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PacketMatch {
+    /// Transport protocol
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<RuleProtocol>,
+    /// Network the source address must be in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src: Option<IpNetwork>,
+    /// Network the destination address must be in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dst: Option<IpNetwork>,
+    /// Source ports (TCP/UDP only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src_ports: Option<PortRange>,
+    /// Destination ports (TCP/UDP only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dst_ports: Option<PortRange>,
+    /// Smallest packet size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<usize>,
+    /// Largest packet size in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_size: Option<usize>,
+    /// Differentiated services code point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dscp: Option<u8>,
+}
+
+impl PacketMatch {
+    /// Returns whether a packet meets all conditions.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - Parsed headers of the packet, `None` if it is not valid IP
+    /// * `size` - Size of the packet in bytes
+    pub fn matches(&self, headers: Option<&PacketHeaders>, size: usize) -> bool {
+
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max)
+        {
+            return false;
+        }
+
+        let needs_headers = self.protocol.is_some()
+            || self.src.is_some()
+            || self.dst.is_some()
+            || self.src_ports.is_some()
+            || self.dst_ports.is_some()
+            || self.dscp.is_some();
+
+        let Some(headers) = headers else {
+            return !needs_headers;
+        };
+
+        let port_matches = |range: Option<PortRange>, port: Option<u16>| {
+            range.map_or(true, |range: PortRange| port.is_some_and(|port| range.contains(port)))
+        };
+
+        self.protocol.map_or(true, |protocol| protocol.matches(headers.protocol))
+            && self.src.map_or(true, |network| network.contains(headers.src_addr))
+            && self.dst.map_or(true, |network| network.contains(headers.dst_addr))
+            && port_matches(self.src_ports, headers.src_port)
+            && port_matches(self.dst_ports, headers.dst_port)
+            && self.dscp.map_or(true, |dscp| dscp == headers.dscp)
+
+    }
+}
+
+/// A packet match with the module settings applied to matching packets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpairmentRule {
+    /// Name shown in the rule table
+    #[serde(default)]
+    pub name: String,
+    /// Whether the rule is evaluated
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Packets the rule applies to
+    #[serde(rename = "match", default)]
+    pub matcher: PacketMatch,
+    /// Module options for matching packets.
+    /// Only the module options are used; session-wide fields such as the
+    /// seed or nested rules are taken from the top-level settings.
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// Returns the index of the first enabled rule matching a packet.
+///
+/// # Arguments
+///
+/// * `rules` - Rules in evaluation order
+/// * `headers` - Parsed headers of the packet, `None` if it is not valid IP
+/// * `size` - Size of the packet in bytes
+pub fn find_matching_rule(
+    rules: &[ImpairmentRule],
+    headers: Option<&PacketHeaders>,
+    size: usize,
+) -> Option<usize> {
+    rules
+        .iter()
+        .position(|rule| rule.enabled && rule.matcher.matches(headers, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::core::headers::tests::udp_ipv4_packet;
+
+    #[test]
+    fn test_ip_network_contains() {
+
+        let network: IpNetwork = "10.0.0.0/8".parse().unwrap();
+
+        assert!(network.contains("10.20.30.40".parse().unwrap()));
+        assert!(!network.contains("11.0.0.1".parse().unwrap()));
+        assert!(!network.contains("::1".parse().unwrap()));
+
+        let any: IpNetwork = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains("192.168.1.1".parse().unwrap()));
+
+        let host: IpNetwork = "2001:db8::1".parse().unwrap();
+        assert_eq!(host.prefix_len, 128);
+        assert!(host.contains("2001:db8::1".parse().unwrap()));
+        assert!(!host.contains("2001:db8::2".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("not-an-ip".parse::<IpNetwork>().is_err());
+
+    }
+
+    #[test]
+    fn test_port_range_parse() {
+
+        assert_eq!("27015".parse::<PortRange>().unwrap().to_string(), "27015");
+        assert!("27000-27100".parse::<PortRange>().unwrap().contains(27050));
+        assert!("200-100".parse::<PortRange>().is_err());
+        assert!("80-".parse::<PortRange>().is_err());
+        assert_eq!(" 27000 - 27100".parse(), PortRange::new(27000, 27100));
+        assert!(PortRange::new(27100, 27000).is_err());
+
+        // Older filter targets stored the bounds as an object
+        let legacy: PortRange = serde_json::from_str(r#"{"start": 3074, "end": 3075}"#).unwrap();
+        assert_eq!(serde_json::to_string(&legacy).unwrap(), r#""3074-3075""#);
+        assert!(serde_json::from_str::<PortRange>(r#"{"start": 9, "end": 1}"#).is_err());
+
+    }
+
+    #[test]
+    fn test_packet_match() {
+
+        // udp_ipv4_packet builds 192.168.1.10 -> 10.0.0.1 with DSCP 46
+        let data = udp_ipv4_packet(5000, 27015, &[0; 16]);
+        let headers = PacketHeaders::parse(&data).unwrap();
+
+        let game = PacketMatch {
+            protocol: Some(RuleProtocol::Udp),
+            dst: Some("10.0.0.0/24".parse().unwrap()),
+            dst_ports: Some("27000-27100".parse().unwrap()),
+            ..PacketMatch::default()
+        };
+        assert!(game.matches(Some(&headers), data.len()));
+
+        let https = PacketMatch {
+            protocol: Some(RuleProtocol::Tcp),
+            dst_ports: Some("443".parse().unwrap()),
+            ..PacketMatch::default()
+        };
+        assert!(!https.matches(Some(&headers), data.len()));
+
+        let small = PacketMatch {
+            max_size: Some(40),
+            ..PacketMatch::default()
+        };
+        assert!(!small.matches(Some(&headers), data.len()));
+
+        let voice = PacketMatch {
+            dscp: Some(46),
+            ..PacketMatch::default()
+        };
+        assert!(voice.matches(Some(&headers), data.len()));
+        assert!(!voice.matches(None, data.len()));
+        assert!(PacketMatch::default().matches(None, data.len()));
+
+    }
+
+    #[test]
+    fn test_first_enabled_rule_wins() {
+
+        let data = udp_ipv4_packet(5000, 27015, &[]);
+        let headers = PacketHeaders::parse(&data).unwrap();
+
+        let rule = |name: &str, enabled| ImpairmentRule {
+            name: name.to_string(),
+            enabled,
+            matcher: PacketMatch::default(),
+            settings: Settings::default(),
+        };
+        let rules = vec![rule("off", false), rule("first", true), rule("second", true)];
+
+        assert_eq!(find_matching_rule(&rules, Some(&headers), data.len()), Some(1));
+        assert_eq!(find_matching_rule(&[], Some(&headers), data.len()), None);
+
+    }
+
+    #[test]
+    fn test_rule_serde_round_trip() {
+
+        let json = r#"{
+            "name": "game",
+            "match": { "protocol": "udp", "dst": "10.0.0.0/8", "dst_ports": "27000-27100" },
+            "settings": { "lag": { "enabled": true, "delay_ms": 150 } }
+        }"#;
+
+        let rule: ImpairmentRule = serde_json::from_str(json).unwrap();
+
+        assert!(rule.enabled);
+        assert_eq!(rule.settings.lag.as_ref().unwrap().delay_ms, 150);
+        assert_eq!(rule.matcher.dst.unwrap().to_string(), "10.0.0.0/8");
+
+        let value = serde_json::to_value(&rule).unwrap();
+        assert_eq!(value["match"]["dst_ports"], "27000-27100");
+
+    }
+}
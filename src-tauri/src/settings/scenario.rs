@@ -0,0 +1,366 @@
+//! Scripted impairment schedules.
+//!
+//! A scenario is a timeline of steps, each switching modules on or off at an
+//! offset from the start and optionally only for a while. The settings in
+//! effect at any moment are a pure function of the elapsed time, so a
+//! scenario replays identically every time it runs.
+//!
+//! ```toml
+//! name = "flaky wifi"
+//! repeat = true
+//! length_ms = 60000
+//!
+//! [[steps]]
+//! at_ms = 0
+//! lag = { delay_ms = 50 }
+//!
+//! [[steps]]
+//! at_ms = 30000
+//! duration_ms = 10000
+//! drop = { probability = 0.05 }
+//!
+//! [[steps]]
+//! at_ms = 45000
+//! duration_ms = 2000
+//! burst = { buffer_ms = 2000 }
+//! ```
+use crate::error::{MyraError, Result};
+use crate::settings::{ModuleKind, Settings};
+use crate::settings::bandwidth::BandwidthOptions;
+use crate::settings::burst::BurstOptions;
+use crate::settings::corruption::CorruptionOptions;
+use crate::settings::drop::DropOptions;
+use crate::settings::duplicate::DuplicateOptions;
+use crate::settings::lag::LagOptions;
+use crate::settings::reorder::ReorderOptions;
+use crate::settings::throttle::ThrottleOptions;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// One entry of a scenario timeline.
+///
+/// Modules given in a step are enabled with those options; modules listed in
+/// `disable` are switched off. Without `duration_ms` the change lasts until a
+/// later step overrides it, otherwise the module goes back to what it was
+/// before the step once the duration has passed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScenarioStep {
+    /// Offset of the step from the start of the scenario, or of the cycle when repeating
+    #[serde(default)]
+    pub at_ms: u64,
+    /// How long the step stays in effect; `None` keeps it until overridden
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Modules switched off by this step
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disable: Vec<ModuleKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drop: Option<DropOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lag: Option<LagOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<ThrottleOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reorder: Option<ReorderOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub corruption: Option<CorruptionOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate: Option<DuplicateOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bandwidth: Option<BandwidthOptions>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<BurstOptions>,
+}
+
+impl ScenarioStep {
+    /// Returns whether the step is in effect `position_ms` into the timeline.
+    fn is_active(&self, position_ms: u64) -> bool {
+        position_ms >= self.at_ms
+            && self
+                .duration_ms
+                .map_or(true, |duration| position_ms < self.at_ms.saturating_add(duration))
+    }
+
+    /// End of the step on the timeline, or its start if it has no duration.
+    fn end_ms(&self) -> u64 {
+        self.at_ms.saturating_add(self.duration_ms.unwrap_or(0))
+    }
+
+    /// Returns whether the step enables or disables `module`.
+    fn touches(&self, module: ModuleKind) -> bool {
+
+        // Enabling on scratch settings only tells whether the step has options for it
+        self.disable.contains(&module) || module.enable_from(self, &mut Settings::default())
+
+    }
+
+    /// Applies the step on top of `settings`.
+    fn apply(&self, settings: &mut Settings) {
+
+        for module in &self.disable {
+            module.disable(settings);
+        }
+
+        for module in ModuleKind::ALL {
+            module.enable_from(self, settings);
+        }
+
+    }
+}
+
+/// A timeline of impairment steps.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scenario {
+    /// Name shown while the scenario runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Start over from the first step when the timeline ends
+    #[serde(default)]
+    pub repeat: bool,
+    /// Length of one pass over the timeline; defaults to the end of the last step
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length_ms: Option<u64>,
+    /// Steps of the timeline, in any order
+    #[serde(default)]
+    pub steps: Vec<ScenarioStep>,
+}
+
+impl Scenario {
+    /// Reads a scenario from a `.json` or `.toml` file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - File to read; anything not ending in `.json` is parsed as TOML
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Scenario)` - The validated scenario
+    /// * `Err(MyraError)` - If the file cannot be read, parsed or validated
+    pub fn load(path: &Path) -> Result<Self> {
+
+        let content = std::fs::read_to_string(path)?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+
+        let scenario: Self = if is_json {
+            serde_json::from_str(&content).map_err(|e| MyraError::Scenario(e.to_string()))?
+        } else {
+            toml::from_str(&content).map_err(|e| MyraError::Scenario(e.to_string()))?
+        };
+
+        scenario.validate()?;
+        Ok(scenario)
+
+    }
+
+    /// Checks that every step can take effect.
+    pub fn validate(&self) -> Result<()> {
+
+        if self.steps.is_empty() {
+            return Err(MyraError::Scenario("Scenario has no steps".to_string()));
+        }
+
+        if let Some(length) = self.length_ms {
+            if let Some(index) = self.steps.iter().position(|step| step.at_ms >= length) {
+                return Err(MyraError::Scenario(format!(
+                    "Step {} starts at {}ms, after the {}ms timeline ends",
+                    index, self.steps[index].at_ms, length
+                )));
+            }
+        }
+
+        Ok(())
+
+    }
+
+    /// Length of one pass over the timeline in milliseconds, at least 1.
+    pub fn cycle_length_ms(&self) -> u64 {
+
+        self.length_ms
+            .unwrap_or_else(|| self.steps.iter().map(ScenarioStep::end_ms).max().unwrap_or(0))
+            .max(1)
+
+    }
+
+    /// Number of completed passes over the timeline after `elapsed`.
+    pub fn cycle(&self, elapsed: Duration) -> u64 {
+
+        if self.repeat {
+            elapsed_ms(elapsed) / self.cycle_length_ms()
+        } else {
+            0
+        }
+
+    }
+
+    /// Whether a non-repeating scenario has reached the end of its timeline.
+    pub fn is_finished(&self, elapsed: Duration) -> bool {
+        !self.repeat && elapsed_ms(elapsed) >= self.cycle_length_ms()
+    }
+
+    /// Modules that any step enables or disables, in processing order.
+    ///
+    /// These are the only settings the scenario changes.
+    pub fn modules(&self) -> Vec<ModuleKind> {
+        ModuleKind::ALL
+            .into_iter()
+            .filter(|&module| self.steps.iter().any(|step| step.touches(module)))
+            .collect()
+    }
+
+    /// Indices of the steps in effect after `elapsed`, in the order they apply.
+    ///
+    /// Steps apply by start time; steps starting together apply in file order.
+    pub fn active_steps(&self, elapsed: Duration) -> Vec<usize> {
+
+        let position = if self.repeat {
+            elapsed_ms(elapsed) % self.cycle_length_ms()
+        } else {
+            elapsed_ms(elapsed)
+        };
+
+        let mut active: Vec<usize> = (0..self.steps.len())
+            .filter(|&index| self.steps[index].is_active(position))
+            .collect();
+
+        active.sort_by_key(|&index| self.steps[index].at_ms);
+        active
+
+    }
+
+    /// Settings in effect after `elapsed`, starting from `base`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - Settings in place before the scenario started
+    /// * `elapsed` - Time since the scenario started
+    pub fn settings_at(&self, base: &Settings, elapsed: Duration) -> Settings {
+
+        let mut settings = base.clone();
+
+        for index in self.active_steps(elapsed) {
+            self.steps[index].apply(&mut settings);
+        }
+
+        settings
+
+    }
+}
+
+fn elapsed_ms(elapsed: Duration) -> u64 {
+    u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+        name = "flaky wifi"
+        repeat = true
+        length_ms = 60000
+
+        [[steps]]
+        at_ms = 0
+        lag = { delay_ms = 50 }
+
+        [[steps]]
+        at_ms = 30000
+        duration_ms = 10000
+        drop = { probability = 0.05 }
+
+        [[steps]]
+        at_ms = 45000
+        duration_ms = 2000
+        burst = { buffer_ms = 2000 }
+        disable = ["lag"]
+    "#;
+
+    fn at(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_parse_toml() {
+
+        let scenario: Scenario = toml::from_str(SCENARIO).unwrap();
+
+        assert!(scenario.validate().is_ok());
+        assert_eq!(scenario.name.as_deref(), Some("flaky wifi"));
+        assert_eq!(scenario.steps.len(), 3);
+        assert_eq!(scenario.steps[2].disable, vec![ModuleKind::Lag]);
+        assert_eq!(scenario.cycle_length_ms(), 60_000);
+        assert_eq!(
+            scenario.modules(),
+            vec![ModuleKind::Drop, ModuleKind::Lag, ModuleKind::Burst]
+        );
+
+    }
+
+    #[test]
+    fn test_settings_follow_the_timeline() {
+
+        let scenario: Scenario = toml::from_str(SCENARIO).unwrap();
+        let base = Settings::default();
+
+        let start = scenario.settings_at(&base, at(1));
+        assert_eq!(start.lag.as_ref().map(|lag| (lag.enabled, lag.delay_ms)), Some((true, 50)));
+        assert!(start.drop.is_none());
+
+        let dropping = scenario.settings_at(&base, at(35));
+        assert!(dropping.drop.is_some_and(|drop| drop.enabled));
+
+        // The drop step has expired and the module goes back to the base
+        assert!(scenario.settings_at(&base, at(41)).drop.is_none());
+
+        let bursting = scenario.settings_at(&base, at(46));
+        assert!(bursting.burst.is_some_and(|burst| burst.enabled));
+        assert!(bursting.lag.is_some_and(|lag| !lag.enabled));
+
+        assert!(scenario.settings_at(&base, at(48)).lag.is_some_and(|lag| lag.enabled));
+
+    }
+
+    #[test]
+    fn test_repeat_wraps_around() {
+
+        let scenario: Scenario = toml::from_str(SCENARIO).unwrap();
+
+        assert_eq!(scenario.active_steps(at(95)), scenario.active_steps(at(35)));
+        assert_eq!(scenario.cycle(at(95)), 1);
+        assert!(!scenario.is_finished(at(3600)));
+
+    }
+
+    #[test]
+    fn test_one_shot_scenario_finishes() {
+
+        let scenario = Scenario {
+            steps: vec![ScenarioStep {
+                at_ms: 1000,
+                duration_ms: Some(500),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        assert_eq!(scenario.cycle_length_ms(), 1500);
+        assert!(scenario.active_steps(Duration::from_millis(999)).is_empty());
+        assert_eq!(scenario.active_steps(Duration::from_millis(1200)), vec![0]);
+        assert!(scenario.is_finished(Duration::from_millis(1500)));
+
+    }
+
+    #[test]
+    fn test_validate_rejects_unreachable_steps() {
+
+        let mut scenario: Scenario = toml::from_str(SCENARIO).unwrap();
+        scenario.length_ms = Some(40_000);
+
+        assert!(scenario.validate().is_err());
+        assert!(Scenario::default().validate().is_err());
+
+    }
+}
@@ -0,0 +1,131 @@
+//! Options of the traffic shaper shared by the bandwidth module and the WFP throttle.
+//!
+//! A shaper sends packets as fast as a token bucket allows and queues the
+//! rest. How deep the bucket is decides how bursty the output can be; how
+//! long the queue may grow, and what it drops once it is full, decides how
+//! much delay a saturated link adds. A long tail-drop queue reproduces
+//! bufferbloat, while RED and `CoDel` model links that manage their queue.
+use crate::network::types::probability::Probability;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+/// Default limit of the queue in bytes (100 MiB)
+const DEFAULT_QUEUE_BYTES: usize = 100 * 1024 * 1024;
+
+/// How a shaper's queue decides which packets to drop
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueDiscipline {
+    /// Arriving packets are dropped only when the queue is full
+    #[default]
+    TailDrop,
+    /// Random Early Detection: arriving packets are dropped with a chance
+    /// that grows with the average fill of the queue
+    Red,
+    /// Controlled Delay (RFC 8289): packets are dropped at the head of the
+    /// queue once they have waited longer than the target for an interval
+    Codel,
+}
+
+/// Parameters of a token bucket shaper and its queue.
+#[derive(Args, Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ShaperOptions {
+    /// Depth of the token bucket in bytes, sent at full speed after an idle
+    /// period (0 = 10 ms worth of the rate, at least one full-size packet)
+    #[arg(long = "bandwidth-burst", id = "bandwidth-burst", default_value_t = 0)]
+    #[serde(default)]
+    pub burst_bytes: usize,
+    /// Most bytes waiting in the queue (0 = no limit)
+    #[arg(
+        long = "bandwidth-queue-bytes",
+        id = "bandwidth-queue-bytes",
+        default_value_t = DEFAULT_QUEUE_BYTES
+    )]
+    #[serde(default = "default_queue_bytes")]
+    pub queue_bytes: usize,
+    /// Most packets waiting in the queue (0 = no limit)
+    #[arg(long = "bandwidth-queue-packets", id = "bandwidth-queue-packets", default_value_t = 0)]
+    #[serde(default)]
+    pub queue_packets: usize,
+    /// How the queue decides which packets to drop
+    #[arg(
+        long = "bandwidth-discipline",
+        id = "bandwidth-discipline",
+        value_enum,
+        default_value_t = QueueDiscipline::TailDrop
+    )]
+    #[serde(default)]
+    pub discipline: QueueDiscipline,
+    /// Average fill of the queue, as a fraction of its limit, at which RED
+    /// starts dropping
+    #[arg(long = "bandwidth-red-min", id = "bandwidth-red-min", default_value_t = 0.25)]
+    #[serde(default = "default_red_min_threshold")]
+    pub red_min_threshold: f64,
+    /// Average fill of the queue, as a fraction of its limit, above which RED
+    /// drops every arriving packet
+    #[arg(long = "bandwidth-red-max", id = "bandwidth-red-max", default_value_t = 0.75)]
+    #[serde(default = "default_red_max_threshold")]
+    pub red_max_threshold: f64,
+    /// Chance of a RED drop when the average fill reaches the maximum threshold
+    #[arg(
+        long = "bandwidth-red-max-probability",
+        id = "bandwidth-red-max-probability",
+        default_value = "0.1"
+    )]
+    #[serde(default = "default_red_max_probability")]
+    pub red_max_probability: Probability,
+    /// Queueing delay `CoDel` tolerates, in milliseconds
+    #[arg(long = "bandwidth-codel-target", id = "bandwidth-codel-target", default_value_t = 5)]
+    #[serde(default = "default_codel_target_ms")]
+    pub codel_target_ms: u64,
+    /// How long the delay must stay above the target before `CoDel` drops, in milliseconds
+    #[arg(
+        long = "bandwidth-codel-interval",
+        id = "bandwidth-codel-interval",
+        default_value_t = 100
+    )]
+    #[serde(default = "default_codel_interval_ms")]
+    pub codel_interval_ms: u64,
+}
+
+fn default_queue_bytes() -> usize {
+    DEFAULT_QUEUE_BYTES
+}
+
+fn default_red_min_threshold() -> f64 {
+    0.25
+}
+
+fn default_red_max_threshold() -> f64 {
+    0.75
+}
+
+fn default_red_max_probability() -> Probability {
+    Probability::new(0.1).unwrap_or_default()
+}
+
+fn default_codel_target_ms() -> u64 {
+    5
+}
+
+fn default_codel_interval_ms() -> u64 {
+    100
+}
+
+impl Default for ShaperOptions {
+    fn default() -> Self {
+
+        Self {
+            burst_bytes: 0,
+            queue_bytes: DEFAULT_QUEUE_BYTES,
+            queue_packets: 0,
+            discipline: QueueDiscipline::default(),
+            red_min_threshold: default_red_min_threshold(),
+            red_max_threshold: default_red_max_threshold(),
+            red_max_probability: default_red_max_probability(),
+            codel_target_ms: default_codel_target_ms(),
+            codel_interval_ms: default_codel_interval_ms(),
+        }
+
+    }
+}
@@ -0,0 +1,113 @@
+use crate::settings::{ModuleKind, Settings};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Options for tapping: switching modules off briefly and back on again.
+///
+/// Switching a buffering module off releases the packets it holds, so a tap
+/// turns a steady impairment into a pulse. Taps either repeat every
+/// `interval_ms` or, in auto mode, start whenever the buffering modules hold
+/// `min_buffered` packets.
+#[derive(Parser, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TapOptions {
+    /// Whether tapping is enabled
+    #[arg(skip)]
+    #[serde(default)]
+    pub enabled: bool,
+    /// Time between the starts of two taps in milliseconds
+    #[arg(long = "tap-interval-ms", id = "tap-interval-ms", default_value_t = 3000)]
+    #[serde(default = "default_interval")]
+    pub interval_ms: u64,
+    /// How long the modules stay off during a tap in milliseconds
+    #[arg(long = "tap-duration-ms", id = "tap-duration-ms", default_value_t = 600)]
+    #[serde(default = "default_duration")]
+    pub duration_ms: u64,
+    /// Modules switched off by a tap; empty switches off every module
+    #[arg(long = "tap-modules", id = "tap-modules", value_delimiter = ',')]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modules: Vec<ModuleKind>,
+    /// Tap when packets pile up instead of on a fixed interval
+    #[arg(long = "tap-auto", id = "tap-auto", default_value_t = false)]
+    #[serde(default)]
+    pub auto: bool,
+    /// Packets held by burst, throttle, lag or reorder that start an auto tap
+    #[arg(long = "tap-min-buffered", id = "tap-min-buffered", default_value_t = 200)]
+    #[serde(default = "default_min_buffered")]
+    pub min_buffered: usize,
+    /// Minimum time from the end of an auto tap to the start of the next in milliseconds
+    #[arg(long = "tap-cooldown-ms", id = "tap-cooldown-ms", default_value_t = 1200)]
+    #[serde(default = "default_cooldown")]
+    pub cooldown_ms: u64,
+}
+
+fn default_interval() -> u64 {
+    3000
+}
+
+fn default_duration() -> u64 {
+    600
+}
+
+fn default_min_buffered() -> usize {
+    200
+}
+
+fn default_cooldown() -> u64 {
+    1200
+}
+
+impl Default for TapOptions {
+    fn default() -> Self {
+
+        Self {
+            enabled: false,
+            interval_ms: default_interval(),
+            duration_ms: default_duration(),
+            modules: Vec::new(),
+            auto: false,
+            min_buffered: default_min_buffered(),
+            cooldown_ms: default_cooldown(),
+        }
+
+    }
+}
+
+impl TapOptions {
+    /// Whether taps can happen with these options.
+    pub fn is_active(&self) -> bool {
+        self.enabled && self.duration_ms > 0 && (self.auto || self.interval_ms > 0)
+    }
+
+    /// Time between the starts of two periodic taps.
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    /// How long a tap lasts.
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+
+    /// Minimum quiet time between two auto taps.
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_millis(self.cooldown_ms)
+    }
+
+    /// Switches off the tapped modules in `settings`.
+    ///
+    /// Only the top-level modules are tapped; impairment rules keep running.
+    pub fn apply(&self, settings: &mut Settings) {
+
+        let modules: &[ModuleKind] = if self.modules.is_empty() {
+            &ModuleKind::ALL
+        } else {
+            &self.modules
+        };
+
+        for module in modules {
+            module.disable(settings);
+        }
+
+    }
+}
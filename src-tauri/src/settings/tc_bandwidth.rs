@@ -1,3 +1,4 @@
+use crate::settings::shaper::ShaperOptions;
 use serde::{Deserialize, Serialize};
 
 /// Direction for TC bandwidth limiting
@@ -26,6 +27,9 @@ pub struct TcBandwidthOptions {
     /// Direction to limit
     #[serde(default)]
     pub direction: TcDirection,
+    /// Token bucket depth, queue limits and queue discipline
+    #[serde(default)]
+    pub shaper: ShaperOptions,
 }
 
 fn default_limit() -> u32 {
@@ -39,6 +43,7 @@ impl TcBandwidthOptions {
             enabled: true,
             limit_kbps,
             direction,
+            shaper: ShaperOptions::default(),
         }
 
     }
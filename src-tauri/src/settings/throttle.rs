@@ -1,5 +1,6 @@
 use crate::network::types::probability::Probability;
 use crate::settings::default_true;
+use crate::settings::direction::PerDirection;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +22,10 @@ pub struct ThrottleOptions {
     #[arg(skip)]
     #[serde(default = "default_true")]
     pub outbound: bool,
+    /// Options used instead of these for packets of one direction
+    #[arg(skip)]
+    #[serde(default, skip_serializing_if = "PerDirection::is_empty")]
+    pub per_direction: PerDirection<Self>,
     /// Probability of triggering a throttle event, ranging from 0.0 to 1.0
     #[arg(long = "throttle-probability", id = "throttle-probability", default_value_t = Probability::default())]
     #[serde(default)]
@@ -75,6 +80,7 @@ impl Default for ThrottleOptions {
             enabled: false,
             inbound: true,
             outbound: true,
+            per_direction: PerDirection::default(),
             probability: Probability::default(),
             throttle_ms: 300,
             duration_ms: 0,
@@ -37,18 +37,88 @@ pub fn log_statistics(received: usize, sent: usize) {
 ///
 /// * `duration_ms` - Duration of the effect in milliseconds (0 = infinite)
 /// * `start_time` - When the effect was started
+/// * `now` - Current time from the module clock
 ///
 /// # Returns
 ///
 /// `true` if the effect is still active, `false` otherwise
-pub fn is_effect_active(duration_ms: u64, start_time: std::time::Instant) -> bool {
+pub fn is_effect_active(
+    duration_ms: u64,
+    start_time: std::time::Instant,
+    now: std::time::Instant,
+) -> bool {
 
     if duration_ms == 0 {
         return true;
     }
 
-    let elapsed = start_time.elapsed().as_millis() as u64;
+    let elapsed = now.duration_since(start_time).as_millis() as u64;
 
     elapsed < duration_ms
 
 }
+
+/// Check if the current process is running with administrator privileges.
+///
+/// Uses Windows API to determine if the current process has admin rights,
+/// which are required for packet manipulation.
+///
+/// # Returns
+///
+/// `bool` - true if the process has administrator privileges
+#[cfg(windows)]
+pub fn is_admin() -> bool {
+
+    use winapi::um::securitybaseapi::{AllocateAndInitializeSid, CheckTokenMembership, FreeSid};
+    use winapi::um::winnt::{
+        DOMAIN_ALIAS_RID_ADMINS, SECURITY_BUILTIN_DOMAIN_RID, SECURITY_NT_AUTHORITY,
+    };
+
+    unsafe {
+        let mut sid = std::ptr::null_mut();
+        let sub_authorities = [SECURITY_BUILTIN_DOMAIN_RID, DOMAIN_ALIAS_RID_ADMINS];
+
+        if AllocateAndInitializeSid(
+            &SECURITY_NT_AUTHORITY as *const _ as *mut _,
+            2,
+            sub_authorities[0],
+            sub_authorities[1],
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            &mut sid,
+        ) == 0
+        {
+            return false;
+        }
+
+        let mut is_member = 0;
+        let is_admin =
+            CheckTokenMembership(std::ptr::null_mut(), sid, &mut is_member) != 0 && is_member != 0;
+
+        FreeSid(sid);
+        is_admin
+    }
+
+}
+
+/// Check if the current process is running as root.
+///
+/// Creating TUN devices and capturing on them needs root on Linux.
+///
+/// # Returns
+///
+/// `bool` - true if the effective user is root
+#[cfg(target_os = "linux")]
+pub fn is_admin() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+/// Packet capture is not supported on other platforms.
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn is_admin() -> bool {
+    false
+}
@@ -14,7 +14,6 @@ import { PresetManager } from "@/components/presets/preset-manager";
 import { TooltipProvider } from "@/components/ui/tooltip";
 import { useHotkeys } from "@/hooks/use-hotkeys";
 import { useTap } from "@/hooks/use-tap";
-import { useClassicTap } from "@/hooks/use-classic-tap";
 
 function App() {
 
@@ -34,7 +33,6 @@ function App() {
 
     // Initialize tap feature for both modes
     useTap();
-    useClassicTap();
 
     useEffect(() => {
 
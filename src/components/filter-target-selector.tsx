@@ -11,7 +11,7 @@ import {
     TooltipTrigger,
 } from "@/components/ui/tooltip";
 import { useNetworkStore } from "@/lib/stores/network";
-import { ProcessInfo } from "@/types";
+import { FilterEntry, ProcessInfo } from "@/types";
 import { ProcessSelector } from "@/components/ui/process-selector";
 import { MyraCheckbox } from "@/components/ui/myra-checkbox";
 import {
@@ -44,16 +44,19 @@ export function FilterTargetSelector({ disabled }: FilterTargetSelectorProps) {
         localFilter: string;
         error: string | null;
         history: string[];
+        library: FilterEntry[];
     }>({
         localFilter: filter || "outbound",
         error: null,
         history: [],
+        library: [],
     });
 
     const {
         localFilter,
         error: filterError,
         history: previousFilters,
+        library: savedFilters,
     } = filterUi;
 
     // Grouped process-list state
@@ -138,6 +141,10 @@ export function FilterTargetSelector({ disabled }: FilterTargetSelectorProps) {
         ManipulationService.getFilterHistory()
             .then((list) => setFilterUi((s) => ({ ...s, history: list ?? [] })))
             .catch(() => setFilterUi((s) => ({ ...s, history: [] })));
+        // Load saved filters
+        ManipulationService.getFilterLibrary()
+            .then((list) => setFilterUi((s) => ({ ...s, library: list ?? [] })))
+            .catch(() => setFilterUi((s) => ({ ...s, library: [] })));
 
     }, [loadProcesses]);
 
@@ -177,6 +184,7 @@ export function FilterTargetSelector({ disabled }: FilterTargetSelectorProps) {
         // If process is selected, build process filter
         if (selectedProcess) {
             const pid = parseInt(selectedProcess);
+            const process = processes.find((p) => p.pid === pid);
             // Stop flow tracking if we had a different process
             if (
                 prevProcessRef.current &&
@@ -185,10 +193,14 @@ export function FilterTargetSelector({ disabled }: FilterTargetSelectorProps) {
                 await invoke("stop_flow_tracking").catch(() => {});
             }
             prevProcessRef.current = selectedProcess;
-            // Start flow tracking for this process
-            await invoke("start_flow_tracking", { pid }).catch((e) =>
-                console.warn("Flow tracking start failed:", e),
-            );
+            // Start flow tracking for this process, and for new instances
+            // of its executable; the tracker keeps the filter up to date
+            await invoke("start_flow_tracking", {
+                pid,
+                selector: { names: process ? [process.name] : [] },
+                includeInbound,
+                includeOutbound,
+            }).catch((e) => console.warn("Flow tracking start failed:", e));
             // Get process filter
             baseFilter = await invoke<string>("build_process_filter", {
                 pid,
@@ -196,14 +208,14 @@ export function FilterTargetSelector({ disabled }: FilterTargetSelectorProps) {
                 includeOutbound,
             });
             // Try to get flow-based filter if available
-            const flowFilter = await invoke<string | null>(
-                "get_flow_filter",
-            ).catch(() => null);
+            const flowFilter = await invoke<string | null>("get_flow_filter", {
+                includeInbound,
+                includeOutbound,
+            }).catch(() => null);
             if (flowFilter) {
                 baseFilter = flowFilter;
             }
             // Update filter target
-            const process = processes.find((p) => p.pid === pid);
             setFilterTarget({
                 mode: "process",
                 processId: pid,
@@ -425,6 +437,36 @@ export function FilterTargetSelector({ disabled }: FilterTargetSelectorProps) {
                             align="start"
                             className="min-w-[240px]"
                         >
+                            {savedFilters.length > 0 && (
+                                <>
+                                    <DropdownMenuLabel>
+                                        Saved Filters
+                                    </DropdownMenuLabel>
+                                    <DropdownMenuSeparator />
+                                    {savedFilters.map((entry) => (
+                                        <DropdownMenuItem
+                                            key={entry.filter}
+                                            onClick={() =>
+                                                applyPreviousFilter(
+                                                    entry.filter,
+                                                )
+                                            }
+                                            title={
+                                                entry.description ??
+                                                entry.filter
+                                            }
+                                            className="text-xs"
+                                        >
+                                            {entry.name ?? (
+                                                <span className="font-mono">
+                                                    {entry.filter}
+                                                </span>
+                                            )}
+                                        </DropdownMenuItem>
+                                    ))}
+                                    <DropdownMenuSeparator />
+                                </>
+                            )}
                             <DropdownMenuLabel>
                                 Recent Filters
                             </DropdownMenuLabel>
@@ -1,6 +1,7 @@
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { useNetworkStore } from "@/lib/stores/network";
 import { ModuleRow } from "@/components/module-row";
+import { SeedInput } from "@/components/seed-input";
 import { useDebounce } from "@/hooks/use-debounce";
 import { ModuleInfo } from "@/types";
 import { useEffect } from "react";
@@ -102,10 +103,11 @@ export function ModulePanel() {
         <div className="relative z-10 flex flex-col">
             <Card className="border-border bg-card/90">
                 <CardHeader className="rounded-t-lg bg-card/90 pb-2">
-                    <div className="flex">
+                    <div className="flex items-center justify-between">
                         <CardTitle className="text-lg text-foreground">
                             Modules
                         </CardTitle>
+                        <SeedInput />
                     </div>
                 </CardHeader>
                 <CardContent className="bg-card/90 px-3 py-2">
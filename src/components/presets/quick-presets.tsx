@@ -39,7 +39,8 @@ const QUICK_PRESETS: QuickPreset[] = [
 
 export function QuickPresets() {
 
-    const { manipulationStatus, loadStatus, isActive } = useNetworkStore();
+    const { manipulationStatus, loadStatus, isActive, seed } =
+        useNetworkStore();
 
     const applyQuickPreset = async (preset: QuickPreset) => {
 
@@ -48,7 +49,8 @@ export function QuickPresets() {
                 await import("@/lib/services/manipulation");
 
             const currentModules = manipulationStatus.modules;
-            const newSettings: PacketManipulationSettings = {};
+            // Keep the seed, presets only change the modules
+            const newSettings: PacketManipulationSettings = { seed };
 
             // Disable all modules by default
             currentModules.forEach((module) => {
@@ -0,0 +1,72 @@
+import { ChangeEvent, useEffect, useState } from "react";
+import { useNetworkStore } from "@/lib/stores/network";
+import { Label } from "@/components/ui/label";
+import { Input } from "@/components/ui/input";
+
+// Parses the seed field; empty means system entropy, null means invalid
+function parseSeed(input: string): number | undefined | null {
+
+    if (input.trim() === "") return undefined;
+    if (!/^\d+$/.test(input.trim())) return null;
+
+    const seed = Number(input.trim());
+
+    // Larger seeds would lose precision on the way to the backend
+    return Number.isSafeInteger(seed) ? seed : null;
+
+}
+
+export function SeedInput() {
+
+    const { seed, setSeed } = useNetworkStore();
+    const [input, setInput] = useState(seed?.toString() ?? "");
+
+    // Follow seeds loaded from the backend or a preset
+    useEffect(() => {
+        setInput(seed?.toString() ?? "");
+    }, [seed]);
+
+    const handleChange = (e: ChangeEvent<HTMLInputElement>) => {
+        setInput(e.target.value);
+    };
+
+    const handleBlur = () => {
+
+        const parsed = parseSeed(input);
+
+        if (parsed === null) {
+            setInput(seed?.toString() ?? "");
+            return;
+        }
+        if (parsed !== seed) {
+            setSeed(parsed);
+        }
+
+    };
+
+    return (
+        <div className="flex items-center gap-1.5">
+            <Label
+                htmlFor="rng-seed"
+                className="whitespace-nowrap text-xs text-foreground/70"
+            >
+                Seed:
+            </Label>
+            <Input
+                id="rng-seed"
+                value={input}
+                onChange={handleChange}
+                onBlur={handleBlur}
+                onKeyDown={(e) => {
+                    if (e.key === "Enter") e.currentTarget.blur();
+                }}
+                placeholder="random"
+                title="Seed for reproducible runs; leave empty for a random one"
+                className="h-7 w-28 px-2 text-center text-xs"
+                type="text"
+                inputMode="numeric"
+            />
+        </div>
+    );
+
+}
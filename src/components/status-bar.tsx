@@ -1,4 +1,5 @@
 import {
+    Activity,
     Database,
     FileText,
     Monitor,
@@ -7,7 +8,8 @@ import {
     Code,
 } from "lucide-react";
 import { useNetworkStore } from "@/lib/stores/network";
-import { FilterTarget } from "@/types";
+import { useStatisticsStream } from "@/hooks/use-statistics-stream";
+import { FilterTarget, StatisticsSnapshot } from "@/types";
 
 const StatusIndicator = ({ isActive }: { isActive: boolean }) => (
     <div className="flex items-center gap-1.5">
@@ -102,12 +104,48 @@ const ModulesIndicator = ({
 
 };
 
+// Follows the pushed snapshots, so bursts between two renders still count
+const TrafficIndicator = ({
+    snapshot,
+}: {
+    snapshot: StatisticsSnapshot | null;
+}) => {
+
+    if (!snapshot?.running) return null;
+
+    const held = snapshot.queues;
+
+    return (
+        <div className="flex items-center gap-1.5">
+            <Activity className="size-3 text-muted-foreground" />
+            <span className="text-muted-foreground">In:</span>
+            <span className="text-foreground/70">
+                {snapshot.inbound.captured_kbps.toFixed(0)} kbps
+            </span>
+            <span className="text-muted-foreground">Out:</span>
+            <span className="text-foreground/70">
+                {snapshot.outbound.captured_kbps.toFixed(0)} kbps
+            </span>
+            <span className="text-muted-foreground">Held:</span>
+            <span className="text-foreground/70">
+                {held.held_packets} (peak {held.peak_held_packets})
+            </span>
+            <span className="text-muted-foreground">Dropped:</span>
+            <span className="text-foreground/70">
+                {snapshot.drop.dropped_packets}
+            </span>
+        </div>
+    );
+
+};
+
 export function StatusBar() {
 
     const { isActive, manipulationStatus, currentPreset, filterTarget } =
         useNetworkStore();
     const modules = manipulationStatus.modules;
     const activeModules = modules.filter((m) => m.enabled);
+    const snapshot = useStatisticsStream();
 
     return (
         <div className="fixed bottom-0 left-0 right-0 z-50 flex h-6 items-center justify-between border-t border-border/30 bg-background/60 px-3 text-xs backdrop-blur-md backdrop-saturate-150">
@@ -117,6 +155,7 @@ export function StatusBar() {
                 <PresetIndicator preset={currentPreset} />
                 <ModulesIndicator activeModules={activeModules} />
             </div>
+            {isActive && <TrafficIndicator snapshot={snapshot} />}
         </div>
     );
 
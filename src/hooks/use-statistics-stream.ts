@@ -0,0 +1,36 @@
+import { useEffect, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import type { StatisticsSnapshot } from "@/types";
+
+// Event the backend pushes a statistics snapshot on, every 250ms while processing
+export const STATISTICS_EVENT = "statistics";
+
+/**
+ * Hook that follows the statistics snapshots pushed by the backend.
+ *
+ * Unlike polling `get_status`, every snapshot is received, and its queue
+ * peaks cover the time since the previous one, so short bursts show up.
+ *
+ * @returns The latest snapshot, or null before the first one arrives
+ */
+export function useStatisticsStream() {
+
+    const [snapshot, setSnapshot] = useState<StatisticsSnapshot | null>(null);
+
+    useEffect(() => {
+
+        const unlisten = listen<StatisticsSnapshot>(STATISTICS_EVENT, (event) => {
+            setSnapshot(event.payload);
+        });
+
+        return () => {
+            unlisten.then((stop) => stop()).catch((error) => {
+                console.error("Error stopping statistics stream:", error);
+            });
+        };
+
+    }, []);
+
+    return snapshot;
+
+}
@@ -1,250 +1,55 @@
-import { useEffect, useRef, useCallback } from "react";
-import { useTapStore } from "@/lib/stores/tap-store";
+import { useEffect } from "react";
+import { toTapOptions, useTapStore } from "@/lib/stores/tap-store";
 import { useNetworkStore } from "@/lib/stores/network";
+import { useClassicStore } from "@/lib/stores/classic-store";
 import { ManipulationService } from "@/lib/services/manipulation";
 
+// How often the tap phase is read back for the indicator
+const PHASE_POLL_MS = 100;
+
 /**
  * Hook that manages the "tap" feature - periodically disabling modules briefly
  * to create a pulsing/tapping effect for the lag.
  *
- * When tap is enabled and manipulation is active:
- * - Every `intervalSeconds`, temporarily disable all enabled modules
- * - Keep them disabled for `durationSeconds`
- * - Then re-enable them
- *
- * Uses updateSettings instead of startProcessing for lighter updates.
+ * The taps themselves are timed by the backend processing loop of either
+ * mode, so they keep their rhythm when the window is throttled or minimized.
+ * This hook only:
+ * - Sends the tap settings to the backend whenever they change
+ * - Sends them again when processing starts, as starting replaces the settings
+ * - Polls the tap phase while processing to keep `isTapping` up to date
  */
 export function useTap() {
 
     const { settings, isTapping, setIsTapping } = useTapStore();
-    const { isActive, manipulationStatus, buildSettings } = useNetworkStore();
-
-    const intervalRef = useRef<NodeJS.Timeout | null>(null);
-    const autoPollRef = useRef<NodeJS.Timeout | null>(null);
-    const tapTimeoutRef = useRef<NodeJS.Timeout | null>(null);
-    const savedModulesRef = useRef<string[]>([]); // Module names that were enabled before tap
-    const isRunningRef = useRef(false); // Prevent overlapping operations
-    const lastTapEndedAtRef = useRef<number>(0);
-
-    // Get list of currently enabled modules
-    const getEnabledModules = useCallback(() => {
-
-        const names: string[] = [];
-
-        for (const m of manipulationStatus.modules) {
-            if (m.enabled) names.push(m.name);
-        }
-
-        return names;
-
-    }, [manipulationStatus.modules]);
-
-    // Disable all enabled modules temporarily
-    const startTap = useCallback(async () => {
-
-        if (isRunningRef.current) return;
-        const enabledModules = getEnabledModules();
-
-        if (enabledModules.length === 0) return;
-        isRunningRef.current = true;
-        // Save which modules were enabled
-        savedModulesRef.current = enabledModules;
-        setIsTapping(true);
-
-        try {
-            // Build settings with all previously enabled modules now disabled
-            const currentSettings = buildSettings();
-            const disabledSettings = { ...currentSettings };
-            // Disable all modules
-            for (const moduleName of enabledModules) {
-                const key = moduleName as keyof typeof disabledSettings;
-                if (
-                    disabledSettings[key] &&
-                    typeof disabledSettings[key] === "object"
-                ) {
-                    (disabledSettings[key] as any).enabled = false;
-                }
-            }
-            // Use updateSettings instead of startProcessing - much lighter operation
-            await ManipulationService.updateSettings(disabledSettings, true);
-        } catch (error) {
-            console.error("Error starting tap:", error);
-        } finally {
-            isRunningRef.current = false;
-        }
-
-    }, [getEnabledModules, buildSettings, setIsTapping]);
-
-    // Re-enable modules after tap duration
-    const endTap = useCallback(async () => {
-
-        const modulesToRestore = savedModulesRef.current;
-
-        if (modulesToRestore.length === 0) {
-            setIsTapping(false);
-            return;
-        }
-
-        try {
-            // Build settings with saved modules re-enabled
-            const currentSettings = buildSettings();
-            const restoredSettings = { ...currentSettings };
-            // Re-enable the modules that were previously enabled
-            for (const moduleName of modulesToRestore) {
-                const key = moduleName as keyof typeof restoredSettings;
-                if (
-                    restoredSettings[key] &&
-                    typeof restoredSettings[key] === "object"
-                ) {
-                    (restoredSettings[key] as any).enabled = true;
-                }
-            }
-            // Use updateSettings instead of startProcessing - much lighter operation
-            await ManipulationService.updateSettings(restoredSettings, true);
-        } catch (error) {
-            console.error("Error ending tap:", error);
-        } finally {
-            setIsTapping(false);
-            savedModulesRef.current = [];
-            lastTapEndedAtRef.current = Date.now();
-        }
+    const { isActive: isStandardActive } = useNetworkStore();
+    const isClassicActive = useClassicStore((state) => state.isProcessing);
+    const isActive = isStandardActive || isClassicActive;
 
-    }, [buildSettings, setIsTapping]);
-
-    // Main tap cycle
-    const runTapCycle = useCallback(async () => {
-
-        // Don't tap if already tapping or if an operation is running
-        if (isTapping || isRunningRef.current) return;
-        await startTap();
-        // Schedule end of tap
-        tapTimeoutRef.current = setTimeout(async () => {
-            await endTap();
-        }, settings.durationMs);
-
-    }, [isTapping, startTap, endTap, settings.durationMs]);
-
-    // Setup/cleanup interval(s)
     useEffect(() => {
+        ManipulationService.setTap(toTapOptions(settings)).catch((error) => {
+            console.error("Error updating tap settings:", error);
+        });
+    }, [settings, isActive]);
 
-        // Clear existing intervals
-        if (intervalRef.current) {
-            clearInterval(intervalRef.current);
-            intervalRef.current = null;
-        }
-
-        if (autoPollRef.current) {
-            clearInterval(autoPollRef.current);
-            autoPollRef.current = null;
-        }
+    useEffect(() => {
 
-        if (tapTimeoutRef.current) {
-            clearTimeout(tapTimeoutRef.current);
-            tapTimeoutRef.current = null;
-        }
-        // Only run if tap is enabled and manipulation is active
-        const shouldRun = settings.enabled && isActive;
+        if (!settings.enabled || !isActive) return;
 
-        if (!shouldRun) {
-            // If we were in a tap, restore modules
-            if (isTapping && savedModulesRef.current.length > 0) {
-                endTap();
+        const poll = setInterval(async () => {
+            try {
+                const status = await ManipulationService.getStatus();
+                setIsTapping(status.tap_phase === "tapping");
+            } catch (error) {
+                console.error("Error reading tap phase:", error);
             }
-            return;
-        }
-        // Check if any modules are enabled
-        const enabledModules = getEnabledModules();
-
-        if (enabledModules.length === 0) {
-            return;
-        }
-        // Manual interval mode
-        if (!settings.autoEnabled) {
-            intervalRef.current = setInterval(() => {
-                runTapCycle();
-            }, settings.intervalMs);
-        }
-        // Auto mode: poll backend stats and trigger taps when pressure is high
-        if (settings.autoEnabled) {
-            const pollMs = Math.max(
-                150,
-                Math.min(1000, Math.floor(settings.intervalMs / 3) || 250),
-            );
-            autoPollRef.current = setInterval(async () => {
-
-                if (isTapping || isRunningRef.current) return;
-                // Respect cooldown between taps
-                const now = Date.now();
-                const cooldownMs = settings.cooldownMs ?? 1200;
-
-                if (now - lastTapEndedAtRef.current < cooldownMs) return;
-                // Require at least one outbound buffering module to be enabled
-                const outboundBufferingEnabled =
-                    manipulationStatus.modules.some(
-                        (m) =>
-                            m.enabled &&
-                            m.config.outbound &&
-                            ["burst", "throttle", "lag", "reorder"].includes(
-                                m.name,
-                            ),
-                    );
-
-                if (!outboundBufferingEnabled) return;
+        }, PHASE_POLL_MS);
 
-                try {
-                    const status = await ManipulationService.getStatus();
-                    const stats = status.statistics;
-                    if (!stats) return;
-                    const threshold = settings.minBufferForTap ?? 200;
-                    const pressure = Math.max(
-                        stats.burst_buffered_count ?? 0,
-                        stats.throttle_buffered_count ?? 0,
-                        stats.lag_current_lagged ?? 0,
-                        stats.reorder_delayed_packets ?? 0,
-                    );
-                    const throttling = !!stats.throttle_is_throttling;
-                    if (pressure >= threshold || throttling) {
-                        await runTapCycle();
-                    }
-                } catch (e) {
-                    console.error("AutoTap poll error:", e);
-                }
-
-            }, pollMs);
-        }
-        // Cleanup on unmount or when dependencies change
         return () => {
-
-            if (intervalRef.current) {
-                clearInterval(intervalRef.current);
-                intervalRef.current = null;
-            }
-
-            if (tapTimeoutRef.current) {
-                clearTimeout(tapTimeoutRef.current);
-                tapTimeoutRef.current = null;
-            }
-
-            if (autoPollRef.current) {
-                clearInterval(autoPollRef.current);
-                autoPollRef.current = null;
-            }
-
+            clearInterval(poll);
+            setIsTapping(false);
         };
 
-    }, [
-        settings.enabled,
-        settings.intervalMs,
-        settings.durationMs,
-        settings.autoEnabled,
-        settings.cooldownMs,
-        settings.minBufferForTap,
-        isActive,
-        isTapping,
-        getEnabledModules,
-        runTapCycle,
-        endTap,
-    ]);
+    }, [settings.enabled, isActive, setIsTapping]);
 
     return {
         isTapping,
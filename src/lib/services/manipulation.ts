@@ -1,13 +1,40 @@
 import { invoke } from "@tauri-apps/api/core";
 import {
+    CaptureBackend,
+    FilterDetails,
+    FilterEntry,
     FilterTarget,
+    FlowStatistics,
     LoadConfigResponse,
     ManipulationMode,
+    NetworkProfile,
     PacketManipulationSettings,
     ProcessingStatus,
+    ShaperOptions,
+    TapOptions,
 } from "@/types";
 import { ClassicBackendSettings } from "@/types/classic";
 
+// Convert camelCase to snake_case for Rust
+function toRustFilterTarget(filterTarget: FilterTarget) {
+    return {
+        mode: filterTarget.mode,
+        process_id: filterTarget.processId,
+        process_name: filterTarget.processName,
+        device_ip: filterTarget.deviceIp,
+        device_name: filterTarget.deviceName,
+        custom_filter: filterTarget.customFilter,
+        include_inbound: filterTarget.includeInbound ?? true,
+        include_outbound: filterTarget.includeOutbound ?? true,
+        process_ids: filterTarget.processIds,
+        device_ips: filterTarget.deviceIps,
+        protocols: filterTarget.protocols,
+        remote_ports: filterTarget.remotePorts,
+        process_names: filterTarget.processNames,
+        include_children: filterTarget.includeChildren ?? false,
+    };
+}
+
 // Track WFP throttle state to avoid duplicate start/stop calls
 let wfpThrottleActive = false;
 
@@ -15,9 +42,10 @@ export const ManipulationService = {
     async startProcessing(
         settings: PacketManipulationSettings,
         filter?: string,
+        backend?: CaptureBackend,
     ): Promise<void> {
         // Start the filtering first
-        await invoke("start_processing", { settings, filter });
+        await invoke("start_processing", { settings, filter, backend });
         // Then start WFP throttle if needed (filtering is now active)
         await this.handleWfpThrottle(settings, true);
     },
@@ -32,6 +60,14 @@ export const ManipulationService = {
     async getStatus(): Promise<ProcessingStatus> {
         return invoke("get_status");
     },
+    async getTopFlows(limit?: number): Promise<FlowStatistics[]> {
+        return invoke("get_top_flows", { limit });
+    },
+    async compileFilterTarget(target: FilterTarget): Promise<string> {
+        return invoke("compile_filter_target", {
+            target: toRustFilterTarget(target),
+        });
+    },
     async updateSettings(
         settings: PacketManipulationSettings,
         isFilteringActive: boolean = false,
@@ -42,7 +78,7 @@ export const ManipulationService = {
         // Create the modules array from settings
         const modules = this.createModulesFromSettings(settings);
 
-        return invoke("update_settings", { modules });
+        return invoke("update_settings", { modules, seed: settings.seed });
     },
     // Handle WFP throttle based on bandwidth settings - only starts when filtering is active
     async handleWfpThrottle(
@@ -62,7 +98,11 @@ export const ManipulationService = {
                     : bandwidth.inbound
                       ? "inbound"
                       : "outbound";
-            await this.startWfpThrottle(bandwidth.limit || 1, direction);
+            await this.startWfpThrottle(
+                bandwidth.limit || 1,
+                direction,
+                bandwidth.shaper,
+            );
             return;
         }
 
@@ -81,15 +121,20 @@ export const ManipulationService = {
                       ? "inbound"
                       : "outbound";
             await this.stopWfpThrottle();
-            await this.startWfpThrottle(bandwidth!.limit || 1, direction);
+            await this.startWfpThrottle(
+                bandwidth!.limit || 1,
+                direction,
+                bandwidth!.shaper,
+            );
         }
     },
     async startWfpThrottle(
         limitKbps: number,
         direction: string,
+        shaper?: ShaperOptions,
     ): Promise<void> {
         try {
-            await invoke("start_tc_bandwidth", { limitKbps, direction });
+            await invoke("start_tc_bandwidth", { limitKbps, direction, shaper });
             wfpThrottleActive = true;
         } catch (e) {
             console.error("Failed to start WFP throttle:", e);
@@ -225,6 +270,7 @@ export const ManipulationService = {
                 duration_ms: bandwidth.duration_ms,
                 limit_kbps: bandwidth.limit, // Map Rust 'limit' to frontend 'limit_kbps'
                 use_wfp: bandwidth.use_wfp ?? false,
+                shaper: bandwidth.shaper,
             },
             params: null,
         });
@@ -314,6 +360,21 @@ export const ManipulationService = {
     async updateFilter(filter: string | null): Promise<void> {
         return invoke("update_filter", { filter });
     },
+    async getTap(): Promise<TapOptions | null> {
+        return invoke("get_tap");
+    },
+    async setTap(tap: TapOptions | null): Promise<void> {
+        return invoke("set_tap", { tap });
+    },
+    async listProfiles(): Promise<NetworkProfile[]> {
+        return invoke("list_profiles");
+    },
+    async applyProfile(
+        id: string,
+        config?: string,
+    ): Promise<PacketManipulationSettings> {
+        return invoke("apply_profile", { id, config });
+    },
     async getFilter(): Promise<string | null> {
         return invoke("get_filter");
     },
@@ -323,6 +384,26 @@ export const ManipulationService = {
     async clearFilterHistory(): Promise<void> {
         return invoke("clear_filter_history");
     },
+    async getFilterLibrary(): Promise<FilterEntry[]> {
+        return invoke("get_filter_library");
+    },
+    async saveFilter(
+        filter: string,
+        details: FilterDetails,
+    ): Promise<FilterEntry> {
+        return invoke("save_filter", { filter, details });
+    },
+    async removeFilter(filter: string): Promise<void> {
+        return invoke("remove_filter", { filter });
+    },
+    // Returns JSON to share; only filters tagged `tag` if given
+    async exportFilterLibrary(tag?: string): Promise<string> {
+        return invoke("export_filter_library", { tag });
+    },
+    // Returns how many filters were added or changed
+    async importFilterLibrary(data: string): Promise<number> {
+        return invoke("import_filter_library", { data });
+    },
     async saveConfig(
         name: string,
         filterTarget?: FilterTarget,
@@ -331,27 +412,15 @@ export const ManipulationService = {
             shortcut: string | null;
             enabled: boolean;
         }[],
-        tap?: { enabled: boolean; interval_ms: number; duration_ms: number },
+        tap?: TapOptions,
         classic?: ClassicBackendSettings,
         mode?: ManipulationMode,
     ): Promise<void> {
-        // Convert camelCase to snake_case for Rust
-        const rustFilterTarget = filterTarget
-            ? {
-                  mode: filterTarget.mode,
-                  process_id: filterTarget.processId,
-                  process_name: filterTarget.processName,
-                  device_ip: filterTarget.deviceIp,
-                  device_name: filterTarget.deviceName,
-                  custom_filter: filterTarget.customFilter,
-                  include_inbound: filterTarget.includeInbound ?? true,
-                  include_outbound: filterTarget.includeOutbound ?? true,
-              }
-            : undefined;
-
         return invoke("save_config", {
             name,
-            filterTarget: rustFilterTarget,
+            filterTarget: filterTarget
+                ? toRustFilterTarget(filterTarget)
+                : undefined,
             hotkeys,
             tap,
             classic,
@@ -231,6 +231,24 @@ describe("NetworkStore", () => {
             expect(settings.bandwidth?.use_wfp).toBe(true);
 
         });
+        it("should include the seed", () => {
+
+            useNetworkStore.setState({
+                manipulationStatus: {
+                    active: false,
+                    filter: "",
+                    modules: createMockModules(),
+                },
+                seed: 42,
+            });
+            expect(useNetworkStore.getState().buildSettings().seed).toBe(42);
+
+            useNetworkStore.setState({ seed: undefined });
+            expect(
+                useNetworkStore.getState().buildSettings().seed,
+            ).toBeUndefined();
+
+        });
 
     });
     describe("setFilterTarget", () => {
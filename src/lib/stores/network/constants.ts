@@ -18,4 +18,5 @@ export const initialState: NetworkState = {
     loadingPresets: false,
     currentPreset: null,
     isInitialized: false,
+    seed: undefined,
 };
@@ -249,6 +249,7 @@ export const createCoreSlice: StateCreator<
             set({
                 isActive: isRunning,
                 filter: newFilter,
+                seed: settings.seed ?? undefined,
                 manipulationStatus: {
                     active: isRunning,
                     filter: newFilter,
@@ -7,9 +7,9 @@ import { createPresetSlice } from "@/lib/stores/network/preset-actions";
 import { ModuleInfo, PacketManipulationSettings } from "@/types";
 
 // Build settings from modules - always includes all modules with enabled flag
-const buildSettings = (modules: ModuleInfo[]) => {
+const buildSettings = (modules: ModuleInfo[], seed?: number) => {
 
-    const settings: PacketManipulationSettings = {};
+    const settings: PacketManipulationSettings = { seed };
 
     modules.forEach((module) => {
 
@@ -64,6 +64,7 @@ const buildSettings = (modules: ModuleInfo[]) => {
                     duration_ms: module.config.duration_ms,
                     limit: module.config.limit_kbps || 100, // Map UI limit_kbps to Rust limit
                     use_wfp: module.config.use_wfp ?? false,
+                    shaper: module.config.shaper,
                 };
                 break;
             case "corruption":
@@ -123,5 +124,6 @@ export const useNetworkStore = create<NetworkStoreWithUtils>()((...a) => ({
     ...createCoreSlice(...a),
     ...createModuleSlice(...a),
     ...createPresetSlice(...a),
-    buildSettings: () => buildSettings(a[1]().manipulationStatus.modules),
+    buildSettings: () =>
+        buildSettings(a[1]().manipulationStatus.modules, a[1]().seed),
 }));
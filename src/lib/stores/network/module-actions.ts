@@ -13,6 +13,7 @@ export const createModuleSlice: StateCreator<
         | "updateModuleSettings"
         | "toggleDirection"
         | "applyModuleSettings"
+        | "setSeed"
     >
 > = (set, get) => ({
     updateModuleConfig: async (
@@ -59,6 +60,7 @@ export const createModuleSlice: StateCreator<
                     limit: config.limit_kbps || 500, // Map UI limit_kbps to Rust limit
                     duration_ms,
                     use_wfp: config.use_wfp ?? false,
+                    shaper: config.shaper,
                 };
                 break;
             case "corruption":
@@ -189,4 +191,18 @@ export const createModuleSlice: StateCreator<
         }
 
     },
+    setSeed: async (seed?: number) => {
+
+        set({ seed });
+
+        try {
+            await ManipulationService.updateSettings(
+                get().buildSettings(),
+                get().isActive,
+            );
+        } catch (error) {
+            console.error("Failed to update seed:", error);
+        }
+
+    },
 });
@@ -3,7 +3,11 @@ import { NetworkStore } from "@/lib/stores/network/types";
 import { ManipulationService } from "@/lib/services/manipulation";
 import { DEFAULT_PRESET_NAME } from "@/lib/stores/network/constants";
 import { useHotkeyStore } from "@/lib/stores/hotkey-store";
-import { useTapStore } from "@/lib/stores/tap-store";
+import {
+    fromTapOptions,
+    toTapOptions,
+    useTapStore,
+} from "@/lib/stores/tap-store";
 import { useClassicStore } from "@/lib/stores/classic-store";
 
 export const createPresetSlice: StateCreator<
@@ -50,12 +54,7 @@ export const createPresetSlice: StateCreator<
                 enabled: binding.enabled,
             }));
             // Get tap settings
-            const tapSettings = useTapStore.getState().settings;
-            const tap = {
-                enabled: tapSettings.enabled,
-                interval_ms: tapSettings.intervalMs,
-                duration_ms: tapSettings.durationMs,
-            };
+            const tap = toTapOptions(useTapStore.getState().settings);
             // Get classic settings
             const classicSettings = useClassicStore
                 .getState()
@@ -116,6 +115,13 @@ export const createPresetSlice: StateCreator<
                         customFilter: response.filter_target.custom_filter,
                         includeInbound: finalInbound,
                         includeOutbound: finalOutbound,
+                        processIds: response.filter_target.process_ids,
+                        deviceIps: response.filter_target.device_ips,
+                        protocols: response.filter_target.protocols,
+                        remotePorts: response.filter_target.remote_ports,
+                        processNames: response.filter_target.process_names,
+                        includeChildren:
+                            response.filter_target.include_children,
                     },
                 });
             }
@@ -127,11 +133,9 @@ export const createPresetSlice: StateCreator<
             }
             // Restore tap settings if present (respect saved enabled state)
             if (response.tap) {
-                useTapStore.getState().updateSettings({
-                    enabled: response.tap.enabled ?? false,
-                    intervalMs: response.tap.interval_ms,
-                    durationMs: response.tap.duration_ms,
-                });
+                useTapStore
+                    .getState()
+                    .updateSettings(fromTapOptions(response.tap));
             }
             // Restore classic settings if present
             if (response.classic) {
@@ -184,11 +188,9 @@ export const createPresetSlice: StateCreator<
             ]);
             const filterTarget = get().filterTarget;
             // Get tap settings (always save with enabled: false by default)
-            const tapSettings = useTapStore.getState().settings;
             const tap = {
+                ...toTapOptions(useTapStore.getState().settings),
                 enabled: false, // Always default to disabled
-                interval_ms: tapSettings.intervalMs,
-                duration_ms: tapSettings.durationMs,
             };
             await Promise.all([
                 ManipulationService.updateSettings(settings, get().isActive),
@@ -17,6 +17,7 @@ export interface NetworkState {
     loadingPresets: boolean;
     currentPreset: string | null;
     isInitialized: boolean; // True after initial preset load completes
+    seed?: number; // RNG seed for reproducible runs, system entropy if unset
 }
 
 export interface NetworkActions {
@@ -43,6 +44,7 @@ export interface NetworkActions {
         moduleName: string,
         enabled: boolean,
     ) => Promise<void>;
+    setSeed: (seed?: number) => Promise<void>;
 
     // Preset actions
     loadPresets: () => Promise<void>;
@@ -1,5 +1,6 @@
 import { create } from "zustand";
 import { persist } from "zustand/middleware";
+import { ModuleKind, TapOptions } from "@/types";
 
 export interface TapSettings {
     enabled: boolean;
@@ -8,6 +9,7 @@ export interface TapSettings {
     autoEnabled?: boolean; // Auto mode: trigger taps based on buffer pressure
     minBufferForTap?: number; // Threshold of buffered/lagged packets to trigger
     cooldownMs?: number; // Minimum quiet period between auto taps
+    modules?: ModuleKind[]; // Modules a tap switches off, all if empty
 }
 
 interface TapState {
@@ -24,6 +26,7 @@ interface TapActions {
     setAutoEnabled: (enabled: boolean) => void;
     setMinBufferForTap: (n: number) => void;
     setCooldownMs: (ms: number) => void;
+    setModules: (modules: ModuleKind[]) => void;
 }
 
 type TapStore = TapState & TapActions;
@@ -37,6 +40,32 @@ const DEFAULT_SETTINGS: TapSettings = {
     cooldownMs: 1200,
 };
 
+// Converts store settings to the backend tap options
+export function toTapOptions(settings: TapSettings): TapOptions {
+    return {
+        enabled: settings.enabled,
+        interval_ms: settings.intervalMs,
+        duration_ms: settings.durationMs,
+        modules: settings.modules ?? [],
+        auto: settings.autoEnabled ?? false,
+        min_buffered: settings.minBufferForTap ?? 200,
+        cooldown_ms: settings.cooldownMs ?? 1200,
+    };
+}
+
+// Converts backend tap options to store settings
+export function fromTapOptions(tap: TapOptions): TapSettings {
+    return {
+        enabled: tap.enabled ?? false,
+        intervalMs: tap.interval_ms,
+        durationMs: tap.duration_ms,
+        modules: tap.modules ?? [],
+        autoEnabled: tap.auto ?? false,
+        minBufferForTap: tap.min_buffered ?? 200,
+        cooldownMs: tap.cooldown_ms ?? 1200,
+    };
+}
+
 export const useTapStore = create<TapStore>()(
     persist(
         (set) => ({
@@ -71,6 +100,10 @@ export const useTapStore = create<TapStore>()(
                 set((state) => ({
                     settings: { ...state.settings, cooldownMs: ms },
                 })),
+            setModules: (modules) =>
+                set((state) => ({
+                    settings: { ...state.settings, modules },
+                })),
         }),
         {
             name: "myra-tap-settings",
@@ -255,6 +255,7 @@ export interface ClassicBackendSettings {
         limit_kbps: number;
         max_buffer: number;
     };
+    seed?: number;
 }
 
 /**
@@ -25,6 +25,23 @@ export interface ModuleConfig {
     use_wfp?: boolean;
     passthrough_threshold?: number;
     reverse?: boolean;
+    jitter_ms?: number;
+    jitter_distribution?: JitterDistribution;
+    jitter_correlation?: number; // percent
+    preserve_order?: boolean;
+    drop_model?: DropModel;
+    good_to_bad?: number; // Gilbert-Elliott transition chances and loss rates, in percent
+    bad_to_good?: number;
+    good_loss?: number;
+    bad_loss?: number;
+    shaper?: ShaperOptions; // Token bucket and queue of the bandwidth limiter
+    per_direction?: PerDirection<ModuleConfig>;
+}
+
+// Options used instead of a module's own for packets of one direction
+export interface PerDirection<T> {
+    inbound?: T;
+    outbound?: T;
 }
 
 export interface ModuleInfo {
@@ -70,35 +87,116 @@ export interface PacketManipulationSettings {
     burst_release_delay_us?: number;
     lag_bypass?: boolean; // swap IPs on send failure
     tap?: TapOptions; // Tap feature settings
+    seed?: number; // RNG seed for reproducible runs
+    rules?: ImpairmentRule[]; // Per-flow rules, first enabled match wins
+}
+
+export interface PacketMatch {
+    protocol?: "tcp" | "udp" | "icmp";
+    src?: string; // CIDR, e.g. "10.0.0.0/8", or a single address
+    dst?: string;
+    src_ports?: string; // "27015" or "27000-27100"
+    dst_ports?: string;
+    min_size?: number;
+    max_size?: number;
+    dscp?: number;
+}
+
+export interface ImpairmentRule {
+    name: string;
+    enabled: boolean;
+    match: PacketMatch;
+    settings: PacketManipulationSettings;
+}
+
+// Built-in link profile; `settings` holds its lag, drop and bandwidth
+export interface NetworkProfile {
+    id: string;
+    name: string;
+    description: string;
+    source: string; // Where the numbers come from
+    settings: PacketManipulationSettings;
 }
 
+export type ModuleKind =
+    | "drop"
+    | "lag"
+    | "throttle"
+    | "reorder"
+    | "corruption"
+    | "duplicate"
+    | "bandwidth"
+    | "burst";
+
 export interface TapOptions {
     enabled: boolean;
     interval_ms: number; // How often to tap (every X ms)
     duration_ms: number; // How long to keep modules off (X ms)
+    modules?: ModuleKind[]; // Modules a tap switches off, all if empty
+    auto?: boolean; // Tap when buffers fill up instead of on the interval
+    min_buffered?: number; // Held packets that trigger an auto tap
+    cooldown_ms?: number; // Minimum quiet period between auto taps
+}
+
+// Phase of the tap cycle run by the backend
+export type TapPhase = "off" | "waiting" | "tapping";
+
+export type DropModel = "uniform" | "gilbert_elliott";
+
+export interface GilbertElliottOptions {
+    good_to_bad: number;
+    bad_to_good: number;
+    good_loss: number;
+    bad_loss: number;
 }
 
 export interface DropOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<DropOptions>;
     probability: number;
     duration_ms: number;
+    model?: DropModel;
+    gilbert_elliott?: GilbertElliottOptions;
 }
 
+// How a bandwidth shaper's queue drops packets it cannot send
+export type QueueDiscipline = "tail_drop" | "red" | "codel";
+
+export interface ShaperOptions {
+    burst_bytes: number; // Token bucket depth, 0 = automatic
+    queue_bytes: number; // 0 = no limit
+    queue_packets: number; // 0 = no limit
+    discipline: QueueDiscipline;
+    red_min_threshold: number; // Fraction of the queue limit
+    red_max_threshold: number;
+    red_max_probability: number;
+    codel_target_ms: number;
+    codel_interval_ms: number;
+}
+
+export type JitterDistribution = "uniform" | "normal" | "pareto" | "pareto_normal";
+
 export interface LagOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<LagOptions>;
     probability: number;
     delay_ms: number; // The actual lag time in ms
     duration_ms: number; // Effect duration (0 = infinite)
+    jitter_ms?: number; // Random variation of the lag time in ms
+    distribution?: JitterDistribution;
+    correlation?: number; // 0.0-1.0
+    preserve_order?: boolean; // Keep packets of a flow in order despite jitter
 }
 
 export interface ThrottleOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<ThrottleOptions>;
     probability: number;
     duration_ms: number;
     throttle_ms?: number;
@@ -111,6 +209,7 @@ export interface ReorderOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<ReorderOptions>;
     probability: number;
     duration_ms: number;
     max_delay?: number;
@@ -120,6 +219,7 @@ export interface CorruptionOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<CorruptionOptions>;
     probability: number;
     duration_ms: number;
 }
@@ -128,6 +228,7 @@ export interface DuplicateOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<DuplicateOptions>;
     probability: number;
     count: number;
     duration_ms: number;
@@ -137,6 +238,7 @@ export interface BandwidthOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<BandwidthOptions>;
     probability: number;
     limit: number; // Bandwidth limit in KB/s (matches Rust 'limit' field)
     duration_ms: number;
@@ -144,12 +246,15 @@ export interface BandwidthOptions {
     passthrough_threshold?: number;
     /** Use WFP token bucket algorithm instead of inline packet pacing */
     use_wfp?: boolean;
+    /** Token bucket depth, queue limits and queue discipline */
+    shaper?: ShaperOptions;
 }
 
 export interface BurstOptions {
     enabled?: boolean;
     inbound?: boolean;
     outbound?: boolean;
+    per_direction?: PerDirection<BurstOptions>;
     probability: number;
     buffer_ms: number;
     duration_ms: number;
@@ -158,10 +263,16 @@ export interface BurstOptions {
     reverse?: boolean;
 }
 
+// Capture backend a session runs on; WinDivert when omitted
+export type CaptureBackend =
+    | { kind: "windivert" }
+    | { kind: "tun"; device: string; local_addresses?: string[] };
+
 export interface ProcessingStatus {
     running: boolean;
     statistics?: ProcessingStatistics;
     modules: ModuleInfo[];
+    tap_phase: TapPhase;
 }
 
 export interface ProcessingStatistics {
@@ -171,13 +282,116 @@ export interface ProcessingStatistics {
     throttle_buffered_count: number;
     throttle_dropped_count: number;
     throttle_is_throttling: boolean;
+    bandwidth_buffered_count: number;
+    bandwidth_dropped_count: number;
+    bandwidth_queue_delay_ms: number; // Queueing delay of the last released packet
     lag_current_lagged: number;
+    lag_delay_histogram: Record<number, number>; // 5ms bucket start -> packet count
     reorder_delayed_packets: number;
+    drop_burst_lengths: Record<number, number>;
+    drop_mean_burst_length: number;
+    tap_phase: TapPhase;
+    tap_count: number;
+}
+
+export interface DirectionTraffic {
+    captured_packets: number;
+    captured_bytes: number;
+    delivered_packets: number;
+    delivered_bytes: number;
+    captured_kbps: number; // Over the interval of the snapshot
+    delivered_kbps: number;
+}
+
+// Packets held by the modules; the peak covers the whole interval
+export interface QueueDepths {
+    held_packets: number;
+    peak_held_packets: number;
+    lag: number;
+    throttle: number;
+    reorder: number;
+    bandwidth: number;
+    burst: number;
+}
+
+// Pushed by the backend as the "statistics" event while processing runs
+export interface StatisticsSnapshot extends ProcessingStatistics {
+    timestamp_ms: number;
+    interval_ms: number;
+    running: boolean; // False on the last snapshot of a session
+    inbound: DirectionTraffic;
+    outbound: DirectionTraffic;
+    queues: QueueDepths;
+    drop: {
+        total_packets: number;
+        dropped_packets: number;
+        drop_rate: number;
+        recent_drop_rate: number;
+    };
+    duplicate: {
+        incoming_packets: number;
+        outgoing_packets: number;
+        recent_multiplier: number;
+    };
+    bandwidth: {
+        total_bytes: number;
+        dropped_packets: number;
+        queue_delay_ms: number;
+        recent_kbps: number;
+    };
+    corruption: {
+        corrupted_bytes: number;
+        checksum_valid: boolean;
+    };
+}
+
+export interface FlowDirection {
+    captured_packets: number;
+    captured_bytes: number;
+    delivered_packets: number;
+    delivered_bytes: number;
+    dropped_packets: number; // Captured but never sent
+    mean_delay_ms: number;
+    max_delay_ms: number;
+}
+
+// One flow as seen from the local endpoint, returned by get_top_flows
+export interface FlowStatistics {
+    protocol: string; // "tcp", "udp", "icmp", "icmpv6" or the protocol number
+    local_addr: string;
+    local_port: number;
+    remote_addr: string;
+    remote_port: number;
+    inbound: FlowDirection;
+    outbound: FlowDirection;
+    rtt_ms: number | null; // TCP only, once a segment was acknowledged
+    idle_ms: number;
+}
+
+// Localhost control API used by scripts
+export interface ControlServerStatus {
+    running: boolean;
+    port?: number;
+    token?: string;
+}
+
+// Progress of a scenario timeline played by the backend
+export interface ScenarioProgress {
+    name?: string;
+    elapsed_ms: number;
+    cycle: number;
+    active_steps: number[];
+    finished: boolean;
 }
 
 // Filter target types for the filter selector
 export type FilterTargetMode = "all" | "process" | "device" | "custom";
 
+export type FilterProtocol = "tcp" | "udp" | "icmp";
+
+// Inclusive port range such as "27015" or "27000-27100"
+export type PortRange = string;
+
 export interface FilterTarget {
     mode: FilterTargetMode;
     processId?: number;
@@ -187,6 +401,35 @@ export interface FilterTarget {
     customFilter?: string;
     includeInbound?: boolean;
     includeOutbound?: boolean;
+    // Further processes and devices, targeted along with processId/deviceIp
+    processIds?: number[];
+    deviceIps?: string[];
+    // Executables to target by name, e.g. "game.exe", whatever their PID
+    processNames?: string[];
+    // Also target every process the targeted processes spawn
+    includeChildren?: boolean;
+    // Restrictions; everything is captured when empty
+    protocols?: FilterProtocol[];
+    remotePorts?: PortRange[];
+}
+
+// Filter in the library; saved filters have a name, description, tags or a pin
+export interface FilterEntry {
+    filter: string;
+    name?: string;
+    description?: string;
+    tags?: string[];
+    pinned: boolean;
+    use_count: number;
+    // Milliseconds since the Unix epoch
+    last_used?: number;
+}
+
+export interface FilterDetails {
+    name?: string;
+    description?: string;
+    tags?: string[];
+    pinned?: boolean;
 }
 
 export interface ProcessInfo {
@@ -211,6 +454,12 @@ export interface LoadConfigResponse {
         custom_filter?: string;
         include_inbound?: boolean;
         include_outbound?: boolean;
+        process_ids?: number[];
+        device_ips?: string[];
+        protocols?: FilterProtocol[];
+        remote_ports?: PortRange[];
+        process_names?: string[];
+        include_children?: boolean;
     };
     hotkeys?: {
         action: string;